@@ -1,10 +1,11 @@
 //! Simulates a flow of ADS-B with multiple reporters
 use futures_lite::stream::StreamExt;
 use lib_common::grpc::get_endpoint_from_env;
+use svc_compliance_client_grpc::mock;
 use svc_compliance_client_grpc::prelude::{compliance::*, *};
 
 async fn mq_listener() -> Result<(), ()> {
-    let mq_addr = format!("amqp://rabbitmq:5672");
+    let mq_addr = "amqp://rabbitmq:5672".to_string();
 
     // Establish connection to RabbitMQ node
     println!("(mq_listener) connecting to MQ server at {}...", mq_addr);
@@ -58,7 +59,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let response = client
         .submit_flight_plan(FlightPlanRequest {
             flight_plan_id: "123".to_string(),
-            data: r#"{ "test": 1 }"#.to_string(),
+            flight_plan: Some(mock::flight_plan()),
         })
         .await?;
     println!("submit_flight_plan RESPONSE={:?}", response.into_inner());
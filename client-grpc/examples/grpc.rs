@@ -1,6 +1,7 @@
 //! gRPC client implementation
 //!
 use lib_common::grpc::get_endpoint_from_env;
+use svc_compliance_client_grpc::mock;
 use svc_compliance_client_grpc::prelude::{compliance::*, *};
 
 /// Example svc-compliance-client-grpc
@@ -18,18 +19,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("is_ready RESPONSE={:?}", response.into_inner());
 
+    let flight_plan = mock::flight_plan();
     let response = client
         .submit_flight_plan(FlightPlanRequest {
-            flight_plan_id: "".to_string(),
-            data: "".to_string(),
+            flight_plan_id: "example".to_string(),
+            flight_plan: Some(flight_plan.clone()),
         })
         .await?;
     println!("submit_flight_plan RESPONSE={:?}", response.into_inner());
 
     let response = client
         .request_flight_release(FlightReleaseRequest {
-            flight_plan_id: "".to_string(),
-            data: "".to_string(),
+            flight_plan_id: "example".to_string(),
+            flight_plan: Some(flight_plan),
         })
         .await?;
     println!(
@@ -37,5 +39,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         response.into_inner()
     );
 
+    let response = client
+        .get_flight_plan_status(FlightPlanStatusRequest {
+            flight_plan_id: "example".to_string(),
+        })
+        .await?;
+    println!(
+        "get_flight_plan_status RESPONSE={:?}",
+        response.into_inner()
+    );
+
     Ok(())
 }
@@ -11,9 +11,21 @@ use rpc_service_client::RpcServiceClient;
 /// GrpcClient implementation of the RpcServiceClient
 pub type ComplianceClient = GrpcClient<RpcServiceClient<Channel>>;
 
+/// Stream of [`FlightPlanEvent`]s of the watched flight plans
+pub type FlightPlanEventStream = tonic::codegen::BoxStream<FlightPlanEvent>;
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "stub_backends")] {
         use svc_compliance::grpc::server::{RpcServiceServer, ServerImpl};
+        use svc_compliance::region::lifecycle::FlightPlanRegistry;
+        use svc_compliance::region::store::AirspaceStore;
+        use svc_compliance::region::RegionInterface;
+        use std::collections::HashMap;
+        use std::sync::{Arc, OnceLock};
+
+        /// Flight plans of the stub backend, shared by all connections like
+        ///  those of a single server
+        static FLIGHT_PLANS: OnceLock<Arc<FlightPlanRegistry>> = OnceLock::new();
 
         #[tonic::async_trait]
         impl lib_common::grpc::ClientConnect<RpcServiceClient<Channel>> for ComplianceClient {
@@ -23,11 +35,20 @@ cfg_if::cfg_if! {
             ) -> Result<RpcServiceClient<Channel>, tonic::transport::Error> {
                 let (client, server) = tokio::io::duplex(1024);
 
-                let region = Box::<svc_compliance::region::RegionImpl>::default();
+                let regions: Vec<Arc<dyn RegionInterface + Send + Sync>> =
+                    vec![Arc::new(svc_compliance::region::nl::RegionImpl::default())];
+
+                // the stub regions have no sources, so their airspace loads empty
+                let store = AirspaceStore::default();
+                for region in &regions {
+                    store.set_restrictions(region.get_region(), HashMap::new());
+                }
 
                 let grpc_service = ServerImpl {
                     mq_channel: None,
-                    region,
+                    regions,
+                    store: Arc::new(store),
+                    flight_plans: FLIGHT_PLANS.get_or_init(Arc::default).clone(),
                 };
 
                 lib_common::grpc::mock::start_mock_server(
@@ -102,6 +123,47 @@ impl service::Client<RpcServiceClient<Channel>> for ComplianceClient {
             .request_flight_release(request)
             .await
     }
+
+    async fn cancel_flight_plan(
+        &self,
+        request: FlightCancellationRequest,
+    ) -> Result<tonic::Response<FlightCancellationResponse>, tonic::Status> {
+        grpc_warn!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        self.get_client().await?.cancel_flight_plan(request).await
+    }
+
+    async fn amend_flight_plan(
+        &self,
+        request: FlightAmendmentRequest,
+    ) -> Result<tonic::Response<FlightAmendmentResponse>, tonic::Status> {
+        grpc_warn!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        self.get_client().await?.amend_flight_plan(request).await
+    }
+
+    async fn get_flight_plan_status(
+        &self,
+        request: FlightPlanStatusRequest,
+    ) -> Result<tonic::Response<FlightPlanStatusResponse>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        self.get_client()
+            .await?
+            .get_flight_plan_status(request)
+            .await
+    }
+
+    async fn watch_flight_plan(
+        &self,
+        request: WatchFlightPlanRequest,
+    ) -> Result<tonic::Response<FlightPlanEventStream>, tonic::Status> {
+        grpc_info!("{} client.", self.get_name());
+        grpc_debug!("request: {:?}", request);
+        let response = self.get_client().await?.watch_flight_plan(request).await?;
+        let stream: FlightPlanEventStream = Box::pin(response.into_inner());
+        Ok(tonic::Response::new(stream))
+    }
 }
 
 #[cfg(feature = "stub_client")]
@@ -128,6 +190,8 @@ impl service::Client<RpcServiceClient<Channel>> for ComplianceClient {
             flight_plan_id: request.flight_plan_id,
             submitted: true,
             result: None,
+            decisions: vec![],
+            violations: vec![],
         }))
     }
 
@@ -141,8 +205,77 @@ impl service::Client<RpcServiceClient<Channel>> for ComplianceClient {
             flight_plan_id: request.flight_plan_id,
             released: true,
             result: None,
+            decisions: vec![],
+            violations: vec![],
+        }))
+    }
+
+    async fn cancel_flight_plan(
+        &self,
+        request: FlightCancellationRequest,
+    ) -> Result<tonic::Response<FlightCancellationResponse>, tonic::Status> {
+        grpc_warn!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(FlightCancellationResponse {
+            flight_plan_id: request.flight_plan_id,
+            cancelled: true,
+            result: None,
+            decisions: vec![],
+            violations: vec![],
         }))
     }
+
+    async fn amend_flight_plan(
+        &self,
+        request: FlightAmendmentRequest,
+    ) -> Result<tonic::Response<FlightAmendmentResponse>, tonic::Status> {
+        grpc_warn!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(FlightAmendmentResponse {
+            flight_plan_id: request.flight_plan_id,
+            amended: true,
+            result: None,
+            decisions: vec![],
+            violations: vec![],
+        }))
+    }
+
+    async fn get_flight_plan_status(
+        &self,
+        request: FlightPlanStatusRequest,
+    ) -> Result<tonic::Response<FlightPlanStatusResponse>, tonic::Status> {
+        grpc_warn!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        Ok(tonic::Response::new(FlightPlanStatusResponse {
+            flight_plan_id: request.flight_plan_id,
+            status: FlightPlanStatus::Accepted as i32,
+        }))
+    }
+
+    async fn watch_flight_plan(
+        &self,
+        request: WatchFlightPlanRequest,
+    ) -> Result<tonic::Response<FlightPlanEventStream>, tonic::Status> {
+        grpc_warn!("(MOCK) {} client.", self.get_name());
+        grpc_debug!("(MOCK) request: {:?}", request);
+        let events = request
+            .flight_plan_ids
+            .into_iter()
+            .map(|flight_plan_id| {
+                Ok(FlightPlanEvent {
+                    flight_plan_id,
+                    status: FlightPlanStatus::Accepted as i32,
+                    previous_status: Some(FlightPlanStatus::Submitted as i32),
+                    time: Some(std::time::SystemTime::now().into()),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(tonic::Response::new(Box::pin(
+            tonic::codegen::tokio_stream::iter(events),
+        )))
+    }
 }
 
 #[cfg(test)]
@@ -178,7 +311,7 @@ mod tests {
         let result = client.is_ready(ReadyRequest {}).await;
         println!("{:?}", result);
         assert!(result.is_ok());
-        assert_eq!(result.unwrap().into_inner().ready, true);
+        assert!(result.unwrap().into_inner().ready);
     }
 
     #[tokio::test]
@@ -191,15 +324,15 @@ mod tests {
 
         let result = client
             .submit_flight_plan(FlightPlanRequest {
-                flight_plan_id: "".to_string(),
-                data: "".to_string(),
+                flight_plan_id: "test".to_string(),
+                flight_plan: Some(crate::mock::flight_plan()),
             })
             .await;
 
         assert!(result.is_ok());
         let result: FlightPlanResponse = result.unwrap().into_inner();
         println!("{:?}", result);
-        assert_eq!(result.submitted, true);
+        assert!(result.submitted);
     }
 
     #[tokio::test]
@@ -210,16 +343,145 @@ mod tests {
 
         let client = ComplianceClient::new_client(&server_host, server_port, name);
 
+        // only accepted flight plans can be released
+        let flight_plan = crate::mock::flight_plan();
+        let result = client
+            .submit_flight_plan(FlightPlanRequest {
+                flight_plan_id: "test-release".to_string(),
+                flight_plan: Some(flight_plan.clone()),
+            })
+            .await;
+        assert!(result.is_ok());
+
         let result = client
             .request_flight_release(FlightReleaseRequest {
-                flight_plan_id: "".to_string(),
-                data: "".to_string(),
+                flight_plan_id: "test-release".to_string(),
+                flight_plan: Some(flight_plan),
             })
             .await;
 
         assert!(result.is_ok());
         let result: FlightReleaseResponse = result.unwrap().into_inner();
         println!("{:?}", result);
-        assert_eq!(result.released, true);
+        assert!(result.released);
+    }
+
+    #[tokio::test]
+    async fn test_client_cancel_flight_plan() {
+        let name = "compliance";
+        let (server_host, server_port) =
+            lib_common::grpc::get_endpoint_from_env("GRPC_HOST", "GRPC_PORT");
+
+        let client = ComplianceClient::new_client(&server_host, server_port, name);
+
+        let result = client
+            .submit_flight_plan(FlightPlanRequest {
+                flight_plan_id: "test-cancel".to_string(),
+                flight_plan: Some(crate::mock::flight_plan()),
+            })
+            .await;
+        assert!(result.is_ok());
+
+        let result = client
+            .cancel_flight_plan(FlightCancellationRequest {
+                flight_plan_id: "test-cancel".to_string(),
+            })
+            .await;
+
+        assert!(result.is_ok());
+        let result: FlightCancellationResponse = result.unwrap().into_inner();
+        println!("{:?}", result);
+        assert!(result.cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_client_amend_flight_plan() {
+        let name = "compliance";
+        let (server_host, server_port) =
+            lib_common::grpc::get_endpoint_from_env("GRPC_HOST", "GRPC_PORT");
+
+        let client = ComplianceClient::new_client(&server_host, server_port, name);
+
+        let result = client
+            .submit_flight_plan(FlightPlanRequest {
+                flight_plan_id: "test-amend".to_string(),
+                flight_plan: Some(crate::mock::flight_plan()),
+            })
+            .await;
+        assert!(result.is_ok());
+
+        let mut flight_plan = crate::mock::flight_plan();
+        flight_plan.operation_type = OperationType::Ferry as i32;
+        let result = client
+            .amend_flight_plan(FlightAmendmentRequest {
+                flight_plan_id: "test-amend".to_string(),
+                flight_plan: Some(flight_plan),
+            })
+            .await;
+
+        assert!(result.is_ok());
+        let result: FlightAmendmentResponse = result.unwrap().into_inner();
+        println!("{:?}", result);
+        assert!(result.amended);
+    }
+
+    #[tokio::test]
+    async fn test_client_get_flight_plan_status() {
+        let name = "compliance";
+        let (server_host, server_port) =
+            lib_common::grpc::get_endpoint_from_env("GRPC_HOST", "GRPC_PORT");
+
+        let client = ComplianceClient::new_client(&server_host, server_port, name);
+
+        let result = client
+            .submit_flight_plan(FlightPlanRequest {
+                flight_plan_id: "test-status".to_string(),
+                flight_plan: Some(crate::mock::flight_plan()),
+            })
+            .await;
+        assert!(result.is_ok());
+
+        let result = client
+            .get_flight_plan_status(FlightPlanStatusRequest {
+                flight_plan_id: "test-status".to_string(),
+            })
+            .await;
+
+        assert!(result.is_ok());
+        let result: FlightPlanStatusResponse = result.unwrap().into_inner();
+        println!("{:?}", result);
+        assert_eq!(result.status, FlightPlanStatus::Accepted as i32);
+    }
+
+    #[tokio::test]
+    async fn test_client_watch_flight_plan() {
+        use futures_lite::StreamExt;
+
+        let name = "compliance";
+        let (server_host, server_port) =
+            lib_common::grpc::get_endpoint_from_env("GRPC_HOST", "GRPC_PORT");
+
+        let client = ComplianceClient::new_client(&server_host, server_port, name);
+
+        let result = client
+            .submit_flight_plan(FlightPlanRequest {
+                flight_plan_id: "test-watch".to_string(),
+                flight_plan: Some(crate::mock::flight_plan()),
+            })
+            .await;
+        assert!(result.is_ok());
+
+        let result = client
+            .watch_flight_plan(WatchFlightPlanRequest {
+                flight_plan_ids: vec!["test-watch".to_string()],
+            })
+            .await;
+
+        assert!(result.is_ok());
+        let mut stream = result.unwrap().into_inner();
+        let event: FlightPlanEvent = stream.next().await.unwrap().unwrap();
+        println!("{:?}", event);
+        assert_eq!(event.flight_plan_id, "test-watch");
+        assert_eq!(event.status, FlightPlanStatus::Accepted as i32);
     }
 }
@@ -1,15 +1,14 @@
 // This file is @generated by prost-build.
 /// FlightPlanRequest
-#[derive(serde::Serialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FlightPlanRequest {
     /// Flight Plan Id
     #[prost(string, tag = "1")]
     pub flight_plan_id: ::prost::alloc::string::String,
-    /// JSON data of the flight plan
-    #[prost(string, tag = "2")]
-    pub data: ::prost::alloc::string::String,
+    /// The flight plan
+    #[prost(message, optional, tag = "3")]
+    pub flight_plan: ::core::option::Option<FlightPlan>,
 }
 /// FlightPlanResponse
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -24,17 +23,23 @@ pub struct FlightPlanResponse {
     /// Optional error or warning message
     #[prost(string, optional, tag = "3")]
     pub result: ::core::option::Option<::prost::alloc::string::String>,
+    /// Decision of each regional authority the route passes through
+    #[prost(message, repeated, tag = "4")]
+    pub decisions: ::prost::alloc::vec::Vec<RegionDecision>,
+    /// Rule violations found by the regional authorities
+    #[prost(message, repeated, tag = "5")]
+    pub violations: ::prost::alloc::vec::Vec<Violation>,
 }
 /// FlightReleaseRequest
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct FlightReleaseRequest {
-    /// Flight Plan Id
+    /// Flight Plan Id of an accepted flight plan
     #[prost(string, tag = "1")]
     pub flight_plan_id: ::prost::alloc::string::String,
-    /// JSON data of the flight plan
-    #[prost(string, tag = "2")]
-    pub data: ::prost::alloc::string::String,
+    /// Optional, must match the accepted flight plan if provided
+    #[prost(message, optional, tag = "3")]
+    pub flight_plan: ::core::option::Option<FlightPlan>,
 }
 /// FlightReleaseResponse
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -49,6 +54,205 @@ pub struct FlightReleaseResponse {
     /// Optional error or warning message
     #[prost(string, optional, tag = "3")]
     pub result: ::core::option::Option<::prost::alloc::string::String>,
+    /// Decision of each regional authority the route passes through
+    #[prost(message, repeated, tag = "4")]
+    pub decisions: ::prost::alloc::vec::Vec<RegionDecision>,
+    /// Rule violations found by the regional authorities
+    #[prost(message, repeated, tag = "5")]
+    pub violations: ::prost::alloc::vec::Vec<Violation>,
+}
+/// FlightCancellationRequest
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlightCancellationRequest {
+    /// Flight Plan Id of a submitted flight plan
+    #[prost(string, tag = "1")]
+    pub flight_plan_id: ::prost::alloc::string::String,
+}
+/// FlightCancellationResponse
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlightCancellationResponse {
+    /// Flight Plan Id
+    #[prost(string, tag = "1")]
+    pub flight_plan_id: ::prost::alloc::string::String,
+    /// Status result for cancelled
+    #[prost(bool, tag = "2")]
+    pub cancelled: bool,
+    /// Optional error or warning message
+    #[prost(string, optional, tag = "3")]
+    pub result: ::core::option::Option<::prost::alloc::string::String>,
+    /// Decision of each regional authority the route passes through
+    #[prost(message, repeated, tag = "4")]
+    pub decisions: ::prost::alloc::vec::Vec<RegionDecision>,
+    /// Rule violations found by the regional authorities
+    #[prost(message, repeated, tag = "5")]
+    pub violations: ::prost::alloc::vec::Vec<Violation>,
+}
+/// FlightAmendmentRequest
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlightAmendmentRequest {
+    /// Flight Plan Id of a submitted flight plan
+    #[prost(string, tag = "1")]
+    pub flight_plan_id: ::prost::alloc::string::String,
+    /// The amended flight plan, replacing the submitted version once accepted
+    #[prost(message, optional, tag = "2")]
+    pub flight_plan: ::core::option::Option<FlightPlan>,
+}
+/// FlightAmendmentResponse
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlightAmendmentResponse {
+    /// Flight Plan Id
+    #[prost(string, tag = "1")]
+    pub flight_plan_id: ::prost::alloc::string::String,
+    /// Status result for amended
+    #[prost(bool, tag = "2")]
+    pub amended: bool,
+    /// Optional error or warning message
+    #[prost(string, optional, tag = "3")]
+    pub result: ::core::option::Option<::prost::alloc::string::String>,
+    /// Decision of each regional authority the route passes through
+    #[prost(message, repeated, tag = "4")]
+    pub decisions: ::prost::alloc::vec::Vec<RegionDecision>,
+    /// Rule violations found by the regional authorities
+    #[prost(message, repeated, tag = "5")]
+    pub violations: ::prost::alloc::vec::Vec<Violation>,
+}
+/// FlightPlanStatusRequest
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlightPlanStatusRequest {
+    /// Flight Plan Id
+    #[prost(string, tag = "1")]
+    pub flight_plan_id: ::prost::alloc::string::String,
+}
+/// FlightPlanStatusResponse
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlightPlanStatusResponse {
+    /// Flight Plan Id
+    #[prost(string, tag = "1")]
+    pub flight_plan_id: ::prost::alloc::string::String,
+    /// Current state of the flight plan
+    #[prost(enumeration = "FlightPlanStatus", tag = "2")]
+    pub status: i32,
+}
+/// WatchFlightPlanRequest
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchFlightPlanRequest {
+    /// Flight Plan Ids to watch, these may be watched before they are submitted
+    #[prost(string, repeated, tag = "1")]
+    pub flight_plan_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// FlightPlanEvent
+/// Sent for each state change of a watched flight plan, and for each decision
+/// of the regional authorities that leaves the state unchanged
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlightPlanEvent {
+    /// Flight Plan Id
+    #[prost(string, tag = "1")]
+    pub flight_plan_id: ::prost::alloc::string::String,
+    /// State of the flight plan after the event
+    #[prost(enumeration = "FlightPlanStatus", tag = "2")]
+    pub status: i32,
+    /// State of the flight plan before the event, not set for the current
+    /// state sent when the watch starts
+    #[prost(enumeration = "FlightPlanStatus", optional, tag = "3")]
+    pub previous_status: ::core::option::Option<i32>,
+    /// When the event occurred
+    #[prost(message, optional, tag = "4")]
+    pub time: ::core::option::Option<::prost_types::Timestamp>,
+    /// Optional error or warning message
+    #[prost(string, optional, tag = "5")]
+    pub result: ::core::option::Option<::prost::alloc::string::String>,
+    /// Decision of each regional authority that led to the event
+    #[prost(message, repeated, tag = "6")]
+    pub decisions: ::prost::alloc::vec::Vec<RegionDecision>,
+    /// Rule violations found by the regional authorities
+    #[prost(message, repeated, tag = "7")]
+    pub violations: ::prost::alloc::vec::Vec<Violation>,
+}
+/// FlightPlan
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct FlightPlan {
+    /// Identifier of the aircraft, such as its registration
+    #[prost(string, tag = "1")]
+    pub aircraft_id: ::prost::alloc::string::String,
+    /// Identifier of the aircraft operator
+    #[prost(string, tag = "2")]
+    pub operator_id: ::prost::alloc::string::String,
+    /// Identifier of the departure vertiport
+    #[prost(string, tag = "3")]
+    pub departure_vertiport_id: ::prost::alloc::string::String,
+    /// Identifier of the arrival vertiport
+    #[prost(string, tag = "4")]
+    pub arrival_vertiport_id: ::prost::alloc::string::String,
+    /// Type of operation
+    #[prost(enumeration = "OperationType", tag = "5")]
+    pub operation_type: i32,
+    /// Route of the flight, in order of travel
+    #[prost(message, repeated, tag = "6")]
+    pub route: ::prost::alloc::vec::Vec<RoutePoint>,
+}
+/// RoutePoint
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RoutePoint {
+    /// Latitude in degrees
+    #[prost(double, tag = "1")]
+    pub latitude: f64,
+    /// Longitude in degrees
+    #[prost(double, tag = "2")]
+    pub longitude: f64,
+    /// Altitude in meters above the WGS84 ellipsoid, not above mean sea level
+    ///   or ground
+    #[prost(float, tag = "3")]
+    pub altitude_meters: f32,
+    /// Time at which the aircraft passes this point
+    #[prost(message, optional, tag = "4")]
+    pub time: ::core::option::Option<::prost_types::Timestamp>,
+}
+/// RegionDecision
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegionDecision {
+    /// Region short code of the authority
+    #[prost(string, tag = "1")]
+    pub region: ::prost::alloc::string::String,
+    /// True if the authority accepted the request
+    #[prost(bool, tag = "2")]
+    pub accepted: bool,
+    /// Optional error or warning message
+    #[prost(string, optional, tag = "3")]
+    pub result: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// Violation
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Violation {
+    /// Region short code of the authority reporting the violation
+    #[prost(string, tag = "1")]
+    pub region: ::prost::alloc::string::String,
+    /// Code of the violated rule (e.g. RESTRICTED_ZONE)
+    #[prost(string, tag = "2")]
+    pub rule_code: ::prost::alloc::string::String,
+    /// Severity of the violation
+    #[prost(enumeration = "Severity", tag = "3")]
+    pub severity: i32,
+    /// Identifier of the zone or waypoint involved
+    #[prost(string, optional, tag = "4")]
+    pub feature_id: ::core::option::Option<::prost::alloc::string::String>,
+    /// Index of the offending route segment, counting from the first route point
+    #[prost(uint32, optional, tag = "5")]
+    pub segment_index: ::core::option::Option<u32>,
+    /// Human readable description
+    #[prost(string, tag = "6")]
+    pub message: ::prost::alloc::string::String,
 }
 /// ReadyRequest body
 ///
@@ -67,6 +271,145 @@ pub struct ReadyResponse {
     #[prost(bool, tag = "1")]
     pub ready: bool,
 }
+/// FlightPlanStatus
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum FlightPlanStatus {
+    /// Not specified
+    Unspecified = 0,
+    /// Created, but not submitted yet
+    Draft = 1,
+    /// Submitted, waiting for the decision of the regional authorities
+    Submitted = 2,
+    /// Accepted by all regional authorities
+    Accepted = 3,
+    /// Rejected by at least one regional authority
+    Rejected = 4,
+    /// Released for takeoff
+    Released = 5,
+    /// The flight is in progress
+    Activated = 6,
+    /// The flight has ended
+    Closed = 7,
+    /// Cancelled by the operator
+    Cancelled = 8,
+    /// Withdrawn by a regional authority
+    Revoked = 9,
+}
+impl FlightPlanStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            FlightPlanStatus::Unspecified => "FLIGHT_PLAN_STATUS_UNSPECIFIED",
+            FlightPlanStatus::Draft => "DRAFT",
+            FlightPlanStatus::Submitted => "SUBMITTED",
+            FlightPlanStatus::Accepted => "ACCEPTED",
+            FlightPlanStatus::Rejected => "REJECTED",
+            FlightPlanStatus::Released => "RELEASED",
+            FlightPlanStatus::Activated => "ACTIVATED",
+            FlightPlanStatus::Closed => "CLOSED",
+            FlightPlanStatus::Cancelled => "CANCELLED",
+            FlightPlanStatus::Revoked => "REVOKED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "FLIGHT_PLAN_STATUS_UNSPECIFIED" => Some(Self::Unspecified),
+            "DRAFT" => Some(Self::Draft),
+            "SUBMITTED" => Some(Self::Submitted),
+            "ACCEPTED" => Some(Self::Accepted),
+            "REJECTED" => Some(Self::Rejected),
+            "RELEASED" => Some(Self::Released),
+            "ACTIVATED" => Some(Self::Activated),
+            "CLOSED" => Some(Self::Closed),
+            "CANCELLED" => Some(Self::Cancelled),
+            "REVOKED" => Some(Self::Revoked),
+            _ => None,
+        }
+    }
+}
+/// OperationType
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum OperationType {
+    /// Not specified, rejected by the server
+    Unspecified = 0,
+    /// Cargo transport
+    Cargo = 1,
+    /// Passenger transport
+    Passenger = 2,
+    /// Emergency services
+    Emergency = 3,
+    /// Repositioning of the aircraft without payload
+    Ferry = 4,
+}
+impl OperationType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            OperationType::Unspecified => "OPERATION_TYPE_UNSPECIFIED",
+            OperationType::Cargo => "CARGO",
+            OperationType::Passenger => "PASSENGER",
+            OperationType::Emergency => "EMERGENCY",
+            OperationType::Ferry => "FERRY",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "OPERATION_TYPE_UNSPECIFIED" => Some(Self::Unspecified),
+            "CARGO" => Some(Self::Cargo),
+            "PASSENGER" => Some(Self::Passenger),
+            "EMERGENCY" => Some(Self::Emergency),
+            "FERRY" => Some(Self::Ferry),
+            _ => None,
+        }
+    }
+}
+/// Severity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum Severity {
+    /// Not specified
+    Unspecified = 0,
+    /// The request is rejected
+    Error = 1,
+    /// The request is accepted, but needs attention
+    Warning = 2,
+    /// Informational only
+    Info = 3,
+}
+impl Severity {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Severity::Unspecified => "SEVERITY_UNSPECIFIED",
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARNING",
+            Severity::Info => "INFO",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SEVERITY_UNSPECIFIED" => Some(Self::Unspecified),
+            "ERROR" => Some(Self::Error),
+            "WARNING" => Some(Self::Warning),
+            "INFO" => Some(Self::Info),
+            _ => None,
+        }
+    }
+}
 /// Generated client implementations.
 #[cfg(not(tarpaulin_include))]
 pub mod rpc_service_client {
@@ -226,5 +569,109 @@ pub mod rpc_service_client {
                 .insert(GrpcMethod::new("grpc.RpcService", "requestFlightRelease"));
             self.inner.unary(req, path, codec).await
         }
+        /// cancel flight plan
+        pub async fn cancel_flight_plan(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FlightCancellationRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FlightCancellationResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/cancelFlightPlan",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "cancelFlightPlan"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// amend flight plan
+        pub async fn amend_flight_plan(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FlightAmendmentRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FlightAmendmentResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/amendFlightPlan",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "amendFlightPlan"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// get flight plan status
+        pub async fn get_flight_plan_status(
+            &mut self,
+            request: impl tonic::IntoRequest<super::FlightPlanStatusRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::FlightPlanStatusResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/getFlightPlanStatus",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "getFlightPlanStatus"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// watch flight plan state changes and decisions
+        pub async fn watch_flight_plan(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchFlightPlanRequest>,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::FlightPlanEvent>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/grpc.RpcService/watchFlightPlan",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("grpc.RpcService", "watchFlightPlan"));
+            self.inner.server_streaming(req, path, codec).await
+        }
     }
 }
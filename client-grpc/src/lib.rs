@@ -1,6 +1,8 @@
 #![doc = include_str!("../README.md")]
 
 pub mod client;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
 pub mod prelude;
 pub mod service;
 
@@ -0,0 +1,6 @@
+//! Mock data generation for the client messages
+
+use super::client::{FlightPlan, OperationType, RoutePoint};
+use lib_common::time::{Duration, Utc};
+
+include!("mock_flight_plan.rs");
@@ -0,0 +1,35 @@
+// Mock flight plan shared by the mock modules of client-grpc and of the
+//  server, whose messages are generated from the same proto file. Kept in
+//  client-grpc so that the published crate holds it, and included by the
+//  server. Included with `FlightPlan`, `OperationType`, `RoutePoint`,
+//  `Duration` and `Utc` in scope.
+
+/// Returns a valid cargo flight plan from Amsterdam to Lelystad, departing
+///  in ten minutes
+pub fn flight_plan() -> FlightPlan {
+    let departure = Utc::now() + Duration::minutes(10);
+    let route = [(52.3745, 4.9160, 0), (52.5337, 5.4339, 20)]
+        .into_iter()
+        .map(|(latitude, longitude, minutes)| {
+            let time = departure + Duration::minutes(minutes);
+            RoutePoint {
+                latitude,
+                longitude,
+                altitude_meters: 120.0,
+                time: Some(prost_types::Timestamp {
+                    seconds: time.timestamp(),
+                    nanos: time.timestamp_subsec_nanos() as i32,
+                }),
+            }
+        })
+        .collect();
+
+    FlightPlan {
+        aircraft_id: "PH-ARW".to_string(),
+        operator_id: "aetheric".to_string(),
+        departure_vertiport_id: "amsterdam-centraal".to_string(),
+        arrival_vertiport_id: "lelystad-airport".to_string(),
+        operation_type: OperationType::Cargo as i32,
+        route,
+    }
+}
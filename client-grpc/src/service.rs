@@ -59,8 +59,12 @@ where
     ///     let client = ComplianceClient::new_client(&host, port, "compliance");
     ///     let response = client
     ///         .submit_flight_plan(compliance::FlightPlanRequest {
-    ///             flight_plan_id: "".to_string(),
-    ///             data: "".to_string(),
+    ///             flight_plan_id: "example".to_string(),
+    ///             flight_plan: Some(compliance::FlightPlan {
+    ///                 aircraft_id: "PH-ARW".to_string(),
+    ///                 operation_type: compliance::OperationType::Cargo as i32,
+    ///                 ..Default::default()
+    ///             }),
     ///         })
     ///         .await?;
     ///     println!("submit_flight_plan RESPONSE={:?}", response.into_inner());
@@ -91,8 +95,8 @@ where
     ///     let client = ComplianceClient::new_client(&host, port, "compliance");
     ///     let response = client
     ///         .request_flight_release(compliance::FlightReleaseRequest {
-    ///             flight_plan_id: "".to_string(),
-    ///             data: "".to_string(),
+    ///             flight_plan_id: "example".to_string(),
+    ///             flight_plan: None,
     ///         })
     ///         .await?;
     ///     println!("submit_flight_plan RESPONSE={:?}", response.into_inner());
@@ -103,4 +107,150 @@ where
         &self,
         request: super::FlightReleaseRequest,
     ) -> Result<tonic::Response<super::FlightReleaseResponse>, tonic::Status>;
+
+    /// Returns a [`tonic::Response`] containing a [`FlightCancellationResponse`](super::FlightCancellationResponse)
+    /// Takes an [`FlightCancellationRequest`](super::FlightCancellationRequest).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`tonic::Status`] with [`Code::NotFound`](tonic::Code::NotFound) if
+    /// the flight plan is unknown, or with
+    /// [`Code::FailedPrecondition`](tonic::Code::FailedPrecondition) if it can
+    /// no longer be cancelled.
+    ///
+    /// # Examples
+    /// ```
+    /// use lib_common::grpc::get_endpoint_from_env;
+    /// use svc_compliance_client_grpc::prelude::*;
+    /// use tonic::transport::Channel;
+    ///
+    /// async fn example () -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (host, port) = get_endpoint_from_env("SERVER_HOSTNAME", "SERVER_PORT_GRPC");
+    ///     let client = ComplianceClient::new_client(&host, port, "compliance");
+    ///     let response = client
+    ///         .cancel_flight_plan(compliance::FlightCancellationRequest {
+    ///             flight_plan_id: "example".to_string(),
+    ///         })
+    ///         .await?;
+    ///     println!("cancel_flight_plan RESPONSE={:?}", response.into_inner());
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn cancel_flight_plan(
+        &self,
+        request: super::FlightCancellationRequest,
+    ) -> Result<tonic::Response<super::FlightCancellationResponse>, tonic::Status>;
+
+    /// Returns a [`tonic::Response`] containing a [`FlightAmendmentResponse`](super::FlightAmendmentResponse)
+    /// Takes an [`FlightAmendmentRequest`](super::FlightAmendmentRequest).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`tonic::Status`] with [`Code::InvalidArgument`](tonic::Code::InvalidArgument)
+    /// if the amended flight plan is invalid, with [`Code::NotFound`](tonic::Code::NotFound)
+    /// if the flight plan is unknown, or with
+    /// [`Code::FailedPrecondition`](tonic::Code::FailedPrecondition) if it cannot
+    /// be amended.
+    ///
+    /// # Examples
+    /// ```
+    /// use lib_common::grpc::get_endpoint_from_env;
+    /// use svc_compliance_client_grpc::prelude::*;
+    /// use tonic::transport::Channel;
+    ///
+    /// async fn example () -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (host, port) = get_endpoint_from_env("SERVER_HOSTNAME", "SERVER_PORT_GRPC");
+    ///     let client = ComplianceClient::new_client(&host, port, "compliance");
+    ///     let response = client
+    ///         .amend_flight_plan(compliance::FlightAmendmentRequest {
+    ///             flight_plan_id: "example".to_string(),
+    ///             flight_plan: Some(compliance::FlightPlan {
+    ///                 aircraft_id: "PH-ARW".to_string(),
+    ///                 operation_type: compliance::OperationType::Ferry as i32,
+    ///                 ..Default::default()
+    ///             }),
+    ///         })
+    ///         .await?;
+    ///     println!("amend_flight_plan RESPONSE={:?}", response.into_inner());
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn amend_flight_plan(
+        &self,
+        request: super::FlightAmendmentRequest,
+    ) -> Result<tonic::Response<super::FlightAmendmentResponse>, tonic::Status>;
+
+    /// Returns a [`tonic::Response`] containing a [`FlightPlanStatusResponse`](super::FlightPlanStatusResponse)
+    /// Takes an [`FlightPlanStatusRequest`](super::FlightPlanStatusRequest).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`tonic::Status`] with [`Code::NotFound`](tonic::Code::NotFound) if
+    /// the flight plan is unknown.
+    ///
+    /// # Examples
+    /// ```
+    /// use lib_common::grpc::get_endpoint_from_env;
+    /// use svc_compliance_client_grpc::prelude::*;
+    /// use tonic::transport::Channel;
+    ///
+    /// async fn example () -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (host, port) = get_endpoint_from_env("SERVER_HOSTNAME", "SERVER_PORT_GRPC");
+    ///     let client = ComplianceClient::new_client(&host, port, "compliance");
+    ///     let response = client
+    ///         .get_flight_plan_status(compliance::FlightPlanStatusRequest {
+    ///             flight_plan_id: "example".to_string(),
+    ///         })
+    ///         .await?;
+    ///     println!("get_flight_plan_status RESPONSE={:?}", response.into_inner());
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn get_flight_plan_status(
+        &self,
+        request: super::FlightPlanStatusRequest,
+    ) -> Result<tonic::Response<super::FlightPlanStatusResponse>, tonic::Status>;
+
+    /// Returns a [`tonic::Response`] containing a [`FlightPlanEventStream`](super::FlightPlanEventStream)
+    /// Takes an [`WatchFlightPlanRequest`](super::WatchFlightPlanRequest).
+    ///
+    /// The stream starts with the current state of each watched flight plan
+    /// that is already known, followed by each state change and decision. It
+    /// ends once all watched flight plans reached a final state.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`tonic::Status`] with [`Code::InvalidArgument`](tonic::Code::InvalidArgument)
+    /// if no flight plan ids are provided. The stream ends with
+    /// [`Code::DeadlineExceeded`](tonic::Code::DeadlineExceeded) after an hour
+    /// without events, and with [`Code::DataLoss`](tonic::Code::DataLoss) if
+    /// the client fell too far behind. In both cases the flight plans should
+    /// be watched again.
+    ///
+    /// # Examples
+    /// ```
+    /// use futures::StreamExt;
+    /// use lib_common::grpc::get_endpoint_from_env;
+    /// use svc_compliance_client_grpc::prelude::*;
+    /// use tonic::transport::Channel;
+    ///
+    /// async fn example () -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (host, port) = get_endpoint_from_env("SERVER_HOSTNAME", "SERVER_PORT_GRPC");
+    ///     let client = ComplianceClient::new_client(&host, port, "compliance");
+    ///     let mut stream = client
+    ///         .watch_flight_plan(compliance::WatchFlightPlanRequest {
+    ///             flight_plan_ids: vec!["example".to_string()],
+    ///         })
+    ///         .await?
+    ///         .into_inner();
+    ///     while let Some(event) = stream.next().await {
+    ///         println!("watch_flight_plan EVENT={:?}", event?);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    async fn watch_flight_plan(
+        &self,
+        request: super::WatchFlightPlanRequest,
+    ) -> Result<tonic::Response<super::FlightPlanEventStream>, tonic::Status>;
 }
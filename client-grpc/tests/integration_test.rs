@@ -4,17 +4,10 @@ fn get_log_string(function: &str, name: &str) -> String {
     #[cfg(feature = "stub_client")]
     return format!("({}) (MOCK) {} client.", function, name);
 
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "test_us")] {
-            let region = "us";
-        } else {
-            let region = "nl";
-        }
-    }
-
     #[cfg(not(feature = "stub_client"))]
     cfg_if::cfg_if! {
         if #[cfg(feature = "stub_backends")] {
+            let region = svc_compliance::region::DEFAULT_REGION_CODE;
             return format!("({}) (MOCK)[{}] {} server.", function, region, name);
         } else {
             return format!("({}) {} client.", function, name);
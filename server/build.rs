@@ -10,8 +10,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .type_attribute("ReadyResponse", "#[derive(Eq, Copy)]")
         .type_attribute("Coordinates", "#[derive(Copy)]")
         .type_attribute("RestrictionsRequest", "#[derive(Copy)]")
-        .type_attribute("WaypointsRequest", "#[derive(Copy)]")
-        .type_attribute("FlightPlanRequest", "#[derive(serde::Serialize)]");
+        .type_attribute("WaypointsRequest", "#[derive(Copy)]");
 
     let client_config = server_config.clone();
 
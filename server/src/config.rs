@@ -26,9 +26,76 @@ pub struct Config {
     /// interval in seconds to refresh waypoints
     pub interval_seconds_refresh_waypoints: u16,
 
+    /// interval in seconds to resend all no-fly zones to svc-gis
+    pub interval_seconds_reconcile_zones: u16,
+
+    /// directory in which the zones last sent to svc-gis are kept across restarts
+    pub zone_sync_directory: Option<String>,
+
     /// path to log configuration YAML file
     pub log_config: String,
 
+    /// comma separated short codes of the regions to serve (e.g. `nl,us`)
+    pub region_code: String,
+
+    /// file path or http(s) URL of the published Dutch drone zones, in GeoJSON
+    pub nl_zones_source: Option<String>,
+
+    /// file path, directory or http(s) URL of the Dutch NOTAMs, in the ICAO format
+    pub nl_notam_source: Option<String>,
+
+    /// file path, directory or http(s) URL of the FAA TFR feed, in XNOTAM or AIXM 5.1
+    pub us_tfr_source: Option<String>,
+
+    /// file path or http(s) URL of the FAA UAS Facility Map grid, in CSV or GeoJSON
+    pub us_uasfm_source: Option<String>,
+
+    /// directory of the FAA NASR subscription, holding FIX.txt and NAV.txt
+    pub us_nasr_path: Option<String>,
+
+    /// file path, directory or http(s) URL of the US NOTAMs, in the ICAO format
+    pub us_notam_source: Option<String>,
+
+    /// file path, directory or http(s) URL of Dutch airspace, in the OpenAir format
+    pub nl_openair_source: Option<String>,
+
+    /// file path, directory or http(s) URL of US airspace, in the OpenAir format
+    pub us_openair_source: Option<String>,
+
+    /// maximum distance in meters between circles or arcs and the polygons sent to svc-gis,
+    ///  above zero
+    pub max_chord_error_meters: f64,
+
+    /// hours ahead for which restriction schedules are expanded to activation windows for svc-gis
+    pub schedule_horizon_hours: u32,
+
+    /// file path, directory or http(s) URL of ad-hoc restriction zones, in KML or KMZ
+    pub kml_source: Option<String>,
+
+    /// path of a US zone layer, as an Esri Shapefile `.shp` with its `.dbf` and `.prj`
+    pub us_zones_shapefile: Option<String>,
+
+    /// columns of the US zone layer as `field=COLUMN` pairs, such as `upper=CEILING`
+    pub us_zones_shapefile_fields: Option<String>,
+
+    /// path of a US waypoint layer, as an Esri Shapefile `.shp` with its `.dbf` and `.prj`
+    pub us_waypoints_shapefile: Option<String>,
+
+    /// columns of the US waypoint layer as `field=COLUMN` pairs, such as `id=NAME`
+    pub us_waypoints_shapefile_fields: Option<String>,
+
+    /// file path or http(s) URL of a navigation database for the Dutch region, in ARINC 424
+    pub nl_arinc424_source: Option<String>,
+
+    /// file path or http(s) URL of a navigation database for the US region, in ARINC 424
+    pub us_arinc424_source: Option<String>,
+
+    /// file path or http(s) URL of the terrain elevation above mean sea level, as an ESRI ASCII grid
+    pub terrain_source: Option<String>,
+
+    /// file path or http(s) URL of the geoid height above the WGS84 ellipsoid, as an ESRI ASCII grid
+    pub geoid_source: Option<String>,
+
     /// AMQP Settings
     pub amqp: deadpool_lapin::Config,
 }
@@ -49,7 +116,29 @@ impl Config {
             gis_port_grpc: 50051,
             interval_seconds_refresh_zones: 30,
             interval_seconds_refresh_waypoints: 30,
+            interval_seconds_reconcile_zones: crate::grpc::zone_sync::RECONCILE_INTERVAL_SECONDS,
+            zone_sync_directory: None,
             log_config: String::from("log4rs.yaml"),
+            region_code: String::from(crate::region::DEFAULT_REGION_CODE),
+            nl_zones_source: None,
+            nl_notam_source: None,
+            us_tfr_source: None,
+            us_uasfm_source: None,
+            us_nasr_path: None,
+            us_notam_source: None,
+            nl_openair_source: None,
+            us_openair_source: None,
+            max_chord_error_meters: crate::region::shape::MAX_CHORD_ERROR_METERS,
+            schedule_horizon_hours: crate::region::schedule::SCHEDULE_HORIZON_HOURS,
+            kml_source: None,
+            us_zones_shapefile: None,
+            us_zones_shapefile_fields: None,
+            us_waypoints_shapefile: None,
+            us_waypoints_shapefile_fields: None,
+            nl_arinc424_source: None,
+            us_arinc424_source: None,
+            terrain_source: None,
+            geoid_source: None,
             amqp: deadpool_lapin::Config {
                 url: None,
                 pool: None,
@@ -59,14 +148,18 @@ impl Config {
     }
 
     /// Create a new `Config` object using environment variables
+    ///
+    /// Empty values, such as `TERRAIN_SOURCE=`, are taken as unset. Returns
+    ///  an error if a value is out of range.
     pub fn try_from_env() -> Result<Self, ConfigError> {
         // read .env file if present
         dotenv().ok();
         let default_config = Config::default();
 
-        config::Config::builder()
+        let config: Config = config::Config::builder()
             .set_default("docker_port_grpc", default_config.docker_port_grpc)?
             .set_default("log_config", default_config.log_config)?
+            .set_default("region_code", default_config.region_code)?
             .set_default(
                 "interval_seconds_refresh_zones",
                 default_config.interval_seconds_refresh_zones,
@@ -75,9 +168,29 @@ impl Config {
                 "interval_seconds_refresh_waypoints",
                 default_config.interval_seconds_refresh_waypoints,
             )?
-            .add_source(Environment::default().separator("__"))
+            .set_default(
+                "interval_seconds_reconcile_zones",
+                default_config.interval_seconds_reconcile_zones,
+            )?
+            .set_default(
+                "max_chord_error_meters",
+                default_config.max_chord_error_meters,
+            )?
+            .set_default(
+                "schedule_horizon_hours",
+                default_config.schedule_horizon_hours,
+            )?
+            .add_source(Environment::default().separator("__").ignore_empty(true))
             .build()?
-            .try_deserialize()
+            .try_deserialize()?;
+
+        match config.max_chord_error_meters > 0.0 {
+            true => Ok(config),
+            false => Err(ConfigError::Message(format!(
+                "max_chord_error_meters must be above zero, got {}",
+                config.max_chord_error_meters
+            ))),
+        }
     }
 }
 
@@ -97,7 +210,29 @@ mod tests {
         assert_eq!(config.gis_port_grpc, 50051);
         assert_eq!(config.interval_seconds_refresh_zones, 30);
         assert_eq!(config.interval_seconds_refresh_waypoints, 30);
+        assert_eq!(config.interval_seconds_reconcile_zones, 3600);
+        assert!(config.zone_sync_directory.is_none());
         assert_eq!(config.log_config, String::from("log4rs.yaml"));
+        assert_eq!(config.region_code, String::from("nl"));
+        assert!(config.nl_zones_source.is_none());
+        assert!(config.us_tfr_source.is_none());
+        assert!(config.us_uasfm_source.is_none());
+        assert!(config.us_nasr_path.is_none());
+        assert!(config.nl_notam_source.is_none());
+        assert!(config.us_notam_source.is_none());
+        assert!(config.nl_openair_source.is_none());
+        assert!(config.us_openair_source.is_none());
+        assert_eq!(config.max_chord_error_meters, 10.0);
+        assert_eq!(config.schedule_horizon_hours, 168);
+        assert!(config.kml_source.is_none());
+        assert!(config.us_zones_shapefile.is_none());
+        assert!(config.us_zones_shapefile_fields.is_none());
+        assert!(config.us_waypoints_shapefile.is_none());
+        assert!(config.us_waypoints_shapefile_fields.is_none());
+        assert!(config.nl_arinc424_source.is_none());
+        assert!(config.us_arinc424_source.is_none());
+        assert!(config.terrain_source.is_none());
+        assert!(config.geoid_source.is_none());
         assert!(config.amqp.url.is_none());
         assert!(config.amqp.pool.is_none());
 
@@ -114,7 +249,32 @@ mod tests {
         std::env::set_var("GIS_PORT_GRPC", "6798");
         std::env::set_var("INTERVAL_SECONDS_REFRESH_ZONES", "40");
         std::env::set_var("INTERVAL_SECONDS_REFRESH_WAYPOINTS", "40");
+        std::env::set_var("INTERVAL_SECONDS_RECONCILE_ZONES", "600");
+        std::env::set_var("ZONE_SYNC_DIRECTORY", "/var/lib/svc-compliance");
         std::env::set_var("LOG_CONFIG", "config_file.yaml");
+        std::env::set_var("REGION_CODE", "us");
+        std::env::set_var("NL_ZONES_SOURCE", "/data/nl_zones.geojson");
+        std::env::set_var("US_TFR_SOURCE", "http://tfr.example.com/tfrs.xml");
+        std::env::set_var("US_UASFM_SOURCE", "/data/uasfm.csv");
+        std::env::set_var("US_NASR_PATH", "/data/nasr");
+        std::env::set_var("NL_NOTAM_SOURCE", "/data/nl_notams");
+        std::env::set_var("US_NOTAM_SOURCE", "http://notam.example.com/us.txt");
+        std::env::set_var("NL_OPENAIR_SOURCE", "/data/nl_openair.txt");
+        std::env::set_var("US_OPENAIR_SOURCE", "http://openair.example.com/us.txt");
+        std::env::set_var("MAX_CHORD_ERROR_METERS", "5");
+        std::env::set_var("SCHEDULE_HORIZON_HOURS", "48");
+        std::env::set_var("KML_SOURCE", "/data/adhoc");
+        std::env::set_var("US_ZONES_SHAPEFILE", "/data/sua/Special_Use_Airspace.shp");
+        std::env::set_var("US_ZONES_SHAPEFILE_FIELDS", "upper=CEILING");
+        std::env::set_var(
+            "US_WAYPOINTS_SHAPEFILE",
+            "/data/points/Designated_Points.shp",
+        );
+        std::env::set_var("US_WAYPOINTS_SHAPEFILE_FIELDS", "id=NAME");
+        std::env::set_var("NL_ARINC424_SOURCE", "/data/eur.pc");
+        std::env::set_var("US_ARINC424_SOURCE", "http://navdata.example.com/usa.pc");
+        std::env::set_var("TERRAIN_SOURCE", "/data/ahn.asc");
+        std::env::set_var("GEOID_SOURCE", "http://geoid.example.com/egm2008.asc");
         std::env::set_var("AMQP__URL", "amqp://test_rabbitmq:5672");
         std::env::set_var("AMQP__POOL__MAX_SIZE", "32");
 
@@ -127,7 +287,74 @@ mod tests {
         assert_eq!(config.gis_port_grpc, 6798);
         assert_eq!(config.interval_seconds_refresh_zones, 40);
         assert_eq!(config.interval_seconds_refresh_waypoints, 40);
+        assert_eq!(config.interval_seconds_reconcile_zones, 600);
+        assert_eq!(
+            config.zone_sync_directory,
+            Some(String::from("/var/lib/svc-compliance"))
+        );
         assert_eq!(config.log_config, String::from("config_file.yaml"));
+        assert_eq!(config.region_code, String::from("us"));
+        assert_eq!(
+            config.nl_zones_source,
+            Some(String::from("/data/nl_zones.geojson"))
+        );
+        assert_eq!(
+            config.us_tfr_source,
+            Some(String::from("http://tfr.example.com/tfrs.xml"))
+        );
+        assert_eq!(
+            config.us_uasfm_source,
+            Some(String::from("/data/uasfm.csv"))
+        );
+        assert_eq!(config.us_nasr_path, Some(String::from("/data/nasr")));
+        assert_eq!(
+            config.nl_notam_source,
+            Some(String::from("/data/nl_notams"))
+        );
+        assert_eq!(
+            config.us_notam_source,
+            Some(String::from("http://notam.example.com/us.txt"))
+        );
+        assert_eq!(
+            config.nl_openair_source,
+            Some(String::from("/data/nl_openair.txt"))
+        );
+        assert_eq!(
+            config.us_openair_source,
+            Some(String::from("http://openair.example.com/us.txt"))
+        );
+        assert_eq!(config.max_chord_error_meters, 5.0);
+        assert_eq!(config.schedule_horizon_hours, 48);
+        assert_eq!(config.kml_source, Some(String::from("/data/adhoc")));
+        assert_eq!(
+            config.us_zones_shapefile,
+            Some(String::from("/data/sua/Special_Use_Airspace.shp"))
+        );
+        assert_eq!(
+            config.us_zones_shapefile_fields,
+            Some(String::from("upper=CEILING"))
+        );
+        assert_eq!(
+            config.us_waypoints_shapefile,
+            Some(String::from("/data/points/Designated_Points.shp"))
+        );
+        assert_eq!(
+            config.us_waypoints_shapefile_fields,
+            Some(String::from("id=NAME"))
+        );
+        assert_eq!(
+            config.nl_arinc424_source,
+            Some(String::from("/data/eur.pc"))
+        );
+        assert_eq!(
+            config.us_arinc424_source,
+            Some(String::from("http://navdata.example.com/usa.pc"))
+        );
+        assert_eq!(config.terrain_source, Some(String::from("/data/ahn.asc")));
+        assert_eq!(
+            config.geoid_source,
+            Some(String::from("http://geoid.example.com/egm2008.asc"))
+        );
         assert_eq!(
             config.amqp.url,
             Some(String::from("amqp://test_rabbitmq:5672"))
@@ -135,6 +362,22 @@ mod tests {
         assert_eq!(config.amqp.get_pool_config().max_size, 32);
         assert!(config.amqp.pool.is_some());
 
+        // a chord error of zero or less cannot be met
+        for value in ["0", "-1", "NaN"] {
+            std::env::set_var("MAX_CHORD_ERROR_METERS", value);
+            assert!(matches!(
+                Config::try_from_env(),
+                Err(config::ConfigError::Message(_))
+            ));
+        }
+        std::env::set_var("MAX_CHORD_ERROR_METERS", "5");
+
+        // empty values are unset
+        std::env::set_var("TERRAIN_SOURCE", "");
+        let config = Config::try_from_env().unwrap();
+        assert!(config.terrain_source.is_none());
+        std::env::set_var("TERRAIN_SOURCE", "/data/ahn.asc");
+
         ut_info!("Success.");
     }
 }
@@ -0,0 +1,233 @@
+//! Validation and conversion of the gRPC flight plan message
+
+use super::server::{FlightPlan, OperationType, RoutePoint};
+use crate::region;
+use lib_common::time::{DateTime, Utc};
+use tonic::Status;
+
+/// Validates a gRPC flight plan and converts it to the domain flight plan
+///
+/// All invalid fields are reported at once in an `INVALID_ARGUMENT` status,
+///  each prefixed with the path of the field (e.g. `flight_plan.route[1].time`).
+pub fn to_flight_plan(
+    flight_plan_id: &str,
+    flight_plan: Option<FlightPlan>,
+) -> Result<region::FlightPlan, Status> {
+    let mut errors: Vec<String> = vec![];
+    if flight_plan_id.trim().is_empty() {
+        errors.push("flight_plan_id: must not be empty".to_string());
+    }
+
+    let Some(flight_plan) = flight_plan else {
+        errors.push("flight_plan: is required".to_string());
+        return Err(invalid_argument(errors));
+    };
+
+    let mut require = |field: &str, value: &str| {
+        if value.trim().is_empty() {
+            errors.push(format!("flight_plan.{field}: must not be empty"));
+        }
+    };
+
+    require("aircraft_id", &flight_plan.aircraft_id);
+    require("operator_id", &flight_plan.operator_id);
+    require(
+        "departure_vertiport_id",
+        &flight_plan.departure_vertiport_id,
+    );
+    require("arrival_vertiport_id", &flight_plan.arrival_vertiport_id);
+
+    let operation_type = match OperationType::try_from(flight_plan.operation_type) {
+        Ok(OperationType::Cargo) => Some(region::OperationType::Cargo),
+        Ok(OperationType::Passenger) => Some(region::OperationType::Passenger),
+        Ok(OperationType::Emergency) => Some(region::OperationType::Emergency),
+        Ok(OperationType::Ferry) => Some(region::OperationType::Ferry),
+        Ok(OperationType::Unspecified) | Err(_) => {
+            errors.push("flight_plan.operation_type: must be specified".to_string());
+            None
+        }
+    };
+
+    if flight_plan.route.len() < 2 {
+        errors.push("flight_plan.route: must contain at least 2 points".to_string());
+    }
+
+    let mut route = vec![];
+    for (index, point) in flight_plan.route.iter().enumerate() {
+        let previous = route
+            .last()
+            .and_then(|p: &region::utils::RoutePoint| p.timestamp);
+        match to_route_point(index, point, previous) {
+            Ok(point) => route.push(point),
+            Err(mut point_errors) => errors.append(&mut point_errors),
+        }
+    }
+
+    match (errors.is_empty(), operation_type) {
+        (true, Some(operation_type)) => Ok(region::FlightPlan {
+            flight_plan_id: flight_plan_id.to_string(),
+            aircraft_id: flight_plan.aircraft_id,
+            operator_id: flight_plan.operator_id,
+            departure_vertiport_id: flight_plan.departure_vertiport_id,
+            arrival_vertiport_id: flight_plan.arrival_vertiport_id,
+            operation_type,
+            route,
+        }),
+        _ => Err(invalid_argument(errors)),
+    }
+}
+
+/// Validates and converts a single route point
+///
+/// The time of a point may not be before the time of the previous point.
+fn to_route_point(
+    index: usize,
+    point: &RoutePoint,
+    previous: Option<DateTime<Utc>>,
+) -> Result<region::utils::RoutePoint, Vec<String>> {
+    let field = format!("flight_plan.route[{index}]");
+    let mut errors = vec![];
+
+    if !(-90.0..=90.0).contains(&point.latitude) {
+        errors.push(format!("{field}.latitude: must be between -90 and 90"));
+    }
+
+    if !(-180.0..=180.0).contains(&point.longitude) {
+        errors.push(format!("{field}.longitude: must be between -180 and 180"));
+    }
+
+    if !point.altitude_meters.is_finite() {
+        errors.push(format!("{field}.altitude_meters: must be a finite number"));
+    }
+
+    let timestamp = match &point.time {
+        None => {
+            errors.push(format!("{field}.time: is required"));
+            None
+        }
+        Some(time) => {
+            let timestamp = u32::try_from(time.nanos)
+                .ok()
+                .and_then(|nanos| DateTime::<Utc>::from_timestamp(time.seconds, nanos));
+
+            match (timestamp, previous) {
+                (None, _) => errors.push(format!("{field}.time: is not a valid timestamp")),
+                (Some(timestamp), Some(previous)) if timestamp < previous => errors.push(format!(
+                    "{field}.time: must not be before the previous point"
+                )),
+                _ => (),
+            }
+
+            timestamp
+        }
+    };
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(region::utils::RoutePoint {
+        latitude: point.latitude,
+        longitude: point.longitude,
+        altitude_meters: Some(point.altitude_meters),
+        timestamp,
+    })
+}
+
+/// Creates an `INVALID_ARGUMENT` status listing the invalid fields
+fn invalid_argument(errors: Vec<String>) -> Status {
+    grpc_warn!("Invalid flight plan: {:?}", errors);
+    Status::invalid_argument(format!("Invalid flight plan: {}", errors.join("; ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grpc::mock;
+
+    #[tokio::test]
+    async fn test_to_flight_plan() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let flight_plan = to_flight_plan("test", Some(mock::flight_plan())).unwrap();
+        assert_eq!(flight_plan.flight_plan_id, "test");
+        assert_eq!(flight_plan.operation_type, region::OperationType::Cargo);
+        assert_eq!(flight_plan.route.len(), 2);
+        assert!(flight_plan.route.iter().all(|p| p.timestamp.is_some()));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_to_flight_plan_missing() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let error = to_flight_plan("test", None).unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+        assert!(error.message().contains("flight_plan: is required"));
+
+        let error = to_flight_plan(" ", Some(mock::flight_plan())).unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+        assert!(error
+            .message()
+            .contains("flight_plan_id: must not be empty"));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_to_flight_plan_invalid_fields() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let mut flight_plan = mock::flight_plan();
+        flight_plan.aircraft_id = " ".to_string();
+        flight_plan.operation_type = OperationType::Unspecified as i32;
+        flight_plan.route[0].latitude = 91.0;
+        flight_plan.route[1].altitude_meters = f32::NAN;
+
+        let error = to_flight_plan("test", Some(flight_plan)).unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+
+        let message = error.message();
+        ut_debug!("{}", message);
+        assert!(message.contains("flight_plan.aircraft_id: must not be empty"));
+        assert!(message.contains("flight_plan.operation_type: must be specified"));
+        assert!(message.contains("flight_plan.route[0].latitude"));
+        assert!(message.contains("flight_plan.route[1].altitude_meters"));
+        assert!(!message.contains("operator_id"));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_to_flight_plan_invalid_route() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let mut flight_plan = mock::flight_plan();
+        flight_plan.route.truncate(1);
+        let error = to_flight_plan("test", Some(flight_plan)).unwrap_err();
+        assert!(error
+            .message()
+            .contains("flight_plan.route: must contain at least 2 points"));
+
+        let mut flight_plan = mock::flight_plan();
+        flight_plan.route.swap(0, 1);
+        let error = to_flight_plan("test", Some(flight_plan)).unwrap_err();
+        assert!(error
+            .message()
+            .contains("flight_plan.route[1].time: must not be before the previous point"));
+
+        let mut flight_plan = mock::flight_plan();
+        flight_plan.route[1].time = None;
+        let error = to_flight_plan("test", Some(flight_plan)).unwrap_err();
+        assert!(error
+            .message()
+            .contains("flight_plan.route[1].time: is required"));
+
+        ut_info!("Success.");
+    }
+}
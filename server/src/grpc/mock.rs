@@ -0,0 +1,6 @@
+//! Mock data generation for the gRPC messages
+
+use super::server::{FlightPlan, OperationType, RoutePoint};
+use lib_common::time::{Duration, Utc};
+
+include!("../../../client-grpc/src/mock_flight_plan.rs");
@@ -3,4 +3,8 @@
 
 #[macro_use]
 pub mod macros;
+pub mod flight_plan;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
 pub mod server;
+pub mod zone_sync;
@@ -2,25 +2,54 @@
 
 ///  module svc_storage generated from svc-storage.proto
 mod grpc_server {
-    #![allow(unused_qualifications, missing_docs)]
+    #![allow(unused_qualifications, missing_docs, non_camel_case_types)]
     tonic::include_proto!("grpc");
 }
 
 pub use crate::amqp::init_mq;
-use crate::region::RestrictionDetails;
+use crate::region::geometry::{normalize_restrictions, normalize_ring, GeometryError};
+use crate::region::utils::UNLIMITED_ALTITUDE_METERS;
+use crate::region::vertical::VerticalModel;
+use crate::region::{RestrictionDetails, RestrictionKind};
 pub use grpc_server::rpc_service_server::{RpcService, RpcServiceServer};
+pub use grpc_server::{FlightAmendmentRequest, FlightAmendmentResponse};
+pub use grpc_server::{FlightCancellationRequest, FlightCancellationResponse};
+pub use grpc_server::{FlightPlan, OperationType, RoutePoint};
+pub use grpc_server::{FlightPlanEvent, WatchFlightPlanRequest};
 pub use grpc_server::{FlightPlanRequest, FlightPlanResponse};
+pub use grpc_server::{FlightPlanStatus, FlightPlanStatusRequest, FlightPlanStatusResponse};
 pub use grpc_server::{FlightReleaseRequest, FlightReleaseResponse};
-pub use grpc_server::{ReadyRequest, ReadyResponse};
+pub use grpc_server::{ReadyRequest, ReadyResponse, RegionDecision};
+pub use grpc_server::{Severity, Violation};
 use svc_gis_client_grpc::prelude::*;
 
+use super::flight_plan::to_flight_plan;
+use super::zone_sync::ZoneSync;
 use crate::config::Config;
-use crate::region::RegionInterface;
+use crate::region;
+use crate::region::kml;
+use crate::region::lifecycle::{
+    FlightPlanRecord, FlightPlanRegistry, FlightPlanState, LifecycleError, LifecycleEvent,
+    RegionOutcome,
+};
+use crate::region::source::Source;
+use crate::region::store::AirspaceStore;
+use crate::region::{from_region_codes, route_in_region, RegionInterface};
+use crate::region::{Decision, RegionError};
+
+#[cfg(feature = "stub_server")]
+use crate::region::compliance::check_flight_plan;
 use crate::shutdown_signal;
 
 use core::fmt;
-use std::collections::HashMap;
+use lib_common::time::{DateTime, Duration, Utc};
+use std::collections::{BTreeSet, HashMap};
 use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::transport::Server;
 use tonic::{Request, Response, Status};
 
@@ -34,8 +63,536 @@ pub struct ServerImpl {
     #[cfg(feature = "stub_server")]
     pub mq_channel: Option<()>,
 
-    /// Region interface
-    pub region: Box<dyn RegionInterface + Send + Sync>,
+    /// Region interfaces, one for each served region
+    pub regions: Vec<Arc<dyn RegionInterface + Send + Sync>>,
+
+    /// Airspace data of the served regions, kept up to date by the refresh loops
+    pub store: Arc<AirspaceStore>,
+
+    /// Lifecycle state of the flight plans handled by this service
+    pub flight_plans: Arc<FlightPlanRegistry>,
+}
+
+impl ServerImpl {
+    /// Returns the short codes of the served regions, for logging
+    fn region_codes(&self) -> String {
+        self.regions
+            .iter()
+            .map(|region| region.get_region())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Returns the served regions the route of the flight plan passes through
+    ///
+    /// Flight plans without a route are sent to every served region.
+    fn route_regions(
+        &self,
+        flight_plan: &region::FlightPlan,
+    ) -> Vec<&(dyn RegionInterface + Send + Sync)> {
+        let route = flight_plan.route_coordinates();
+        self.regions
+            .iter()
+            .map(|region| region.as_ref())
+            .filter(|region| route.is_empty() || route_in_region(*region, &route))
+            .collect()
+    }
+
+    /// Returns the record of a known flight plan
+    fn record(&self, flight_plan_id: &str) -> Result<FlightPlanRecord, Status> {
+        self.flight_plans.get(flight_plan_id).ok_or_else(|| {
+            lifecycle_status(LifecycleError::UnknownFlightPlan(
+                flight_plan_id.to_string(),
+            ))
+        })
+    }
+
+    /// Returns the record of a known flight plan that may move to the next state
+    ///
+    /// Checked before the regional authorities are contacted, the transition
+    ///  itself is only made once they have decided. The caller holds the
+    ///  request lock of the flight plan meanwhile, so the state cannot change.
+    fn record_for_transition(
+        &self,
+        flight_plan_id: &str,
+        next: FlightPlanState,
+    ) -> Result<FlightPlanRecord, Status> {
+        let record = self.record(flight_plan_id)?;
+        if !record.state.can_transition_to(next) {
+            return Err(lifecycle_status(LifecycleError::IllegalTransition {
+                flight_plan_id: flight_plan_id.to_string(),
+                from: record.state,
+                to: next,
+            }));
+        }
+
+        Ok(record)
+    }
+
+    /// Returns the accepted flight plan to release
+    ///
+    /// A flight plan included in the request must match the accepted version.
+    fn release_candidate(
+        &self,
+        flight_plan_id: &str,
+        flight_plan: Option<FlightPlan>,
+    ) -> Result<region::FlightPlan, Status> {
+        let record = self.record_for_transition(flight_plan_id, FlightPlanState::Released)?;
+        if flight_plan.is_some()
+            && to_flight_plan(flight_plan_id, flight_plan)? != record.flight_plan
+        {
+            grpc_warn!(
+                "Flight plan '{}' differs from the accepted version.",
+                flight_plan_id
+            );
+            return Err(Status::failed_precondition(
+                "flight_plan: does not match the accepted flight plan",
+            ));
+        }
+
+        Ok(record.flight_plan)
+    }
+}
+
+impl ServerImpl {
+    /// Returns the served regions on the amended route, and those only on
+    ///  the route of the previous version
+    fn amendment_regions(
+        &self,
+        previous: &region::FlightPlan,
+        amended: &region::FlightPlan,
+    ) -> (
+        Vec<&(dyn RegionInterface + Send + Sync)>,
+        Vec<&(dyn RegionInterface + Send + Sync)>,
+    ) {
+        let regions = self.route_regions(amended);
+        let dropped = self
+            .route_regions(previous)
+            .into_iter()
+            .filter(|region| {
+                !regions
+                    .iter()
+                    .any(|r| r.get_region() == region.get_region())
+            })
+            .collect();
+
+        (regions, dropped)
+    }
+}
+
+/// Returns true if the regional authorities accepted the request
+fn outcomes_accepted(outcomes: &[RegionOutcome]) -> bool {
+    let (decisions, violations) = to_region_decisions(outcomes);
+    combine_decisions(&decisions, &violations).0
+}
+
+/// Number of events buffered for each flight plan watcher
+const WATCH_BUFFER_SIZE: usize = 16;
+
+/// Seconds a watch stream waits for an event before it ends
+pub const WATCH_TIMEOUT_SECONDS: u64 = 3600;
+
+/// Owner of the ad-hoc zones, next to the region codes, in the logs and the
+///  files keeping the zones sent to svc-gis
+const KML_ZONE_OWNER: &str = "kml";
+
+impl ServerImpl {
+    /// Streams the lifecycle events of the watched flight plans
+    ///
+    /// The stream starts with the current state of each watched flight plan
+    ///  that is already known, and ends once all watched flight plans reached
+    ///  a final state. Final flight plans are only evicted from the registry
+    ///  after their last event, so an evicted flight plan ends the watch too.
+    ///  A stream without events for `timeout` ends with a `DEADLINE_EXCEEDED`
+    ///  status, such as one watching a flight plan that is never submitted.
+    ///  A watcher that falls too far behind receives a `DATA_LOSS` status.
+    ///  In both cases the client should watch again.
+    fn watch(
+        &self,
+        flight_plan_ids: Vec<String>,
+        timeout: std::time::Duration,
+    ) -> Result<ReceiverStream<Result<FlightPlanEvent, Status>>, Status> {
+        let mut watched = flight_plan_ids
+            .into_iter()
+            .filter(|id| !id.trim().is_empty())
+            .collect::<BTreeSet<_>>();
+
+        if watched.is_empty() {
+            grpc_warn!("No flight plans to watch.");
+            return Err(Status::invalid_argument(
+                "flight_plan_ids: must not be empty",
+            ));
+        }
+
+        // The current states and the later events, without gaps or repeats
+        let (records, mut events) = self.flight_plans.watch(&watched);
+        let mut current = vec![];
+        for record in records {
+            let id = record.flight_plan.flight_plan_id;
+            if record.state.is_final() {
+                watched.remove(&id);
+            }
+
+            current.push(FlightPlanEvent {
+                flight_plan_id: id,
+                status: to_flight_plan_status(record.state) as i32,
+                previous_status: None,
+                time: Some(SystemTime::now().into()),
+                ..Default::default()
+            });
+        }
+
+        let (tx, rx) = mpsc::channel(WATCH_BUFFER_SIZE);
+        tokio::spawn(async move {
+            for event in current {
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+
+            let mut deadline = tokio::time::Instant::now() + timeout;
+            while !watched.is_empty() {
+                let event = tokio::select! {
+                    _ = tx.closed() => return,
+                    _ = tokio::time::sleep_until(deadline) => {
+                        grpc_debug!("Watch timed out.");
+                        let status = Status::deadline_exceeded(format!(
+                            "No events for {} seconds, watch again.",
+                            timeout.as_secs()
+                        ));
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                    event = events.recv() => event,
+                };
+
+                let event = match event {
+                    Ok(event) if watched.contains(&event.flight_plan_id) => event,
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        grpc_warn!("Watcher missed {} flight plan events.", missed);
+                        let status =
+                            Status::data_loss(format!("Missed {missed} events, watch again."));
+                        let _ = tx.send(Err(status)).await;
+                        return;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+
+                deadline = tokio::time::Instant::now() + timeout;
+                if event.state.is_final() {
+                    watched.remove(&event.flight_plan_id);
+                }
+
+                if tx.send(Ok(to_flight_plan_event(event))).await.is_err() {
+                    return;
+                }
+            }
+
+            grpc_debug!("All watched flight plans are final.");
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+}
+
+#[cfg(not(feature = "stub_server"))]
+impl ServerImpl {
+    /// Publishes the flight plan to the AMQP channel, if connected
+    async fn publish_flight_plan(&self, flight_plan: &region::FlightPlan) {
+        let Some(mq_channel) = &self.mq_channel else {
+            return;
+        };
+
+        let Ok(payload) = serde_json::to_vec(flight_plan) else {
+            grpc_error!("Could not serialize flight plan.");
+            return;
+        };
+
+        let result = mq_channel
+            .basic_publish(
+                crate::amqp::EXCHANGE_NAME_FLIGHTPLAN,
+                crate::amqp::QUEUE_NAME_CARGO,
+                lapin::options::BasicPublishOptions::default(),
+                &payload,
+                lapin::BasicProperties::default(),
+            )
+            .await;
+
+        match result {
+            Ok(_) => grpc_info!("Telemetry pushed to RabbitMQ."),
+            Err(e) => {
+                grpc_error!("Telemetry push to RabbitMQ failed: {e}")
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "stub_server"))]
+impl ServerImpl {
+    /// Forwards an amendment to the regional authorities
+    ///
+    /// Regions on the amended route receive the amendment. Once all of them
+    ///  accepted it, regions the amended route no longer passes through
+    ///  receive a cancellation of the previous version. If the amendment is
+    ///  refused, the regions that accepted their part are given back the
+    ///  previous version.
+    async fn forward_amendment(
+        &self,
+        previous: &region::FlightPlan,
+        amended: &region::FlightPlan,
+    ) -> Vec<RegionOutcome> {
+        let (regions, dropped) = self.amendment_regions(previous, amended);
+        let mut outcomes = vec![];
+        for region in &regions {
+            let airspace = self.store.snapshot(region.get_region());
+            let result = region.amend_flight_plan(amended, &airspace).await;
+            outcomes.push((region.get_region().to_string(), result));
+        }
+
+        if outcomes_accepted(&outcomes) {
+            for region in &dropped {
+                let result = region.cancel_flight_plan(previous).await;
+                outcomes.push((region.get_region().to_string(), result));
+            }
+        }
+
+        if outcomes_accepted(&outcomes) {
+            return outcomes;
+        }
+
+        let accepted = outcomes
+            .iter()
+            .filter(|(_, result)| matches!(result, Ok(decision) if decision.accepted))
+            .map(|(code, _)| code.as_str())
+            .collect::<Vec<_>>();
+        let previous_regions = self.route_regions(previous);
+        for region in regions
+            .iter()
+            .chain(dropped.iter())
+            .filter(|region| accepted.contains(&region.get_region()))
+        {
+            let code = region.get_region();
+            let airspace = self.store.snapshot(code);
+            let was_on_route = previous_regions.iter().any(|r| r.get_region() == code);
+            let is_on_route = regions.iter().any(|r| r.get_region() == code);
+            let result = match (was_on_route, is_on_route) {
+                (true, true) => region.amend_flight_plan(previous, &airspace).await,
+                (false, _) => region.cancel_flight_plan(amended).await,
+                (true, false) => region.submit_flight_plan(previous, &airspace).await,
+            };
+
+            match result {
+                Ok(decision) if decision.accepted => {
+                    grpc_debug!(
+                        "[{}] Restored flight plan '{}'.",
+                        code,
+                        previous.flight_plan_id
+                    )
+                }
+                _ => grpc_warn!(
+                    "[{}] Could not restore flight plan '{}' after a refused amendment: {:?}",
+                    code,
+                    previous.flight_plan_id,
+                    result
+                ),
+            }
+        }
+
+        outcomes
+    }
+}
+
+/// Maps the lifecycle state of a flight plan to a [`FlightPlanStatus`]
+fn to_flight_plan_status(state: FlightPlanState) -> FlightPlanStatus {
+    match state {
+        FlightPlanState::Draft => FlightPlanStatus::Draft,
+        FlightPlanState::Submitted => FlightPlanStatus::Submitted,
+        FlightPlanState::Accepted => FlightPlanStatus::Accepted,
+        FlightPlanState::Rejected => FlightPlanStatus::Rejected,
+        FlightPlanState::Released => FlightPlanStatus::Released,
+        FlightPlanState::Activated => FlightPlanStatus::Activated,
+        FlightPlanState::Closed => FlightPlanStatus::Closed,
+        FlightPlanState::Cancelled => FlightPlanStatus::Cancelled,
+        FlightPlanState::Revoked => FlightPlanStatus::Revoked,
+    }
+}
+
+/// Combines the decisions of the regional authorities on a cancellation
+///
+/// Flight plans outside of all served regions were never forwarded to an
+///  authority, so they are cancelled right away.
+fn combine_cancellations(
+    decisions: &[RegionDecision],
+    violations: &[Violation],
+) -> (bool, Option<String>) {
+    match decisions.is_empty() {
+        true => (true, None),
+        false => combine_decisions(decisions, violations),
+    }
+}
+
+/// Maps a lifecycle change refused after the regional authorities decided
+///  to a gRPC status, telling the caller their decision was not recorded
+fn decision_status(e: LifecycleError, accepted: bool) -> Status {
+    let decision = match accepted {
+        true => "accepted",
+        false => "refused",
+    };
+
+    grpc_error!(
+        "The regional authorities {} the request, but it was not recorded: {}",
+        decision,
+        e
+    );
+    Status::aborted(format!(
+        "{e} The regional authorities {decision} the request, but it was not recorded."
+    ))
+}
+
+/// Maps a refused lifecycle change to a gRPC status
+fn lifecycle_status(e: LifecycleError) -> Status {
+    match e {
+        LifecycleError::UnknownFlightPlan(_) => Status::not_found(e.to_string()),
+        LifecycleError::IllegalTransition { .. } | LifecycleError::NotAmendable { .. } => {
+            Status::failed_precondition(e.to_string())
+        }
+    }
+}
+
+/// Rule code reported when a regional authority could not be consulted
+const RULE_AUTHORITY_FAILURE: &str = "AUTHORITY_FAILURE";
+
+/// Maps a rule violation reported by a regional authority to a [`Violation`]
+fn to_violation(region: &str, violation: region::Violation) -> Violation {
+    let severity = match violation.severity {
+        region::Severity::Error => Severity::Error,
+        region::Severity::Warning => Severity::Warning,
+        region::Severity::Info => Severity::Info,
+    };
+
+    Violation {
+        region: region.to_string(),
+        rule_code: violation.rule_code,
+        severity: severity as i32,
+        feature_id: violation.feature_id,
+        segment_index: violation
+            .segment_index
+            .and_then(|index| u32::try_from(index).ok()),
+        message: violation.message,
+    }
+}
+
+/// Maps the outcome of a regional authority request to a [`RegionDecision`]
+///  and the violations reported by that authority
+///
+/// A failed request counts as a rejection by that authority.
+fn to_region_decision(
+    region: &str,
+    result: Result<Decision, RegionError>,
+) -> (RegionDecision, Vec<Violation>) {
+    match result {
+        Ok(decision) => (
+            RegionDecision {
+                region: region.to_string(),
+                accepted: decision.accepted,
+                result: decision.result,
+            },
+            decision
+                .violations
+                .into_iter()
+                .map(|violation| to_violation(region, violation))
+                .collect(),
+        ),
+        Err(e) => {
+            grpc_error!("[{}] {}", region, e);
+            (
+                RegionDecision {
+                    region: region.to_string(),
+                    accepted: false,
+                    result: Some(e.to_string()),
+                },
+                vec![Violation {
+                    region: region.to_string(),
+                    rule_code: RULE_AUTHORITY_FAILURE.to_string(),
+                    severity: Severity::Error as i32,
+                    feature_id: None,
+                    segment_index: None,
+                    message: e.to_string(),
+                }],
+            )
+        }
+    }
+}
+
+/// Maps the outcomes of several regional authority requests to their
+///  [`RegionDecision`]s and the violations reported by those authorities
+fn to_region_decisions(outcomes: &[RegionOutcome]) -> (Vec<RegionDecision>, Vec<Violation>) {
+    let mut decisions = vec![];
+    let mut violations = vec![];
+    for (region, result) in outcomes {
+        let (decision, region_violations) = to_region_decision(region, result.clone());
+        decisions.push(decision);
+        violations.extend(region_violations);
+    }
+
+    (decisions, violations)
+}
+
+/// Maps a lifecycle event to a [`FlightPlanEvent`]
+fn to_flight_plan_event(event: LifecycleEvent) -> FlightPlanEvent {
+    let (decisions, violations) = to_region_decisions(&event.outcomes);
+    let result = match decisions.is_empty() {
+        true => None,
+        false => combine_decisions(&decisions, &violations).1,
+    };
+
+    FlightPlanEvent {
+        flight_plan_id: event.flight_plan_id,
+        status: to_flight_plan_status(event.state) as i32,
+        previous_status: Some(to_flight_plan_status(event.previous) as i32),
+        time: Some(SystemTime::from(event.timestamp).into()),
+        result,
+        decisions,
+        violations,
+    }
+}
+
+/// Combines the decisions of several regional authorities
+///
+/// The combined request is accepted only if at least one authority was
+///  consulted, all of them accepted it and none of them reported an
+///  `ERROR` violation. Warnings and informational violations never lead to
+///  a rejection.
+fn combine_decisions(
+    decisions: &[RegionDecision],
+    violations: &[Violation],
+) -> (bool, Option<String>) {
+    if decisions.is_empty() {
+        return (
+            false,
+            Some("Route does not pass through any served region.".to_string()),
+        );
+    }
+
+    let accepted = decisions.iter().all(|decision| decision.accepted)
+        && violations
+            .iter()
+            .all(|violation| violation.severity != Severity::Error as i32);
+    let results = decisions
+        .iter()
+        .filter_map(|decision| {
+            decision
+                .result
+                .as_ref()
+                .map(|result| format!("[{}] {}", decision.region, result))
+        })
+        .collect::<Vec<_>>();
+
+    match results.is_empty() {
+        true => (accepted, None),
+        false => (accepted, Some(results.join("; "))),
+    }
 }
 
 /// Results of updating restrictions
@@ -67,7 +624,7 @@ pub enum UpdateWaypointsError {
 impl fmt::Debug for ServerImpl {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ServerImpl")
-            .field("region", &"RegionInterface (not printable)")
+            .field("regions", &self.region_codes())
             .finish()
     }
 }
@@ -80,7 +637,7 @@ impl RpcService for ServerImpl {
         &self,
         request: Request<ReadyRequest>,
     ) -> Result<Response<ReadyResponse>, Status> {
-        let region = self.region.get_region();
+        let region = self.region_codes();
         grpc_info!("[{}] compliance server.", region);
         grpc_debug!("[{}] [{:?}].", region, request);
         let response = ReadyResponse { ready: true };
@@ -91,48 +648,209 @@ impl RpcService for ServerImpl {
         &self,
         request: Request<FlightPlanRequest>,
     ) -> Result<Response<FlightPlanResponse>, Status> {
-        let region = self.region.get_region();
+        let region = self.region_codes();
         grpc_info!("[{}] compliance server.", region);
         grpc_debug!("[{}] [{:?}].", region, request);
         let request = request.into_inner();
-        let response = self.region.submit_flight_plan(request.clone())?;
+        let _lock = self.flight_plans.lock(&request.flight_plan_id).await;
+        let flight_plan = to_flight_plan(&request.flight_plan_id, request.flight_plan)?;
+        let _submission = self
+            .flight_plans
+            .submit(flight_plan.clone())
+            .map_err(lifecycle_status)?;
+
+        let mut outcomes = vec![];
+        for region in self.route_regions(&flight_plan) {
+            let airspace = self.store.snapshot(region.get_region());
+            let result = region.submit_flight_plan(&flight_plan, &airspace).await;
+            outcomes.push((region.get_region().to_string(), result));
+        }
+        let (decisions, violations) = to_region_decisions(&outcomes);
+
+        let (submitted, result) = combine_decisions(&decisions, &violations);
+        let state = match submitted {
+            true => FlightPlanState::Accepted,
+            false => FlightPlanState::Rejected,
+        };
+        self.flight_plans
+            .decide(&flight_plan.flight_plan_id, state, outcomes)
+            .map_err(|e| decision_status(e, submitted))?;
+        let response = Response::new(FlightPlanResponse {
+            flight_plan_id: request.flight_plan_id.clone(),
+            submitted,
+            result,
+            decisions,
+            violations,
+        });
 
         // send flight plan to AMQP
-        if let Some(mq_channel) = &self.mq_channel {
-            let Ok(payload) = serde_json::to_vec(&request) else {
-                grpc_error!("Could not serialize flight plan.");
-                return Ok(response);
-            };
+        self.publish_flight_plan(&flight_plan).await;
+        Ok(response)
+    }
 
-            let result = mq_channel
-                .basic_publish(
-                    crate::amqp::EXCHANGE_NAME_FLIGHTPLAN,
-                    crate::amqp::QUEUE_NAME_CARGO,
-                    lapin::options::BasicPublishOptions::default(),
-                    &payload,
-                    lapin::BasicProperties::default(),
+    async fn request_flight_release(
+        &self,
+        request: Request<FlightReleaseRequest>,
+    ) -> Result<Response<FlightReleaseResponse>, Status> {
+        let region = self.region_codes();
+        grpc_info!("[{}] compliance server.", region);
+        grpc_debug!("[{}] [{:?}].", region, request);
+        let request = request.into_inner();
+        let _lock = self.flight_plans.lock(&request.flight_plan_id).await;
+        let flight_plan = self.release_candidate(&request.flight_plan_id, request.flight_plan)?;
+
+        let mut outcomes = vec![];
+        for region in self.route_regions(&flight_plan) {
+            let airspace = self.store.snapshot(region.get_region());
+            let result = region.request_flight_release(&flight_plan, &airspace).await;
+            outcomes.push((region.get_region().to_string(), result));
+        }
+        let (decisions, violations) = to_region_decisions(&outcomes);
+
+        let (released, result) = combine_decisions(&decisions, &violations);
+
+        // A denied release leaves the flight plan accepted, it may be requested again
+        match released {
+            true => self
+                .flight_plans
+                .decide(
+                    &flight_plan.flight_plan_id,
+                    FlightPlanState::Released,
+                    outcomes,
                 )
-                .await;
+                .map(|_| ()),
+            false => self
+                .flight_plans
+                .report(&flight_plan.flight_plan_id, outcomes),
+        }
+        .map_err(|e| decision_status(e, released))?;
+        Ok(Response::new(FlightReleaseResponse {
+            flight_plan_id: request.flight_plan_id,
+            released,
+            result,
+            decisions,
+            violations,
+        }))
+    }
 
-            match result {
-                Ok(_) => grpc_info!("Telemetry pushed to RabbitMQ."),
-                Err(e) => {
-                    grpc_error!("Telemetry push to RabbitMQ failed: {e}")
-                }
-            }
+    async fn cancel_flight_plan(
+        &self,
+        request: Request<FlightCancellationRequest>,
+    ) -> Result<Response<FlightCancellationResponse>, Status> {
+        let region = self.region_codes();
+        grpc_info!("[{}] compliance server.", region);
+        grpc_debug!("[{}] [{:?}].", region, request);
+        let request = request.into_inner();
+        let _lock = self.flight_plans.lock(&request.flight_plan_id).await;
+        let record =
+            self.record_for_transition(&request.flight_plan_id, FlightPlanState::Cancelled)?;
+
+        let mut outcomes = vec![];
+        for region in self.route_regions(&record.flight_plan) {
+            let result = region.cancel_flight_plan(&record.flight_plan).await;
+            outcomes.push((region.get_region().to_string(), result));
+        }
+        let (decisions, violations) = to_region_decisions(&outcomes);
+
+        let (cancelled, result) = combine_cancellations(&decisions, &violations);
+
+        // A refused cancellation leaves the flight plan unchanged, it may be requested again
+        match cancelled {
+            true => self
+                .flight_plans
+                .decide(
+                    &request.flight_plan_id,
+                    FlightPlanState::Cancelled,
+                    outcomes,
+                )
+                .map(|_| ()),
+            false => self.flight_plans.report(&request.flight_plan_id, outcomes),
+        }
+        .map_err(|e| decision_status(e, cancelled))?;
+        Ok(Response::new(FlightCancellationResponse {
+            flight_plan_id: request.flight_plan_id,
+            cancelled,
+            result,
+            decisions,
+            violations,
+        }))
+    }
+
+    async fn amend_flight_plan(
+        &self,
+        request: Request<FlightAmendmentRequest>,
+    ) -> Result<Response<FlightAmendmentResponse>, Status> {
+        let region = self.region_codes();
+        grpc_info!("[{}] compliance server.", region);
+        grpc_debug!("[{}] [{:?}].", region, request);
+        let request = request.into_inner();
+        let _lock = self.flight_plans.lock(&request.flight_plan_id).await;
+        let flight_plan = to_flight_plan(&request.flight_plan_id, request.flight_plan)?;
+        let previous = self
+            .flight_plans
+            .amendable(&request.flight_plan_id)
+            .map_err(lifecycle_status)?
+            .flight_plan;
+
+        let outcomes = self.forward_amendment(&previous, &flight_plan).await;
+        let (decisions, violations) = to_region_decisions(&outcomes);
+
+        let (amended, result) = combine_decisions(&decisions, &violations);
+
+        // A refused amendment leaves the previous version and its state unchanged
+        match amended {
+            true => self
+                .flight_plans
+                .amend(flight_plan.clone(), outcomes)
+                .map(|_| ()),
+            false => self.flight_plans.report(&request.flight_plan_id, outcomes),
+        }
+        .map_err(|e| decision_status(e, amended))?;
+        let response = Response::new(FlightAmendmentResponse {
+            flight_plan_id: request.flight_plan_id,
+            amended,
+            result,
+            decisions,
+            violations,
+        });
+
+        // send the amended flight plan to AMQP
+        if amended {
+            self.publish_flight_plan(&flight_plan).await;
         }
 
         Ok(response)
     }
 
-    async fn request_flight_release(
+    async fn get_flight_plan_status(
         &self,
-        request: Request<FlightReleaseRequest>,
-    ) -> Result<Response<FlightReleaseResponse>, Status> {
-        let region = self.region.get_region();
+        request: Request<FlightPlanStatusRequest>,
+    ) -> Result<Response<FlightPlanStatusResponse>, Status> {
+        let region = self.region_codes();
+        grpc_info!("[{}] compliance server.", region);
+        grpc_debug!("[{}] [{:?}].", region, request);
+        let request = request.into_inner();
+        let record = self.record(&request.flight_plan_id)?;
+        Ok(Response::new(FlightPlanStatusResponse {
+            flight_plan_id: request.flight_plan_id,
+            status: to_flight_plan_status(record.state) as i32,
+        }))
+    }
+
+    type watchFlightPlanStream = ReceiverStream<Result<FlightPlanEvent, Status>>;
+
+    async fn watch_flight_plan(
+        &self,
+        request: Request<WatchFlightPlanRequest>,
+    ) -> Result<Response<Self::watchFlightPlanStream>, Status> {
+        let region = self.region_codes();
         grpc_info!("[{}] compliance server.", region);
         grpc_debug!("[{}] [{:?}].", region, request);
-        self.region.request_flight_release(request)
+        let request = request.into_inner();
+        Ok(Response::new(self.watch(
+            request.flight_plan_ids,
+            std::time::Duration::from_secs(WATCH_TIMEOUT_SECONDS),
+        )?))
     }
 }
 
@@ -167,94 +885,287 @@ pub async fn update_waypoints(
     Ok(())
 }
 
-/// Periodically pulls down waypoints from the regional interface and
-///  pushes them to the GIS microservice
+/// Periodically pulls down waypoints from the regional interface, stores
+///  them and pushes them to the GIS microservice
 #[cfg(not(tarpaulin_include))]
 // no_coverage: (Rnever) not unit testable, only integration tests
-pub async fn waypoints_loop(config: Config, region: Box<dyn RegionInterface + Send + Sync>) {
+pub async fn waypoints_loop(
+    config: Config,
+    region: Arc<dyn RegionInterface + Send + Sync>,
+    store: Arc<AirspaceStore>,
+) {
     let host = config.gis_host_grpc;
     let port = config.gis_port_grpc;
 
     grpc_debug!(
-        "Starting loop with interval: {} seconds.",
+        "[{}] Starting loop with interval: {} seconds.",
+        region.get_region(),
         config.interval_seconds_refresh_waypoints
     );
 
     let interval_duration =
         tokio::time::Duration::from_secs(config.interval_seconds_refresh_waypoints as u64);
     let mut interval = tokio::time::interval(interval_duration);
-    let mut cache: HashMap<String, gis::Coordinates> = HashMap::new();
     loop {
         // Pull down waypoints from regional interface
+        let mut cache = (*store.snapshot(region.get_region()).waypoints).clone();
         region.acquire_waypoints(&mut cache).await;
+        store.set_waypoints(region.get_region(), cache.clone());
         let _ = update_waypoints(host.clone(), port, &cache).await;
         interval.tick().await;
     }
 }
 
-/// Sends the restrictions to the GIS microservice
+/// Converts a restriction to a GIS zone
+///
+/// The GIS microservice only knows restricted altitude bands, so a ceiling
+///  restriction becomes a zone covering the airspace above the ceiling.
+///  Circles and arcs become polygons within the maximum chord error, and
+///  polygons that fail validation are returned as errors.
+///
+/// Altitude limits are converted to heights above the ellipsoid. Where a
+///  limit varies over the zone, such as a height above uneven terrain, the
+///  band is widened to the lowest floor and the highest top, or lowered to
+///  the lowest ceiling.
+fn to_gis_zone(
+    label: &str,
+    details: &RestrictionDetails,
+    max_chord_error_meters: f64,
+    vertical: &VerticalModel,
+) -> Result<gis::Zone, GeometryError> {
+    let vertices = normalize_ring(&details.shape.to_polygon(max_chord_error_meters))?;
+    let (lower, _) = vertical.height_range_meters(&details.lower, &vertices);
+    let (upper_min, upper_max) = vertical.height_range_meters(&details.upper, &vertices);
+    let (altitude_meters_min, altitude_meters_max) = match details.kind {
+        RestrictionKind::NoFly | RestrictionKind::Authorisation | RestrictionKind::Conditional => {
+            (lower as f32, upper_max as f32)
+        }
+        RestrictionKind::Ceiling => (upper_min as f32, UNLIMITED_ALTITUDE_METERS as f32),
+    };
+
+    Ok(gis::Zone {
+        identifier: label.to_string(),
+        zone_type: details.zone_type as i32,
+        altitude_meters_max,
+        altitude_meters_min,
+        vertices,
+        time_start: details.timestamp_start.map(|t| t.into()),
+        time_end: details.timestamp_end.map(|t| t.into()),
+    })
+}
+
+/// Expands a scheduled restriction to one restriction per activation
+///  window between now and the horizon
+///
+/// Windows are labelled by their start time, so that a window keeps its
+///  label while it is active. Restrictions without a schedule are returned
+///  as they are.
+fn expand_schedule(
+    label: &str,
+    details: &RestrictionDetails,
+    now: DateTime<Utc>,
+    horizon: DateTime<Utc>,
+) -> Vec<(String, RestrictionDetails)> {
+    let Some(schedule) = &details.schedule else {
+        return vec![(label.to_string(), details.clone())];
+    };
+
+    let from = details.timestamp_start.map_or(now, |start| start.max(now));
+    let to = details
+        .timestamp_end
+        .map_or(horizon, |end| end.min(horizon));
+    schedule
+        .windows(&details.shape.center(), from, to)
+        .into_iter()
+        .map(|(start, end)| {
+            let start = details.timestamp_start.map_or(start, |s| s.max(start));
+            let end = details.timestamp_end.map_or(end, |e| e.min(end));
+            let window = RestrictionDetails {
+                timestamp_start: Some(start),
+                timestamp_end: Some(end),
+                schedule: None,
+                ..details.clone()
+            };
+
+            (
+                format!("{}-{}", label, start.format("%Y%m%dT%H%MZ")),
+                window,
+            )
+        })
+        .collect()
+}
+
+/// Sends the changes of the restrictions to the GIS microservice
+///
+/// Scheduled restrictions are sent as their activation windows within the
+///  schedule horizon. Restrictions with an invalid polygon are quarantined:
+///  they are logged and left out of the update. Only zones that changed
+///  since the last accepted update are sent, along with the removed zones,
+///  unless a full reconciliation is due.
 pub async fn update_restrictions(
     host: String,
     port: u16,
     restrictions: &HashMap<String, RestrictionDetails>,
+    max_chord_error_meters: f64,
+    schedule_horizon_hours: u32,
+    vertical: &VerticalModel,
+    sync: &mut ZoneSync,
 ) -> Result<(), UpdateRestrictionsError> {
+    let now = Utc::now();
+    let horizon = now + Duration::hours(i64::from(schedule_horizon_hours));
     let zones = restrictions
         .iter()
-        .map(|(label, details)| gis::Zone {
-            identifier: label.clone(),
-            zone_type: details.zone_type as i32,
-            altitude_meters_max: details.altitude_meters_max,
-            altitude_meters_min: details.altitude_meters_min,
-            vertices: details
-                .vertices
-                .iter()
-                .map(|v| gis::Coordinates {
-                    latitude: v.latitude,
-                    longitude: v.longitude,
-                })
-                .collect(),
-            time_start: details.timestamp_start.map(|t| t.into()),
-            time_end: details.timestamp_end.map(|t| t.into()),
+        .flat_map(|(label, details)| expand_schedule(label, details, now, horizon))
+        .filter_map(|(label, details)| {
+            match to_gis_zone(&label, &details, max_chord_error_meters, vertical) {
+                Ok(zone) => Some((label, zone)),
+                Err(e) => {
+                    grpc_warn!("Quarantined restriction {}: {}", label, e);
+                    None
+                }
+            }
         })
-        .collect::<Vec<_>>();
+        .collect::<HashMap<_, _>>();
 
-    if zones.is_empty() {
+    if zones.is_empty() && sync.is_empty() {
         grpc_warn!("No restrictions to update.");
         return Err(UpdateRestrictionsError::NoRestrictions);
     }
 
+    let update = sync.diff(&zones, now);
+    if update.is_empty() {
+        grpc_debug!("No changes to {} restrictions.", zones.len());
+        return Ok(());
+    }
+
     let response = GisClient::new_client(&host, port, "gis")
-        .update_zones(gis::UpdateZonesRequest { zones })
+        .update_zones(gis::UpdateZonesRequest {
+            zones: sync.request_zones(&update, now),
+        })
         .await
         .map_err(|e| {
             grpc_error!("{:?}", e);
             UpdateRestrictionsError::RequestFailure
         })?;
 
-    grpc_info!("{:?}", response);
+    grpc_info!(
+        "Sent {} {} and {} removed restrictions: {:?}",
+        update.zones.len(),
+        match update.full {
+            true => "reconciled",
+            false => "changed",
+        },
+        update.removed.len(),
+        response
+    );
+    sync.accept(update, now);
+    if let Err(e) = sync.save().await {
+        grpc_warn!("Could not keep the restrictions sent: {}", e);
+    }
+
     Ok(())
 }
 
-/// Periodically pulls down restrictions from the regional interface and
-///  pushes them to the GIS microservice
+/// Restores the zones last sent to the GIS microservice by their owner, a
+///  region or the ad-hoc zones, if a directory is configured to keep them
+async fn zone_sync(config: &Config, owner: &str) -> ZoneSync {
+    match config.zone_sync_directory.as_deref() {
+        Some(directory) if !directory.is_empty() => {
+            ZoneSync::restore(
+                config.interval_seconds_reconcile_zones,
+                Path::new(directory).join(format!("{}.zones", owner)),
+            )
+            .await
+        }
+        _ => ZoneSync::new(config.interval_seconds_reconcile_zones),
+    }
+}
+
+/// Periodically pulls down restrictions from the regional interface, stores
+///  them along with the ad-hoc zones overlapping the region and then pushes
+///  them to the GIS microservice
 #[cfg(not(tarpaulin_include))]
 // no_coverage: (Rnever) not unit testable, only integration tests
-pub async fn restrictions_loop(config: Config, region: Box<dyn RegionInterface + Send + Sync>) {
-    let host = config.gis_host_grpc;
+pub async fn restrictions_loop(
+    config: Config,
+    region: Arc<dyn RegionInterface + Send + Sync>,
+    store: Arc<AirspaceStore>,
+) {
+    let host = config.gis_host_grpc.clone();
     let port = config.gis_port_grpc;
-    let mut cache: HashMap<String, RestrictionDetails> = HashMap::new();
 
     grpc_info!(
-        "Starting loop with interval: {} seconds.",
+        "[{}] Starting loop with interval: {} seconds.",
+        region.get_region(),
         config.interval_seconds_refresh_zones
     );
 
     let interval_duration =
         tokio::time::Duration::from_secs(config.interval_seconds_refresh_zones as u64);
     let mut interval = tokio::time::interval(interval_duration);
+    let boundaries = region.get_boundaries();
+    let mut sync = zone_sync(&config, region.get_region()).await;
     loop {
+        let mut cache = (*store.snapshot(region.get_region()).restrictions).clone();
         region.acquire_restrictions(&mut cache).await;
-        let _ = update_restrictions(host.clone(), port, &cache).await;
+        normalize_restrictions(&mut cache, config.max_chord_error_meters);
+
+        // local checks use the restrictions without waiting for svc-gis
+        let mut stored = cache.clone();
+        stored.extend(kml::overlapping(&store.adhoc_restrictions(), &boundaries));
+        store.set_restrictions(region.get_region(), stored);
+        let _ = update_restrictions(
+            host.clone(),
+            port,
+            &cache,
+            config.max_chord_error_meters,
+            config.schedule_horizon_hours,
+            &store.vertical(),
+            &mut sync,
+        )
+        .await;
+        interval.tick().await;
+    }
+}
+
+/// Periodically pulls down the ad-hoc KML zones overlapping any served
+///  region, stores them and pushes them to the GIS microservice
+///
+/// The zones are loaded and pushed once for all regions, so that a zone
+///  overlapping several regions has a single owner in svc-gis.
+#[cfg(not(tarpaulin_include))]
+// no_coverage: (Rnever) not unit testable, only integration tests
+pub async fn kml_loop(
+    config: Config,
+    boundaries: Vec<Vec<gis::Coordinates>>,
+    store: Arc<AirspaceStore>,
+    source: Source,
+) {
+    grpc_info!(
+        "[{}] Starting loop with interval: {} seconds.",
+        KML_ZONE_OWNER,
+        config.interval_seconds_refresh_zones
+    );
+
+    let interval_duration =
+        tokio::time::Duration::from_secs(config.interval_seconds_refresh_zones as u64);
+    let mut interval = tokio::time::interval(interval_duration);
+    let mut zones = (*store.adhoc_restrictions()).clone();
+    let mut sync = zone_sync(&config, KML_ZONE_OWNER).await;
+    loop {
+        kml::acquire_restrictions(&source, &boundaries, &mut zones).await;
+        normalize_restrictions(&mut zones, config.max_chord_error_meters);
+        store.set_adhoc_restrictions(zones.clone());
+        let _ = update_restrictions(
+            config.gis_host_grpc.clone(),
+            config.gis_port_grpc,
+            &zones,
+            config.max_chord_error_meters,
+            config.schedule_horizon_hours,
+            &store.vertical(),
+            &mut sync,
+        )
+        .await;
         interval.tick().await;
     }
 }
@@ -283,23 +1194,62 @@ pub async fn grpc_server(
         grpc_error!("Failed to parse gRPC address: {}", e);
     })?;
 
+    let regions = from_region_codes(&config.region_code, &config).map_err(|e| {
+        grpc_error!("Could not select regions: {}", e);
+    })?;
+    let kml_source = match &config.kml_source {
+        Some(source) => Some(source.parse::<Source>().map_err(|e| {
+            grpc_error!("Invalid KML source: {}", e);
+        })?),
+        None => None,
+    };
+    let vertical_source = |source: &Option<String>| match source {
+        Some(source) => source.parse::<Source>().map(Some).map_err(|e| {
+            grpc_error!("Invalid vertical model source: {}", e);
+        }),
+        None => Ok(None),
+    };
+    let terrain_source = vertical_source(&config.terrain_source)?;
+    let geoid_source = vertical_source(&config.geoid_source)?;
+    let vertical = VerticalModel::load(terrain_source.as_ref(), geoid_source.as_ref())
+        .await
+        .map_err(|e| {
+            grpc_error!("Could not load the terrain and geoid models: {}", e);
+        })?;
+    let store = Arc::new(AirspaceStore::new(vertical));
+
+    // The ad-hoc zones are refreshed once for all regions
+    if let Some(source) = kml_source {
+        let boundaries = regions
+            .iter()
+            .flat_map(|region| region.get_boundaries())
+            .collect();
+        tokio::spawn(kml_loop(config.clone(), boundaries, store.clone(), source));
+    }
+
+    // Each region refreshes its own restrictions and waypoints into the shared store
+    for region in &regions {
+        tokio::spawn(restrictions_loop(
+            config.clone(),
+            region.clone(),
+            store.clone(),
+        ));
+        tokio::spawn(waypoints_loop(
+            config.clone(),
+            region.clone(),
+            store.clone(),
+        ));
+    }
+
     let imp = ServerImpl {
         mq_channel: Some(init_mq(config.clone()).await.map_err(|e| {
             grpc_error!("Could not create channel to amqp server: {}", e);
         })?),
-        region: Box::<crate::region::RegionImpl>::default(),
+        regions,
+        store,
+        flight_plans: Arc::default(),
     };
 
-    tokio::spawn(restrictions_loop(
-        config.clone(),
-        Box::<crate::region::RegionImpl>::default(),
-    ));
-
-    tokio::spawn(waypoints_loop(
-        config.clone(),
-        Box::<crate::region::RegionImpl>::default(),
-    ));
-
     let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
     health_reporter
         .set_serving::<RpcServiceServer<ServerImpl>>()
@@ -308,7 +1258,7 @@ pub async fn grpc_server(
     //start server
     grpc_info!(
         "[{}] Starting gRPC services on: {}",
-        imp.region.get_region(),
+        imp.region_codes(),
         full_grpc_addr
     );
 
@@ -332,7 +1282,7 @@ impl RpcService for ServerImpl {
         &self,
         request: Request<ReadyRequest>,
     ) -> Result<Response<ReadyResponse>, Status> {
-        let region = self.region.get_region();
+        let region = self.region_codes();
         grpc_warn!("(MOCK)[{}] compliance server.", region);
         grpc_debug!("(MOCK)[{}] [{:?}].", region, request);
         let response = ReadyResponse { ready: true };
@@ -343,14 +1293,39 @@ impl RpcService for ServerImpl {
         &self,
         request: Request<FlightPlanRequest>,
     ) -> Result<Response<FlightPlanResponse>, Status> {
-        let region = self.region.get_region();
+        let region = self.region_codes();
         grpc_warn!("(MOCK)[{}] compliance server.", region);
         grpc_debug!("(MOCK)[{}] [{:?}].", region, request);
         let request = request.into_inner();
+        let _lock = self.flight_plans.lock(&request.flight_plan_id).await;
+        let flight_plan = to_flight_plan(&request.flight_plan_id, request.flight_plan)?;
+        let _submission = self
+            .flight_plans
+            .submit(flight_plan.clone())
+            .map_err(lifecycle_status)?;
+        // Only the local restriction checks, no regional authorities are contacted
+        let mut outcomes = vec![];
+        for region in self.route_regions(&flight_plan) {
+            let airspace = self.store.snapshot(region.get_region());
+            let result = Ok(check_flight_plan(&flight_plan, &airspace));
+            outcomes.push((region.get_region().to_string(), result));
+        }
+        let (decisions, violations) = to_region_decisions(&outcomes);
+
+        let (submitted, result) = combine_decisions(&decisions, &violations);
+        let state = match submitted {
+            true => FlightPlanState::Accepted,
+            false => FlightPlanState::Rejected,
+        };
+        self.flight_plans
+            .decide(&flight_plan.flight_plan_id, state, outcomes)
+            .map_err(|e| decision_status(e, submitted))?;
         Ok(tonic::Response::new(FlightPlanResponse {
             flight_plan_id: request.flight_plan_id,
-            submitted: true,
-            result: None,
+            submitted,
+            result,
+            decisions,
+            violations,
         }))
     }
 
@@ -358,29 +1333,202 @@ impl RpcService for ServerImpl {
         &self,
         request: Request<FlightReleaseRequest>,
     ) -> Result<Response<FlightReleaseResponse>, Status> {
-        let region = self.region.get_region();
+        let region = self.region_codes();
         grpc_warn!("(MOCK)[{}] compliance server.", region);
         grpc_debug!("(MOCK)[{}] [{:?}].", region, request);
         let request = request.into_inner();
+        let _lock = self.flight_plans.lock(&request.flight_plan_id).await;
+        let flight_plan = self.release_candidate(&request.flight_plan_id, request.flight_plan)?;
+        // Only the local restriction checks, no regional authorities are contacted
+        let mut outcomes = vec![];
+        for region in self.route_regions(&flight_plan) {
+            let airspace = self.store.snapshot(region.get_region());
+            let result = Ok(check_flight_plan(&flight_plan, &airspace));
+            outcomes.push((region.get_region().to_string(), result));
+        }
+        let (decisions, violations) = to_region_decisions(&outcomes);
+
+        let (released, result) = combine_decisions(&decisions, &violations);
+
+        // A denied release leaves the flight plan accepted, it may be requested again
+        match released {
+            true => self
+                .flight_plans
+                .decide(
+                    &flight_plan.flight_plan_id,
+                    FlightPlanState::Released,
+                    outcomes,
+                )
+                .map(|_| ()),
+            false => self
+                .flight_plans
+                .report(&flight_plan.flight_plan_id, outcomes),
+        }
+        .map_err(|e| decision_status(e, released))?;
         Ok(tonic::Response::new(FlightReleaseResponse {
             flight_plan_id: request.flight_plan_id,
-            released: true,
-            result: None,
+            released,
+            result,
+            decisions,
+            violations,
         }))
     }
+
+    async fn cancel_flight_plan(
+        &self,
+        request: Request<FlightCancellationRequest>,
+    ) -> Result<Response<FlightCancellationResponse>, Status> {
+        let region = self.region_codes();
+        grpc_warn!("(MOCK)[{}] compliance server.", region);
+        grpc_debug!("(MOCK)[{}] [{:?}].", region, request);
+        let request = request.into_inner();
+        let _lock = self.flight_plans.lock(&request.flight_plan_id).await;
+        let record =
+            self.record_for_transition(&request.flight_plan_id, FlightPlanState::Cancelled)?;
+        // No regional authorities are contacted, all of them acknowledge
+        let outcomes = self
+            .route_regions(&record.flight_plan)
+            .into_iter()
+            .map(|region| {
+                let decision = Decision {
+                    accepted: true,
+                    result: None,
+                    violations: vec![],
+                };
+                (region.get_region().to_string(), Ok(decision))
+            })
+            .collect::<Vec<_>>();
+        let (decisions, violations) = to_region_decisions(&outcomes);
+
+        let (cancelled, result) = combine_cancellations(&decisions, &violations);
+        self.flight_plans
+            .decide(
+                &request.flight_plan_id,
+                FlightPlanState::Cancelled,
+                outcomes,
+            )
+            .map_err(|e| decision_status(e, cancelled))?;
+        Ok(tonic::Response::new(FlightCancellationResponse {
+            flight_plan_id: request.flight_plan_id,
+            cancelled,
+            result,
+            decisions,
+            violations,
+        }))
+    }
+
+    async fn amend_flight_plan(
+        &self,
+        request: Request<FlightAmendmentRequest>,
+    ) -> Result<Response<FlightAmendmentResponse>, Status> {
+        let region = self.region_codes();
+        grpc_warn!("(MOCK)[{}] compliance server.", region);
+        grpc_debug!("(MOCK)[{}] [{:?}].", region, request);
+        let request = request.into_inner();
+        let _lock = self.flight_plans.lock(&request.flight_plan_id).await;
+        let flight_plan = to_flight_plan(&request.flight_plan_id, request.flight_plan)?;
+        let previous = self
+            .flight_plans
+            .amendable(&request.flight_plan_id)
+            .map_err(lifecycle_status)?
+            .flight_plan;
+        // Only the local restriction checks, no regional authorities are contacted
+        let (regions, dropped) = self.amendment_regions(&previous, &flight_plan);
+        let mut outcomes = vec![];
+        for region in regions {
+            let airspace = self.store.snapshot(region.get_region());
+            let result = Ok(check_flight_plan(&flight_plan, &airspace));
+            outcomes.push((region.get_region().to_string(), result));
+        }
+
+        // Regions the amended route no longer passes through acknowledge
+        if outcomes_accepted(&outcomes) {
+            for region in dropped {
+                let decision = Decision {
+                    accepted: true,
+                    result: None,
+                    violations: vec![],
+                };
+                outcomes.push((region.get_region().to_string(), Ok(decision)));
+            }
+        }
+        let (decisions, violations) = to_region_decisions(&outcomes);
+
+        let (amended, result) = combine_decisions(&decisions, &violations);
+        match amended {
+            true => self
+                .flight_plans
+                .amend(flight_plan.clone(), outcomes)
+                .map(|_| ()),
+            false => self.flight_plans.report(&request.flight_plan_id, outcomes),
+        }
+        .map_err(|e| decision_status(e, amended))?;
+        Ok(tonic::Response::new(FlightAmendmentResponse {
+            flight_plan_id: request.flight_plan_id,
+            amended,
+            result,
+            decisions,
+            violations,
+        }))
+    }
+
+    async fn get_flight_plan_status(
+        &self,
+        request: Request<FlightPlanStatusRequest>,
+    ) -> Result<Response<FlightPlanStatusResponse>, Status> {
+        let region = self.region_codes();
+        grpc_warn!("(MOCK)[{}] compliance server.", region);
+        grpc_debug!("(MOCK)[{}] [{:?}].", region, request);
+        let request = request.into_inner();
+        let record = self.record(&request.flight_plan_id)?;
+        Ok(tonic::Response::new(FlightPlanStatusResponse {
+            flight_plan_id: request.flight_plan_id,
+            status: to_flight_plan_status(record.state) as i32,
+        }))
+    }
+
+    type watchFlightPlanStream = ReceiverStream<Result<FlightPlanEvent, Status>>;
+
+    async fn watch_flight_plan(
+        &self,
+        request: Request<WatchFlightPlanRequest>,
+    ) -> Result<Response<Self::watchFlightPlanStream>, Status> {
+        let region = self.region_codes();
+        grpc_warn!("(MOCK)[{}] compliance server.", region);
+        grpc_debug!("(MOCK)[{}] [{:?}].", region, request);
+        let request = request.into_inner();
+        Ok(tonic::Response::new(self.watch(
+            request.flight_plan_ids,
+            std::time::Duration::from_secs(WATCH_TIMEOUT_SECONDS),
+        )?))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::grpc_server::*;
     use super::*;
+    use crate::grpc::zone_sync::RECONCILE_INTERVAL_SECONDS;
+    use crate::region::schedule::Schedule;
+    use crate::region::shape::{Shape, MAX_CHORD_ERROR_METERS};
+    use crate::region::utils::AltitudeLimit;
+    use crate::region::vertical::Grid;
     use lib_common::time::Utc;
 
+    /// A server for the default region, whose airspace loaded without
+    ///  restrictions
     fn get_server_impl() -> ServerImpl {
-        let region = Box::<crate::region::RegionImpl>::default();
+        let regions = from_region_codes(region::DEFAULT_REGION_CODE, &Config::new()).unwrap();
+        let store = AirspaceStore::default();
+        for region in &regions {
+            store.set_restrictions(region.get_region(), HashMap::new());
+        }
+
         ServerImpl {
             mq_channel: None,
-            region,
+            regions,
+            store: Arc::new(store),
+            flight_plans: Arc::default(),
         }
     }
 
@@ -390,13 +1538,15 @@ mod tests {
         ut_info!("Start.");
 
         let imp = get_server_impl();
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "us")] {
-                assert_eq!(imp.region.get_region(), "us");
-            } else {
-                assert_eq!(imp.region.get_region(), "nl");
-            }
-        }
+        assert_eq!(imp.region_codes(), "nl");
+
+        let imp = ServerImpl {
+            mq_channel: None,
+            regions: from_region_codes("nl,us", &Config::new()).unwrap(),
+            store: Arc::default(),
+            flight_plans: Arc::default(),
+        };
+        assert_eq!(imp.region_codes(), "nl,us");
 
         ut_info!("Success.");
     }
@@ -423,15 +1573,118 @@ mod tests {
         let imp = get_server_impl();
         let result = imp
             .submit_flight_plan(Request::new(FlightPlanRequest {
-                flight_plan_id: "".to_string(),
-                data: "".to_string(),
+                flight_plan_id: "test".to_string(),
+                flight_plan: Some(crate::grpc::mock::flight_plan()),
             }))
             .await;
 
         assert!(result.is_ok());
         let result: FlightPlanResponse = result.unwrap().into_inner();
         println!("{:?}", result);
-        assert_eq!(result.submitted, true);
+        assert!(result.submitted);
+        assert_eq!(result.decisions.len(), 1);
+        assert_eq!(result.decisions[0].region, "nl");
+
+        let record = imp.flight_plans.get("test").unwrap();
+        assert_eq!(record.state, FlightPlanState::Accepted);
+
+        // an accepted flight plan cannot be submitted again
+        let error = imp
+            .submit_flight_plan(Request::new(FlightPlanRequest {
+                flight_plan_id: "test".to_string(),
+                flight_plan: Some(crate::grpc::mock::flight_plan()),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_grpc_submit_flight_plan_unloaded() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // the restrictions of the region were never stored
+        let imp = ServerImpl {
+            store: Arc::default(),
+            ..get_server_impl()
+        };
+        let result = imp
+            .submit_flight_plan(Request::new(FlightPlanRequest {
+                flight_plan_id: "test".to_string(),
+                flight_plan: Some(crate::grpc::mock::flight_plan()),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!result.submitted);
+        assert_eq!(result.violations.len(), 1);
+        assert_eq!(
+            result.violations[0].rule_code,
+            region::compliance::RULE_AIRSPACE_UNAVAILABLE
+        );
+        assert_eq!(
+            imp.flight_plans.get("test").unwrap().state,
+            FlightPlanState::Rejected
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_grpc_submit_flight_plan_violations() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // restriction around the departure of the mock flight plan
+        let imp = get_server_impl();
+        imp.store.set_restrictions(
+            "nl",
+            HashMap::from([(
+                "zone".to_string(),
+                RestrictionDetails {
+                    shape: Shape::Polygon(
+                        [(52.36, 4.90), (52.36, 4.93), (52.39, 4.93), (52.39, 4.90)]
+                            .into_iter()
+                            .map(|(latitude, longitude)| gis::Coordinates {
+                                latitude,
+                                longitude,
+                            })
+                            .collect(),
+                    ),
+                    timestamp_start: None,
+                    timestamp_end: None,
+                    schedule: None,
+                    lower: AltitudeLimit::SURFACE,
+                    upper: AltitudeLimit::msl(500.),
+                    zone_type: gis::ZoneType::Restriction,
+                    kind: RestrictionKind::NoFly,
+                },
+            )]),
+        );
+
+        let result = imp
+            .submit_flight_plan(Request::new(FlightPlanRequest {
+                flight_plan_id: "test".to_string(),
+                flight_plan: Some(crate::grpc::mock::flight_plan()),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        println!("{:?}", result);
+        assert!(!result.submitted);
+        assert_eq!(
+            imp.flight_plans.get("test").unwrap().state,
+            FlightPlanState::Rejected
+        );
+        assert_eq!(result.violations.len(), 1);
+        assert_eq!(result.violations[0].region, "nl");
+        assert_eq!(result.violations[0].severity, Severity::Error as i32);
+        assert_eq!(result.violations[0].feature_id, Some("zone".to_string()));
+        assert_eq!(result.violations[0].segment_index, Some(0));
 
         ut_info!("Success.");
     }
@@ -442,17 +1695,475 @@ mod tests {
         ut_info!("Start.");
 
         let imp = get_server_impl();
+        let flight_plan = crate::grpc::mock::flight_plan();
+        imp.submit_flight_plan(Request::new(FlightPlanRequest {
+            flight_plan_id: "test".to_string(),
+            flight_plan: Some(flight_plan.clone()),
+        }))
+        .await
+        .unwrap();
+
         let result = imp
             .request_flight_release(Request::new(FlightReleaseRequest {
-                flight_plan_id: "".to_string(),
-                data: "".to_string(),
+                flight_plan_id: "test".to_string(),
+                flight_plan: Some(flight_plan),
             }))
             .await;
 
         assert!(result.is_ok());
         let result: FlightReleaseResponse = result.unwrap().into_inner();
         println!("{:?}", result);
-        assert_eq!(result.released, true);
+        assert!(result.released);
+        assert_eq!(result.decisions.len(), 1);
+        assert_eq!(
+            imp.flight_plans.get("test").unwrap().state,
+            FlightPlanState::Released
+        );
+
+        // a released flight plan cannot be released again
+        let error = imp
+            .request_flight_release(Request::new(FlightReleaseRequest {
+                flight_plan_id: "test".to_string(),
+                flight_plan: None,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_grpc_request_flight_release_refused() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let imp = get_server_impl();
+        let request = |flight_plan: Option<FlightPlan>| {
+            Request::new(FlightReleaseRequest {
+                flight_plan_id: "test".to_string(),
+                flight_plan,
+            })
+        };
+
+        // never submitted
+        let error = imp.request_flight_release(request(None)).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::NotFound);
+
+        // not accepted yet
+        let _submission = imp
+            .flight_plans
+            .submit(region::FlightPlan {
+                flight_plan_id: "test".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let error = imp.request_flight_release(request(None)).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+        assert!(error.message().contains("submitted to released"));
+
+        // differs from the accepted version
+        imp.flight_plans
+            .transition("test", FlightPlanState::Accepted)
+            .unwrap();
+        let error = imp
+            .request_flight_release(request(Some(crate::grpc::mock::flight_plan())))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+        assert_eq!(
+            imp.flight_plans.get("test").unwrap().state,
+            FlightPlanState::Accepted
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_grpc_cancel_flight_plan() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let imp = get_server_impl();
+        let request = || {
+            Request::new(FlightCancellationRequest {
+                flight_plan_id: "test".to_string(),
+            })
+        };
+
+        let error = imp.cancel_flight_plan(request()).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::NotFound);
+
+        imp.submit_flight_plan(Request::new(FlightPlanRequest {
+            flight_plan_id: "test".to_string(),
+            flight_plan: Some(crate::grpc::mock::flight_plan()),
+        }))
+        .await
+        .unwrap();
+
+        let result = imp
+            .cancel_flight_plan(request())
+            .await
+            .unwrap()
+            .into_inner();
+        println!("{:?}", result);
+        assert!(result.cancelled);
+        assert_eq!(result.decisions.len(), 1);
+        assert_eq!(result.decisions[0].region, "nl");
+        assert_eq!(
+            imp.flight_plans.get("test").unwrap().state,
+            FlightPlanState::Cancelled
+        );
+
+        // cancelled flight plans are final
+        let error = imp.cancel_flight_plan(request()).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+
+        let error = imp
+            .request_flight_release(Request::new(FlightReleaseRequest {
+                flight_plan_id: "test".to_string(),
+                flight_plan: None,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_grpc_amend_flight_plan() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let imp = get_server_impl();
+        let request = |flight_plan: FlightPlan| {
+            Request::new(FlightAmendmentRequest {
+                flight_plan_id: "test".to_string(),
+                flight_plan: Some(flight_plan),
+            })
+        };
+
+        let error = imp
+            .amend_flight_plan(request(crate::grpc::mock::flight_plan()))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::NotFound);
+
+        imp.submit_flight_plan(Request::new(FlightPlanRequest {
+            flight_plan_id: "test".to_string(),
+            flight_plan: Some(crate::grpc::mock::flight_plan()),
+        }))
+        .await
+        .unwrap();
+
+        // the amendment is checked again
+        let mut flight_plan = crate::grpc::mock::flight_plan();
+        flight_plan.route[1].latitude = 91.0;
+        let error = imp
+            .amend_flight_plan(request(flight_plan))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+
+        let mut flight_plan = crate::grpc::mock::flight_plan();
+        flight_plan.aircraft_id = "PH-NEW".to_string();
+        let result = imp
+            .amend_flight_plan(request(flight_plan.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(result.amended);
+        let record = imp.flight_plans.get("test").unwrap();
+        assert_eq!(record.state, FlightPlanState::Accepted);
+        assert_eq!(record.flight_plan.aircraft_id, "PH-NEW");
+
+        // route now crosses a restriction around the arrival
+        imp.store.set_restrictions(
+            "nl",
+            HashMap::from([(
+                "zone".to_string(),
+                RestrictionDetails {
+                    shape: Shape::Polygon(
+                        [(52.50, 5.40), (52.50, 5.45), (52.55, 5.45), (52.55, 5.40)]
+                            .into_iter()
+                            .map(|(latitude, longitude)| gis::Coordinates {
+                                latitude,
+                                longitude,
+                            })
+                            .collect(),
+                    ),
+                    timestamp_start: None,
+                    timestamp_end: None,
+                    schedule: None,
+                    lower: AltitudeLimit::SURFACE,
+                    upper: AltitudeLimit::msl(500.),
+                    zone_type: gis::ZoneType::Restriction,
+                    kind: RestrictionKind::NoFly,
+                },
+            )]),
+        );
+
+        let mut flight_plan = crate::grpc::mock::flight_plan();
+        flight_plan.aircraft_id = "PH-AMD".to_string();
+        let result = imp
+            .amend_flight_plan(request(flight_plan))
+            .await
+            .unwrap()
+            .into_inner();
+        println!("{:?}", result);
+        assert!(!result.amended);
+        assert_eq!(result.violations.len(), 1);
+        assert_eq!(result.violations[0].feature_id, Some("zone".to_string()));
+
+        // the accepted version remains
+        let record = imp.flight_plans.get("test").unwrap();
+        assert_eq!(record.state, FlightPlanState::Accepted);
+        assert_eq!(record.flight_plan.aircraft_id, "PH-NEW");
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_grpc_get_flight_plan_status() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let imp = get_server_impl();
+        let request = || {
+            Request::new(FlightPlanStatusRequest {
+                flight_plan_id: "test".to_string(),
+            })
+        };
+
+        let error = imp.get_flight_plan_status(request()).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::NotFound);
+
+        imp.submit_flight_plan(Request::new(FlightPlanRequest {
+            flight_plan_id: "test".to_string(),
+            flight_plan: Some(crate::grpc::mock::flight_plan()),
+        }))
+        .await
+        .unwrap();
+
+        let result = imp
+            .get_flight_plan_status(request())
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(result.flight_plan_id, "test");
+        assert_eq!(result.status, FlightPlanStatus::Accepted as i32);
+
+        assert_eq!(
+            to_flight_plan_status(FlightPlanState::Revoked),
+            FlightPlanStatus::Revoked
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_grpc_watch_flight_plan() {
+        use tokio_stream::StreamExt;
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let imp = get_server_impl();
+        let error = imp
+            .watch_flight_plan(Request::new(WatchFlightPlanRequest {
+                flight_plan_ids: vec![" ".to_string()],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+
+        // watched before it is submitted
+        let mut stream = imp
+            .watch_flight_plan(Request::new(WatchFlightPlanRequest {
+                flight_plan_ids: vec!["test".to_string()],
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        imp.submit_flight_plan(Request::new(FlightPlanRequest {
+            flight_plan_id: "other".to_string(),
+            flight_plan: Some(crate::grpc::mock::flight_plan()),
+        }))
+        .await
+        .unwrap();
+        imp.submit_flight_plan(Request::new(FlightPlanRequest {
+            flight_plan_id: "test".to_string(),
+            flight_plan: Some(crate::grpc::mock::flight_plan()),
+        }))
+        .await
+        .unwrap();
+
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.flight_plan_id, "test");
+        assert_eq!(event.status, FlightPlanStatus::Submitted as i32);
+        assert_eq!(event.previous_status, Some(FlightPlanStatus::Draft as i32));
+
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.status, FlightPlanStatus::Accepted as i32);
+        assert_eq!(event.decisions.len(), 1);
+        assert!(event.decisions[0].accepted);
+
+        // a new watcher starts with the current state
+        let mut current = imp
+            .watch_flight_plan(Request::new(WatchFlightPlanRequest {
+                flight_plan_ids: vec!["test".to_string(), "unknown".to_string()],
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        let event = current.next().await.unwrap().unwrap();
+        assert_eq!(event.status, FlightPlanStatus::Accepted as i32);
+        assert_eq!(event.previous_status, None);
+
+        // the stream ends once the flight plan is final
+        imp.cancel_flight_plan(Request::new(FlightCancellationRequest {
+            flight_plan_id: "test".to_string(),
+        }))
+        .await
+        .unwrap();
+
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.status, FlightPlanStatus::Cancelled as i32);
+        assert!(stream.next().await.is_none());
+
+        // a flight plan that never changes ends the watch after the timeout
+        let mut idle = imp
+            .watch(
+                vec!["never".to_string()],
+                std::time::Duration::from_millis(50),
+            )
+            .unwrap();
+        let error = idle.next().await.unwrap().unwrap_err();
+        assert_eq!(error.code(), tonic::Code::DeadlineExceeded);
+        assert!(idle.next().await.is_none());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_grpc_route_regions() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let imp = ServerImpl {
+            mq_channel: None,
+            regions: from_region_codes("nl,us", &Config::new()).unwrap(),
+            store: Arc::default(),
+            flight_plans: Arc::default(),
+        };
+
+        let flight_plan = |points: &[(f64, f64)]| region::FlightPlan {
+            route: points
+                .iter()
+                .map(|(latitude, longitude)| region::utils::RoutePoint {
+                    latitude: *latitude,
+                    longitude: *longitude,
+                    altitude_meters: None,
+                    timestamp: None,
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        // No route, all regions decide
+        let regions = imp.route_regions(&flight_plan(&[]));
+        assert_eq!(regions.len(), 2);
+
+        // Amsterdam to Lelystad
+        let regions = imp.route_regions(&flight_plan(&[(52.3745, 4.9160), (52.5337, 5.4339)]));
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].get_region(), "nl");
+
+        // Atlantic crossing from Amsterdam to New York
+        let regions = imp.route_regions(&flight_plan(&[(52.3745, 4.9160), (40.7128, -74.0060)]));
+        assert_eq!(regions.len(), 2);
+
+        // Outside of all regions
+        assert!(imp.route_regions(&flight_plan(&[(0.0, 0.0)])).is_empty());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_combine_decisions() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let (decision, violations) = to_region_decision(
+            "nl",
+            Err(RegionError::AuthorityFailure("timeout".to_string())),
+        );
+        assert!(!decision.accepted);
+        assert!(decision.result.unwrap().contains("timeout"));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule_code, RULE_AUTHORITY_FAILURE);
+        assert_eq!(violations[0].severity, Severity::Error as i32);
+
+        let (decision, violations) = to_region_decision(
+            "us",
+            Ok(Decision {
+                accepted: true,
+                result: None,
+                violations: vec![region::Violation {
+                    rule_code: "TEST".to_string(),
+                    severity: region::Severity::Warning,
+                    feature_id: Some("zone".to_string()),
+                    segment_index: Some(2),
+                    message: "Close to zone.".to_string(),
+                }],
+            }),
+        );
+        assert!(decision.accepted);
+        assert_eq!(
+            violations,
+            vec![Violation {
+                region: "us".to_string(),
+                rule_code: "TEST".to_string(),
+                severity: Severity::Warning as i32,
+                feature_id: Some("zone".to_string()),
+                segment_index: Some(2),
+                message: "Close to zone.".to_string(),
+            }]
+        );
+
+        let (accepted, result) = combine_decisions(&[], &[]);
+        assert!(!accepted);
+        assert!(result.is_some());
+
+        let mut decisions = vec![
+            RegionDecision {
+                region: "nl".to_string(),
+                accepted: true,
+                result: None,
+            },
+            RegionDecision {
+                region: "us".to_string(),
+                accepted: true,
+                result: None,
+            },
+        ];
+
+        let (accepted, result) = combine_decisions(&decisions, &violations);
+        assert!(accepted);
+        assert!(result.is_none());
+
+        // an error violation rejects the request, even if accepted
+        let mut error = violations[0].clone();
+        error.severity = Severity::Error as i32;
+        let (accepted, _) = combine_decisions(&decisions, &[error]);
+        assert!(!accepted);
+
+        decisions[1].accepted = false;
+        decisions[1].result = Some("Denied.".to_string());
+        let (accepted, result) = combine_decisions(&decisions, &violations);
+        assert!(!accepted);
+        assert_eq!(result, Some("[us] Denied.".to_string()));
 
         ut_info!("Success.");
     }
@@ -462,30 +2173,253 @@ mod tests {
         lib_common::logger::get_log_handle().await;
         ut_info!("Start.");
 
+        let flat = VerticalModel::default();
         let host = "localhost".to_string();
         let port = 50008;
 
+        let mut sync = ZoneSync::new(RECONCILE_INTERVAL_SECONDS);
         let mut cache: HashMap<String, RestrictionDetails> = HashMap::new();
-        let error = update_restrictions(host.clone(), port, &cache)
-            .await
-            .unwrap_err();
+        let error = update_restrictions(
+            host.clone(),
+            port,
+            &cache,
+            MAX_CHORD_ERROR_METERS,
+            24,
+            &flat,
+            &mut sync,
+        )
+        .await
+        .unwrap_err();
         assert_eq!(error, UpdateRestrictionsError::NoRestrictions);
 
-        cache.insert(
-            "test".to_string(),
-            RestrictionDetails {
-                vertices: vec![],
-                timestamp_start: Some(Utc::now()),
-                timestamp_end: None,
-                altitude_meters_max: 0.,
-                altitude_meters_min: 200.,
-                zone_type: gis::ZoneType::Restriction,
+        // invalid polygons are quarantined
+        let mut details = RestrictionDetails {
+            shape: Shape::Polygon(vec![]),
+            timestamp_start: Some(Utc::now()),
+            timestamp_end: None,
+            schedule: None,
+            lower: AltitudeLimit::msl(200.),
+            upper: AltitudeLimit::SURFACE,
+            zone_type: gis::ZoneType::Restriction,
+            kind: RestrictionKind::NoFly,
+        };
+        cache.insert("test".to_string(), details.clone());
+        let error = update_restrictions(
+            host.clone(),
+            port,
+            &cache,
+            MAX_CHORD_ERROR_METERS,
+            24,
+            &flat,
+            &mut sync,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error, UpdateRestrictionsError::NoRestrictions);
+
+        details.shape = Shape::Polygon(
+            [
+                (52.0, 4.0),
+                (52.0, 4.01),
+                (52.01, 4.01),
+                (52.01, 4.0),
+                (52.0, 4.0),
+            ]
+            .into_iter()
+            .map(|(latitude, longitude)| gis::Coordinates {
+                latitude,
+                longitude,
+            })
+            .collect(),
+        );
+        cache.insert("test".to_string(), details);
+        update_restrictions(
+            host.clone(),
+            port,
+            &cache,
+            MAX_CHORD_ERROR_METERS,
+            24,
+            &flat,
+            &mut sync,
+        )
+        .await
+        .unwrap();
+        assert_eq!(sync.len(), 1);
+
+        // removed restrictions are still sent once
+        cache.clear();
+        update_restrictions(
+            host.clone(),
+            port,
+            &cache,
+            MAX_CHORD_ERROR_METERS,
+            24,
+            &flat,
+            &mut sync,
+        )
+        .await
+        .unwrap();
+        assert!(sync.is_empty());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_to_gis_zone() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let flat = VerticalModel::default();
+        let mut details = RestrictionDetails {
+            shape: Shape::Polygon(
+                [
+                    (52.0, 4.0),
+                    (52.0, 4.01),
+                    (52.01, 4.01),
+                    (52.01, 4.0),
+                    (52.0, 4.0),
+                ]
+                .into_iter()
+                .map(|(latitude, longitude)| gis::Coordinates {
+                    latitude,
+                    longitude,
+                })
+                .collect(),
+            ),
+            timestamp_start: None,
+            timestamp_end: None,
+            schedule: None,
+            lower: AltitudeLimit::msl(30.),
+            upper: AltitudeLimit::msl(120.),
+            zone_type: gis::ZoneType::Restriction,
+            kind: RestrictionKind::NoFly,
+        };
+
+        let zone = to_gis_zone("test", &details, MAX_CHORD_ERROR_METERS, &flat).unwrap();
+        assert_eq!(zone.identifier, "test");
+        assert_eq!(zone.vertices.len(), 5);
+        assert_eq!(zone.altitude_meters_min, 30.);
+        assert_eq!(zone.altitude_meters_max, 120.);
+
+        // the airspace above a ceiling is restricted
+        details.kind = RestrictionKind::Ceiling;
+        let zone = to_gis_zone("test", &details, MAX_CHORD_ERROR_METERS, &flat).unwrap();
+        assert_eq!(zone.altitude_meters_min, 120.);
+        assert_eq!(zone.altitude_meters_max, UNLIMITED_ALTITUDE_METERS as f32);
+
+        // heights above the terrain follow the highest ground for the top
+        //  of a zone and the lowest ground for a ceiling
+        let vertical = VerticalModel::new(
+            Grid::parse("ncols 2 nrows 1 xllcorner 4.0 yllcorner 52.0 cellsize 0.01 0 100").ok(),
+            None,
+        );
+        details.upper = AltitudeLimit::agl(120.);
+        let zone = to_gis_zone("test", &details, MAX_CHORD_ERROR_METERS, &vertical).unwrap();
+        assert!((zone.altitude_meters_min - 120.).abs() < 1e-3);
+        details.kind = RestrictionKind::NoFly;
+        let zone = to_gis_zone("test", &details, MAX_CHORD_ERROR_METERS, &vertical).unwrap();
+        assert_eq!(zone.altitude_meters_min, 30.);
+        assert!((zone.altitude_meters_max - 170.).abs() < 1e-3);
+
+        // circles are sent as polygons, finer for a smaller chord error
+        details.shape = Shape::Circle {
+            center: gis::Coordinates {
+                latitude: 52.37,
+                longitude: 4.90,
             },
+            radius_meters: 1000.0,
+        };
+        let zone = to_gis_zone("test", &details, 10.0, &flat).unwrap();
+        assert_eq!(zone.vertices.len(), 24);
+        assert_eq!(zone.vertices.first(), zone.vertices.last());
+        let zone = to_gis_zone("test", &details, 1.0, &flat).unwrap();
+        assert_eq!(zone.vertices.len(), 72);
+
+        // polygons that cannot be repaired are rejected
+        details.shape = Shape::Polygon(vec![]);
+        let error = to_gis_zone("test", &details, MAX_CHORD_ERROR_METERS, &flat).unwrap_err();
+        assert_eq!(error, GeometryError::TooFewVertices(0));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_expand_schedule() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let time = |text: &str| text.parse::<DateTime<Utc>>().unwrap();
+        let mut details = RestrictionDetails {
+            shape: Shape::Circle {
+                center: gis::Coordinates {
+                    latitude: 52.37,
+                    longitude: 4.90,
+                },
+                radius_meters: 1000.0,
+            },
+            timestamp_start: None,
+            timestamp_end: None,
+            schedule: None,
+            lower: AltitudeLimit::SURFACE,
+            upper: AltitudeLimit::msl(120.),
+            zone_type: gis::ZoneType::Restriction,
+            kind: RestrictionKind::NoFly,
+        };
+
+        // unscheduled restrictions are kept as they are
+        let now = time("2024-06-05T12:00:00Z");
+        let horizon = time("2024-06-07T12:00:00Z");
+        let expanded = expand_schedule("test", &details, now, horizon);
+        assert_eq!(expanded.len(), 1);
+        assert_eq!(expanded[0].0, "test");
+        assert!(expanded[0].1.timestamp_start.is_none());
+
+        // windows until the horizon, the current one from its start
+        details.schedule = Schedule::parse("0800-1800", chrono_tz::Tz::UTC).ok();
+        let expanded = expand_schedule("test", &details, now, horizon);
+        let windows = expanded
+            .iter()
+            .map(|(label, details)| {
+                assert!(details.schedule.is_none());
+                (
+                    label.as_str(),
+                    details.timestamp_start.unwrap(),
+                    details.timestamp_end.unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            windows,
+            vec![
+                (
+                    "test-20240605T0800Z",
+                    time("2024-06-05T08:00:00Z"),
+                    time("2024-06-05T18:00:00Z")
+                ),
+                (
+                    "test-20240606T0800Z",
+                    time("2024-06-06T08:00:00Z"),
+                    time("2024-06-06T18:00:00Z")
+                ),
+                (
+                    "test-20240607T0800Z",
+                    time("2024-06-07T08:00:00Z"),
+                    time("2024-06-07T18:00:00Z")
+                ),
+            ]
+        );
+
+        // windows are clipped to the validity of the restriction
+        details.timestamp_start = Some(time("2024-06-05T09:00:00Z"));
+        details.timestamp_end = Some(time("2024-06-06T12:00:00Z"));
+        let expanded = expand_schedule("test", &details, now, horizon);
+        assert_eq!(expanded.len(), 2);
+        assert_eq!(expanded[0].0, "test-20240605T0900Z");
+        assert_eq!(
+            expanded[1].1.timestamp_end,
+            Some(time("2024-06-06T12:00:00Z"))
         );
 
-        let _ = update_restrictions(host.clone(), port, &cache)
-            .await
-            .unwrap();
         ut_info!("Success.");
     }
 
@@ -511,7 +2445,7 @@ mod tests {
             },
         );
 
-        let _ = update_waypoints(host.clone(), port, &cache).await.unwrap();
+        update_waypoints(host.clone(), port, &cache).await.unwrap();
         ut_info!("Success.");
     }
 
@@ -0,0 +1,383 @@
+//! Change detection of the zones pushed to svc-gis
+//!
+//! Only zones that were added or changed since the last accepted update
+//!  are sent. svc-gis has no call to delete a zone, so a removed zone is
+//!  sent once more with its validity ending now, after which svc-gis no
+//!  longer applies it. All zones are sent again at a regular interval, to
+//!  correct any drift such as after a restart of svc-gis.
+//!
+//! Each owner of zones, a region or the ad-hoc zones shared by the regions,
+//!  keeps its own state, so that one owner never ends the zones of another.
+//!  The state may be kept in a file, so that zones removed while this
+//!  service was down are still ended after a restart.
+
+use lib_common::time::{DateTime, Duration, Utc};
+use prost::Message;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use svc_gis_client_grpc::prelude::gis;
+
+/// Seconds between full reconciliations of the zones in svc-gis
+pub const RECONCILE_INTERVAL_SECONDS: u16 = 3600;
+
+/// An update of the zones in svc-gis
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneUpdate {
+    /// Added and changed zones, or all zones on a full reconciliation
+    pub zones: Vec<gis::Zone>,
+
+    /// Identifiers of the removed zones, sent as ended zones
+    pub removed: Vec<String>,
+
+    /// True if all zones are sent
+    pub full: bool,
+}
+
+impl ZoneUpdate {
+    /// Returns true if the update sends nothing
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// The zones last accepted by svc-gis
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneSync {
+    /// Zones as last sent, by identifier
+    sent: HashMap<String, gis::Zone>,
+
+    /// Time of the last full reconciliation, if any
+    reconciled: Option<DateTime<Utc>>,
+
+    /// Seconds between full reconciliations
+    interval_seconds_reconcile: u16,
+
+    /// File keeping the zones as last sent across restarts, if any
+    file: Option<PathBuf>,
+}
+
+impl ZoneSync {
+    /// Creates the state of a service that has not sent any zones yet
+    pub fn new(interval_seconds_reconcile: u16) -> Self {
+        ZoneSync {
+            sent: HashMap::new(),
+            reconciled: None,
+            interval_seconds_reconcile,
+            file: None,
+        }
+    }
+
+    /// Restores the zones last sent from the file written by
+    ///  [`ZoneSync::save`], and keeps them there from now on
+    ///
+    /// Zones sent before a restart that are gone since are ended by the
+    ///  first update. Starts without zones if the file does not exist yet
+    ///  or cannot be read. An unreadable file is moved aside with the
+    ///  extension `corrupt` before it would be overwritten, as the zones
+    ///  removed while the service was down are then no longer ended.
+    pub async fn restore(interval_seconds_reconcile: u16, file: PathBuf) -> Self {
+        let mut sync = Self::new(interval_seconds_reconcile);
+        match tokio::fs::read(&file).await {
+            Ok(bytes) => match gis::UpdateZonesRequest::decode(bytes.as_slice()) {
+                Ok(request) => {
+                    sync.sent = request
+                        .zones
+                        .into_iter()
+                        .map(|zone| (zone.identifier.clone(), zone))
+                        .collect();
+                    grpc_info!(
+                        "Restored {} zones sent before from {}.",
+                        sync.sent.len(),
+                        file.display()
+                    );
+                }
+                Err(e) => {
+                    let corrupt = file.with_extension("corrupt");
+                    grpc_error!(
+                        "Could not decode the zones sent before in {}, moving it to {}: {}",
+                        file.display(),
+                        corrupt.display(),
+                        e
+                    );
+                    if let Err(e) = tokio::fs::rename(&file, &corrupt).await {
+                        grpc_error!("Could not move {}: {}", file.display(), e);
+                    }
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                grpc_info!("No zones sent before in {}.", file.display())
+            }
+            Err(e) => grpc_error!(
+                "Could not read the zones sent before in {}: {}",
+                file.display(),
+                e
+            ),
+        }
+
+        sync.file = Some(file);
+        sync
+    }
+
+    /// Writes the zones last sent to the file, if any
+    ///
+    /// The file is replaced at once, so that it is never left half written.
+    pub async fn save(&self) -> Result<(), std::io::Error> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+
+        let mut zones = self.sent.values().cloned().collect::<Vec<_>>();
+        zones.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+        let partial = file.with_extension("partial");
+        tokio::fs::write(&partial, gis::UpdateZonesRequest { zones }.encode_to_vec()).await?;
+        tokio::fs::rename(&partial, file).await
+    }
+
+    /// Returns true if a full reconciliation is due at `now`
+    fn is_reconciliation_due(&self, now: DateTime<Utc>) -> bool {
+        match self.reconciled {
+            Some(reconciled) => {
+                now - reconciled >= Duration::seconds(i64::from(self.interval_seconds_reconcile))
+            }
+            None => true,
+        }
+    }
+
+    /// The update from the zones last sent to the given zones at `now`
+    pub fn diff(&self, zones: &HashMap<String, gis::Zone>, now: DateTime<Utc>) -> ZoneUpdate {
+        let full = self.is_reconciliation_due(now);
+        let mut changed = zones
+            .iter()
+            .filter(|(id, zone)| full || self.sent.get(*id) != Some(*zone))
+            .map(|(_, zone)| zone.clone())
+            .collect::<Vec<_>>();
+        changed.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+
+        let mut removed = self
+            .sent
+            .keys()
+            .filter(|id| !zones.contains_key(*id))
+            .cloned()
+            .collect::<Vec<_>>();
+        removed.sort();
+
+        ZoneUpdate {
+            zones: changed,
+            removed,
+            full,
+        }
+    }
+
+    /// Zones of the update as sent to svc-gis, with the removed zones
+    ///  ended at `now`
+    ///
+    /// Removed zones keep their start, unless it is still to come, in which
+    ///  case they start just before `now` so that they never apply.
+    pub fn request_zones(&self, update: &ZoneUpdate, now: DateTime<Utc>) -> Vec<gis::Zone> {
+        let ended = update
+            .removed
+            .iter()
+            .filter_map(|id| self.sent.get(id))
+            .map(|zone| {
+                let time_start = zone.time_start.clone().map(|start| {
+                    let start = DateTime::<Utc>::from(start);
+                    match start < now {
+                        true => start.into(),
+                        false => (now - Duration::seconds(1)).into(),
+                    }
+                });
+
+                gis::Zone {
+                    time_start,
+                    time_end: Some(now.into()),
+                    ..zone.clone()
+                }
+            });
+
+        update.zones.iter().cloned().chain(ended).collect()
+    }
+
+    /// Records the zones once svc-gis accepted the update
+    pub fn accept(&mut self, update: ZoneUpdate, now: DateTime<Utc>) {
+        for id in &update.removed {
+            self.sent.remove(id);
+        }
+
+        if update.full {
+            self.reconciled = Some(now);
+        }
+
+        self.sent.extend(
+            update
+                .zones
+                .into_iter()
+                .map(|zone| (zone.identifier.clone(), zone)),
+        );
+    }
+
+    /// Number of zones last sent
+    pub fn len(&self) -> usize {
+        self.sent.len()
+    }
+
+    /// Returns true if no zones were sent
+    pub fn is_empty(&self) -> bool {
+        self.sent.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn zone(identifier: &str, altitude_meters_max: f32) -> gis::Zone {
+        gis::Zone {
+            identifier: identifier.to_string(),
+            zone_type: gis::ZoneType::Restriction as i32,
+            altitude_meters_max,
+            altitude_meters_min: 0.,
+            vertices: vec![],
+            time_start: None,
+            time_end: None,
+        }
+    }
+
+    fn zones(list: &[gis::Zone]) -> HashMap<String, gis::Zone> {
+        list.iter()
+            .map(|zone| (zone.identifier.clone(), zone.clone()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_diff() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let now = time("2024-06-05T12:00:00Z");
+        let mut sync = ZoneSync::new(RECONCILE_INTERVAL_SECONDS);
+        assert!(sync.is_empty());
+
+        // everything is sent at first
+        let current = zones(&[zone("a", 120.), zone("b", 120.)]);
+        let update = sync.diff(&current, now);
+        assert!(update.full);
+        assert_eq!(update.zones.len(), 2);
+        assert!(update.removed.is_empty());
+        sync.accept(update, now);
+        assert_eq!(sync.len(), 2);
+
+        // nothing changed
+        let now = now + Duration::seconds(30);
+        let update = sync.diff(&current, now);
+        assert!(!update.full);
+        assert!(update.is_empty());
+
+        // one zone changed, one removed and one added
+        let current = zones(&[zone("a", 150.), zone("c", 120.)]);
+        let update = sync.diff(&current, now);
+        assert_eq!(update.zones, vec![zone("a", 150.), zone("c", 120.)]);
+        assert_eq!(update.removed, vec!["b".to_string()]);
+
+        // removed zones are sent as ended, keeping their start
+        let request = sync.request_zones(&update, now);
+        assert_eq!(request.len(), 3);
+        assert_eq!(request[2].identifier, "b");
+        assert_eq!(request[2].time_start, None);
+        assert_eq!(request[2].time_end, Some(now.into()));
+
+        // a failed update is sent again
+        assert_eq!(sync.diff(&current, now), update);
+        sync.accept(update, now);
+        assert_eq!(sync.len(), 2);
+        assert!(sync.diff(&current, now).is_empty());
+
+        // a removed zone that has not started yet never applies
+        let mut scheduled = zone("d", 120.);
+        scheduled.time_start = Some((now + Duration::hours(1)).into());
+        let update = sync.diff(&zones(&[scheduled]), now);
+        sync.accept(update, now);
+        let update = sync.diff(&HashMap::new(), now);
+        assert_eq!(update.removed, vec!["d".to_string()]);
+        let request = sync.request_zones(&update, now);
+        assert_eq!(
+            request[0].time_start,
+            Some((now - Duration::seconds(1)).into())
+        );
+        assert_eq!(request[0].time_end, Some(now.into()));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_reconciliation() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let now = time("2024-06-05T12:00:00Z");
+        let mut sync = ZoneSync::new(60);
+        let current = zones(&[zone("a", 120.), zone("b", 120.)]);
+        let update = sync.diff(&current, now);
+        sync.accept(update, now);
+
+        // unchanged zones are sent again once the interval passed
+        let update = sync.diff(&current, now + Duration::seconds(59));
+        assert!(update.is_empty());
+        let now = now + Duration::seconds(60);
+        let update = sync.diff(&current, now);
+        assert!(update.full);
+        assert_eq!(update.zones.len(), 2);
+        sync.accept(update, now);
+        assert!(sync.diff(&current, now).is_empty());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_restore() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("nl.zones");
+
+        // nothing was sent before the first start
+        let now = time("2024-06-05T12:00:00Z");
+        let mut sync = ZoneSync::restore(RECONCILE_INTERVAL_SECONDS, file.clone()).await;
+        assert!(sync.is_empty());
+        let current = zones(&[zone("a", 120.), zone("b", 120.)]);
+        let update = sync.diff(&current, now);
+        sync.accept(update, now);
+        sync.save().await.unwrap();
+
+        // a zone removed while the service was down is ended after a restart
+        let mut sync = ZoneSync::restore(RECONCILE_INTERVAL_SECONDS, file.clone()).await;
+        assert_eq!(sync.len(), 2);
+        let current = zones(&[zone("a", 120.)]);
+        let update = sync.diff(&current, now);
+        assert!(update.full);
+        assert_eq!(update.removed, vec!["b".to_string()]);
+        let request = sync.request_zones(&update, now);
+        assert_eq!(request[1].identifier, "b");
+        assert_eq!(request[1].time_end, Some(now.into()));
+        sync.accept(update, now);
+        sync.save().await.unwrap();
+        let sync = ZoneSync::restore(RECONCILE_INTERVAL_SECONDS, file.clone()).await;
+        assert_eq!(sync.len(), 1);
+
+        // an unreadable file is moved aside and not overwritten
+        std::fs::write(&file, "not zones").unwrap();
+        let sync = ZoneSync::restore(RECONCILE_INTERVAL_SECONDS, file.clone()).await;
+        assert!(sync.is_empty());
+        assert!(!file.exists());
+        assert_eq!(
+            std::fs::read(file.with_extension("corrupt")).unwrap(),
+            b"not zones"
+        );
+
+        ut_info!("Success.");
+    }
+}
@@ -21,6 +21,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("(main) Server startup.");
 
+    // Fail early if no implementation exists for a configured region
+    region::from_region_codes(&config.region_code, &config)?;
+
     let _ = tokio::spawn(grpc::server::grpc_server(config, None)).await?;
 
     info!("(main) Server shutdown.");
@@ -0,0 +1,338 @@
+//! Reader for navigation databases in the ARINC 424 format
+//!
+//! A database is a text file of fixed width records of 132 columns. Enroute
+//!  waypoints are read from the `EA` records, terminal waypoints from the
+//!  `PC` records and navaids from the VHF navaid (`D`) and NDB (`DB`, `PN`)
+//!  records. Continuation records and other sections, such as airways and
+//!  procedures, are ignored. Coordinates are given in degrees, minutes and
+//!  hundredths of seconds, such as `N30143187` and `W097520833`.
+
+use crate::region::coordinates::{field, parse_packed, Axis, Field};
+use crate::region::source::Source;
+use crate::region::RegionError;
+use std::collections::HashMap;
+use svc_gis_client_grpc::prelude::gis::Coordinates;
+
+/// Columns of the airport identifier, blank for enroute records
+const AIRPORT: Field = (7, 4);
+
+/// Columns of the ICAO region code of the point
+const ICAO_CODE: Field = (20, 2);
+
+/// Column of the continuation record number
+const CONTINUATION: Field = (22, 1);
+
+/// Columns of the latitude of the point
+const LATITUDE: Field = (33, 9);
+
+/// Columns of the longitude of the point
+const LONGITUDE: Field = (42, 10);
+
+/// Columns of the DME latitude, used for navaids without a VOR
+const DME_LATITUDE: Field = (56, 9);
+
+/// Columns of the DME longitude, used for navaids without a VOR
+const DME_LONGITUDE: Field = (65, 10);
+
+/// Kinds of navigation points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointKind {
+    /// Waypoint of the enroute structure
+    EnrouteWaypoint,
+
+    /// Waypoint of an airport, used by its procedures
+    TerminalWaypoint,
+
+    /// VOR, DME or TACAN
+    VhfNavaid,
+
+    /// Non-directional beacon
+    Ndb,
+}
+
+impl PointKind {
+    /// Identifies the kind of a primary record from its section codes
+    fn from_record(line: &str) -> Option<Self> {
+        let column = |column: usize| line.as_bytes().get(column - 1).copied();
+        if !matches!(column(1), Some(b'S' | b'T')) {
+            return None;
+        }
+
+        match (column(5), column(6), column(13)) {
+            (Some(b'E'), Some(b'A'), _) => Some(PointKind::EnrouteWaypoint),
+            (Some(b'P'), Some(b' '), Some(b'C')) => Some(PointKind::TerminalWaypoint),
+            (Some(b'P'), Some(b' '), Some(b'N')) => Some(PointKind::Ndb),
+            (Some(b'D'), Some(b' '), _) => Some(PointKind::VhfNavaid),
+            (Some(b'D'), Some(b'B'), _) => Some(PointKind::Ndb),
+            _ => None,
+        }
+    }
+
+    /// Columns of the identifier
+    fn id(&self) -> Field {
+        match self {
+            PointKind::EnrouteWaypoint | PointKind::TerminalWaypoint => (14, 5),
+            PointKind::VhfNavaid | PointKind::Ndb => (14, 4),
+        }
+    }
+
+    /// Columns of the waypoint type or the navaid class
+    fn type_code(&self) -> Field {
+        match self {
+            PointKind::EnrouteWaypoint | PointKind::TerminalWaypoint => (27, 3),
+            PointKind::VhfNavaid | PointKind::Ndb => (28, 5),
+        }
+    }
+}
+
+/// A waypoint or navaid of a navigation database
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavPoint {
+    /// Published identifier, such as a five letter waypoint name
+    pub id: String,
+
+    /// ICAO region code, such as `K4` or `EH`
+    pub icao_code: String,
+
+    /// Airport of a terminal waypoint or navaid
+    pub airport: Option<String>,
+
+    /// Kind of the point
+    pub kind: PointKind,
+
+    /// Waypoint type or navaid class as published, such as `C` or `VDHW`
+    pub type_code: String,
+
+    /// Location of the point
+    pub coordinates: Coordinates,
+}
+
+/// Parses the primary waypoint and navaid records of a database
+///
+/// Returns the result of parsing each record in file order.
+pub fn parse_points(data: &[u8]) -> Vec<Result<NavPoint, String>> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let kind = PointKind::from_record(line)?;
+            if !matches!(field(line, CONTINUATION), "0" | "1") {
+                return None;
+            }
+
+            Some(to_point(line, kind).map_err(|e| format!("line {}: {}", index + 1, e)))
+        })
+        .collect()
+}
+
+/// Converts the latitude and longitude fields of a record
+fn to_coordinates(
+    line: &str,
+    id: &str,
+    (latitude, longitude): (Field, Field),
+) -> Result<Coordinates, String> {
+    let latitude = field(line, latitude);
+    let latitude = parse_packed(latitude, Axis::Latitude)
+        .ok_or_else(|| format!("{}: invalid latitude '{}'", id, latitude))?;
+
+    let longitude = field(line, longitude);
+    let longitude = parse_packed(longitude, Axis::Longitude)
+        .ok_or_else(|| format!("{}: invalid longitude '{}'", id, longitude))?;
+
+    Ok(Coordinates {
+        latitude,
+        longitude,
+    })
+}
+
+/// Converts a primary record
+fn to_point(line: &str, kind: PointKind) -> Result<NavPoint, String> {
+    let id = field(line, kind.id());
+    if id.is_empty() {
+        return Err("missing identifier".to_string());
+    }
+
+    // a DME or TACAN without a VOR only has the DME location
+    let location = match kind == PointKind::VhfNavaid && field(line, LATITUDE).is_empty() {
+        true => (DME_LATITUDE, DME_LONGITUDE),
+        false => (LATITUDE, LONGITUDE),
+    };
+
+    let airport = field(line, AIRPORT);
+    Ok(NavPoint {
+        id: id.to_string(),
+        icao_code: field(line, ICAO_CODE).to_string(),
+        airport: match airport.is_empty() || airport == "ENRT" {
+            true => None,
+            false => Some(airport.to_string()),
+        },
+        kind,
+        type_code: field(line, kind.type_code()).to_string(),
+        coordinates: to_coordinates(line, id, location)?,
+    })
+}
+
+/// Reads the waypoints and navaids of the given ICAO regions, skipping
+///  malformed records
+///
+/// Regions are matched by the start of their code, such as `K` for the
+///  contiguous US. Enroute waypoints and navaids take precedence over
+///  terminal waypoints of the same identifier, otherwise the first record
+///  is kept.
+pub async fn load_waypoints(
+    source: &Source,
+    icao_codes: &[&str],
+) -> Result<HashMap<String, Coordinates>, RegionError> {
+    let data = source
+        .read()
+        .await
+        .map_err(|e| RegionError::DatasetFailure(e.to_string()))?;
+
+    let mut points = vec![];
+    let mut skipped = 0;
+    for point in parse_points(&data) {
+        match point {
+            Ok(point) if icao_codes.iter().any(|c| point.icao_code.starts_with(c)) => {
+                points.push(point)
+            }
+            Ok(_) => (),
+            Err(e) => {
+                region_warn!("Skipping malformed record in {}: {}", source, e);
+                skipped += 1;
+            }
+        }
+    }
+
+    // stable, so the file order is kept within each group
+    points.sort_by_key(|point| point.kind == PointKind::TerminalWaypoint);
+
+    let mut waypoints = HashMap::new();
+    for point in points {
+        if waypoints.contains_key(&point.id) {
+            region_debug!(
+                "Skipping duplicate {:?} {} in {}.",
+                point.kind,
+                point.id,
+                source
+            );
+            continue;
+        }
+
+        waypoints.insert(point.id, point.coordinates);
+    }
+
+    region_info!(
+        "Loaded {} waypoints from {}, skipped {} malformed.",
+        waypoints.len(),
+        source,
+        skipped
+    );
+
+    Ok(waypoints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The navigation database shipped for tests
+    const ARINC424_FIXTURE: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/arinc424.dat");
+
+    fn assert_near(coordinates: &Coordinates, latitude: f64, longitude: f64) {
+        assert!((coordinates.latitude - latitude).abs() < 1e-5);
+        assert!((coordinates.longitude - longitude).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_parse_points() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let points = parse_points(&std::fs::read(ARINC424_FIXTURE).unwrap());
+        assert_eq!(points.len(), 12);
+
+        let point = points[0].as_ref().unwrap();
+        assert_eq!(point.id, "BLEWE");
+        assert_eq!(point.icao_code, "K4");
+        assert_eq!(point.kind, PointKind::EnrouteWaypoint);
+        assert_eq!(point.type_code, "C");
+        assert_eq!(point.airport, None);
+        assert_near(&point.coordinates, 30.242186, -97.868981);
+
+        // invalid minutes of the latitude
+        let error = points[2].as_ref().unwrap_err();
+        assert_eq!(error, "line 5: WILIE: invalid latitude 'N3025X000'");
+
+        let point = points[3].as_ref().unwrap();
+        assert_eq!(point.id, "CF17R");
+        assert_eq!(point.kind, PointKind::TerminalWaypoint);
+        assert_eq!(point.airport, Some("KAUS".to_string()));
+
+        let point = points[4].as_ref().unwrap();
+        assert_eq!(point.id, "CWK");
+        assert_eq!(point.kind, PointKind::VhfNavaid);
+        assert_eq!(point.type_code, "VDHW");
+        assert_near(&point.coordinates, 30.378775, -97.530064);
+
+        // DME without a VOR
+        let point = points[5].as_ref().unwrap();
+        assert_eq!(point.id, "GRK");
+        assert_near(&point.coordinates, 31.067239, -97.828847);
+
+        let point = points[6].as_ref().unwrap();
+        assert_eq!(point.id, "AU");
+        assert_eq!(point.kind, PointKind::Ndb);
+
+        let point = points[8].as_ref().unwrap();
+        assert_eq!(point.id, "EH610");
+        assert_eq!(point.icao_code, "EH");
+        assert_eq!(point.airport, Some("EHAM".to_string()));
+        assert_near(&point.coordinates, 52.342706, 4.716392);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_short_records() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let points = parse_points(b"SUSAEA\nSUSAEAENRT   BLEWE K40\nSUSAEAENRT         K40\n");
+        assert_eq!(points.len(), 2);
+        assert_eq!(
+            points[0],
+            Err("line 2: BLEWE: invalid latitude ''".to_string())
+        );
+        assert_eq!(points[1], Err("line 3: missing identifier".to_string()));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_load_waypoints() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let source = Source::File(ARINC424_FIXTURE.into());
+        let waypoints = load_waypoints(&source, &["K"]).await.unwrap();
+        let mut labels = waypoints.keys().cloned().collect::<Vec<_>>();
+        labels.sort();
+        assert_eq!(labels, vec!["AU", "BLEWE", "CF17R", "CWK", "GRK", "KAVVA"]);
+
+        // the enroute waypoint takes precedence over the later terminal one
+        assert_near(waypoints.get("KAVVA").unwrap(), 30.512394, -97.421128);
+
+        let waypoints = load_waypoints(&source, &["EH"]).await.unwrap();
+        let mut labels = waypoints.keys().cloned().collect::<Vec<_>>();
+        labels.sort();
+        assert_eq!(labels, vec!["ARTIP", "EH610", "SPY"]);
+
+        let error = load_waypoints(&Source::File("/nonexistent".into()), &["K"])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, RegionError::DatasetFailure(_)));
+
+        ut_info!("Success.");
+    }
+}
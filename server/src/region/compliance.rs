@@ -0,0 +1,661 @@
+//! Checks flight plans against the restrictions of a region
+//!
+//! Every segment of the route is checked in four dimensions: it conflicts
+//!  with a restriction if it crosses the restriction polygon while its
+//!  altitude band and time window overlap those of the restriction. Route
+//!  points without an altitude or a time are treated conservatively, as if
+//!  they overlap any altitude or time.
+//!
+//! Ceiling restrictions, such as the grid cells of a UAS Facility Map, may
+//!  be entered up to their maximum altitude. A segment conflicts with them
+//!  if it crosses the polygon above the ceiling, or without an altitude.
+//!
+//! Route altitudes are heights above the WGS84 ellipsoid. The limits of a
+//!  restriction are converted at both ends of the segment, so a limit
+//!  above ground follows the terrain under the route. The lowest floor and
+//!  the highest top, or the lowest ceiling, apply to the whole segment.
+
+use super::store::Airspace;
+use super::utils::RoutePoint;
+use super::vertical::VerticalModel;
+use super::{Decision, FlightPlan, RestrictionDetails, RestrictionKind, Severity, Violation};
+use lib_common::time::{DateTime, Utc};
+use std::collections::HashMap;
+use svc_gis_client_grpc::prelude::gis;
+
+/// Rule code of a route crossing an active restriction
+pub const RULE_RESTRICTED_ZONE: &str = "RESTRICTED_ZONE";
+
+/// Rule code of a route exceeding the ceiling of a restriction
+pub const RULE_ALTITUDE_CEILING: &str = "ALTITUDE_CEILING";
+
+/// Rule code of a route through a region whose restrictions have not been
+///  loaded yet
+pub const RULE_AIRSPACE_UNAVAILABLE: &str = "AIRSPACE_UNAVAILABLE";
+
+/// A route segment crossing an active restriction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// Identifier of the restriction
+    pub zone_id: String,
+
+    /// Index of the route segment, counting from the first route point
+    pub segment_index: usize,
+}
+
+/// Returns true if the closed ranges [a_min, a_max] and [b_min, b_max] overlap
+fn ranges_overlap<T: PartialOrd>(a_min: T, a_max: T, b_min: T, b_max: T) -> bool {
+    a_min <= b_max && b_min <= a_max
+}
+
+/// Heights in meters above the ellipsoid of the lower and upper limits of
+///  the restriction at both route points
+fn limit_heights(
+    a: &RoutePoint,
+    b: &RoutePoint,
+    restriction: &RestrictionDetails,
+    vertical: &VerticalModel,
+) -> [(f64, f64); 2] {
+    [a, b].map(|p| {
+        let point = gis::Coordinates {
+            latitude: p.latitude,
+            longitude: p.longitude,
+        };
+
+        (
+            vertical.height_meters(&restriction.lower, &point),
+            vertical.height_meters(&restriction.upper, &point),
+        )
+    })
+}
+
+/// Returns true if the segment between the two route points overlaps the
+///  altitude band of the restriction
+fn altitude_overlaps(
+    a: &RoutePoint,
+    b: &RoutePoint,
+    restriction: &RestrictionDetails,
+    vertical: &VerticalModel,
+) -> bool {
+    let (Some(alt_a), Some(alt_b)) = (a.altitude_meters, b.altitude_meters) else {
+        return true;
+    };
+
+    // tolerate restrictions with swapped limits
+    let heights = limit_heights(a, b, restriction, vertical)
+        .into_iter()
+        .flat_map(|(lower, upper)| [lower, upper])
+        .collect::<Vec<_>>();
+    let zone_min = heights.iter().copied().fold(f64::INFINITY, f64::min);
+    let zone_max = heights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    let (alt_a, alt_b) = (f64::from(alt_a), f64::from(alt_b));
+    ranges_overlap(alt_a.min(alt_b), alt_a.max(alt_b), zone_min, zone_max)
+}
+
+/// Returns true if the segment between the two route points exceeds the
+///  ceiling of the restriction
+fn altitude_exceeds(
+    a: &RoutePoint,
+    b: &RoutePoint,
+    restriction: &RestrictionDetails,
+    vertical: &VerticalModel,
+) -> bool {
+    let (Some(alt_a), Some(alt_b)) = (a.altitude_meters, b.altitude_meters) else {
+        return true;
+    };
+
+    let ceiling = limit_heights(a, b, restriction, vertical)
+        .into_iter()
+        .map(|(_, upper)| upper)
+        .fold(f64::INFINITY, f64::min);
+
+    f64::from(alt_a.max(alt_b)) > ceiling
+}
+
+/// Returns true if the segment between the two route points overlaps the
+///  time window of the restriction
+///
+/// A restriction without a start or end time is active indefinitely in
+///  that direction. A restriction with a schedule is only active in its
+///  windows, with sun events taken at the start of the segment.
+fn time_overlaps(a: &RoutePoint, b: &RoutePoint, restriction: &RestrictionDetails) -> bool {
+    let (Some(time_a), Some(time_b)) = (a.timestamp, b.timestamp) else {
+        return true;
+    };
+
+    let zone_start = restriction
+        .timestamp_start
+        .unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let zone_end = restriction
+        .timestamp_end
+        .unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+    let (start, end) = (time_a.min(time_b), time_a.max(time_b));
+    if !ranges_overlap(start, end, zone_start, zone_end) {
+        return false;
+    }
+
+    let Some(schedule) = &restriction.schedule else {
+        return true;
+    };
+
+    let location = gis::Coordinates {
+        latitude: a.latitude,
+        longitude: a.longitude,
+    };
+    schedule.is_active(&location, start.max(zone_start), end.min(zone_end))
+}
+
+/// Returns true if the segment between the two route points conflicts
+///  with the restriction
+fn segment_conflicts(
+    a: &RoutePoint,
+    b: &RoutePoint,
+    restriction: &RestrictionDetails,
+    vertical: &VerticalModel,
+) -> bool {
+    if restriction.zone_type != gis::ZoneType::Restriction {
+        return false;
+    }
+
+    let altitude_conflicts = match restriction.kind {
+        RestrictionKind::NoFly | RestrictionKind::Authorisation | RestrictionKind::Conditional => {
+            altitude_overlaps(a, b, restriction, vertical)
+        }
+        RestrictionKind::Ceiling => altitude_exceeds(a, b, restriction, vertical),
+    };
+
+    if !altitude_conflicts || !time_overlaps(a, b, restriction) {
+        return false;
+    }
+
+    let segment = [a, b].map(|p| gis::Coordinates {
+        latitude: p.latitude,
+        longitude: p.longitude,
+    });
+
+    restriction.shape.intersects_path(&segment)
+}
+
+/// Returns all conflicts of the route with the restrictions, ordered by
+///  segment and zone identifier
+///
+/// A route with a single point is checked as a segment of zero length.
+pub fn check_route(
+    path: &[RoutePoint],
+    restrictions: &HashMap<String, RestrictionDetails>,
+    vertical: &VerticalModel,
+) -> Vec<Conflict> {
+    let segments: Vec<(&RoutePoint, &RoutePoint)> = match path {
+        [] => vec![],
+        [point] => vec![(point, point)],
+        _ => path.windows(2).map(|w| (&w[0], &w[1])).collect(),
+    };
+
+    let mut conflicts = vec![];
+    for (segment_index, (a, b)) in segments.into_iter().enumerate() {
+        let mut zone_ids = restrictions
+            .iter()
+            .filter(|(_, restriction)| segment_conflicts(a, b, restriction, vertical))
+            .map(|(zone_id, _)| zone_id.clone())
+            .collect::<Vec<_>>();
+
+        zone_ids.sort();
+        conflicts.extend(zone_ids.into_iter().map(|zone_id| Conflict {
+            zone_id,
+            segment_index,
+        }));
+    }
+
+    conflicts
+}
+
+/// Checks the flight plan against the restrictions in the airspace snapshot
+///
+/// The flight plan is rejected if its route conflicts with any restriction.
+///  Each conflict is reported as a violation, and the result names the
+///  violated zones. Until the restrictions of the region are loaded, every
+///  flight plan is rejected.
+pub fn check_flight_plan(flight_plan: &FlightPlan, airspace: &Airspace) -> Decision {
+    if !airspace.loaded {
+        region_warn!(
+            "Flight plan {} cannot be checked, the airspace is not loaded yet.",
+            flight_plan.flight_plan_id
+        );
+
+        let message = "The restrictions of the region are not loaded yet.".to_string();
+        return Decision {
+            accepted: false,
+            result: Some(message.clone()),
+            violations: vec![Violation {
+                rule_code: RULE_AIRSPACE_UNAVAILABLE.to_string(),
+                severity: Severity::Error,
+                message,
+                feature_id: None,
+                segment_index: None,
+            }],
+        };
+    }
+
+    let conflicts = check_route(
+        &flight_plan.route,
+        &airspace.restrictions,
+        &airspace.vertical,
+    );
+    if conflicts.is_empty() {
+        return Decision {
+            accepted: true,
+            result: None,
+            violations: vec![],
+        };
+    }
+
+    let mut zone_ids = conflicts
+        .iter()
+        .map(|c| c.zone_id.as_str())
+        .collect::<Vec<_>>();
+    zone_ids.sort();
+    zone_ids.dedup();
+
+    region_info!(
+        "Flight plan {} violates restrictions: {:?}",
+        flight_plan.flight_plan_id,
+        zone_ids
+    );
+
+    let result = format!("Route violates restrictions: {}.", zone_ids.join(", "));
+    let violations = conflicts
+        .into_iter()
+        .map(|conflict| {
+            let ceiling = airspace
+                .restrictions
+                .get(&conflict.zone_id)
+                .filter(|restriction| restriction.kind == RestrictionKind::Ceiling)
+                .map(|restriction| restriction.upper);
+
+            let (rule_code, message) = match ceiling {
+                Some(ceiling) => (
+                    RULE_ALTITUDE_CEILING,
+                    format!(
+                        "Segment {} exceeds the ceiling of {} ({}).",
+                        conflict.segment_index, conflict.zone_id, ceiling
+                    ),
+                ),
+                None => (
+                    RULE_RESTRICTED_ZONE,
+                    format!(
+                        "Segment {} crosses restriction {}.",
+                        conflict.segment_index, conflict.zone_id
+                    ),
+                ),
+            };
+
+            Violation {
+                rule_code: rule_code.to_string(),
+                severity: Severity::Error,
+                message,
+                feature_id: Some(conflict.zone_id),
+                segment_index: Some(conflict.segment_index),
+            }
+        })
+        .collect();
+
+    Decision {
+        accepted: false,
+        result: Some(result),
+        violations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::schedule::Schedule;
+    use crate::region::shape::Shape;
+    use crate::region::utils::{AltitudeLimit, AltitudeReference};
+    use crate::region::vertical::Grid;
+    use lib_common::time::Duration;
+    use std::sync::Arc;
+
+    fn point(latitude: f64, longitude: f64) -> RoutePoint {
+        RoutePoint {
+            latitude,
+            longitude,
+            altitude_meters: None,
+            timestamp: None,
+        }
+    }
+
+    /// Square restriction from (0, 0) to (1, 1), from 0 to 100 meters
+    fn square() -> RestrictionDetails {
+        RestrictionDetails {
+            shape: Shape::Polygon(
+                [(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)]
+                    .iter()
+                    .map(|(latitude, longitude)| gis::Coordinates {
+                        latitude: *latitude,
+                        longitude: *longitude,
+                    })
+                    .collect(),
+            ),
+            timestamp_start: None,
+            timestamp_end: None,
+            schedule: None,
+            zone_type: gis::ZoneType::Restriction,
+            lower: AltitudeLimit::SURFACE,
+            upper: AltitudeLimit::msl(100.),
+            kind: RestrictionKind::NoFly,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_route_2d() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let flat = VerticalModel::default();
+        let restrictions = HashMap::from([("square".to_string(), square())]);
+
+        // second segment crosses the square
+        let path = vec![point(-1.0, -1.0), point(-1.0, 0.5), point(2.0, 0.5)];
+        let conflicts = check_route(&path, &restrictions, &flat);
+        assert_eq!(
+            conflicts,
+            vec![Conflict {
+                zone_id: "square".to_string(),
+                segment_index: 1
+            }]
+        );
+
+        // single point inside
+        assert_eq!(
+            check_route(&[point(0.5, 0.5)], &restrictions, &flat).len(),
+            1
+        );
+
+        // passes by
+        let path = vec![point(2.0, -1.0), point(2.0, 2.0)];
+        assert!(check_route(&path, &restrictions, &flat).is_empty());
+        assert!(check_route(&[], &restrictions, &flat).is_empty());
+
+        // vertiport zones are not restrictions
+        let mut port = square();
+        port.zone_type = gis::ZoneType::Port;
+        let restrictions = HashMap::from([("port".to_string(), port)]);
+        assert!(check_route(&[point(0.5, 0.5)], &restrictions, &flat).is_empty());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_check_route_altitude() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let flat = VerticalModel::default();
+        let restrictions = HashMap::from([("square".to_string(), square())]);
+        let mut a = point(0.5, -1.0);
+        let mut b = point(0.5, 2.0);
+
+        // above the restriction
+        a.altitude_meters = Some(150.);
+        b.altitude_meters = Some(200.);
+        assert!(check_route(&[a, b], &restrictions, &flat).is_empty());
+
+        // descending into the restriction
+        b.altitude_meters = Some(50.);
+        assert_eq!(check_route(&[a, b], &restrictions, &flat).len(), 1);
+
+        // swapped limits are tolerated
+        let mut swapped = square();
+        swapped.lower = AltitudeLimit::msl(100.);
+        swapped.upper = AltitudeLimit::SURFACE;
+        let restrictions = HashMap::from([("swapped".to_string(), swapped)]);
+        assert_eq!(check_route(&[a, b], &restrictions, &flat).len(), 1);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_check_route_time() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let flat = VerticalModel::default();
+        let now = Utc::now();
+        let mut restriction = square();
+        restriction.timestamp_start = Some(now + Duration::hours(1));
+        restriction.timestamp_end = Some(now + Duration::hours(2));
+        let restrictions = HashMap::from([("tfr".to_string(), restriction)]);
+
+        let mut a = point(0.5, -1.0);
+        let mut b = point(0.5, 2.0);
+
+        // before the restriction is active
+        a.timestamp = Some(now);
+        b.timestamp = Some(now + Duration::minutes(30));
+        assert!(check_route(&[a, b], &restrictions, &flat).is_empty());
+
+        // during the restriction
+        b.timestamp = Some(now + Duration::minutes(90));
+        assert_eq!(check_route(&[a, b], &restrictions, &flat).len(), 1);
+
+        // unknown times are treated as overlapping
+        a.timestamp = None;
+        b.timestamp = None;
+        assert_eq!(check_route(&[a, b], &restrictions, &flat).len(), 1);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_check_route_schedule() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let flat = VerticalModel::default();
+        let mut restriction = square();
+        restriction.schedule =
+            Some(Schedule::parse("MON-FRI 0800-1800", chrono_tz::Tz::UTC).unwrap());
+        let restrictions = HashMap::from([("weekdays".to_string(), restriction)]);
+
+        let time = |text: &str| text.parse::<DateTime<Utc>>().ok();
+        let mut a = point(0.5, -1.0);
+        let mut b = point(0.5, 2.0);
+
+        // Wednesday evening, after the window
+        a.timestamp = time("2024-06-05T18:30:00Z");
+        b.timestamp = time("2024-06-05T19:00:00Z");
+        assert!(check_route(&[a, b], &restrictions, &flat).is_empty());
+
+        // Saturday during the daily hours
+        a.timestamp = time("2024-06-08T12:00:00Z");
+        b.timestamp = time("2024-06-08T12:30:00Z");
+        assert!(check_route(&[a, b], &restrictions, &flat).is_empty());
+
+        // running into the window on Thursday morning
+        a.timestamp = time("2024-06-06T07:30:00Z");
+        b.timestamp = time("2024-06-06T08:15:00Z");
+        assert_eq!(check_route(&[a, b], &restrictions, &flat).len(), 1);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_check_route_ceiling() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let flat = VerticalModel::default();
+        let mut cell = square();
+        cell.kind = RestrictionKind::Ceiling;
+        cell.upper = AltitudeLimit::agl(60.);
+        let restrictions = HashMap::from([("cell".to_string(), cell)]);
+
+        let mut a = point(0.5, -1.0);
+        let mut b = point(0.5, 2.0);
+
+        // below the ceiling
+        a.altitude_meters = Some(30.);
+        b.altitude_meters = Some(60.);
+        assert!(check_route(&[a, b], &restrictions, &flat).is_empty());
+
+        // climbing above the ceiling
+        b.altitude_meters = Some(90.);
+        assert_eq!(check_route(&[a, b], &restrictions, &flat).len(), 1);
+
+        // unknown altitudes are treated as exceeding the ceiling
+        b.altitude_meters = None;
+        assert_eq!(check_route(&[a, b], &restrictions, &flat).len(), 1);
+
+        // passes by above the ceiling
+        let a = RoutePoint {
+            altitude_meters: Some(120.),
+            ..point(2.0, -1.0)
+        };
+        let b = RoutePoint {
+            altitude_meters: Some(120.),
+            ..point(2.0, 2.0)
+        };
+        assert!(check_route(&[a, b], &restrictions, &flat).is_empty());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_check_route_vertical_reference() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // terrain at 300 meters, the geoid 45 meters above the ellipsoid
+        let vertical = VerticalModel::new(
+            Grid::parse("ncols 1 nrows 1 xllcorner -1 yllcorner -1 cellsize 3 300").ok(),
+            Grid::parse("ncols 1 nrows 1 xllcorner -10 yllcorner -10 cellsize 20 45").ok(),
+        );
+
+        let mut cell = square();
+        cell.kind = RestrictionKind::Ceiling;
+        cell.upper = AltitudeLimit::new(400., "FT", AltitudeReference::Agl).unwrap();
+
+        let mut flight_level = square();
+        flight_level.lower = AltitudeLimit::new(65., "FL", AltitudeReference::Standard).unwrap();
+        flight_level.upper = AltitudeLimit::UNLIMITED;
+
+        let restrictions = HashMap::from([
+            ("cell".to_string(), cell),
+            ("fl065".to_string(), flight_level),
+        ]);
+
+        let mut a = point(0.5, -1.0);
+        let mut b = point(0.5, 2.0);
+
+        // below 400 ft above the terrain, which is 466.92 m above the ellipsoid
+        a.altitude_meters = Some(400.);
+        b.altitude_meters = Some(460.);
+        assert!(check_route(&[a, b], &restrictions, &vertical).is_empty());
+        assert_eq!(
+            check_route(&[a, b], &restrictions, &VerticalModel::default()).len(),
+            1
+        );
+
+        // FL065 is 2026.2 m above the ellipsoid in the standard atmosphere
+        a.altitude_meters = Some(2000.);
+        b.altitude_meters = Some(2020.);
+        let conflicts = check_route(&[a, b], &restrictions, &vertical);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].zone_id, "cell");
+
+        b.altitude_meters = Some(2100.);
+        let zone_ids = check_route(&[a, b], &restrictions, &vertical)
+            .into_iter()
+            .map(|c| c.zone_id)
+            .collect::<Vec<_>>();
+        assert_eq!(zone_ids, vec!["cell", "fl065"]);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_check_flight_plan() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let mut other = square();
+        if let Shape::Polygon(vertices) = &mut other.shape {
+            vertices.iter_mut().for_each(|v| v.longitude += 2.0);
+        }
+
+        let airspace = Airspace {
+            restrictions: Arc::new(HashMap::from([
+                ("b".to_string(), square()),
+                ("a".to_string(), other),
+            ])),
+            loaded: true,
+            ..Default::default()
+        };
+
+        let mut flight_plan = FlightPlan {
+            flight_plan_id: "test".to_string(),
+            route: vec![point(0.5, -1.0), point(0.5, 4.0)],
+            ..Default::default()
+        };
+
+        let decision = check_flight_plan(&flight_plan, &airspace);
+        assert!(!decision.accepted);
+        assert_eq!(
+            decision.result,
+            Some("Route violates restrictions: a, b.".to_string())
+        );
+        assert_eq!(decision.violations.len(), 2);
+        assert_eq!(
+            decision.violations[0],
+            Violation {
+                rule_code: RULE_RESTRICTED_ZONE.to_string(),
+                severity: Severity::Error,
+                feature_id: Some("a".to_string()),
+                segment_index: Some(0),
+                message: "Segment 0 crosses restriction a.".to_string(),
+            }
+        );
+
+        let mut cell = square();
+        cell.kind = RestrictionKind::Ceiling;
+        cell.upper = AltitudeLimit::agl(60.);
+        let ceiling_airspace = Airspace {
+            restrictions: Arc::new(HashMap::from([("cell".to_string(), cell)])),
+            loaded: true,
+            ..Default::default()
+        };
+
+        let decision = check_flight_plan(&flight_plan, &ceiling_airspace);
+        assert!(!decision.accepted);
+        assert_eq!(
+            decision.violations,
+            vec![Violation {
+                rule_code: RULE_ALTITUDE_CEILING.to_string(),
+                severity: Severity::Error,
+                feature_id: Some("cell".to_string()),
+                segment_index: Some(0),
+                message: "Segment 0 exceeds the ceiling of cell (60 m AGL).".to_string(),
+            }]
+        );
+
+        flight_plan.route = vec![point(5.0, 5.0), point(6.0, 6.0)];
+        let decision = check_flight_plan(&flight_plan, &airspace);
+        assert!(decision.accepted);
+        assert!(decision.result.is_none());
+        assert!(decision.violations.is_empty());
+
+        // nothing is accepted before the restrictions are loaded
+        let decision = check_flight_plan(&flight_plan, &Airspace::default());
+        assert!(!decision.accepted);
+        assert_eq!(decision.violations.len(), 1);
+        assert_eq!(
+            decision.violations[0].rule_code,
+            RULE_AIRSPACE_UNAVAILABLE.to_string()
+        );
+
+        ut_info!("Success.");
+    }
+}
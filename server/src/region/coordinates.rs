@@ -0,0 +1,292 @@
+//! Latitudes and longitudes in the notations of the datasets
+//!
+//! Angles in degrees, minutes and seconds are read into a [`DMS`] and
+//!  converted to decimal degrees, within the range of their axis. The
+//!  fixed width records holding them are read by [`field`].
+
+use dms_coordinates::{Cardinal, DMS};
+
+/// A field of a fixed width record, as the documented one based start
+///  column and the width
+pub type Field = (usize, usize);
+
+/// Returns the trimmed content of a field, empty if the line is too short
+pub fn field(line: &str, (start, width): Field) -> &str {
+    let start = start - 1;
+    line.get(start..line.len().min(start + width))
+        .unwrap_or_default()
+        .trim()
+}
+
+/// Axis of an angle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    /// North or south of the equator
+    Latitude,
+
+    /// East or west of the prime meridian
+    Longitude,
+}
+
+impl Axis {
+    /// Number of digits of the degrees in packed notations
+    fn degree_digits(self) -> usize {
+        match self {
+            Axis::Latitude => 2,
+            Axis::Longitude => 3,
+        }
+    }
+
+    /// Largest angle in degrees on either side of the axis
+    fn max_degrees(self) -> f64 {
+        match self {
+            Axis::Latitude => 90.0,
+            Axis::Longitude => 180.0,
+        }
+    }
+
+    /// Cardinal point of a hemisphere letter, if it lies on the axis
+    fn cardinal(self, hemisphere: char) -> Option<Cardinal> {
+        match (self, hemisphere.to_ascii_uppercase()) {
+            (Axis::Latitude, 'N') => Some(Cardinal::North),
+            (Axis::Latitude, 'S') => Some(Cardinal::South),
+            (Axis::Longitude, 'E') => Some(Cardinal::East),
+            (Axis::Longitude, 'W') => Some(Cardinal::West),
+            _ => None,
+        }
+    }
+}
+
+/// Converts degrees, minutes and seconds in a hemisphere to decimal degrees
+///
+/// The minutes may carry decimals if there are no seconds. Returns `None`
+///  if a part is out of range.
+fn to_degrees(
+    degrees: f64,
+    minutes: f64,
+    seconds: f64,
+    hemisphere: char,
+    axis: Axis,
+) -> Option<f64> {
+    let cardinal = axis.cardinal(hemisphere)?;
+    let valid = (0.0..=axis.max_degrees()).contains(&degrees)
+        && degrees.fract() == 0.0
+        && (0.0..60.0).contains(&minutes)
+        && (0.0..60.0).contains(&seconds)
+        && (minutes.fract() == 0.0 || seconds == 0.0);
+    if !valid {
+        return None;
+    }
+
+    let seconds = seconds + minutes.fract() * 60.0;
+    let angle = DMS::new(degrees as u16, minutes as u8, seconds, Some(cardinal)).to_ddeg_angle();
+    (angle.abs() <= axis.max_degrees()).then_some(angle)
+}
+
+/// Splits the hemisphere letter from the end or the start of the text
+fn split_hemisphere(text: &str) -> Option<(char, &str)> {
+    let text = text.trim();
+    match (text.chars().next()?, text.chars().last()?) {
+        (_, last) if last.is_ascii_alphabetic() => Some((last, text[..text.len() - 1].trim_end())),
+        (first, _) if first.is_ascii_alphabetic() => Some((first, text[1..].trim_start())),
+        _ => None,
+    }
+}
+
+/// Parses an angle of degrees, minutes and optional seconds separated by a
+///  character, with the hemisphere, such as `52:21.5 N`
+fn parse_separated(text: &str, separator: char, seconds_required: bool, axis: Axis) -> Option<f64> {
+    let (hemisphere, body) = split_hemisphere(text)?;
+    let parts = body
+        .split(separator)
+        .map(|part| part.trim().parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    match parts[..] {
+        [degrees, minutes] if !seconds_required => {
+            to_degrees(degrees, minutes, 0.0, hemisphere, axis)
+        }
+        [degrees, minutes, seconds] => to_degrees(degrees, minutes, seconds, hemisphere, axis),
+        _ => None,
+    }
+}
+
+/// Parses an angle of dash separated degrees, minutes and seconds followed
+///  by the hemisphere, such as `30-11-43.940N`
+pub fn parse_dashed(text: &str, axis: Axis) -> Option<f64> {
+    parse_separated(text, '-', true, axis)
+}
+
+/// Parses an angle of colon separated degrees and minutes, with optional
+///  seconds, followed by the hemisphere, such as `52:21:30 N` or `52:21.5N`
+pub fn parse_colon(text: &str, axis: Axis) -> Option<f64> {
+    parse_separated(text, ':', false, axis)
+}
+
+/// Parses an angle of packed degrees, minutes and seconds with the
+///  hemisphere before or after, such as `5212N`, `385323.5N` or `N30143187`
+///
+/// The degrees take two digits in latitudes and three in longitudes. They
+///  are followed by two digits of minutes, then optionally two digits of
+///  seconds and two of hundredths of seconds. The minutes or the seconds
+///  may carry decimals if they come last.
+pub fn parse_packed(text: &str, axis: Axis) -> Option<f64> {
+    let (hemisphere, body) = split_hemisphere(text)?;
+    let (integer, fraction) = match body.split_once('.') {
+        Some((integer, fraction)) if fraction.bytes().all(|b| b.is_ascii_digit()) => {
+            (integer, &body[integer.len()..])
+        }
+        Some(_) => return None,
+        None => (body, ""),
+    };
+    if !integer.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let n = axis.degree_digits();
+    let last = |start: usize| {
+        format!("{}{}", &integer[start..], fraction)
+            .parse::<f64>()
+            .ok()
+    };
+    let (minutes, seconds) = match integer.len().checked_sub(n)? {
+        2 => (last(n)?, 0.0),
+        4 => (integer[n..n + 2].parse().ok()?, last(n + 2)?),
+        6 if fraction.is_empty() => (
+            integer[n..n + 2].parse().ok()?,
+            integer[n + 2..].parse::<f64>().ok()? / 100.0,
+        ),
+        _ => return None,
+    };
+
+    let degrees = integer[..n].parse::<f64>().ok()?;
+    to_degrees(degrees, minutes, seconds, hemisphere, axis)
+}
+
+/// Parses an angle in packed degrees, minutes and seconds, or in decimal
+///  degrees with a hemisphere or a sign, such as `385323.5N`, `38.8897N` or
+///  `-77.0365`
+pub fn parse_degrees(text: &str, axis: Axis) -> Option<f64> {
+    if let Some(angle) = parse_packed(text, axis) {
+        return Some(angle);
+    }
+
+    let angle = match split_hemisphere(text) {
+        Some((hemisphere, body)) => {
+            let angle = body.parse::<f64>().ok().filter(|angle| *angle >= 0.0)?;
+            match axis.cardinal(hemisphere)? {
+                Cardinal::South | Cardinal::West => -angle,
+                _ => angle,
+            }
+        }
+        None => text.trim().parse::<f64>().ok()?,
+    };
+
+    (angle.abs() <= axis.max_degrees()).then_some(angle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_field() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert_eq!(field("FIX1 ABC  30", (6, 5)), "ABC");
+        assert_eq!(field("FIX1 ABC  30", (11, 5)), "30");
+        assert_eq!(field("FIX1", (6, 5)), "");
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_dashed() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let latitude = parse_dashed("30-11-43.940N", Axis::Latitude).unwrap();
+        assert!((latitude - 30.195539).abs() < 1e-6);
+        let longitude = parse_dashed(" 097-40-11.310W ", Axis::Longitude).unwrap();
+        assert!((longitude + 97.669808).abs() < 1e-6);
+        let latitude = parse_dashed("33-52-00.000s", Axis::Latitude).unwrap();
+        assert!((latitude + 33.866667).abs() < 1e-6);
+        let longitude = parse_dashed("151-12-30.000E", Axis::Longitude).unwrap();
+        assert!((longitude - 151.208333).abs() < 1e-6);
+
+        // wrong hemisphere, out of range or malformed
+        assert_eq!(parse_dashed("097-40-11.310W", Axis::Latitude), None);
+        assert_eq!(parse_dashed("91-00-00.000N", Axis::Latitude), None);
+        assert_eq!(parse_dashed("097-60-00.000W", Axis::Longitude), None);
+        assert_eq!(parse_dashed("097-40-60.000W", Axis::Longitude), None);
+        assert_eq!(parse_dashed("097-40W", Axis::Longitude), None);
+        assert_eq!(parse_dashed("097-40-11-00W", Axis::Longitude), None);
+        assert_eq!(parse_dashed("", Axis::Longitude), None);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_colon() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let latitude = parse_colon("52:21:36 N", Axis::Latitude).unwrap();
+        assert!((latitude - 52.36).abs() < 1e-9);
+        let longitude = parse_colon("070:45.25W", Axis::Longitude).unwrap();
+        assert!((longitude + 70.754167).abs() < 1e-6);
+
+        assert_eq!(parse_colon("52:21.5:30 N", Axis::Latitude), None);
+        assert_eq!(parse_colon("52.5:21 N", Axis::Latitude), None);
+        assert_eq!(parse_colon("52 N", Axis::Latitude), None);
+        assert_eq!(parse_colon("52:21:30", Axis::Latitude), None);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_packed() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // NOTAM qualifier, TFR and ARINC 424 notations
+        let latitude = parse_packed("5212N", Axis::Latitude).unwrap();
+        assert!((latitude - 52.2).abs() < 1e-9);
+        assert_eq!(parse_packed("00445E", Axis::Longitude), Some(4.75));
+        assert_eq!(parse_packed("383000N", Axis::Latitude), Some(38.5));
+        assert_eq!(parse_packed("0771500.0W", Axis::Longitude), Some(-77.25));
+        let latitude = parse_packed("N30143187", Axis::Latitude).unwrap();
+        assert!((latitude - 30.242186).abs() < 1e-6);
+        let longitude = parse_packed("W097520833", Axis::Longitude).unwrap();
+        assert!((longitude + 97.868981).abs() < 1e-6);
+
+        assert_eq!(parse_packed("386000N", Axis::Latitude), None);
+        assert_eq!(parse_packed("9100N", Axis::Latitude), None);
+        assert_eq!(parse_packed("N3014318.7", Axis::Latitude), None);
+        assert_eq!(parse_packed("38.5N", Axis::Latitude), None);
+        assert_eq!(parse_packed("E30143187", Axis::Latitude), None);
+        assert_eq!(parse_packed("N3014 3187", Axis::Latitude), None);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_degrees() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert_eq!(parse_degrees("38.5N", Axis::Latitude), Some(38.5));
+        assert_eq!(parse_degrees("077.25W", Axis::Longitude), Some(-77.25));
+        assert_eq!(parse_degrees("-12.5", Axis::Latitude), Some(-12.5));
+        assert_eq!(parse_degrees("383000N", Axis::Latitude), Some(38.5));
+        assert_eq!(parse_degrees("0771500.0W", Axis::Longitude), Some(-77.25));
+
+        assert_eq!(parse_degrees("91.0N", Axis::Latitude), None);
+        assert_eq!(parse_degrees("386000N", Axis::Latitude), None);
+        assert_eq!(parse_degrees("-1.5N", Axis::Latitude), None);
+        assert_eq!(parse_degrees("north", Axis::Latitude), None);
+
+        ut_info!("Success.");
+    }
+}
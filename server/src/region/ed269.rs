@@ -0,0 +1,645 @@
+//! Reader for UAS geographical zones in the EUROCAE ED-269 format
+//!
+//! An ED-269 publication is a JSON object whose `features` are
+//!  `UASZoneVersion` objects. Each zone has a restriction type, time
+//!  periods in `applicability` and one or more volumes in `geometry`, each
+//!  a polygon or circle with lower and upper limits.
+//!
+//! Zones of type `PROHIBITED`, `REQ_AUTHORISATION` and `CONDITIONAL` become
+//!  restrictions of the [`RestrictionKind`] of their type. svc-gis only
+//!  distinguishes ports from restrictions, so all three are pushed as
+//!  [`ZoneType::Restriction`], and flights through them are checked as
+//!  no-fly, as authorisations and conditions cannot be handled here.
+//!  `NO_RESTRICTION` zones are informational and skipped. Daily schedules
+//!  are kept as the [`Schedule`] of each restriction, in UTC.
+
+use crate::region::geojson::to_ring;
+use crate::region::schedule::{DailyRule, Schedule, SunEvent, TimeOfDay, Weekdays};
+use crate::region::shape::Shape;
+use crate::region::utils::{distance_to_meters, AltitudeLimit, AltitudeReference};
+use crate::region::{RestrictionDetails, RestrictionKind};
+use chrono_tz::Tz;
+use lib_common::time::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use svc_gis_client_grpc::prelude::gis::{Coordinates, ZoneType};
+
+/// Unit of measurement of volumes that do not publish one
+const DEFAULT_UOM: &str = "M";
+
+/// A period of a zone, open ended if a bound is missing, with its daily
+///  schedule if any
+type Period = (
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+    Option<Schedule>,
+);
+
+/// Errors when reading an ED-269 publication
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum Ed269Error {
+    /// The document is not valid JSON
+    #[error("error: Invalid ED-269 document: {0}")]
+    Invalid(String),
+
+    /// The document has no list of zones
+    #[error("error: ED-269 document has no features.")]
+    MissingFeatures,
+}
+
+/// Restriction type of a zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Restriction {
+    /// Flights are prohibited
+    Prohibited,
+
+    /// Flights require an authorisation of the zone authority
+    ReqAuthorisation,
+
+    /// Flights are subject to the restriction conditions
+    Conditional,
+
+    /// No restriction, the zone is informational
+    NoRestriction,
+}
+
+/// A time period in which the zone applies
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Applicability {
+    /// `YES` if the zone applies at all times
+    #[serde(default)]
+    pub permanent: Option<String>,
+
+    /// Start of the period, RFC 3339
+    #[serde(default)]
+    pub start_date_time: Option<String>,
+
+    /// End of the period, RFC 3339
+    #[serde(default)]
+    pub end_date_time: Option<String>,
+
+    /// Daily activation times within the period
+    #[serde(default)]
+    pub schedule: Vec<DailyPeriod>,
+}
+
+/// Daily activation times on the given days
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyPeriod {
+    /// Days of the week, such as `MON`, or `ANY`
+    pub day: Vec<String>,
+
+    /// Start time of day in UTC, `HH:MM` or `HH:MM:SS`
+    #[serde(default)]
+    pub start_time: Option<String>,
+
+    /// Start at a sun event instead, `BMCT`, `SR`, `SS` or `EECT`
+    #[serde(default)]
+    pub start_event: Option<String>,
+
+    /// End time of day in UTC, before the start time if overnight
+    #[serde(default)]
+    pub end_time: Option<String>,
+
+    /// End at a sun event instead, `BMCT`, `SR`, `SS` or `EECT`
+    #[serde(default)]
+    pub end_event: Option<String>,
+}
+
+/// Horizontal projection of a volume
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum Projection {
+    /// Outer ring of `[longitude, latitude]` positions followed by any holes
+    Polygon {
+        /// Rings of the polygon
+        coordinates: Vec<Vec<Vec<f64>>>,
+    },
+
+    /// Circle around a `[longitude, latitude]` center
+    Circle {
+        /// Center of the circle
+        center: Vec<f64>,
+
+        /// Radius in the unit of the volume
+        radius: f64,
+    },
+}
+
+/// A volume of a zone
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Volume {
+    /// Unit of the limits and radius, `M` or `FT`
+    #[serde(default)]
+    pub uom_dimensions: Option<String>,
+
+    /// Lower limit, the surface if missing
+    #[serde(default)]
+    pub lower_limit: Option<f64>,
+
+    /// Reference of the lower limit, `AGL`, `AMSL` or `WGS84`
+    #[serde(default)]
+    pub lower_vertical_reference: Option<String>,
+
+    /// Upper limit, unlimited if missing
+    #[serde(default)]
+    pub upper_limit: Option<f64>,
+
+    /// Reference of the upper limit, `AGL`, `AMSL` or `WGS84`
+    #[serde(default)]
+    pub upper_vertical_reference: Option<String>,
+
+    /// Horizontal extent of the volume
+    pub horizontal_projection: Projection,
+}
+
+/// A `UASZoneVersion`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UasZone {
+    /// Identifier of the zone
+    pub identifier: String,
+
+    /// Name of the zone
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Restriction type
+    pub restriction: Restriction,
+
+    /// Periods in which the zone applies, permanent if empty
+    #[serde(default)]
+    pub applicability: Vec<Applicability>,
+
+    /// Volumes of the zone
+    pub geometry: Vec<Volume>,
+}
+
+/// Returns true if the document looks like an ED-269 publication rather
+///  than a GeoJSON feature collection
+pub fn is_ed269(data: &[u8]) -> bool {
+    match serde_json::from_slice::<Value>(data) {
+        Ok(Value::Object(document)) => {
+            document.get("type").and_then(Value::as_str) != Some("FeatureCollection")
+                && document.get("features").is_some_and(Value::is_array)
+        }
+        _ => false,
+    }
+}
+
+/// Parses an ED-269 publication
+///
+/// Returns an error if the document itself cannot be read, otherwise the
+///  result of parsing each zone in document order.
+pub fn parse_zones(data: &[u8]) -> Result<Vec<Result<UasZone, String>>, Ed269Error> {
+    let document: Value =
+        serde_json::from_slice(data).map_err(|e| Ed269Error::Invalid(e.to_string()))?;
+
+    let Some(Value::Array(features)) = document.get("features") else {
+        return Err(Ed269Error::MissingFeatures);
+    };
+
+    Ok(features
+        .iter()
+        .map(|feature| UasZone::deserialize(feature).map_err(|e| e.to_string()))
+        .collect())
+}
+
+/// Converts a zone to restrictions, one per volume and period
+///
+/// Identifiers are the prefix and the zone identifier, followed by a
+///  sequence number if the zone has more than one volume or period.
+///  Periods that ended before `now` are dropped.
+pub fn to_restrictions(
+    zone: &UasZone,
+    prefix: &str,
+    now: DateTime<Utc>,
+) -> Result<Vec<(String, RestrictionDetails)>, String> {
+    let kind = match zone.restriction {
+        Restriction::Prohibited => RestrictionKind::NoFly,
+        Restriction::ReqAuthorisation => RestrictionKind::Authorisation,
+        Restriction::Conditional => RestrictionKind::Conditional,
+        Restriction::NoRestriction => return Ok(vec![]),
+    };
+
+    if zone.identifier.trim().is_empty() {
+        return Err("missing identifier".to_string());
+    }
+
+    if zone.geometry.is_empty() {
+        return Err(format!("{}: no geometry", zone.identifier));
+    }
+
+    let error = |e: String| format!("{}: {}", zone.identifier, e);
+    let volumes = zone
+        .geometry
+        .iter()
+        .map(to_volume)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(error)?;
+    let periods = to_periods(&zone.applicability, now).map_err(error)?;
+
+    let count = volumes.len() * periods.len();
+    let mut restrictions = vec![];
+    for (shape, lower, upper) in &volumes {
+        for (timestamp_start, timestamp_end, schedule) in &periods {
+            let label = match count {
+                1 => format!("{}{}", prefix, zone.identifier),
+                _ => format!("{}{}-{}", prefix, zone.identifier, restrictions.len() + 1),
+            };
+
+            let details = RestrictionDetails {
+                shape: shape.clone(),
+                timestamp_start: *timestamp_start,
+                timestamp_end: *timestamp_end,
+                schedule: schedule.clone(),
+                lower: *lower,
+                upper: *upper,
+                zone_type: ZoneType::Restriction,
+                kind,
+            };
+
+            restrictions.push((label, details));
+        }
+    }
+
+    Ok(restrictions)
+}
+
+/// Converts a volume to its boundary and altitude limits
+fn to_volume(volume: &Volume) -> Result<(Shape, AltitudeLimit, AltitudeLimit), String> {
+    let uom = volume.uom_dimensions.as_deref().unwrap_or(DEFAULT_UOM);
+    let shape = match &volume.horizontal_projection {
+        Projection::Polygon { coordinates } => match coordinates.first() {
+            Some(ring) => Shape::Polygon(to_ring(ring)?),
+            None => return Err("polygon has no rings".to_string()),
+        },
+        Projection::Circle { center, radius } => {
+            let center = match center[..] {
+                [longitude, latitude]
+                    if (-180.0..=180.0).contains(&longitude)
+                        && (-90.0..=90.0).contains(&latitude) =>
+                {
+                    Coordinates {
+                        latitude,
+                        longitude,
+                    }
+                }
+                _ => return Err(format!("invalid circle center {:?}", center)),
+            };
+
+            let radius = distance_to_meters(*radius, uom)
+                .filter(|radius| *radius > 0.0)
+                .ok_or_else(|| format!("invalid radius {} {}", radius, uom))?;
+
+            Shape::Circle {
+                center,
+                radius_meters: radius,
+            }
+        }
+    };
+
+    let lower = match volume.lower_limit {
+        Some(limit) => to_limit(limit, uom, volume.lower_vertical_reference.as_deref())?,
+        None => AltitudeLimit::SURFACE,
+    };
+
+    let upper = match volume.upper_limit {
+        Some(limit) => to_limit(limit, uom, volume.upper_vertical_reference.as_deref())?,
+        None => AltitudeLimit::UNLIMITED,
+    };
+
+    if lower.meters > upper.meters {
+        return Err(format!(
+            "lower limit {} m above upper limit {} m",
+            lower.meters, upper.meters
+        ));
+    }
+
+    Ok((shape, lower, upper))
+}
+
+/// Converts an altitude limit with its unit and reference
+///
+/// Altitudes without a reference are taken as above ground, with a warning.
+fn to_limit(value: f64, uom: &str, reference: Option<&str>) -> Result<AltitudeLimit, String> {
+    let reference = match reference.map(str::to_uppercase).as_deref() {
+        None => {
+            region_warn!(
+                "No vertical reference for the limit {} {}, taken as AGL.",
+                value,
+                uom
+            );
+            AltitudeReference::Agl
+        }
+        Some("AGL") => AltitudeReference::Agl,
+        Some("AMSL") => AltitudeReference::Msl,
+        Some("WGS84") => AltitudeReference::Ellipsoid,
+        Some(other) => return Err(format!("unknown vertical reference '{}'", other)),
+    };
+
+    AltitudeLimit::new(value, uom, reference).ok_or_else(|| format!("unknown unit '{}'", uom))
+}
+
+/// Parses an optional RFC 3339 timestamp
+fn to_timestamp(timestamp: &Option<String>) -> Result<Option<DateTime<Utc>>, String> {
+    timestamp
+        .as_ref()
+        .map(|timestamp| {
+            DateTime::parse_from_rfc3339(timestamp)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| format!("invalid timestamp '{}': {}", timestamp, e))
+        })
+        .transpose()
+}
+
+/// Converts a daily period to a rule of a schedule
+fn to_rule(daily: &DailyPeriod) -> Result<DailyRule, String> {
+    let days = daily
+        .day
+        .iter()
+        .map(|day| Weekdays::parse(day).ok_or_else(|| format!("invalid day '{}'", day)))
+        .reduce(|days, day| Ok(days? | day?))
+        .ok_or("daily period without days")??;
+
+    let time_of_day = |time: &Option<String>, event: &Option<String>| match (time, event) {
+        (_, Some(event)) => SunEvent::parse(event)
+            .map(|event| TimeOfDay::Sun(event, 0))
+            .ok_or_else(|| format!("invalid sun event '{}'", event)),
+        (Some(time), None) => TimeOfDay::parse(time)
+            .filter(|time| matches!(time, TimeOfDay::Clock(_)))
+            .ok_or_else(|| format!("invalid time of day '{}'", time)),
+        (None, None) => Err("daily period without times".to_string()),
+    };
+
+    Ok(DailyRule {
+        days,
+        start: time_of_day(&daily.start_time, &daily.start_event)?,
+        end: time_of_day(&daily.end_time, &daily.end_event)?,
+    })
+}
+
+/// Reads the periods of the zone with their daily schedules
+///
+/// A zone without periods is permanent. Periods that ended before `now`
+///  are dropped.
+fn to_periods(applicability: &[Applicability], now: DateTime<Utc>) -> Result<Vec<Period>, String> {
+    if applicability.is_empty() {
+        return Ok(vec![(None, None, None)]);
+    }
+
+    let mut periods = vec![];
+    for period in applicability {
+        let permanent = period
+            .permanent
+            .as_deref()
+            .is_some_and(|p| p.eq_ignore_ascii_case("YES"));
+        let (start, end) = match permanent {
+            true => (None, None),
+            false => (
+                to_timestamp(&period.start_date_time)?,
+                to_timestamp(&period.end_date_time)?,
+            ),
+        };
+
+        if let (Some(start), Some(end)) = (start, end) {
+            if start >= end {
+                return Err(format!("period ends at {} before it starts", end));
+            }
+        }
+
+        let schedule = match period.schedule.is_empty() {
+            true => None,
+            false => Some(Schedule {
+                timezone: Tz::UTC,
+                rules: period
+                    .schedule
+                    .iter()
+                    .map(to_rule)
+                    .collect::<Result<Vec<_>, _>>()?,
+            }),
+        };
+
+        if !matches!(end, Some(end) if end <= now) {
+            periods.push((start, end, schedule));
+        }
+    }
+
+    periods.sort_by_key(|(start, _, _)| *start);
+    Ok(periods)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ED-269 publication shipped for tests
+    const ED269_FIXTURE: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/nl_ed269.json");
+
+    fn time(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn zones() -> Vec<Result<UasZone, String>> {
+        parse_zones(&std::fs::read(ED269_FIXTURE).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_zones() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let zones = zones();
+        assert_eq!(zones.len(), 6);
+        assert!(zones[5].is_err());
+
+        let zone = zones[0].as_ref().unwrap();
+        assert_eq!(zone.identifier, "EHP1");
+        assert_eq!(zone.restriction, Restriction::Prohibited);
+        assert_eq!(zone.geometry.len(), 1);
+
+        let zone = zones[1].as_ref().unwrap();
+        assert_eq!(zone.restriction, Restriction::ReqAuthorisation);
+        assert_eq!(zone.applicability[0].schedule.len(), 1);
+
+        assert!(is_ed269(&std::fs::read(ED269_FIXTURE).unwrap()));
+        assert!(!is_ed269(
+            br#"{ "type": "FeatureCollection", "features": [] }"#
+        ));
+        assert!(!is_ed269(b"[]"));
+        assert_eq!(parse_zones(b"{}").unwrap_err(), Ed269Error::MissingFeatures);
+        assert!(matches!(parse_zones(b"{"), Err(Ed269Error::Invalid(_))));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_to_restrictions() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let zones = zones();
+        let now = time("2024-06-05T12:00:00Z");
+
+        // permanent polygon, limits in feet above mean sea level
+        let restrictions = to_restrictions(zones[0].as_ref().unwrap(), "TEST-", now).unwrap();
+        assert_eq!(restrictions.len(), 1);
+        let (label, details) = &restrictions[0];
+        assert_eq!(label, "TEST-EHP1");
+        assert!(details.timestamp_start.is_none());
+        assert!(details.timestamp_end.is_none());
+        assert_eq!(details.lower.meters, 0.0);
+        assert!((details.upper.meters - 609.6).abs() < 1e-3);
+        assert_eq!(details.upper.to_string(), "2000 ft AMSL");
+        assert!(matches!(&details.shape, Shape::Polygon(vertices) if vertices.len() == 5));
+        assert_eq!(details.kind, RestrictionKind::NoFly);
+        assert_eq!(details.zone_type, ZoneType::Restriction);
+
+        // the type of the zone is kept
+        let restrictions = to_restrictions(zones[1].as_ref().unwrap(), "TEST-", now).unwrap();
+        assert!(restrictions
+            .iter()
+            .all(|(_, details)| details.kind == RestrictionKind::Authorisation));
+
+        // conditional circle within a period
+        let restrictions = to_restrictions(zones[2].as_ref().unwrap(), "TEST-", now).unwrap();
+        assert_eq!(restrictions.len(), 1);
+        let details = &restrictions[0].1;
+        assert_eq!(details.timestamp_start, Some(time("2024-06-01T00:00:00Z")));
+        assert_eq!(details.timestamp_end, Some(time("2024-07-01T00:00:00Z")));
+        assert_eq!(details.upper, AltitudeLimit::agl(120.0));
+        assert_eq!(details.kind, RestrictionKind::Conditional);
+        let center = Coordinates {
+            latitude: 52.09,
+            longitude: 5.12,
+        };
+        assert_eq!(
+            details.shape,
+            Shape::Circle {
+                center,
+                radius_meters: 500.0
+            }
+        );
+
+        // no restriction
+        let restrictions = to_restrictions(zones[3].as_ref().unwrap(), "TEST-", now).unwrap();
+        assert!(restrictions.is_empty());
+
+        // lower limit above the upper limit
+        assert!(to_restrictions(zones[4].as_ref().unwrap(), "TEST-", now).is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_to_limit() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert_eq!(
+            to_limit(120.0, "M", Some("agl")),
+            Ok(AltitudeLimit::agl(120.0))
+        );
+        assert_eq!(
+            to_limit(120.0, "M", Some("AMSL")).unwrap().reference,
+            AltitudeReference::Msl
+        );
+        assert_eq!(
+            to_limit(120.0, "M", Some("WGS84")).unwrap().reference,
+            AltitudeReference::Ellipsoid
+        );
+
+        // a missing reference is taken as above ground
+        assert_eq!(to_limit(120.0, "M", None), Ok(AltitudeLimit::agl(120.0)));
+
+        assert!(to_limit(120.0, "M", Some("QNH")).is_err());
+        assert!(to_limit(120.0, "XX", Some("AGL")).is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_schedule() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // weekdays from 08:00 to 18:00 until 2024-06-10, two volumes
+        let zone = zones()[1].clone().unwrap();
+
+        // Wednesday 2024-06-05 at noon
+        let now = time("2024-06-05T12:00:00Z");
+        let restrictions = to_restrictions(&zone, "TEST-", now).unwrap();
+
+        // one restriction per volume, active on weekdays within the period
+        assert_eq!(restrictions.len(), 2);
+        assert_eq!(restrictions[0].0, "TEST-EHR2-1");
+        assert_eq!(restrictions[1].0, "TEST-EHR2-2");
+        let details = &restrictions[0].1;
+        assert_eq!(details.timestamp_start, Some(time("2024-06-03T00:00:00Z")));
+        assert_eq!(details.timestamp_end, Some(time("2024-06-10T12:00:00Z")));
+        let schedule = details.schedule.as_ref().unwrap();
+        assert_eq!(schedule.timezone, Tz::UTC);
+        assert_eq!(
+            schedule.windows(&details.shape.center(), now, details.timestamp_end.unwrap()),
+            vec![
+                (time("2024-06-05T08:00:00Z"), time("2024-06-05T18:00:00Z")),
+                (time("2024-06-06T08:00:00Z"), time("2024-06-06T18:00:00Z")),
+                (time("2024-06-07T08:00:00Z"), time("2024-06-07T18:00:00Z")),
+                (time("2024-06-10T08:00:00Z"), time("2024-06-10T18:00:00Z")),
+            ]
+        );
+
+        // sun events and overnight periods
+        let period = Applicability {
+            permanent: Some("NO".to_string()),
+            start_date_time: None,
+            end_date_time: None,
+            schedule: vec![
+                DailyPeriod {
+                    day: vec!["ANY".to_string()],
+                    start_time: Some("22:00Z".to_string()),
+                    start_event: None,
+                    end_time: Some("02:00:00Z".to_string()),
+                    end_event: None,
+                },
+                DailyPeriod {
+                    day: vec!["SAT".to_string(), "SUN".to_string()],
+                    start_time: None,
+                    start_event: Some("SR".to_string()),
+                    end_time: None,
+                    end_event: Some("SS".to_string()),
+                },
+            ],
+        };
+        let periods = to_periods(std::slice::from_ref(&period), now).unwrap();
+        assert_eq!(periods.len(), 1);
+        let schedule = periods[0].2.as_ref().unwrap();
+        assert_eq!(schedule.rules.len(), 2);
+        assert_eq!(schedule.rules[0].days, Weekdays::ALL);
+        assert_eq!(schedule.rules[1].end, TimeOfDay::Sun(SunEvent::Sunset, 0));
+
+        // invalid days and times
+        let mut invalid = period.clone();
+        invalid.schedule[0].day = vec!["SOMEDAY".to_string()];
+        assert!(to_periods(&[invalid], now).is_err());
+        let mut invalid = period;
+        invalid.schedule[1].start_event = None;
+        assert!(to_periods(&[invalid], now).is_err());
+
+        // ended periods are dropped
+        let period = Applicability {
+            permanent: None,
+            start_date_time: Some("2024-01-01T00:00:00Z".to_string()),
+            end_date_time: Some("2024-02-01T00:00:00Z".to_string()),
+            schedule: vec![],
+        };
+        assert!(to_periods(&[period], now).unwrap().is_empty());
+
+        ut_info!("Success.");
+    }
+}
@@ -0,0 +1,274 @@
+//! Minimal GeoJSON reader for published zone datasets
+//!
+//! Only the parts needed for airspace zones are read: the features of a
+//!  `FeatureCollection` with a `Polygon` or `MultiPolygon` geometry and
+//!  their properties. Positions are `[longitude, latitude]` as required by
+//!  RFC 7946, holes in polygons are ignored.
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use svc_gis_client_grpc::prelude::gis::Coordinates;
+
+/// Minimum number of positions of a closed linear ring
+const MIN_RING_POSITIONS: usize = 4;
+
+/// Errors when reading a GeoJSON document
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum GeoJsonError {
+    /// The document is not valid JSON
+    #[error("error: Invalid GeoJSON document: {0}")]
+    Invalid(String),
+
+    /// The document is valid JSON but not a feature collection
+    #[error("error: Expected a FeatureCollection, found '{0}'.")]
+    NotFeatureCollection(String),
+}
+
+/// Top level document
+#[derive(Debug, Deserialize)]
+struct FeatureCollection {
+    /// GeoJSON object type
+    #[serde(rename = "type")]
+    kind: String,
+
+    /// Features, parsed one by one so a malformed feature can be skipped
+    #[serde(default)]
+    features: Vec<Value>,
+}
+
+/// Geometry of a zone
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", content = "coordinates")]
+pub enum Geometry {
+    /// Outer ring followed by any holes
+    Polygon(Vec<Vec<Vec<f64>>>),
+
+    /// Several polygons
+    MultiPolygon(Vec<Vec<Vec<Vec<f64>>>>),
+}
+
+/// A single GeoJSON feature
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Feature {
+    /// Optional feature identifier
+    #[serde(default)]
+    pub id: Option<Value>,
+
+    /// The zone geometry
+    pub geometry: Geometry,
+
+    /// Feature properties
+    #[serde(default)]
+    pub properties: Option<Map<String, Value>>,
+}
+
+/// Parses a feature collection
+///
+/// Returns an error if the document itself cannot be read, otherwise the
+///  result of parsing each feature in document order.
+pub fn parse_features(data: &[u8]) -> Result<Vec<Result<Feature, String>>, GeoJsonError> {
+    let collection: FeatureCollection =
+        serde_json::from_slice(data).map_err(|e| GeoJsonError::Invalid(e.to_string()))?;
+
+    if collection.kind != "FeatureCollection" {
+        return Err(GeoJsonError::NotFeatureCollection(collection.kind));
+    }
+
+    Ok(collection
+        .features
+        .into_iter()
+        .map(|feature| serde_json::from_value::<Feature>(feature).map_err(|e| e.to_string()))
+        .collect())
+}
+
+impl Feature {
+    /// Returns the feature identifier as a string
+    pub fn id(&self) -> Option<String> {
+        match &self.id {
+            Some(Value::String(id)) => Some(id.clone()),
+            Some(Value::Number(id)) => Some(id.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Returns the first present property of the given names, ignoring case
+    pub fn property(&self, names: &[&str]) -> Option<&Value> {
+        let properties = self.properties.as_ref()?;
+        names.iter().find_map(|name| {
+            properties
+                .iter()
+                .find(|(key, value)| key.eq_ignore_ascii_case(name) && !value.is_null())
+                .map(|(_, value)| value)
+        })
+    }
+
+    /// Returns the first present property of the given names as a string
+    ///
+    /// Numbers and booleans are converted to their string representation.
+    pub fn property_str(&self, names: &[&str]) -> Option<String> {
+        match self.property(names)? {
+            Value::String(value) => Some(value.trim().to_string()),
+            Value::Number(value) => Some(value.to_string()),
+            Value::Bool(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Returns the outer ring of each polygon of the feature
+    pub fn polygons(&self) -> Result<Vec<Vec<Coordinates>>, String> {
+        let polygons = match &self.geometry {
+            Geometry::Polygon(rings) => vec![rings],
+            Geometry::MultiPolygon(polygons) => polygons.iter().collect(),
+        };
+
+        if polygons.is_empty() {
+            return Err("geometry has no polygons".to_string());
+        }
+
+        polygons
+            .into_iter()
+            .map(|rings| match rings.first() {
+                Some(ring) => to_ring(ring),
+                None => Err("polygon has no rings".to_string()),
+            })
+            .collect()
+    }
+}
+
+/// Converts a linear ring of `[longitude, latitude]` positions
+pub fn to_ring(ring: &[Vec<f64>]) -> Result<Vec<Coordinates>, String> {
+    if ring.len() < MIN_RING_POSITIONS {
+        return Err(format!(
+            "ring has {} positions, expected at least {}",
+            ring.len(),
+            MIN_RING_POSITIONS
+        ));
+    }
+
+    ring.iter()
+        .map(|position| match position[..] {
+            [longitude, latitude, ..]
+                if (-180.0..=180.0).contains(&longitude) && (-90.0..=90.0).contains(&latitude) =>
+            {
+                Ok(Coordinates {
+                    latitude,
+                    longitude,
+                })
+            }
+            _ => Err(format!("invalid position {:?}", position)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parse_features() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let data = br#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "id": 7,
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[[4.0, 52.0], [5.0, 52.0], [5.0, 53.0], [4.0, 52.0]]]
+                    },
+                    "properties": { "Name": "zone", "upperLimit": 120 }
+                },
+                {
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "MultiPolygon",
+                        "coordinates": [
+                            [[[4.0, 52.0], [5.0, 52.0], [5.0, 53.0], [4.0, 52.0]]],
+                            [[[6.0, 52.0], [7.0, 52.0], [7.0, 53.0], [6.0, 52.0]]]
+                        ]
+                    }
+                },
+                { "type": "Feature", "geometry": { "type": "Point", "coordinates": [4.0, 52.0] } },
+                { "type": "Feature", "geometry": null }
+            ]
+        }"#;
+
+        let features = parse_features(data).unwrap();
+        assert_eq!(features.len(), 4);
+        assert!(features[2].is_err());
+        assert!(features[3].is_err());
+
+        let feature = features[0].as_ref().unwrap();
+        assert_eq!(feature.id(), Some("7".to_string()));
+        assert_eq!(feature.property_str(&["name"]), Some("zone".to_string()));
+        assert_eq!(
+            feature.property_str(&["lower", "UPPERLIMIT"]),
+            Some("120".to_string())
+        );
+        assert!(feature.property(&["missing"]).is_none());
+
+        let polygons = feature.polygons().unwrap();
+        assert_eq!(polygons.len(), 1);
+        assert_eq!(polygons[0][1].longitude, 5.0);
+        assert_eq!(polygons[0][1].latitude, 52.0);
+
+        let feature = features[1].as_ref().unwrap();
+        assert!(feature.id().is_none());
+        assert_eq!(feature.polygons().unwrap().len(), 2);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_polygons() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let feature = |coordinates: Vec<Vec<Vec<f64>>>| Feature {
+            id: None,
+            geometry: Geometry::Polygon(coordinates),
+            properties: None,
+        };
+
+        // Too few positions
+        let ring = vec![vec![4.0, 52.0], vec![5.0, 52.0], vec![4.0, 52.0]];
+        assert!(feature(vec![ring]).polygons().is_err());
+
+        // Latitude out of range
+        let ring = vec![
+            vec![4.0, 52.0],
+            vec![5.0, 52.0],
+            vec![5.0, 95.0],
+            vec![4.0, 52.0],
+        ];
+        assert!(feature(vec![ring]).polygons().is_err());
+
+        // Missing latitude
+        let ring = vec![vec![4.0], vec![5.0, 52.0], vec![5.0, 53.0], vec![4.0, 52.0]];
+        assert!(feature(vec![ring]).polygons().is_err());
+
+        assert!(feature(vec![]).polygons().is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_invalid_document() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert!(matches!(
+            parse_features(b"not json"),
+            Err(GeoJsonError::Invalid(_))
+        ));
+        assert_eq!(
+            parse_features(br#"{ "type": "Feature" }"#).unwrap_err(),
+            GeoJsonError::NotFeatureCollection("Feature".to_string())
+        );
+
+        ut_info!("Success.");
+    }
+}
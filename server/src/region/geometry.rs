@@ -0,0 +1,264 @@
+//! Validation of restriction polygons
+//!
+//! Polygons are checked and normalised when the restrictions are stored,
+//!  so flight plans are checked against the same rings that are sent to
+//!  svc-gis. Zones that cannot be repaired are quarantined: they are logged
+//!  with the reason and left out, instead of being rejected by svc-gis or
+//!  silently covering the wrong airspace.
+
+use crate::region::shape::{to_local, Shape};
+use crate::region::utils::{close_ring, segments_intersect};
+use crate::region::RestrictionDetails;
+use std::collections::HashMap;
+use svc_gis_client_grpc::prelude::gis::Coordinates;
+
+/// Minimum number of vertices of a closed ring, the first one repeated
+const MIN_RING_VERTICES: usize = 4;
+
+/// Smallest area of a zone in square meters, about ten by ten meters
+const MIN_AREA_SQUARE_METERS: f64 = 100.0;
+
+/// Reasons a polygon is quarantined
+#[derive(thiserror::Error, Debug, Clone, Copy, PartialEq)]
+pub enum GeometryError {
+    /// A vertex is outside the latitude or longitude range, often because
+    ///  the two were swapped
+    #[error("error: Vertex {0} out of range: latitude {1}, longitude {2}")]
+    OutOfRange(usize, f64, f64),
+
+    /// The ring has too few distinct vertices to enclose an area
+    #[error("error: Ring has only {0} vertices")]
+    TooFewVertices(usize),
+
+    /// Two edges of the ring cross or touch
+    #[error("error: Edges {0} and {1} intersect")]
+    SelfIntersection(usize, usize),
+
+    /// The ring encloses less than [`MIN_AREA_SQUARE_METERS`]
+    #[error("error: Ring area of {0:.1} square meters is too small")]
+    TooSmall(f64),
+}
+
+/// Checks a polygon ring and returns it closed, without duplicate
+///  vertices and counter-clockwise
+///
+/// Counter-clockwise outer rings follow the right-hand rule of GeoJSON
+///  (RFC 7946), whatever the winding of the source.
+pub fn normalize_ring(ring: &[Coordinates]) -> Result<Vec<Coordinates>, GeometryError> {
+    if let Some((i, vertex)) = ring.iter().enumerate().find(|(_, v)| {
+        !(-90.0..=90.0).contains(&v.latitude) || !(-180.0..=180.0).contains(&v.longitude)
+    }) {
+        return Err(GeometryError::OutOfRange(
+            i,
+            vertex.latitude,
+            vertex.longitude,
+        ));
+    }
+
+    let mut ring = close_ring(ring.to_vec());
+    if ring.len() < MIN_RING_VERTICES {
+        return Err(GeometryError::TooFewVertices(ring.len()));
+    }
+
+    if let Some((i, j)) = self_intersection(&ring) {
+        return Err(GeometryError::SelfIntersection(i, j));
+    }
+
+    let area = signed_area_square_meters(&ring);
+    if area.abs() < MIN_AREA_SQUARE_METERS {
+        return Err(GeometryError::TooSmall(area.abs()));
+    }
+
+    if area < 0.0 {
+        ring.reverse();
+    }
+
+    Ok(ring)
+}
+
+/// Normalises the polygons of the restrictions and quarantines the
+///  restrictions that cannot be repaired
+///
+/// Circles and arcs are kept as they are if the polygon replacing them
+///  within the chord error is valid.
+pub fn normalize_restrictions(
+    restrictions: &mut HashMap<String, RestrictionDetails>,
+    max_chord_error_meters: f64,
+) {
+    restrictions.retain(|label, details| {
+        let normalized = match &details.shape {
+            Shape::Polygon(ring) => normalize_ring(ring).map(Some),
+            shape => normalize_ring(&shape.to_polygon(max_chord_error_meters)).map(|_| None),
+        };
+
+        match normalized {
+            Ok(Some(ring)) => {
+                details.shape = Shape::Polygon(ring);
+                true
+            }
+            Ok(None) => true,
+            Err(e) => {
+                region_warn!("Quarantined restriction {}: {}", label, e);
+                false
+            }
+        }
+    });
+}
+
+/// First pair of non-adjacent edges of a closed ring that intersect
+fn self_intersection(ring: &[Coordinates]) -> Option<(usize, usize)> {
+    let edges = ring.len() - 1;
+    (0..edges).find_map(|i| {
+        (i + 2..edges)
+            // the first and last edges share the closing vertex
+            .filter(|&j| !(i == 0 && j == edges - 1))
+            .find(|&j| segments_intersect(&ring[i], &ring[i + 1], &ring[j], &ring[j + 1]))
+            .map(|j| (i, j))
+    })
+}
+
+/// Area of a closed ring in square meters, positive if counter-clockwise
+fn signed_area_square_meters(ring: &[Coordinates]) -> f64 {
+    let points = ring
+        .iter()
+        .map(|v| to_local(&ring[0], v))
+        .collect::<Vec<_>>();
+
+    points
+        .windows(2)
+        .map(|w| w[0].0 * w[1].1 - w[1].0 * w[0].1)
+        .sum::<f64>()
+        / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::utils::AltitudeLimit;
+    use crate::region::RestrictionKind;
+    use svc_gis_client_grpc::prelude::gis::ZoneType;
+
+    fn coords(points: &[(f64, f64)]) -> Vec<Coordinates> {
+        points
+            .iter()
+            .map(|(latitude, longitude)| Coordinates {
+                latitude: *latitude,
+                longitude: *longitude,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_normalize_ring() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // open, clockwise, with a repeated vertex
+        let ring = coords(&[
+            (52.0, 4.0),
+            (52.01, 4.0),
+            (52.01, 4.01),
+            (52.01, 4.01),
+            (52.0, 4.01),
+        ]);
+        assert_eq!(
+            normalize_ring(&ring).unwrap(),
+            coords(&[
+                (52.0, 4.0),
+                (52.0, 4.01),
+                (52.01, 4.01),
+                (52.01, 4.0),
+                (52.0, 4.0)
+            ])
+        );
+
+        // already valid rings are kept as they are
+        let valid = normalize_ring(&ring).unwrap();
+        assert_eq!(normalize_ring(&valid).unwrap(), valid);
+
+        // longitude and latitude swapped
+        let swapped = coords(&[
+            (-104.0471, 30.9321),
+            (-104.0428, 30.9313),
+            (-104.042, 30.9316),
+        ]);
+        assert_eq!(
+            normalize_ring(&swapped).unwrap_err(),
+            GeometryError::OutOfRange(0, -104.0471, 30.9321)
+        );
+
+        let ring = coords(&[(52.0, 4.0), (52.01, 4.01), (52.0, 4.0)]);
+        assert_eq!(
+            normalize_ring(&ring).unwrap_err(),
+            GeometryError::TooFewVertices(3)
+        );
+        assert_eq!(
+            normalize_ring(&[]).unwrap_err(),
+            GeometryError::TooFewVertices(0)
+        );
+
+        // bow tie
+        let ring = coords(&[(52.0, 4.0), (52.01, 4.01), (52.0, 4.01), (52.01, 4.0)]);
+        assert_eq!(
+            normalize_ring(&ring).unwrap_err(),
+            GeometryError::SelfIntersection(0, 2)
+        );
+
+        // about five by three meters
+        let ring = coords(&[
+            (52.0, 4.0),
+            (52.0, 4.00005),
+            (52.00005, 4.00005),
+            (52.00005, 4.0),
+        ]);
+        assert!(matches!(
+            normalize_ring(&ring).unwrap_err(),
+            GeometryError::TooSmall(_)
+        ));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_normalize_restrictions() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let restriction = |shape: Shape| RestrictionDetails {
+            shape,
+            timestamp_start: None,
+            timestamp_end: None,
+            schedule: None,
+            zone_type: ZoneType::Restriction,
+            lower: AltitudeLimit::SURFACE,
+            upper: AltitudeLimit::msl(100.0),
+            kind: RestrictionKind::NoFly,
+        };
+
+        let clockwise = coords(&[(52.0, 4.0), (52.01, 4.0), (52.01, 4.01), (52.0, 4.01)]);
+        let circle = Shape::Circle {
+            center: coords(&[(52.0, 4.0)])[0],
+            radius_meters: 500.0,
+        };
+        let bow_tie = coords(&[(52.0, 4.0), (52.01, 4.01), (52.0, 4.01), (52.01, 4.0)]);
+        let mut restrictions = HashMap::from([
+            (
+                "polygon".to_string(),
+                restriction(Shape::Polygon(clockwise.clone())),
+            ),
+            ("circle".to_string(), restriction(circle.clone())),
+            ("bow tie".to_string(), restriction(Shape::Polygon(bow_tie))),
+        ]);
+        normalize_restrictions(&mut restrictions, 10.0);
+
+        // the stored polygon is the ring sent to svc-gis
+        assert_eq!(
+            restrictions.get("polygon").unwrap().shape,
+            Shape::Polygon(normalize_ring(&clockwise).unwrap())
+        );
+        assert_eq!(restrictions.get("circle").unwrap().shape, circle);
+        assert!(!restrictions.contains_key("bow tie"));
+
+        ut_info!("Success.");
+    }
+}
@@ -0,0 +1,580 @@
+//! Reader for ad-hoc restriction zones drawn in KML or KMZ
+//!
+//! Each placemark with polygons, directly or within a `MultiGeometry`,
+//!  becomes a restriction per polygon. Only the outer boundary of a polygon
+//!  is used. Altitude limits and validity times are read from the
+//!  `ExtendedData` of the placemark, as `Data` or `SchemaData` fields, and
+//!  the validity times also from a `TimeSpan`:
+//!
+//! | Field | Names | Meaning |
+//! | --- | --- | --- |
+//! | Lower limit | `lowerLimit`, `lower`, `floor` | The surface if missing |
+//! | Upper limit | `upperLimit`, `upper`, `ceiling` | Unlimited if missing |
+//! | Altitude unit | `uom`, `unit` | `M` (default), `FT` or `FL` |
+//! | Altitude reference | `reference`, `verticalReference` | `AMSL` (default), `AGL` or `WGS84` |
+//! | Validity | `start`, `validFrom` and `end`, `validTo` | RFC 3339 timestamps or dates |
+//! | Schedule | `schedule` | Daily activation as in NOTAM item `D)`, such as `SAT SUN 1200-2300` |
+//! | Timezone | `timezone`, `tz` | Timezone of the schedule, `UTC` (default) or such as `Europe/Amsterdam` |
+//!
+//! Zones are not bound to a region, each region applies the zones that
+//!  overlap its boundaries.
+
+use crate::region::schedule::{parse_timezone, Schedule};
+use crate::region::shape::{Shape, MAX_CHORD_ERROR_METERS};
+use crate::region::source::{Source, SourceError};
+use crate::region::utils::{
+    path_intersects_polygon, to_closed_ring, AltitudeLimit, AltitudeReference,
+};
+use crate::region::{RegionError, RestrictionDetails, RestrictionKind};
+use chrono_tz::Tz;
+use lib_common::time::{DateTime, NaiveDate, Utc};
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use svc_gis_client_grpc::prelude::gis::{Coordinates, ZoneType};
+
+/// Prefix of the identifiers of ad-hoc zones
+pub const KML_ID_PREFIX: &str = "ARROW-KML-";
+
+/// File extension of KML documents in a directory
+const KML_EXTENSION: &str = "kml";
+
+/// File extension of KMZ archives in a directory
+const KMZ_EXTENSION: &str = "kmz";
+
+/// Leading bytes of a ZIP archive
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Unit of measurement of zones that do not publish one
+const DEFAULT_ALTITUDE_UNIT: &str = "M";
+
+/// Vertical reference of zones that do not publish one
+const DEFAULT_ALTITUDE_REFERENCE: AltitudeReference = AltitudeReference::Msl;
+
+/// Timezone of schedules that do not publish one
+const DEFAULT_TIMEZONE: Tz = Tz::UTC;
+
+/// Extended data names, matched ignoring case in order of preference
+const FIELD_LOWER_LIMIT: &[&str] = &["lowerLimit", "lower", "floor"];
+const FIELD_UPPER_LIMIT: &[&str] = &["upperLimit", "upper", "ceiling"];
+const FIELD_UNIT: &[&str] = &["uom", "unit"];
+const FIELD_REFERENCE: &[&str] = &["reference", "verticalReference"];
+const FIELD_START: &[&str] = &["start", "validFrom"];
+const FIELD_END: &[&str] = &["end", "validTo"];
+const FIELD_SCHEDULE: &[&str] = &["schedule"];
+const FIELD_TIMEZONE: &[&str] = &["timezone", "tz"];
+
+/// Errors when reading a KML document or KMZ archive
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum KmlError {
+    /// The document is not well-formed XML
+    #[error("error: Invalid XML: {0}")]
+    Xml(String),
+
+    /// The KMZ archive cannot be read
+    #[error("error: Invalid KMZ archive: {0}")]
+    Archive(String),
+
+    /// The KMZ archive holds no KML document
+    #[error("error: KMZ archive has no KML document.")]
+    MissingDocument,
+}
+
+/// A placemark with one or more polygons
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placemark {
+    /// Identifier of the placemark, or its name if it has none
+    pub id: String,
+
+    /// Closed boundaries of the polygons
+    pub polygons: Vec<Vec<Coordinates>>,
+
+    /// Lower limit
+    pub lower: AltitudeLimit,
+
+    /// Upper limit
+    pub upper: AltitudeLimit,
+
+    /// Start of the validity, if any
+    pub timestamp_start: Option<DateTime<Utc>>,
+
+    /// End of the validity, if any
+    pub timestamp_end: Option<DateTime<Utc>>,
+
+    /// Recurring activation within the validity, if any
+    pub schedule: Option<Schedule>,
+}
+
+/// Returns true if the node is an element with the local name
+fn has_name(node: Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
+
+/// Trimmed text of the first child element with the local name
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|n| has_name(*n, name))
+        .and_then(|n| n.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+/// Reads the KML document of a KMZ archive
+///
+/// The document is `doc.kml` if present, otherwise the first `.kml` entry.
+fn read_kmz(data: &[u8]) -> Result<String, KmlError> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(data)).map_err(|e| KmlError::Archive(e.to_string()))?;
+
+    let names = archive
+        .file_names()
+        .filter(|name| name.to_lowercase().ends_with(".kml"))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let name = names
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case("doc.kml"))
+        .or(names.first())
+        .ok_or(KmlError::MissingDocument)?;
+
+    let mut document = String::new();
+    archive
+        .by_name(name)
+        .map_err(|e| KmlError::Archive(e.to_string()))?
+        .read_to_string(&mut document)
+        .map_err(|e| KmlError::Archive(e.to_string()))?;
+
+    Ok(document)
+}
+
+/// Parses the placemarks of a KML document or KMZ archive
+///
+/// Returns an error if the document itself cannot be read, otherwise the
+///  result of parsing each placemark in document order.
+pub fn parse_placemarks(data: &[u8]) -> Result<Vec<Result<Placemark, String>>, KmlError> {
+    let text = match data.starts_with(ZIP_MAGIC) {
+        true => read_kmz(data)?,
+        false => String::from_utf8_lossy(data).into_owned(),
+    };
+
+    let document = Document::parse(&text).map_err(|e| KmlError::Xml(e.to_string()))?;
+    Ok(document
+        .descendants()
+        .filter(|n| has_name(*n, "Placemark"))
+        .map(to_placemark)
+        .collect())
+}
+
+/// Values of the extended data of a placemark, by lower case name
+fn extended_data(placemark: Node) -> HashMap<String, String> {
+    placemark
+        .children()
+        .filter(|n| has_name(*n, "ExtendedData"))
+        .flat_map(|n| n.descendants())
+        .filter_map(|n| {
+            let value = match n.tag_name().name() {
+                "Data" if n.is_element() => child_text(n, "value"),
+                "SimpleData" if n.is_element() => n.text().map(str::trim),
+                _ => return None,
+            };
+
+            Some((n.attribute("name")?.to_lowercase(), value?.to_string()))
+        })
+        .collect()
+}
+
+/// Value of the first field present with one of the names
+fn field<'a>(data: &'a HashMap<String, String>, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .find_map(|name| data.get(&name.to_lowercase()))
+        .map(String::as_str)
+}
+
+/// Parses an RFC 3339 timestamp or a date, as midnight UTC
+fn to_timestamp(text: Option<&str>) -> Result<Option<DateTime<Utc>>, String> {
+    let Some(text) = text else {
+        return Ok(None);
+    };
+
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(text) {
+        return Ok(Some(timestamp.with_timezone(&Utc)));
+    }
+
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| Some(time.and_utc()))
+        .ok_or_else(|| format!("invalid timestamp '{}'", text))
+}
+
+/// Parses a `coordinates` element of `longitude,latitude[,altitude]` tuples
+fn to_ring(text: &str) -> Result<Vec<Coordinates>, String> {
+    let ring = text
+        .split_whitespace()
+        .map(|tuple| {
+            let values = tuple
+                .split(',')
+                .map(|value| value.parse::<f64>().ok())
+                .collect::<Option<Vec<_>>>();
+            match values.as_deref() {
+                Some([longitude, latitude, ..])
+                    if (-180.0..=180.0).contains(longitude)
+                        && (-90.0..=90.0).contains(latitude) =>
+                {
+                    Ok(Coordinates {
+                        latitude: *latitude,
+                        longitude: *longitude,
+                    })
+                }
+                _ => Err(format!("invalid coordinates '{}'", tuple)),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    to_closed_ring(ring)
+}
+
+/// Converts a `Placemark` element
+fn to_placemark(node: Node) -> Result<Placemark, String> {
+    let id = node
+        .attribute("id")
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .or_else(|| child_text(node, "name"))
+        .ok_or("placemark without id or name")?
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-");
+    let error = |e: String| format!("{}: {}", id, e);
+
+    let polygons = node
+        .descendants()
+        .filter(|n| has_name(*n, "Polygon"))
+        .map(|polygon| {
+            let coordinates = polygon
+                .children()
+                .find(|n| has_name(*n, "outerBoundaryIs"))
+                .and_then(|n| n.descendants().find(|n| has_name(*n, "coordinates")))
+                .and_then(|n| n.text())
+                .ok_or("polygon without outer boundary")?;
+            to_ring(coordinates)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(error)?;
+    if polygons.is_empty() {
+        return Err(error("no polygon".to_string()));
+    }
+
+    let data = extended_data(node);
+    let unit = field(&data, FIELD_UNIT).unwrap_or(DEFAULT_ALTITUDE_UNIT);
+    let reference = match field(&data, FIELD_REFERENCE) {
+        Some(reference) => AltitudeReference::parse(reference)
+            .ok_or_else(|| error(format!("unknown vertical reference '{}'", reference)))?,
+        None => DEFAULT_ALTITUDE_REFERENCE,
+    };
+    let altitude = |names: &[&str], default: AltitudeLimit| match field(&data, names) {
+        Some(value) => value
+            .parse::<f64>()
+            .ok()
+            .and_then(|value| AltitudeLimit::new(value, unit, reference))
+            .ok_or_else(|| format!("invalid altitude '{}' {}", value, unit)),
+        None => Ok(default),
+    };
+    let lower = altitude(FIELD_LOWER_LIMIT, AltitudeLimit::SURFACE).map_err(error)?;
+    let upper = altitude(FIELD_UPPER_LIMIT, AltitudeLimit::UNLIMITED).map_err(error)?;
+    if lower.meters > upper.meters {
+        return Err(error(format!(
+            "lower limit {} m above upper limit {} m",
+            lower.meters, upper.meters
+        )));
+    }
+
+    let time_span = node.children().find(|n| has_name(*n, "TimeSpan"));
+    let start = field(&data, FIELD_START).or(time_span.and_then(|n| child_text(n, "begin")));
+    let end = field(&data, FIELD_END).or(time_span.and_then(|n| child_text(n, "end")));
+    let timestamp_start = to_timestamp(start).map_err(error)?;
+    let timestamp_end = to_timestamp(end).map_err(error)?;
+
+    let timezone = match field(&data, FIELD_TIMEZONE) {
+        Some(timezone) => parse_timezone(timezone).map_err(|e| error(e.to_string()))?,
+        None => DEFAULT_TIMEZONE,
+    };
+    let schedule = field(&data, FIELD_SCHEDULE)
+        .map(|schedule| Schedule::parse(schedule, timezone))
+        .transpose()
+        .map_err(|e| error(e.to_string()))?;
+
+    Ok(Placemark {
+        id,
+        polygons,
+        lower,
+        upper,
+        timestamp_start,
+        timestamp_end,
+        schedule,
+    })
+}
+
+/// Converts a placemark to restrictions, one per polygon
+///
+/// Identifiers are the prefix and the placemark id, followed by a sequence
+///  number if the placemark has more than one polygon.
+pub fn to_restrictions(placemark: &Placemark, prefix: &str) -> Vec<(String, RestrictionDetails)> {
+    placemark
+        .polygons
+        .iter()
+        .enumerate()
+        .map(|(i, vertices)| {
+            let label = match placemark.polygons.len() {
+                1 => format!("{}{}", prefix, placemark.id),
+                _ => format!("{}{}-{}", prefix, placemark.id, i + 1),
+            };
+
+            let details = RestrictionDetails {
+                shape: Shape::Polygon(vertices.clone()),
+                timestamp_start: placemark.timestamp_start,
+                timestamp_end: placemark.timestamp_end,
+                schedule: placemark.schedule.clone(),
+                lower: placemark.lower,
+                upper: placemark.upper,
+                zone_type: ZoneType::Restriction,
+                kind: RestrictionKind::NoFly,
+            };
+
+            (label, details)
+        })
+        .collect()
+}
+
+/// Reads the zones from the source, skipping malformed documents and
+///  placemarks
+///
+/// A directory source holds `.kml` documents and `.kmz` archives.
+pub async fn load_kml(
+    source: &Source,
+    prefix: &str,
+) -> Result<HashMap<String, RestrictionDetails>, RegionError> {
+    let read_error = |e: SourceError| RegionError::DatasetFailure(e.to_string());
+    let mut documents = source.read_all(KML_EXTENSION).await.map_err(read_error)?;
+    if source.is_directory().await {
+        documents.extend(source.read_all(KMZ_EXTENSION).await.map_err(read_error)?);
+        documents.sort();
+    }
+
+    let mut restrictions = HashMap::new();
+    let mut skipped = 0;
+    for (name, data) in documents {
+        let placemarks = match parse_placemarks(&data) {
+            Ok(placemarks) => placemarks,
+            Err(e) => {
+                region_warn!("Skipping malformed KML document {}: {}", name, e);
+                skipped += 1;
+                continue;
+            }
+        };
+
+        for placemark in placemarks {
+            match placemark {
+                Ok(placemark) => restrictions.extend(to_restrictions(&placemark, prefix)),
+                Err(e) => {
+                    region_warn!("Skipping malformed placemark in {}: {}", name, e);
+                    skipped += 1;
+                }
+            }
+        }
+    }
+
+    region_info!(
+        "Loaded {} KML zones from {}, skipped {} malformed.",
+        restrictions.len(),
+        source,
+        skipped
+    );
+
+    Ok(restrictions)
+}
+
+/// The zones overlapping any of the boundaries
+pub fn overlapping(
+    zones: &HashMap<String, RestrictionDetails>,
+    boundaries: &[Vec<Coordinates>],
+) -> HashMap<String, RestrictionDetails> {
+    zones
+        .iter()
+        .filter(|(_, details)| {
+            let polygon = details.shape.to_polygon(MAX_CHORD_ERROR_METERS);
+            boundaries
+                .iter()
+                .any(|boundary| path_intersects_polygon(&polygon, boundary))
+        })
+        .map(|(label, details)| (label.clone(), details.clone()))
+        .collect()
+}
+
+/// Refreshes the ad-hoc zones overlapping the region boundaries
+///
+/// The zones of the source replace the current ones. If the source cannot
+///  be read, the current zones are kept.
+pub async fn acquire_restrictions(
+    source: &Source,
+    boundaries: &[Vec<Coordinates>],
+    restrictions: &mut HashMap<String, RestrictionDetails>,
+) {
+    match load_kml(source, KML_ID_PREFIX).await {
+        Ok(zones) => *restrictions = overlapping(&zones, boundaries),
+        Err(e) => region_error!("Keeping current KML zones: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::RegionInterface;
+    use std::io::Write;
+
+    /// The ad-hoc zones shipped for tests
+    const KML_FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/adhoc_zones.kml"
+    );
+
+    fn time(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_placemarks() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let placemarks = parse_placemarks(&std::fs::read(KML_FIXTURE).unwrap()).unwrap();
+        assert_eq!(placemarks.len(), 6);
+
+        // altitudes in feet, validity from the time span
+        let placemark = placemarks[0].as_ref().unwrap();
+        assert_eq!(placemark.id, "event-1");
+        assert_eq!(placemark.polygons.len(), 1);
+        assert_eq!(placemark.polygons[0].len(), 5);
+        assert_eq!(placemark.lower.meters, 0.0);
+        assert_eq!(placemark.upper.to_string(), "400 ft AGL");
+        assert_eq!(
+            placemark.timestamp_start,
+            Some(time("2024-06-01T08:00:00Z"))
+        );
+        assert_eq!(placemark.timestamp_end, Some(time("2024-06-01T20:00:00Z")));
+        assert!(placemark.schedule.is_none());
+
+        // multi geometry with schema data, open ended validity
+        let placemark = placemarks[1].as_ref().unwrap();
+        assert_eq!(placemark.id, "stadium");
+        assert_eq!(placemark.polygons.len(), 2);
+        assert_eq!(placemark.upper, AltitudeLimit::msl(120.0));
+        assert_eq!(
+            placemark.timestamp_start,
+            Some(time("2024-06-02T00:00:00Z"))
+        );
+        assert_eq!(placemark.timestamp_end, None);
+
+        // weekend afternoons and evenings in local time
+        let schedule = placemark.schedule.as_ref().unwrap();
+        assert_eq!(schedule.timezone, chrono_tz::Europe::Amsterdam);
+        assert_eq!(
+            schedule.windows(
+                &placemark.polygons[0][0],
+                time("2024-06-07T00:00:00Z"),
+                time("2024-06-10T00:00:00Z")
+            ),
+            vec![
+                (time("2024-06-08T10:00:00Z"), time("2024-06-08T21:00:00Z")),
+                (time("2024-06-09T10:00:00Z"), time("2024-06-09T21:00:00Z")),
+            ]
+        );
+
+        // named placemark without id, unlimited
+        let placemark = placemarks[2].as_ref().unwrap();
+        assert_eq!(placemark.id, "Crane-site");
+        assert_eq!(placemark.upper, AltitudeLimit::UNLIMITED);
+
+        assert_eq!(placemarks[3], Err("pin: no polygon".to_string()));
+        assert_eq!(
+            placemarks[4],
+            Err("bad-altitude: invalid altitude 'high' M".to_string())
+        );
+
+        assert!(matches!(
+            parse_placemarks(b"<kml><Placemark>"),
+            Err(KmlError::Xml(_))
+        ));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_kmz() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let archive = |name: &str| {
+            let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+            writer
+                .start_file(name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer
+                .write_all(&std::fs::read(KML_FIXTURE).unwrap())
+                .unwrap();
+            writer.finish().unwrap().into_inner()
+        };
+
+        let placemarks = parse_placemarks(&archive("doc.kml")).unwrap();
+        assert_eq!(placemarks.len(), 6);
+
+        let error = parse_placemarks(&archive("images/icon.png")).unwrap_err();
+        assert_eq!(error, KmlError::MissingDocument);
+
+        let error = parse_placemarks(b"PK\x03\x04 truncated").unwrap_err();
+        assert!(matches!(error, KmlError::Archive(_)));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_acquire_restrictions() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let source = Source::File(KML_FIXTURE.into());
+        let boundaries = crate::region::nl::RegionImpl::default().get_boundaries();
+        let mut restrictions = HashMap::new();
+        acquire_restrictions(&source, &boundaries, &mut restrictions).await;
+
+        // the zone in the United States is left out
+        let mut labels = restrictions.keys().cloned().collect::<Vec<_>>();
+        labels.sort();
+        assert_eq!(
+            labels,
+            vec![
+                "ARROW-KML-Crane-site",
+                "ARROW-KML-event-1",
+                "ARROW-KML-stadium-1",
+                "ARROW-KML-stadium-2",
+            ]
+        );
+
+        // the current zones are kept if the source cannot be read
+        let source = Source::File("/nonexistent.kml".into());
+        acquire_restrictions(&source, &boundaries, &mut restrictions).await;
+        assert_eq!(restrictions.len(), 4);
+
+        // zones loaded for all regions are split by region
+        let source = Source::File(KML_FIXTURE.into());
+        let us = crate::region::us::RegionImpl::default().get_boundaries();
+        let all = [boundaries.clone(), us.clone()].concat();
+        acquire_restrictions(&source, &all, &mut restrictions).await;
+        assert_eq!(restrictions.len(), 5);
+        assert_eq!(overlapping(&restrictions, &boundaries).len(), 4);
+        assert_eq!(overlapping(&restrictions, &us).len(), 1);
+
+        ut_info!("Success.");
+    }
+}
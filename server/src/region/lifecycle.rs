@@ -0,0 +1,840 @@
+//! Lifecycle of the flight plans handled by this service
+//!
+//! Each flight plan moves through the states below. Any transition that
+//!  is not listed is refused.
+//!
+//! | From        | To                                   |
+//! | ----------- | ------------------------------------ |
+//! | `draft`     | `submitted`, `cancelled`             |
+//! | `submitted` | `accepted`, `rejected`, `cancelled`  |
+//! | `accepted`  | `released`, `cancelled`, `revoked`   |
+//! | `rejected`  | `submitted`, `cancelled`             |
+//! | `released`  | `activated`, `cancelled`, `revoked`  |
+//! | `activated` | `closed`, `revoked`                  |
+//!
+//! `closed`, `cancelled` and `revoked` are final.
+//!
+//! Only `accepted`, `rejected` and `released` flight plans may be amended.
+//!  The amended version replaces the flight plan and moves it to `accepted`
+//!  once the regional authorities accepted it. Until then, and if they
+//!  refuse it, the previous version and its state remain.
+//!
+//! Every state change, and every decision of the regional authorities that
+//!  leaves the state unchanged, is published as a [`LifecycleEvent`].
+//!
+//! A request holds the lock of its flight plan from the state check until
+//!  the decision is recorded, so concurrent requests for the same flight
+//!  plan are handled one after the other. A submission that ends without
+//!  a decision, such as a cancelled request, gives the flight plan back its
+//!  previous version and state. Flight plans in a final state
+//!  are forgotten [`FINAL_RETENTION_HOURS`] after their last change.
+
+use super::{Decision, FlightPlan, RegionError};
+use lib_common::time::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::sync::{broadcast, OwnedMutexGuard};
+
+/// Number of events kept for slow subscribers, older events are dropped
+const EVENT_CAPACITY: usize = 256;
+
+/// Hours a flight plan in a final state is kept before it is forgotten
+pub const FINAL_RETENTION_HOURS: i64 = 24;
+
+/// State of a flight plan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlightPlanState {
+    /// Created, but not submitted yet
+    Draft,
+
+    /// Submitted, waiting for the decision of the regional authorities
+    Submitted,
+
+    /// Accepted by all regional authorities
+    Accepted,
+
+    /// Rejected by at least one regional authority
+    Rejected,
+
+    /// Released for takeoff
+    Released,
+
+    /// The flight is in progress
+    Activated,
+
+    /// The flight has ended
+    Closed,
+
+    /// Cancelled by the operator
+    Cancelled,
+
+    /// Withdrawn by a regional authority
+    Revoked,
+}
+
+impl FlightPlanState {
+    /// Returns true if a flight plan in this state may move to the next state
+    pub fn can_transition_to(self, next: FlightPlanState) -> bool {
+        use FlightPlanState::*;
+
+        matches!(
+            (self, next),
+            (Draft, Submitted | Cancelled)
+                | (Submitted, Accepted | Rejected | Cancelled)
+                | (Accepted, Released | Cancelled | Revoked)
+                | (Rejected, Submitted | Cancelled)
+                | (Released, Activated | Cancelled | Revoked)
+                | (Activated, Closed | Revoked)
+        )
+    }
+
+    /// Returns true if a flight plan in this state may be amended
+    pub fn can_be_amended(self) -> bool {
+        matches!(
+            self,
+            FlightPlanState::Accepted | FlightPlanState::Rejected | FlightPlanState::Released
+        )
+    }
+
+    /// Returns true if no further transitions are possible
+    pub fn is_final(self) -> bool {
+        matches!(
+            self,
+            FlightPlanState::Closed | FlightPlanState::Cancelled | FlightPlanState::Revoked
+        )
+    }
+}
+
+impl fmt::Display for FlightPlanState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self {
+            FlightPlanState::Draft => "draft",
+            FlightPlanState::Submitted => "submitted",
+            FlightPlanState::Accepted => "accepted",
+            FlightPlanState::Rejected => "rejected",
+            FlightPlanState::Released => "released",
+            FlightPlanState::Activated => "activated",
+            FlightPlanState::Closed => "closed",
+            FlightPlanState::Cancelled => "cancelled",
+            FlightPlanState::Revoked => "revoked",
+        };
+
+        write!(f, "{}", state)
+    }
+}
+
+/// Errors when changing the state of a flight plan
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum LifecycleError {
+    /// No flight plan with this identifier is known
+    #[error("error: Unknown flight plan '{0}'.")]
+    UnknownFlightPlan(String),
+
+    /// The flight plan cannot move from its current state to the requested state
+    #[error("error: Flight plan '{flight_plan_id}' cannot move from {from} to {to}.")]
+    IllegalTransition {
+        /// Flight Plan Id
+        flight_plan_id: String,
+
+        /// The current state
+        from: FlightPlanState,
+
+        /// The requested state
+        to: FlightPlanState,
+    },
+
+    /// The flight plan cannot be amended in its current state
+    #[error("error: Flight plan '{flight_plan_id}' cannot be amended while {state}.")]
+    NotAmendable {
+        /// Flight Plan Id
+        flight_plan_id: String,
+
+        /// The current state
+        state: FlightPlanState,
+    },
+}
+
+/// Outcome of a request to a regional authority, with the region short code
+pub type RegionOutcome = (String, Result<Decision, RegionError>);
+
+/// A change of the state of a flight plan, or a decision on it
+#[derive(Debug, Clone)]
+pub struct LifecycleEvent {
+    /// Flight Plan Id
+    pub flight_plan_id: String,
+
+    /// The state before the event
+    pub previous: FlightPlanState,
+
+    /// The state after the event, equal to the previous state if a
+    ///  decision left it unchanged
+    pub state: FlightPlanState,
+
+    /// When the event occurred
+    pub timestamp: DateTime<Utc>,
+
+    /// Outcomes of the regional authority requests that led to the event
+    pub outcomes: Vec<RegionOutcome>,
+}
+
+/// A flight plan and its current state
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlightPlanRecord {
+    /// The latest submitted version of the flight plan
+    pub flight_plan: FlightPlan,
+
+    /// The current state
+    pub state: FlightPlanState,
+
+    /// When the state last changed
+    pub updated: DateTime<Utc>,
+}
+
+/// A flight plan submitted to the regional authorities, waiting for their
+///  decision
+///
+/// Dropped while the flight plan is still `submitted`, such as when the
+///  request is cancelled before the decision is recorded, the flight plan
+///  is given back its previous version and state, so it can be submitted
+///  again.
+#[must_use = "the submission is rolled back when dropped"]
+#[derive(Debug)]
+pub struct Submission<'a> {
+    /// Registry holding the flight plan
+    registry: &'a FlightPlanRegistry,
+
+    /// Identifier of the flight plan
+    flight_plan_id: String,
+
+    /// State before the submission
+    previous_state: FlightPlanState,
+
+    /// Version before the submission
+    previous_flight_plan: FlightPlan,
+}
+
+impl Drop for Submission<'_> {
+    fn drop(&mut self) {
+        let mut records = self.registry.write();
+        let Some(record) = records
+            .get_mut(&self.flight_plan_id)
+            .filter(|record| record.state == FlightPlanState::Submitted)
+        else {
+            return;
+        };
+
+        region_warn!(
+            "Flight plan '{}' was not decided, moving it back to {}.",
+            self.flight_plan_id,
+            self.previous_state
+        );
+        record.flight_plan = std::mem::take(&mut self.previous_flight_plan);
+        record.state = self.previous_state;
+        record.updated = Utc::now();
+        self.registry.publish(
+            &self.flight_plan_id,
+            FlightPlanState::Submitted,
+            self.previous_state,
+            vec![],
+        );
+    }
+}
+
+/// The flight plans handled by this service, by flight plan id
+#[derive(Debug)]
+pub struct FlightPlanRegistry {
+    /// Flight plan records by flight plan id
+    records: RwLock<HashMap<String, FlightPlanRecord>>,
+
+    /// Publishes the lifecycle events of all flight plans
+    events: broadcast::Sender<LifecycleEvent>,
+
+    /// Request locks by flight plan id
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl Default for FlightPlanRegistry {
+    fn default() -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            records: RwLock::default(),
+            events,
+            locks: Mutex::default(),
+        }
+    }
+}
+
+impl FlightPlanRegistry {
+    /// Returns a read lock, recovering the data if a writer panicked
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, FlightPlanRecord>> {
+        self.records.read().unwrap_or_else(|e| {
+            region_warn!("Registry lock was poisoned, recovering.");
+            e.into_inner()
+        })
+    }
+
+    /// Returns a write lock, recovering the data if a writer panicked
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, FlightPlanRecord>> {
+        self.records.write().unwrap_or_else(|e| {
+            region_warn!("Registry lock was poisoned, recovering.");
+            e.into_inner()
+        })
+    }
+
+    /// Publishes a lifecycle event to the current subscribers
+    ///
+    /// Called while holding the write lock, so subscribers receive the
+    ///  events of a flight plan in order.
+    fn publish(
+        &self,
+        flight_plan_id: &str,
+        previous: FlightPlanState,
+        state: FlightPlanState,
+        outcomes: Vec<RegionOutcome>,
+    ) {
+        let event = LifecycleEvent {
+            flight_plan_id: flight_plan_id.to_string(),
+            previous,
+            state,
+            timestamp: Utc::now(),
+            outcomes,
+        };
+
+        // An error only means that nobody is subscribed
+        if self.events.send(event).is_err() {
+            region_debug!("No subscribers for flight plan '{}'.", flight_plan_id);
+        }
+    }
+
+    /// Subscribes to the lifecycle events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.events.subscribe()
+    }
+
+    /// Returns the records of the known flight plans among the given ones,
+    ///  and subscribes to the lifecycle events published after them
+    ///
+    /// Events are published while holding the write lock, so under the read
+    ///  lock no event is both in the records and the subscription, nor
+    ///  missing from both.
+    pub fn watch<'a>(
+        &self,
+        flight_plan_ids: impl IntoIterator<Item = &'a String>,
+    ) -> (Vec<FlightPlanRecord>, broadcast::Receiver<LifecycleEvent>) {
+        let records = self.read();
+        let events = self.events.subscribe();
+        let current = flight_plan_ids
+            .into_iter()
+            .filter_map(|id| records.get(id).cloned())
+            .collect();
+
+        (current, events)
+    }
+
+    /// Waits for the request lock of the flight plan
+    ///
+    /// Held by a request from its state check until its decision is
+    ///  recorded, while the regional authorities are contacted.
+    pub async fn lock(&self, flight_plan_id: &str) -> OwnedMutexGuard<()> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap_or_else(|e| {
+                region_warn!("Registry lock was poisoned, recovering.");
+                e.into_inner()
+            })
+            .entry(flight_plan_id.to_string())
+            .or_default()
+            .clone();
+
+        lock.lock_owned().await
+    }
+
+    /// Forgets the flight plans that reached a final state more than
+    ///  [`FINAL_RETENTION_HOURS`] before `now`, returning their number
+    pub fn evict(&self, now: DateTime<Utc>) -> usize {
+        let expired = now - Duration::hours(FINAL_RETENTION_HOURS);
+        let mut records = self.write();
+        let count = records.len();
+        records.retain(|_, record| !record.state.is_final() || record.updated > expired);
+
+        // Request locks still held or awaited are kept
+        self.locks
+            .lock()
+            .unwrap_or_else(|e| {
+                region_warn!("Registry lock was poisoned, recovering.");
+                e.into_inner()
+            })
+            .retain(|id, lock| records.contains_key(id) || Arc::strong_count(lock) > 1);
+
+        let evicted = count - records.len();
+        if evicted > 0 {
+            region_debug!("Forgot {} final flight plans.", evicted);
+        }
+
+        evicted
+    }
+
+    /// Returns the record of the flight plan, if known
+    pub fn get(&self, flight_plan_id: &str) -> Option<FlightPlanRecord> {
+        self.read().get(flight_plan_id).cloned()
+    }
+
+    /// Moves a new or existing flight plan to `submitted`, storing the
+    ///  submitted version
+    ///
+    /// Unknown flight plans start out as a draft. The submission must be
+    ///  kept until the decision is recorded, see [`Submission`].
+    pub fn submit(&self, flight_plan: FlightPlan) -> Result<Submission<'_>, LifecycleError> {
+        let now = Utc::now();
+        self.evict(now);
+        let mut records = self.write();
+        let record = records
+            .entry(flight_plan.flight_plan_id.clone())
+            .or_insert_with(|| FlightPlanRecord {
+                flight_plan: flight_plan.clone(),
+                state: FlightPlanState::Draft,
+                updated: now,
+            });
+
+        let previous = record.state;
+        check_transition(
+            &flight_plan.flight_plan_id,
+            previous,
+            FlightPlanState::Submitted,
+        )?;
+
+        let flight_plan_id = flight_plan.flight_plan_id.clone();
+        let previous_flight_plan = std::mem::replace(&mut record.flight_plan, flight_plan);
+        record.state = FlightPlanState::Submitted;
+        record.updated = now;
+        self.publish(
+            &flight_plan_id,
+            previous,
+            FlightPlanState::Submitted,
+            vec![],
+        );
+        Ok(Submission {
+            registry: self,
+            flight_plan_id,
+            previous_state: previous,
+            previous_flight_plan,
+        })
+    }
+
+    /// Returns the record of a flight plan that may be amended
+    pub fn amendable(&self, flight_plan_id: &str) -> Result<FlightPlanRecord, LifecycleError> {
+        let records = self.read();
+        let Some(record) = records.get(flight_plan_id) else {
+            region_warn!("Unknown flight plan '{}'.", flight_plan_id);
+            return Err(LifecycleError::UnknownFlightPlan(
+                flight_plan_id.to_string(),
+            ));
+        };
+
+        check_amendment(flight_plan_id, record.state)?;
+        Ok(record.clone())
+    }
+
+    /// Replaces the flight plan with the amended version accepted by the
+    ///  regional authorities and moves it to `accepted`, returning the
+    ///  previous state
+    ///
+    /// A refused amendment is only reported, the previous version and its
+    ///  state remain.
+    pub fn amend(
+        &self,
+        flight_plan: FlightPlan,
+        outcomes: Vec<RegionOutcome>,
+    ) -> Result<FlightPlanState, LifecycleError> {
+        let flight_plan_id = flight_plan.flight_plan_id.clone();
+        let mut records = self.write();
+        let Some(record) = records.get_mut(&flight_plan_id) else {
+            region_warn!("Unknown flight plan '{}'.", flight_plan_id);
+            return Err(LifecycleError::UnknownFlightPlan(flight_plan_id));
+        };
+
+        let previous = record.state;
+        check_amendment(&flight_plan_id, previous)?;
+
+        region_debug!("Flight plan '{}' is amended.", flight_plan_id);
+        record.flight_plan = flight_plan;
+        record.state = FlightPlanState::Accepted;
+        record.updated = Utc::now();
+        self.publish(
+            &flight_plan_id,
+            previous,
+            FlightPlanState::Accepted,
+            outcomes,
+        );
+        Ok(previous)
+    }
+
+    /// Moves the flight plan to the next state, returning the previous state
+    pub fn transition(
+        &self,
+        flight_plan_id: &str,
+        next: FlightPlanState,
+    ) -> Result<FlightPlanState, LifecycleError> {
+        self.decide(flight_plan_id, next, vec![])
+    }
+
+    /// Moves the flight plan to the next state as decided by the regional
+    ///  authorities, returning the previous state
+    pub fn decide(
+        &self,
+        flight_plan_id: &str,
+        next: FlightPlanState,
+        outcomes: Vec<RegionOutcome>,
+    ) -> Result<FlightPlanState, LifecycleError> {
+        let mut records = self.write();
+        let Some(record) = records.get_mut(flight_plan_id) else {
+            region_warn!("Unknown flight plan '{}'.", flight_plan_id);
+            return Err(LifecycleError::UnknownFlightPlan(
+                flight_plan_id.to_string(),
+            ));
+        };
+
+        let previous = record.state;
+        check_transition(flight_plan_id, previous, next)?;
+        record.state = next;
+        record.updated = Utc::now();
+        self.publish(flight_plan_id, previous, next, outcomes);
+        Ok(previous)
+    }
+
+    /// Publishes a decision of the regional authorities that leaves the
+    ///  state of the flight plan unchanged, such as a denied release
+    pub fn report(
+        &self,
+        flight_plan_id: &str,
+        outcomes: Vec<RegionOutcome>,
+    ) -> Result<(), LifecycleError> {
+        let records = self.write();
+        let Some(record) = records.get(flight_plan_id) else {
+            region_warn!("Unknown flight plan '{}'.", flight_plan_id);
+            return Err(LifecycleError::UnknownFlightPlan(
+                flight_plan_id.to_string(),
+            ));
+        };
+
+        self.publish(flight_plan_id, record.state, record.state, outcomes);
+        Ok(())
+    }
+}
+
+/// Refuses the amendment of a flight plan in this state
+fn check_amendment(flight_plan_id: &str, state: FlightPlanState) -> Result<(), LifecycleError> {
+    if state.can_be_amended() {
+        return Ok(());
+    }
+
+    region_warn!(
+        "Flight plan '{}' cannot be amended while {}.",
+        flight_plan_id,
+        state
+    );
+
+    Err(LifecycleError::NotAmendable {
+        flight_plan_id: flight_plan_id.to_string(),
+        state,
+    })
+}
+
+/// Refuses the transition if it is not allowed
+fn check_transition(
+    flight_plan_id: &str,
+    from: FlightPlanState,
+    to: FlightPlanState,
+) -> Result<(), LifecycleError> {
+    if from.can_transition_to(to) {
+        region_debug!(
+            "Flight plan '{}' moves from {} to {}.",
+            flight_plan_id,
+            from,
+            to
+        );
+        return Ok(());
+    }
+
+    region_warn!(
+        "Flight plan '{}' cannot move from {} to {}.",
+        flight_plan_id,
+        from,
+        to
+    );
+
+    Err(LifecycleError::IllegalTransition {
+        flight_plan_id: flight_plan_id.to_string(),
+        from,
+        to,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use FlightPlanState::*;
+
+    fn flight_plan(flight_plan_id: &str) -> FlightPlan {
+        FlightPlan {
+            flight_plan_id: flight_plan_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_can_transition_to() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert!(Submitted.can_transition_to(Accepted));
+        assert!(Accepted.can_transition_to(Released));
+        assert!(Released.can_transition_to(Activated));
+        assert!(Activated.can_transition_to(Closed));
+        assert!(Rejected.can_transition_to(Submitted));
+
+        assert!(!Submitted.can_transition_to(Released));
+        assert!(!Rejected.can_transition_to(Released));
+        assert!(!Activated.can_transition_to(Cancelled));
+
+        let states = [
+            Draft, Submitted, Accepted, Rejected, Released, Activated, Closed, Cancelled, Revoked,
+        ];
+        for state in states.iter().filter(|s| s.is_final()) {
+            assert!(states.iter().all(|next| !state.can_transition_to(*next)));
+        }
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_registry_lifecycle() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let registry = FlightPlanRegistry::default();
+        assert!(registry.get("a").is_none());
+
+        let _a = registry.submit(flight_plan("a")).unwrap();
+        assert_eq!(registry.get("a").unwrap().state, Submitted);
+
+        // already submitted
+        let error = registry.submit(flight_plan("a")).unwrap_err();
+        assert_eq!(
+            error,
+            LifecycleError::IllegalTransition {
+                flight_plan_id: "a".to_string(),
+                from: Submitted,
+                to: Submitted
+            }
+        );
+
+        assert_eq!(registry.transition("a", Accepted).unwrap(), Submitted);
+        assert_eq!(registry.transition("a", Released).unwrap(), Accepted);
+        assert_eq!(registry.get("a").unwrap().state, Released);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_registry_refuses_release_of_rejected() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let registry = FlightPlanRegistry::default();
+        let _a = registry.submit(flight_plan("a")).unwrap();
+        registry.transition("a", Rejected).unwrap();
+
+        let error = registry.transition("a", Released).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("cannot move from rejected to released"));
+        assert_eq!(registry.get("a").unwrap().state, Rejected);
+
+        // a rejected plan may be submitted again
+        let _a = registry.submit(flight_plan("a")).unwrap();
+        assert_eq!(registry.get("a").unwrap().state, Submitted);
+
+        let error = registry.transition("b", Released).unwrap_err();
+        assert_eq!(error, LifecycleError::UnknownFlightPlan("b".to_string()));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_registry_amend() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let registry = FlightPlanRegistry::default();
+        let error = registry.amendable("a").unwrap_err();
+        assert_eq!(error, LifecycleError::UnknownFlightPlan("a".to_string()));
+        let error = registry.amend(flight_plan("a"), vec![]).unwrap_err();
+        assert_eq!(error, LifecycleError::UnknownFlightPlan("a".to_string()));
+
+        // still waiting for a decision
+        let _a = registry.submit(flight_plan("a")).unwrap();
+        let error = registry.amendable("a").unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("cannot be amended while submitted"));
+
+        registry.transition("a", Accepted).unwrap();
+        registry.transition("a", Released).unwrap();
+        assert_eq!(registry.amendable("a").unwrap().state, Released);
+
+        // the amendment only replaces the flight plan once accepted
+        let mut amended = flight_plan("a");
+        amended.aircraft_id = "PH-ARW".to_string();
+        assert_eq!(registry.get("a").unwrap().flight_plan, flight_plan("a"));
+        assert_eq!(registry.amend(amended.clone(), vec![]).unwrap(), Released);
+
+        let record = registry.get("a").unwrap();
+        assert_eq!(record.state, Accepted);
+        assert_eq!(record.flight_plan, amended);
+
+        // cancelled flight plans are final
+        registry.transition("a", Cancelled).unwrap();
+        assert!(registry.amendable("a").is_err());
+        assert!(registry.amend(flight_plan("a"), vec![]).is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_registry_submission_dropped() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let registry = FlightPlanRegistry::default();
+        let mut events = registry.subscribe();
+
+        // a request cancelled before the decision
+        let submission = registry.submit(flight_plan("a")).unwrap();
+        assert_eq!(registry.get("a").unwrap().state, Submitted);
+        drop(submission);
+        assert_eq!(registry.get("a").unwrap().state, Draft);
+        assert_eq!(events.try_recv().unwrap().state, Submitted);
+        let event = events.try_recv().unwrap();
+        assert_eq!((event.previous, event.state), (Submitted, Draft));
+
+        // it may be submitted again, and a decided submission is kept
+        let submission = registry.submit(flight_plan("a")).unwrap();
+        registry.transition("a", Rejected).unwrap();
+        drop(submission);
+        assert_eq!(registry.get("a").unwrap().state, Rejected);
+
+        // a rejected flight plan gets its previous version back
+        let mut changed = flight_plan("a");
+        changed.aircraft_id = "PH-ARW".to_string();
+        drop(registry.submit(changed).unwrap());
+        let record = registry.get("a").unwrap();
+        assert_eq!(record.state, Rejected);
+        assert_eq!(record.flight_plan, flight_plan("a"));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_registry_evict() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let registry = FlightPlanRegistry::default();
+        let _a = registry.submit(flight_plan("a")).unwrap();
+        let _b = registry.submit(flight_plan("b")).unwrap();
+        registry.transition("b", Cancelled).unwrap();
+
+        // final flight plans are kept for a while
+        let now = Utc::now();
+        assert_eq!(registry.evict(now), 0);
+        assert!(registry.get("b").is_some());
+
+        let later = now + Duration::hours(FINAL_RETENTION_HOURS) + Duration::seconds(1);
+        assert_eq!(registry.evict(later), 1);
+        assert!(registry.get("b").is_none());
+        assert_eq!(registry.get("a").unwrap().state, Submitted);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_registry_lock() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let registry = FlightPlanRegistry::default();
+        let guard = registry.lock("a").await;
+
+        // a second request for the same flight plan waits
+        let wait = std::time::Duration::from_millis(50);
+        assert!(tokio::time::timeout(wait, registry.lock("a"))
+            .await
+            .is_err());
+        assert!(tokio::time::timeout(wait, registry.lock("b")).await.is_ok());
+
+        drop(guard);
+        assert!(tokio::time::timeout(wait, registry.lock("a")).await.is_ok());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_registry_events() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let registry = FlightPlanRegistry::default();
+
+        // events without subscribers are dropped
+        let _a = registry.submit(flight_plan("a")).unwrap();
+
+        let mut events = registry.subscribe();
+        let decision = Decision {
+            accepted: true,
+            result: None,
+            violations: vec![],
+        };
+        registry
+            .decide("a", Accepted, vec![("nl".to_string(), Ok(decision))])
+            .unwrap();
+        registry
+            .report(
+                "a",
+                vec![(
+                    "nl".to_string(),
+                    Err(RegionError::AuthorityFailure("timeout".to_string())),
+                )],
+            )
+            .unwrap();
+        registry.transition("a", Cancelled).unwrap();
+        assert!(registry.report("b", vec![]).is_err());
+
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.flight_plan_id, "a");
+        assert_eq!((event.previous, event.state), (Submitted, Accepted));
+        assert_eq!(event.outcomes.len(), 1);
+
+        let event = events.recv().await.unwrap();
+        assert_eq!((event.previous, event.state), (Accepted, Accepted));
+        assert!(event.outcomes[0].1.is_err());
+
+        let event = events.recv().await.unwrap();
+        assert_eq!((event.previous, event.state), (Accepted, Cancelled));
+        assert!(event.outcomes.is_empty());
+        assert!(events.try_recv().is_err());
+
+        // a watch starts from the current records, with only later events
+        let ids = ["a".to_string(), "b".to_string()];
+        let (records, mut events) = registry.watch(&ids);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].state, Cancelled);
+        assert!(events.try_recv().is_err());
+        let _b = registry.submit(flight_plan("b")).unwrap();
+        assert_eq!(events.try_recv().unwrap().flight_plan_id, "b");
+
+        ut_info!("Success.");
+    }
+}
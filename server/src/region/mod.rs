@@ -3,36 +3,298 @@
 #[macro_use]
 pub mod macros;
 
-cfg_if::cfg_if! {
-    if #[cfg(feature = "us")] {
-        pub mod us;
-    } else {
-        pub mod nl;
-    }
-}
-
+pub mod arinc424;
+pub mod compliance;
+pub mod coordinates;
+pub mod ed269;
+pub mod geojson;
+pub mod geometry;
+pub mod kml;
+pub mod lifecycle;
+pub mod nasr;
+pub mod nl;
+pub mod notam;
+pub mod openair;
+pub mod projection;
+pub mod schedule;
+pub mod shape;
+pub mod shapefile;
+pub mod source;
+pub mod store;
+pub mod tfr;
+pub mod uasfm;
+pub mod us;
 pub mod utils;
+pub mod vertical;
 
-use crate::grpc::server;
+use crate::config::Config;
 use lib_common::time::{DateTime, Utc};
-use server::{FlightPlanRequest, FlightPlanResponse};
-use server::{FlightReleaseRequest, FlightReleaseResponse};
+use serde::Serialize;
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use store::Airspace;
 use svc_gis_client_grpc::prelude::gis;
-use tonic::{Request, Response, Status};
+use utils::{AltitudeLimit, RoutePoint};
 
-/// Generic region struct to be used to implement the region specific traits
-#[derive(Debug, Clone)]
-pub struct RegionImpl {
-    /// The implemented region short code
-    pub region: String,
+/// Region short code used when none is configured
+pub const DEFAULT_REGION_CODE: &str = "nl";
+
+/// Constructor for a shared region implementation
+type RegionConstructor = fn(&Config) -> Result<Arc<dyn RegionInterface + Send + Sync>, RegionError>;
+
+/// Registry of all region implementations built into this service
+const REGIONS: &[(&str, RegionConstructor)] = &[
+    ("nl", |config| Ok(Arc::new(nl::RegionImpl::new(config)?))),
+    ("us", |config| Ok(Arc::new(us::RegionImpl::new(config)?))),
+];
+
+/// Errors when selecting a region implementation
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum RegionError {
+    /// The region code has no implementation
+    #[error("error: Unknown region code '{0}', expected one of: {}.", region_codes().join(", "))]
+    UnknownRegion(String),
+
+    /// No region codes were provided
+    #[error("error: No region codes provided.")]
+    NoRegions,
+
+    /// The regional authority could not be reached or returned an error
+    #[error("error: Request to the regional authority failed: {0}")]
+    AuthorityFailure(String),
+
+    /// The region specific configuration is invalid
+    #[error("error: Invalid region configuration: {0}")]
+    InvalidConfiguration(String),
+
+    /// A published dataset could not be read
+    #[error("error: Could not load the regional dataset: {0}")]
+    DatasetFailure(String),
+}
+
+/// Returns the short codes of all supported regions
+pub fn region_codes() -> Vec<&'static str> {
+    REGIONS.iter().map(|(code, _)| *code).collect()
+}
+
+/// Creates the region implementation for the provided region short code
+pub fn from_region_code(
+    region_code: &str,
+    config: &Config,
+) -> Result<Arc<dyn RegionInterface + Send + Sync>, RegionError> {
+    let region_code = region_code.trim().to_lowercase();
+    let (_, constructor) = REGIONS
+        .iter()
+        .find(|(code, _)| *code == region_code)
+        .ok_or_else(|| {
+            region_error!("Unknown region code '{}'.", region_code);
+            RegionError::UnknownRegion(region_code.clone())
+        })?;
+
+    constructor(config).map_err(|e| {
+        region_error!("Could not create region '{}': {}", region_code, e);
+        e
+    })
+}
+
+/// Creates the region implementations for a comma separated list of region short codes
+///
+/// Duplicate region codes are ignored.
+pub fn from_region_codes(
+    region_codes: &str,
+    config: &Config,
+) -> Result<Vec<Arc<dyn RegionInterface + Send + Sync>>, RegionError> {
+    let mut regions: Vec<Arc<dyn RegionInterface + Send + Sync>> = vec![];
+    for code in region_codes.split(',').filter(|c| !c.trim().is_empty()) {
+        let region = from_region_code(code, config)?;
+        if regions
+            .iter()
+            .all(|r| r.get_region() != region.get_region())
+        {
+            regions.push(region);
+        }
+    }
+
+    if regions.is_empty() {
+        region_error!("No region codes provided.");
+        return Err(RegionError::NoRegions);
+    }
+
+    Ok(regions)
+}
+
+/// Loads the restrictions of a dataset
+///
+/// The current restrictions with the prefix of the dataset are kept if it
+///  cannot be read, and dropped if it is not configured.
+pub async fn load_or_keep<'a, S, F, Fut>(
+    dataset: &str,
+    source: Option<&'a S>,
+    load: F,
+    prefix: &str,
+    current: &HashMap<String, RestrictionDetails>,
+    from_remote: &mut HashMap<String, RestrictionDetails>,
+) where
+    S: ?Sized,
+    F: FnOnce(&'a S) -> Fut,
+    Fut: Future<Output = Result<HashMap<String, RestrictionDetails>, RegionError>>,
+{
+    let Some(source) = source else {
+        region_warn!("No {} source configured.", dataset);
+        return;
+    };
+
+    match load(source).await {
+        Ok(restrictions) => from_remote.extend(restrictions),
+        Err(e) => {
+            region_error!("Keeping current {} restrictions: {}", dataset, e);
+            from_remote.extend(
+                current
+                    .iter()
+                    .filter(|(k, _)| k.starts_with(prefix))
+                    .map(|(k, v)| (k.clone(), v.clone())),
+            );
+        }
+    }
+}
+
+/// Returns true if any part of the route passes through the region
+pub fn route_in_region(region: &dyn RegionInterface, route: &[gis::Coordinates]) -> bool {
+    region
+        .get_boundaries()
+        .iter()
+        .any(|boundary| utils::path_intersects_polygon(route, boundary))
+}
+
+/// Type of operation of a flight
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationType {
+    /// Cargo transport
+    #[default]
+    Cargo,
+
+    /// Passenger transport
+    Passenger,
+
+    /// Emergency services
+    Emergency,
+
+    /// Repositioning of the aircraft without payload
+    Ferry,
+}
+
+/// A flight plan as handled by the regional authorities
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FlightPlan {
+    /// Flight Plan Id
+    pub flight_plan_id: String,
+
+    /// Identifier of the aircraft
+    pub aircraft_id: String,
+
+    /// Identifier of the aircraft operator
+    pub operator_id: String,
+
+    /// Identifier of the departure vertiport
+    pub departure_vertiport_id: String,
+
+    /// Identifier of the arrival vertiport
+    pub arrival_vertiport_id: String,
+
+    /// Type of operation
+    pub operation_type: OperationType,
+
+    /// The route of the flight, in order of travel
+    pub route: Vec<RoutePoint>,
+}
+
+impl FlightPlan {
+    /// Returns the route as GIS coordinates
+    pub fn route_coordinates(&self) -> Vec<gis::Coordinates> {
+        self.route
+            .iter()
+            .map(|p| gis::Coordinates {
+                latitude: p.latitude,
+                longitude: p.longitude,
+            })
+            .collect()
+    }
+}
+
+/// Severity of a rule violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The request is rejected
+    Error,
+
+    /// The request is accepted, but needs attention
+    Warning,
+
+    /// Informational only
+    Info,
+}
+
+/// A rule violated by a flight plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// Code of the violated rule (e.g. `RESTRICTED_ZONE`)
+    pub rule_code: String,
+
+    /// Severity of the violation
+    pub severity: Severity,
+
+    /// Identifier of the zone or waypoint involved
+    pub feature_id: Option<String>,
+
+    /// Index of the offending route segment
+    pub segment_index: Option<usize>,
+
+    /// Human readable description
+    pub message: String,
+}
+
+/// Decision of a regional authority on a submission or release request
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    /// True if the authority accepted the request
+    pub accepted: bool,
+
+    /// Optional error or warning message
+    pub result: Option<String>,
+
+    /// Rule violations found by the authority
+    pub violations: Vec<Violation>,
+}
+
+/// How a restriction limits flights
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestrictionKind {
+    /// Flights may not enter the restriction between its altitude limits
+    #[default]
+    NoFly,
+
+    /// Flights may enter the restriction up to its maximum altitude, such
+    ///  as the grid cells of a UAS Facility Map
+    Ceiling,
+
+    /// Flights may only enter the restriction with an authorisation of the
+    ///  zone authority. Authorisations cannot be requested yet, so it is
+    ///  checked as [`RestrictionKind::NoFly`].
+    Authorisation,
+
+    /// Flights may enter the restriction under conditions of the zone
+    ///  authority. The conditions are not evaluated, so it is checked as
+    ///  [`RestrictionKind::NoFly`].
+    Conditional,
 }
 
 /// Details of a flight restriction
 #[derive(Debug, Clone)]
 pub struct RestrictionDetails {
-    /// The boundary vertices of the restriction
-    pub vertices: Vec<gis::Coordinates>,
+    /// The boundary of the restriction
+    pub shape: shape::Shape,
 
     /// The start time of the restriction
     pub timestamp_start: Option<DateTime<Utc>>,
@@ -40,14 +302,21 @@ pub struct RestrictionDetails {
     /// The end time of the restriction
     pub timestamp_end: Option<DateTime<Utc>>,
 
+    /// The recurring schedule on which the restriction is active between
+    ///  its start and end times, or always if none
+    pub schedule: Option<schedule::Schedule>,
+
     /// The restriction type
     pub zone_type: gis::ZoneType,
 
-    /// The maximum altitude
-    pub altitude_meters_max: f32,
+    /// The lower altitude limit, as published
+    pub lower: AltitudeLimit,
 
-    /// The minimum altitude
-    pub altitude_meters_min: f32,
+    /// The upper altitude limit, as published
+    pub upper: AltitudeLimit,
+
+    /// How the altitude limits apply
+    pub kind: RestrictionKind,
 }
 
 /// Interface to regional authorities
@@ -56,17 +325,34 @@ pub trait RegionInterface {
     /// Return the region short code of the implementation
     fn get_region(&self) -> &str;
 
-    /// Submit a new flight plan for the region
-    fn submit_flight_plan(
+    /// Return the coarse boundaries of the region, used to route flight plans
+    fn get_boundaries(&self) -> Vec<Vec<gis::Coordinates>>;
+
+    /// Submit a new flight plan for the region, given a snapshot of its airspace
+    async fn submit_flight_plan(
         &self,
-        request: FlightPlanRequest,
-    ) -> Result<Response<FlightPlanResponse>, Status>;
+        flight_plan: &FlightPlan,
+        airspace: &Airspace,
+    ) -> Result<Decision, RegionError>;
+
+    /// Request a flight plan release for the region, given a snapshot of its airspace
+    async fn request_flight_release(
+        &self,
+        flight_plan: &FlightPlan,
+        airspace: &Airspace,
+    ) -> Result<Decision, RegionError>;
+
+    /// Forward the cancellation of a flight plan to the region
+    async fn cancel_flight_plan(&self, flight_plan: &FlightPlan) -> Result<Decision, RegionError>;
 
-    /// Request a flight plan release for the region
-    fn request_flight_release(
+    /// Submit an amended flight plan for the region, given a snapshot of its airspace
+    ///
+    /// The amended flight plan replaces the previously submitted version.
+    async fn amend_flight_plan(
         &self,
-        request: Request<FlightReleaseRequest>,
-    ) -> Result<Response<FlightReleaseResponse>, Status>;
+        flight_plan: &FlightPlan,
+        airspace: &Airspace,
+    ) -> Result<Decision, RegionError>;
 
     /// Refresh the in memory stored restrictions
     async fn acquire_restrictions(&self, restrictions: &mut HashMap<String, RestrictionDetails>);
@@ -84,15 +370,80 @@ mod tests {
         lib_common::logger::get_log_handle().await;
         ut_info!("Start.");
 
-        let region_impl = RegionImpl::default();
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "us")] {
-                assert_eq!(region_impl.region, "us");
-            } else {
-                assert_eq!(region_impl.region, "nl");
-            }
+        for code in region_codes() {
+            let region_impl = from_region_code(code, &Config::new()).unwrap();
+            assert_eq!(region_impl.get_region(), code);
         }
 
+        let region_impl = from_region_code(" US ", &Config::new()).unwrap();
+        assert_eq!(region_impl.get_region(), "us");
+
+        let region_impl = from_region_code(DEFAULT_REGION_CODE, &Config::new()).unwrap();
+        assert_eq!(region_impl.get_region(), DEFAULT_REGION_CODE);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_region_codes() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let regions = from_region_codes("nl, us,nl", &Config::new()).unwrap();
+        let codes = regions.iter().map(|r| r.get_region()).collect::<Vec<_>>();
+        assert_eq!(codes, vec!["nl", "us"]);
+
+        let error = from_region_codes(" , ", &Config::new()).err().unwrap();
+        assert_eq!(error, RegionError::NoRegions);
+
+        let error = from_region_codes("nl,xx", &Config::new()).err().unwrap();
+        assert_eq!(error, RegionError::UnknownRegion("xx".to_string()));
+
+        let config = Config {
+            nl_zones_source: Some("ftp://example.com/zones.geojson".to_string()),
+            ..Config::new()
+        };
+        let error = from_region_codes("us,nl", &config).err().unwrap();
+        assert!(matches!(error, RegionError::InvalidConfiguration(_)));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_route_in_region() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let nl = from_region_code("nl", &Config::new()).unwrap();
+        let us = from_region_code("us", &Config::new()).unwrap();
+
+        // Amsterdam to Lelystad
+        let route = vec![
+            gis::Coordinates {
+                latitude: 52.3745,
+                longitude: 4.9160,
+            },
+            gis::Coordinates {
+                latitude: 52.5337,
+                longitude: 5.4339,
+            },
+        ];
+
+        assert!(route_in_region(nl.as_ref(), &route));
+        assert!(!route_in_region(us.as_ref(), &route));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_region_code_unknown() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let error = from_region_code("xx", &Config::new()).err().unwrap();
+        assert_eq!(error, RegionError::UnknownRegion("xx".to_string()));
+        assert!(error.to_string().contains("nl, us"));
+
         ut_info!("Success.");
     }
 }
@@ -0,0 +1,184 @@
+//! Reader for the FAA National Airspace System Resources (NASR) subscription
+//!
+//! The 28 day subscription is a set of fixed width text files. Fixes are
+//!  read from the `FIX1` records of `FIX.txt` and navaids from the `NAV1`
+//!  records of `NAV.txt`, other records are ignored. Coordinates are given
+//!  in degrees, minutes and seconds, such as `30-11-43.940N`.
+
+use crate::region::coordinates::{field, parse_dashed, Axis, Field};
+use svc_gis_client_grpc::prelude::gis::Coordinates;
+
+/// Columns of a record in a subscription file
+struct Layout {
+    /// Record type at the start of the line
+    record_type: &'static str,
+
+    /// Identifier of the fix or navaid
+    id: Field,
+
+    /// Latitude in degrees, minutes and seconds
+    latitude: Field,
+
+    /// Longitude in degrees, minutes and seconds
+    longitude: Field,
+}
+
+/// Layout of the `FIX1` base record
+const FIX_LAYOUT: Layout = Layout {
+    record_type: "FIX1",
+    id: (5, 30),
+    latitude: (67, 14),
+    longitude: (81, 14),
+};
+
+/// Layout of the `NAV1` base record
+const NAV_LAYOUT: Layout = Layout {
+    record_type: "NAV1",
+    id: (5, 4),
+    latitude: (372, 14),
+    longitude: (397, 14),
+};
+
+/// Subscription files holding waypoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NasrFile {
+    /// Fixes, reporting points and waypoints
+    Fix,
+
+    /// Navigational aids such as VORs and NDBs
+    Nav,
+}
+
+impl NasrFile {
+    /// Name of the file in the subscription
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            NasrFile::Fix => "FIX.txt",
+            NasrFile::Nav => "NAV.txt",
+        }
+    }
+
+    /// Layout of the base records of the file
+    fn layout(&self) -> &'static Layout {
+        match self {
+            NasrFile::Fix => &FIX_LAYOUT,
+            NasrFile::Nav => &NAV_LAYOUT,
+        }
+    }
+}
+
+/// A fix or navaid
+#[derive(Debug, Clone, PartialEq)]
+pub struct NasrPoint {
+    /// Published identifier, such as a five letter fix name
+    pub id: String,
+
+    /// Location of the fix or navaid
+    pub coordinates: Coordinates,
+}
+
+/// Parses the base records of a subscription file
+///
+/// Returns the result of parsing each base record in file order.
+pub fn parse_points(data: &[u8], file: NasrFile) -> Vec<Result<NasrPoint, String>> {
+    let layout = file.layout();
+    String::from_utf8_lossy(data)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.starts_with(layout.record_type))
+        .map(|(index, line)| {
+            to_point(line, layout).map_err(|e| format!("line {}: {}", index + 1, e))
+        })
+        .collect()
+}
+
+/// Converts a base record
+fn to_point(line: &str, layout: &Layout) -> Result<NasrPoint, String> {
+    let id = field(line, layout.id);
+    if id.is_empty() {
+        return Err("missing identifier".to_string());
+    }
+
+    let latitude = field(line, layout.latitude);
+    let latitude = parse_dashed(latitude, Axis::Latitude)
+        .ok_or_else(|| format!("{}: invalid latitude '{}'", id, latitude))?;
+
+    let longitude = field(line, layout.longitude);
+    let longitude = parse_dashed(longitude, Axis::Longitude)
+        .ok_or_else(|| format!("{}: invalid longitude '{}'", id, longitude))?;
+
+    Ok(NasrPoint {
+        id: id.to_string(),
+        coordinates: Coordinates {
+            latitude,
+            longitude,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The NASR subscription shipped for tests
+    const NASR_FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/us_nasr");
+
+    fn read_fixture(file: NasrFile) -> Vec<u8> {
+        std::fs::read(format!("{}/{}", NASR_FIXTURES, file.file_name())).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_fixes() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let points = parse_points(&read_fixture(NasrFile::Fix), NasrFile::Fix);
+        assert_eq!(points.len(), 5);
+
+        let point = points[0].as_ref().unwrap();
+        assert_eq!(point.id, "BLEWE");
+        assert!((point.coordinates.latitude - 30.242186).abs() < 1e-6);
+        assert!((point.coordinates.longitude + 97.868981).abs() < 1e-6);
+
+        // hemisphere of the longitude is invalid
+        let error = points[4].as_ref().unwrap_err();
+        assert!(error.starts_with("line 9: WILIE: invalid longitude"));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_navaids() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let points = parse_points(&read_fixture(NasrFile::Nav), NasrFile::Nav);
+        assert_eq!(points.len(), 4);
+
+        let point = points[0].as_ref().unwrap();
+        assert_eq!(point.id, "CWK");
+        assert!((point.coordinates.latitude - 30.378775).abs() < 1e-6);
+        assert!((point.coordinates.longitude + 97.530064).abs() < 1e-6);
+
+        // missing latitude
+        assert!(points[3].is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_short_records() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let points = parse_points(b"FIX1\nFIX1BLEWE\nNAV1CWK\n", NasrFile::Fix);
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0], Err("line 1: missing identifier".to_string()));
+        assert_eq!(
+            points[1],
+            Err("line 2: BLEWE: invalid latitude ''".to_string())
+        );
+
+        ut_info!("Success.");
+    }
+}
@@ -1,14 +1,22 @@
 //! Region implementation for The Netherlands (NL)
 
-use crate::grpc::server::{
-    FlightPlanRequest, FlightPlanResponse, FlightReleaseRequest, FlightReleaseResponse,
-};
-
-use crate::region::RegionInterface;
-use crate::region::RestrictionDetails;
+use crate::config::Config;
+use crate::region::arinc424;
+use crate::region::compliance::check_flight_plan;
+use crate::region::ed269::{self, is_ed269};
+use crate::region::geojson::{parse_features, Feature};
+use crate::region::notam::load_notams;
+use crate::region::openair::load_openair;
+use crate::region::shape::Shape;
+use crate::region::source::Source;
+use crate::region::store::Airspace;
+use crate::region::utils::{AltitudeLimit, AltitudeReference};
+use crate::region::{load_or_keep, RegionInterface};
+use crate::region::{Decision, FlightPlan, RegionError};
+use crate::region::{RestrictionDetails, RestrictionKind};
+use lib_common::time::{DateTime, Utc};
 use std::collections::HashMap;
 use svc_gis_client_grpc::prelude::gis::{Coordinates, ZoneType};
-use tonic::{Request, Response, Status};
 
 //
 // TODO(R5): Refresh intervals for receiving data from external sources
@@ -17,196 +25,422 @@ use tonic::{Request, Response, Status};
 // const NL_RESTRICTION_REFRESH_INTERVAL_MS: u64 = 30000; // 30s
 // const NL_WAYPOINT_REFRESH_INTERVAL_MS: u64 = 60000; // 60s
 
-impl Default for super::RegionImpl {
+/// Prefix of the identifiers of Dutch no-fly zones
+const ZONE_ID_PREFIX: &str = "ARROW-NL-NOFLY-";
+
+/// Prefix of the identifiers of Dutch NOTAMs
+const NOTAM_ID_PREFIX: &str = "ARROW-NL-NOTAM-";
+
+/// ICAO region code of The Netherlands in navigation databases
+const ARINC424_ICAO_CODES: &[&str] = &["EH"];
+
+/// Prefix of the identifiers of Dutch OpenAir airspaces
+const OPENAIR_ID_PREFIX: &str = "ARROW-NL-OPENAIR-";
+
+/// Lower limit in meters of zones that do not publish one
+const DEFAULT_ALTITUDE_METERS_MIN: f64 = 0.0;
+
+/// Unit of measurement of zones that do not publish one
+const DEFAULT_ALTITUDE_UNIT: &str = "M";
+
+/// Vertical reference of zones that do not publish one
+const DEFAULT_ALTITUDE_REFERENCE: AltitudeReference = AltitudeReference::Msl;
+
+/// Zone feature property names, matched ignoring case in order of preference
+const PROPERTY_ID: &[&str] = &["identifier", "id", "name"];
+const PROPERTY_ZONE_TYPE: &[&str] = &["localType", "zoneType", "type"];
+const PROPERTY_LOWER_LIMIT: &[&str] = &["lowerLimit", "lower"];
+const PROPERTY_UPPER_LIMIT: &[&str] = &["upperLimit", "upper"];
+const PROPERTY_UNIT: &[&str] = &["uom", "uomDimensions", "unit"];
+const PROPERTY_LOWER_REFERENCE: &[&str] = &["lowerVerticalReference", "lowerReference"];
+const PROPERTY_UPPER_REFERENCE: &[&str] = &["upperVerticalReference", "upperReference"];
+const PROPERTY_VALID_FROM: &[&str] = &["validFrom", "startDateTime", "start"];
+const PROPERTY_VALID_TO: &[&str] = &["validTo", "endDateTime", "end"];
+
+/// Zone types published for take-off and landing sites rather than restrictions
+const PORT_ZONE_TYPES: &[&str] = &["port", "vertiport", "heliport"];
+
+/// Altitude limits referring to the surface
+const SURFACE_LIMITS: &[&str] = &["SFC", "GND"];
+
+/// Region implementation for The Netherlands
+#[derive(Debug, Clone)]
+pub struct RegionImpl {
+    /// The implemented region short code
+    pub region: String,
+
+    /// The published drone zone dataset, in GeoJSON
+    pub zones_source: Option<Source>,
+
+    /// The NOTAMs of the Amsterdam FIR, in the ICAO format
+    pub notam_source: Option<Source>,
+
+    /// Dutch airspace, in the OpenAir format
+    pub openair_source: Option<Source>,
+
+    /// Navigation database, in ARINC 424
+    pub arinc424_source: Option<Source>,
+}
+
+impl Default for RegionImpl {
     fn default() -> Self {
         Self {
             region: String::from("nl"),
+            zones_source: None,
+            notam_source: None,
+            openair_source: None,
+            arinc424_source: None,
         }
     }
 }
 
+impl RegionImpl {
+    /// Creates the region implementation from the service configuration
+    pub fn new(config: &Config) -> Result<Self, RegionError> {
+        let parse = |source: &Option<String>| match source {
+            Some(source) => source
+                .parse::<Source>()
+                .map(Some)
+                .map_err(|e| RegionError::InvalidConfiguration(e.to_string())),
+            None => Ok(None),
+        };
+
+        Ok(Self {
+            zones_source: parse(&config.nl_zones_source)?,
+            notam_source: parse(&config.nl_notam_source)?,
+            openair_source: parse(&config.nl_openair_source)?,
+            arinc424_source: parse(&config.nl_arinc424_source)?,
+            ..Default::default()
+        })
+    }
+}
+
+/// Reads the drone zones from the source, skipping malformed features
+///
+/// The source is either a GeoJSON feature collection or an ED-269
+///  publication of UAS geographical zones.
+async fn load_restrictions(
+    source: &Source,
+    now: DateTime<Utc>,
+) -> Result<HashMap<String, RestrictionDetails>, RegionError> {
+    let data = source
+        .read()
+        .await
+        .map_err(|e| RegionError::DatasetFailure(e.to_string()))?;
+
+    let zones = match is_ed269(&data) {
+        true => ed269::parse_zones(&data)
+            .map_err(|e| RegionError::DatasetFailure(e.to_string()))?
+            .into_iter()
+            .map(|zone| zone.and_then(|zone| ed269::to_restrictions(&zone, ZONE_ID_PREFIX, now)))
+            .collect::<Vec<_>>(),
+        false => parse_features(&data)
+            .map_err(|e| RegionError::DatasetFailure(e.to_string()))?
+            .into_iter()
+            .map(|feature| feature.and_then(|feature| to_restrictions(&feature)))
+            .collect::<Vec<_>>(),
+    };
+
+    let mut restrictions = HashMap::new();
+    let mut skipped = 0;
+    for (index, zone) in zones.into_iter().enumerate() {
+        match zone {
+            Ok(zones) => restrictions.extend(zones),
+            Err(e) => {
+                region_warn!(
+                    "[nl] Skipping malformed zone feature {} in {}: {}",
+                    index,
+                    source,
+                    e
+                );
+                skipped += 1;
+            }
+        }
+    }
+
+    region_info!(
+        "[nl] Loaded {} zones from {}, skipped {} features.",
+        restrictions.len(),
+        source,
+        skipped
+    );
+
+    Ok(restrictions)
+}
+
+/// Converts a drone zone feature to restrictions, one per polygon
+///
+/// Feature properties are read as follows, any of the listed names may be used:
+///
+/// | Property | Names | Meaning |
+/// | --- | --- | --- |
+/// | Identifier | `identifier`, `id`, `name` | Falls back to the feature id |
+/// | Zone type | `localType`, `zoneType`, `type` | `port`, `vertiport` and `heliport` are ports, anything else is a restriction |
+/// | Altitude limits | `lowerLimit`, `upperLimit` | Numbers, or `SFC`/`GND` for the surface |
+/// | Altitude unit | `uom`, `uomDimensions`, `unit` | `M` (default), `FT` or `FL` |
+/// | Validity | `validFrom`, `validTo` (or `start`, `end`) | RFC 3339 timestamps |
+fn to_restrictions(feature: &Feature) -> Result<Vec<(String, RestrictionDetails)>, String> {
+    let id = feature
+        .property_str(PROPERTY_ID)
+        .or_else(|| feature.id())
+        .filter(|id| !id.is_empty())
+        .ok_or("missing identifier")?;
+
+    let zone_type = match feature.property_str(PROPERTY_ZONE_TYPE) {
+        Some(zone_type)
+            if PORT_ZONE_TYPES
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(&zone_type)) =>
+        {
+            ZoneType::Port
+        }
+        _ => ZoneType::Restriction,
+    };
+
+    let unit = feature
+        .property_str(PROPERTY_UNIT)
+        .unwrap_or_else(|| DEFAULT_ALTITUDE_UNIT.to_string());
+    let lower = to_limit(
+        feature.property_str(PROPERTY_LOWER_LIMIT),
+        &unit,
+        feature.property_str(PROPERTY_LOWER_REFERENCE),
+        AltitudeLimit::msl(DEFAULT_ALTITUDE_METERS_MIN),
+    )?;
+    let upper = to_limit(
+        feature.property_str(PROPERTY_UPPER_LIMIT),
+        &unit,
+        feature.property_str(PROPERTY_UPPER_REFERENCE),
+        AltitudeLimit::UNLIMITED,
+    )?;
+    if lower.meters > upper.meters {
+        return Err(format!(
+            "lower limit {} m above upper limit {} m",
+            lower.meters, upper.meters
+        ));
+    }
+
+    let timestamp_start = to_timestamp(feature.property_str(PROPERTY_VALID_FROM))?;
+    let timestamp_end = to_timestamp(feature.property_str(PROPERTY_VALID_TO))?;
+    if let (Some(start), Some(end)) = (timestamp_start, timestamp_end) {
+        if start >= end {
+            return Err(format!("validity ends at {} before it starts", end));
+        }
+    }
+
+    let polygons = feature.polygons()?;
+    let count = polygons.len();
+    Ok(polygons
+        .into_iter()
+        .enumerate()
+        .map(|(i, vertices)| {
+            let label = match count {
+                1 => format!("{}{}", ZONE_ID_PREFIX, id),
+                _ => format!("{}{}-{}", ZONE_ID_PREFIX, id, i + 1),
+            };
+
+            let details = RestrictionDetails {
+                shape: Shape::Polygon(vertices),
+                timestamp_start,
+                timestamp_end,
+                schedule: None,
+                lower,
+                upper,
+                zone_type,
+                kind: RestrictionKind::NoFly,
+            };
+
+            (label, details)
+        })
+        .collect())
+}
+
+/// Converts a published altitude limit with its unit and reference
+///
+/// Limits without a reference are above mean sea level, and missing
+///  limits take the default.
+fn to_limit(
+    limit: Option<String>,
+    unit: &str,
+    reference: Option<String>,
+    default: AltitudeLimit,
+) -> Result<AltitudeLimit, String> {
+    let reference = match reference {
+        Some(reference) => AltitudeReference::parse(&reference)
+            .ok_or_else(|| format!("unknown vertical reference '{}'", reference))?,
+        None => DEFAULT_ALTITUDE_REFERENCE,
+    };
+
+    let Some(limit) = limit else {
+        // An unlimited zone stays unlimited whatever its reference
+        if default.is_unlimited() {
+            return Ok(default);
+        }
+
+        return Ok(AltitudeLimit {
+            reference,
+            ..default
+        });
+    };
+
+    if SURFACE_LIMITS
+        .iter()
+        .any(|s| s.eq_ignore_ascii_case(&limit))
+    {
+        return Ok(AltitudeLimit::SURFACE);
+    }
+
+    let value = limit
+        .parse::<f64>()
+        .map_err(|_| format!("invalid altitude limit '{}'", limit))?;
+
+    AltitudeLimit::new(value, unit, reference)
+        .ok_or_else(|| format!("unknown altitude unit '{}'", unit))
+}
+
+/// Parses an optional RFC 3339 timestamp
+fn to_timestamp(timestamp: Option<String>) -> Result<Option<DateTime<Utc>>, String> {
+    timestamp
+        .map(|timestamp| {
+            DateTime::parse_from_rfc3339(&timestamp)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| format!("invalid timestamp '{}': {}", timestamp, e))
+        })
+        .transpose()
+}
+
 /// Processes for submission to the Dutch (Netherlands) authorities
 #[tonic::async_trait]
-impl RegionInterface for super::RegionImpl {
+impl RegionInterface for RegionImpl {
     fn get_region(&self) -> &str {
         &self.region
     }
 
-    fn submit_flight_plan(
+    fn get_boundaries(&self) -> Vec<Vec<Coordinates>> {
+        // Coarse outline of the Dutch mainland, islands and coastal waters
+        //  as (latitude, longitude)
+        let boundary: Vec<(f64, f64)> = vec![
+            (51.20, 3.30),
+            (51.85, 3.55),
+            (52.40, 4.35),
+            (53.20, 4.55),
+            (53.60, 5.10),
+            (53.65, 6.90),
+            (53.25, 7.25),
+            (52.20, 7.10),
+            (51.85, 6.85),
+            (51.20, 6.25),
+            (50.70, 6.05),
+            (50.75, 5.60),
+            (51.30, 5.05),
+            (51.30, 3.80),
+            (51.20, 3.30),
+        ];
+
+        vec![boundary
+            .into_iter()
+            .map(|(latitude, longitude)| Coordinates {
+                latitude,
+                longitude,
+            })
+            .collect()]
+    }
+
+    async fn submit_flight_plan(
         &self,
-        request: FlightPlanRequest,
-    ) -> Result<Response<FlightPlanResponse>, Status> {
+        flight_plan: &FlightPlan,
+        airspace: &Airspace,
+    ) -> Result<Decision, RegionError> {
         region_info!("[nl] entry.");
+        region_debug!("[nl] flight plan: {}", flight_plan.flight_plan_id);
 
         //
-        // TODO(R5) implement
+        // TODO(R5) submit accepted flight plans to the authority
         //
 
-        let flight_plan_id = request.flight_plan_id;
-        Ok(Response::new(FlightPlanResponse {
-            flight_plan_id,
-            submitted: true,
-            result: None,
-        }))
+        Ok(check_flight_plan(flight_plan, airspace))
     }
 
-    fn request_flight_release(
+    async fn request_flight_release(
         &self,
-        request: Request<FlightReleaseRequest>,
-    ) -> Result<Response<FlightReleaseResponse>, Status> {
+        flight_plan: &FlightPlan,
+        airspace: &Airspace,
+    ) -> Result<Decision, RegionError> {
         region_info!("[nl] entry.");
+        region_debug!("[nl] flight plan: {}", flight_plan.flight_plan_id);
 
         //
-        // TODO(R5) implement
+        // TODO(R5) request the release from the authority
         //
 
-        let flight_plan_id = request.into_inner().flight_plan_id;
-        Ok(Response::new(FlightReleaseResponse {
-            flight_plan_id,
-            released: true,
-            result: None,
-        }))
+        // Restrictions published since the acceptance are caught here
+        Ok(check_flight_plan(flight_plan, airspace))
     }
 
-    async fn acquire_restrictions(&self, restrictions: &mut HashMap<String, RestrictionDetails>) {
+    async fn cancel_flight_plan(&self, flight_plan: &FlightPlan) -> Result<Decision, RegionError> {
+        region_info!("[nl] entry.");
+        region_debug!("[nl] flight plan: {}", flight_plan.flight_plan_id);
+
         //
-        // TODO(R5): This is currently hardcoded. This should be replaced with a call to
-        //  an API.
+        // TODO(R5) forward the cancellation to the authority
         //
-        let zones: Vec<(&str, Vec<(f64, f64)>)> = vec![
-            (
-                "schiphol",
-                vec![
-                    (4.7091866, 52.3827247),
-                    (4.6507947, 52.3294647),
-                    (4.7560834, 52.2572307),
-                    (4.8234058, 52.3214912),
-                    (4.7091866, 52.3827247),
-                ],
-            ),
-            (
-                "hoorn",
-                vec![
-                    (5.0232724, 52.6317085),
-                    (5.1069102, 52.6347298),
-                    (5.1036471, 52.6459798),
-                    (5.1227104, 52.6501458),
-                    (5.0948883, 52.6829387),
-                    (5.0306572, 52.6710736),
-                    (5.0358094, 52.6534782),
-                    (5.0102200, 52.6393135),
-                    (5.0229289, 52.6317085),
-                    (5.0232724, 52.6317085),
-                ],
-            ),
-            (
-                "enkuizen",
-                vec![
-                    (5.2790303, 52.6962570),
-                    (5.2867586, 52.6928238),
-                    (5.3121763, 52.7067632),
-                    (5.2864152, 52.7253768),
-                    (5.2604823, 52.7204902),
-                    (5.2611692, 52.7089473),
-                    (5.2790303, 52.6962570),
-                ],
-            ),
-            (
-                "paleis",
-                vec![
-                    (4.8822724, 52.3688393),
-                    (4.8832170, 52.3781666),
-                    (4.9007345, 52.3777998),
-                    (4.9001335, 52.3680532),
-                    (4.8822724, 52.3688393),
-                ],
-            ),
-            (
-                "lelystad",
-                vec![
-                    (5.4339886, 52.5337670),
-                    (5.4030752, 52.4942783),
-                    (5.5226069, 52.4806893),
-                    (5.5325679, 52.5329316),
-                    (5.4339886, 52.5337670),
-                ],
-            ),
-            (
-                "almere",
-                vec![
-                    (5.1146438, 52.3818865),
-                    (5.1273526, 52.3447783),
-                    (5.1541442, 52.3240093),
-                    (5.2294815, 52.3269469),
-                    (5.3468376, 52.4038851),
-                    (5.2664628, 52.4350834),
-                    (5.2279928, 52.4262914),
-                    (5.1551747, 52.3672147),
-                    (5.1359397, 52.3869157),
-                    (5.1146438, 52.3818865),
-                ],
-            ),
-            (
-                "volendam",
-                vec![
-                    (5.0379751, 52.4928151),
-                    (5.0702624, 52.4842438),
-                    (5.0912148, 52.5071317),
-                    (5.0742124, 52.5225926),
-                    (5.0465621, 52.5207125),
-                    (5.0379751, 52.4928151),
-                ],
-            ),
-            (
-                "monnickendam",
-                vec![
-                    (5.0302965, 52.4649000),
-                    (5.0241138, 52.4491049),
-                    (5.0486728, 52.4423563),
-                    (5.0503902, 52.4547018),
-                    (5.0423184, 52.4629128),
-                    (5.0302965, 52.4649000),
-                ],
-            ),
-            (
-                "marken",
-                vec![
-                    (5.0982680, 52.4568985),
-                    (5.1042360, 52.4545449),
-                    (5.1099464, 52.4564540),
-                    (5.1135959, 52.4619715),
-                    (5.1092594, 52.4657889),
-                    (5.1006295, 52.4612655),
-                    (5.0982680, 52.4568985),
-                ],
-            ),
-        ];
 
-        let from_remote = zones
-            .into_iter()
-            .map(|(zone_name, zone_vertices)| {
-                let data = RestrictionDetails {
-                    vertices: zone_vertices
-                        .into_iter()
-                        .map(|(x, y)| Coordinates {
-                            latitude: y,
-                            longitude: x,
-                        })
-                        .collect(),
-                    timestamp_start: None,
-                    timestamp_end: None,
-                    altitude_meters_min: 0.0,
-                    altitude_meters_max: 1000.0,
-                    zone_type: ZoneType::Restriction,
-                };
-
-                (format!("ARROW-NL-NOFLY-{}", zone_name), data)
-            })
-            .collect::<HashMap<String, RestrictionDetails>>();
+        Ok(Decision {
+            accepted: true,
+            result: None,
+            violations: vec![],
+        })
+    }
+
+    async fn amend_flight_plan(
+        &self,
+        flight_plan: &FlightPlan,
+        airspace: &Airspace,
+    ) -> Result<Decision, RegionError> {
+        region_info!("[nl] entry.");
+        region_debug!("[nl] flight plan: {}", flight_plan.flight_plan_id);
 
         //
-        // END HARDCODE
+        // TODO(R5) submit accepted amendments to the authority
         //
+
+        Ok(check_flight_plan(flight_plan, airspace))
+    }
+
+    async fn acquire_restrictions(&self, restrictions: &mut HashMap<String, RestrictionDetails>) {
+        let mut from_remote: HashMap<String, RestrictionDetails> = HashMap::new();
+        let now = Utc::now();
+        let boundaries = self.get_boundaries();
+
+        load_or_keep(
+            "Dutch zone",
+            self.zones_source.as_ref(),
+            |source| load_restrictions(source, now),
+            ZONE_ID_PREFIX,
+            restrictions,
+            &mut from_remote,
+        )
+        .await;
+
+        load_or_keep(
+            "Dutch NOTAM",
+            self.notam_source.as_ref(),
+            |source| load_notams(source, NOTAM_ID_PREFIX, &boundaries, now),
+            NOTAM_ID_PREFIX,
+            restrictions,
+            &mut from_remote,
+        )
+        .await;
+
+        load_or_keep(
+            "Dutch OpenAir",
+            self.openair_source.as_ref(),
+            |source| load_openair(source, OPENAIR_ID_PREFIX),
+            OPENAIR_ID_PREFIX,
+            restrictions,
+            &mut from_remote,
+        )
+        .await;
+
         restrictions.retain(|k, _| from_remote.contains_key(k));
         for (label, details) in from_remote.into_iter() {
             restrictions.insert(label, details);
@@ -218,93 +452,93 @@ impl RegionInterface for super::RegionImpl {
         // TODO(R5): This is currently hardcoded. This should be replaced with a call to an API
         //
 
-        // Amsterdam Drone Lab Waypoints
+        // Amsterdam Drone Lab Waypoints, as (latitude, longitude)
         let from_remote: Vec<(f64, f64)> = vec![
             // Valid waypoints
-            (4.9160, 52.3745),
-            (4.9156, 52.3749),
-            (4.9153, 52.3752),
-            (4.9156, 52.3753),
+            (52.3745, 4.9160),
+            (52.3749, 4.9156),
+            (52.3752, 4.9153),
+            (52.3753, 4.9156),
             // Waypoint within the TFR
-            (4.9161, 52.3750),
+            (52.3750, 4.9161),
             // Others
-            (5.2021015, 52.9635294),
-            (5.2570586, 52.8691297),
-            (5.3724685, 52.7878146),
-            (5.4164342, 52.6780473),
-            (5.3559814, 52.6047156),
-            (5.2735457, 52.5513062),
-            (5.2021015, 52.4944873),
-            (5.1361530, 52.4174978),
-            (5.0784480, 52.3638604),
-            (5.0234941, 52.2858024),
-            (4.9699542, 52.2542885),
-            (4.9095214, 52.2742499),
-            (4.9075041, 52.3103678),
-            (5.2844417, 52.6672220),
-            (5.2133705, 52.6122211),
-            (5.1309348, 52.5930378),
-            (5.0732299, 52.5796880),
-            (5.0057699, 52.5550636),
-            (4.9314804, 52.5341847),
-            (4.8697511, 52.5015937),
-            (4.8566987, 52.4677236),
-            (4.8148570, 52.5329316),
-            (4.7921165, 52.5742635),
-            (4.8375440, 52.6038816),
-            (4.8918141, 52.6330629),
-            (4.9550148, 52.6701368),
-            (5.0051632, 52.6959449),
-            (5.0553115, 52.7092593),
-            (5.1189528, 52.7421118),
-            (5.1892695, 52.8060822),
-            (5.5227080, 52.7703701),
-            (5.5213340, 52.7080113),
-            (5.5309515, 52.6380634),
-            (5.5007251, 52.5888665),
-            (5.3784456, 52.5270836),
-            (5.3399756, 52.4727431),
-            (5.3866891, 52.4258727),
-            (5.4457680, 52.3630218),
-            (5.6862054, 52.4091213),
-            (5.7865021, 52.4568462),
-            (5.8538245, 52.5496361),
-            (5.0791343, 53.0230443),
-            (4.9870812, 53.0519455),
-            (4.9280023, 53.1030944),
-            (4.9664723, 53.1739423),
-            (5.0502818, 53.2380987),
-            (5.2357621, 53.3103651),
-            (5.3621634, 53.3513711),
-            (5.5023041, 53.3857856),
-            (5.6026008, 53.4005262),
-            (5.7606025, 53.4136247),
-            (4.8892276, 52.3661141),
-            (4.8931777, 52.3793717),
-            (4.8784938, 52.3742892),
-            (4.9035680, 52.3718788),
-            (4.7266651, 52.2700483),
-            (4.8111616, 52.3481340),
-            (4.6383902, 52.3676339),
-            (4.8558143, 52.2631146),
-            (4.8805450, 52.3166644),
-            (4.7946745, 52.2286406),
-            (4.6164074, 52.3109975),
-            (4.7022778, 52.4068174),
-            (5.0968408, 52.6132634),
-            (5.1521414, 52.6491043),
-            (5.1342804, 52.6751331),
-            (5.0985582, 52.6969853),
-            (5.0456620, 52.6903267),
-            (5.0034138, 52.6645152),
-            (4.9955137, 52.6330629),
-            (5.0463490, 52.6134719),
+            (52.9635294, 5.2021015),
+            (52.8691297, 5.2570586),
+            (52.7878146, 5.3724685),
+            (52.6780473, 5.4164342),
+            (52.6047156, 5.3559814),
+            (52.5513062, 5.2735457),
+            (52.4944873, 5.2021015),
+            (52.4174978, 5.1361530),
+            (52.3638604, 5.0784480),
+            (52.2858024, 5.0234941),
+            (52.2542885, 4.9699542),
+            (52.2742499, 4.9095214),
+            (52.3103678, 4.9075041),
+            (52.6672220, 5.2844417),
+            (52.6122211, 5.2133705),
+            (52.5930378, 5.1309348),
+            (52.5796880, 5.0732299),
+            (52.5550636, 5.0057699),
+            (52.5341847, 4.9314804),
+            (52.5015937, 4.8697511),
+            (52.4677236, 4.8566987),
+            (52.5329316, 4.8148570),
+            (52.5742635, 4.7921165),
+            (52.6038816, 4.8375440),
+            (52.6330629, 4.8918141),
+            (52.6701368, 4.9550148),
+            (52.6959449, 5.0051632),
+            (52.7092593, 5.0553115),
+            (52.7421118, 5.1189528),
+            (52.8060822, 5.1892695),
+            (52.7703701, 5.5227080),
+            (52.7080113, 5.5213340),
+            (52.6380634, 5.5309515),
+            (52.5888665, 5.5007251),
+            (52.5270836, 5.3784456),
+            (52.4727431, 5.3399756),
+            (52.4258727, 5.3866891),
+            (52.3630218, 5.4457680),
+            (52.4091213, 5.6862054),
+            (52.4568462, 5.7865021),
+            (52.5496361, 5.8538245),
+            (53.0230443, 5.0791343),
+            (53.0519455, 4.9870812),
+            (53.1030944, 4.9280023),
+            (53.1739423, 4.9664723),
+            (53.2380987, 5.0502818),
+            (53.3103651, 5.2357621),
+            (53.3513711, 5.3621634),
+            (53.3857856, 5.5023041),
+            (53.4005262, 5.6026008),
+            (53.4136247, 5.7606025),
+            (52.3661141, 4.8892276),
+            (52.3793717, 4.8931777),
+            (52.3742892, 4.8784938),
+            (52.3718788, 4.9035680),
+            (52.2700483, 4.7266651),
+            (52.3481340, 4.8111616),
+            (52.3676339, 4.6383902),
+            (52.2631146, 4.8558143),
+            (52.3166644, 4.8805450),
+            (52.2286406, 4.7946745),
+            (52.3109975, 4.6164074),
+            (52.4068174, 4.7022778),
+            (52.6132634, 5.0968408),
+            (52.6491043, 5.1521414),
+            (52.6751331, 5.1342804),
+            (52.6969853, 5.0985582),
+            (52.6903267, 5.0456620),
+            (52.6645152, 5.0034138),
+            (52.6330629, 4.9955137),
+            (52.6134719, 5.0463490),
         ];
 
-        let from_remote: HashMap<String, Coordinates> = from_remote
+        let mut from_remote: HashMap<String, Coordinates> = from_remote
             .iter()
             .enumerate()
-            .map(|(i, (longitude, latitude))| {
+            .map(|(i, (latitude, longitude))| {
                 (
                     format!("ARROW-WEG-{}", i),
                     Coordinates {
@@ -319,6 +553,17 @@ impl RegionInterface for super::RegionImpl {
         // END HARDCODE
         //
 
+        match &self.arinc424_source {
+            Some(source) => match arinc424::load_waypoints(source, ARINC424_ICAO_CODES).await {
+                Ok(points) => from_remote.extend(points),
+                Err(e) => {
+                    region_error!("[nl] Keeping current waypoints: {}", e);
+                    return;
+                }
+            },
+            None => region_warn!("[nl] No ARINC 424 database configured."),
+        }
+
         waypoints.retain(|k, _| from_remote.contains_key(k));
         for (label, details) in from_remote.into_iter() {
             waypoints.insert(label, details);
@@ -329,7 +574,48 @@ impl RegionInterface for super::RegionImpl {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::region::RegionImpl;
+
+    /// The Dutch drone zones shipped for tests
+    const ZONES_FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/nl_zones.geojson"
+    );
+
+    /// The Dutch UAS geographical zones in ED-269 shipped for tests
+    const ED269_FIXTURE: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/nl_ed269.json");
+
+    /// The navigation database shipped for tests
+    const ARINC424_FIXTURE: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/arinc424.dat");
+
+    /// NOTAMs shipped for tests
+    const NOTAM_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/notams.txt");
+
+    /// OpenAir airspaces shipped for tests
+    const OPENAIR_FIXTURE: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/openair.txt");
+
+    fn get_region_impl() -> RegionImpl {
+        RegionImpl {
+            zones_source: Some(Source::File(ZONES_FIXTURE.into())),
+            notam_source: Some(Source::File(NOTAM_FIXTURE.into())),
+            openair_source: Some(Source::File(OPENAIR_FIXTURE.into())),
+            ..Default::default()
+        }
+    }
+
+    /// Airspace without restrictions, as loaded from empty sources
+    fn loaded_airspace() -> Airspace {
+        Airspace {
+            loaded: true,
+            ..Default::default()
+        }
+    }
+
+    fn to_feature(value: serde_json::Value) -> Feature {
+        serde_json::from_value(value).unwrap()
+    }
 
     #[tokio::test]
     async fn test_region_code() {
@@ -348,15 +634,54 @@ mod tests {
         ut_info!("[nl] Start.");
 
         let region = RegionImpl::default();
-        let result = region.submit_flight_plan(FlightPlanRequest {
-            flight_plan_id: "".to_string(),
-            data: "".to_string(),
-        });
+        let result = region
+            .submit_flight_plan(&FlightPlan::default(), &loaded_airspace())
+            .await;
 
         assert!(result.is_ok());
-        let result: FlightPlanResponse = result.unwrap().into_inner();
+        let result: Decision = result.unwrap();
         ut_debug!("[nl] Result: {:?}", result);
-        assert_eq!(result.submitted, true);
+        assert!(result.accepted);
+
+        ut_info!("[nl] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_submit_flight_plan_restricted() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[nl] Start.");
+
+        let region = get_region_impl();
+        let mut restrictions = HashMap::new();
+        region.acquire_restrictions(&mut restrictions).await;
+        let airspace = Airspace {
+            restrictions: std::sync::Arc::new(restrictions),
+            loaded: true,
+            ..Default::default()
+        };
+
+        // Haarlem to Amstelveen, across Schiphol
+        let flight_plan = FlightPlan {
+            flight_plan_id: "test".to_string(),
+            route: [(52.3874, 4.6462), (52.3114, 4.8725)]
+                .into_iter()
+                .map(|(latitude, longitude)| crate::region::utils::RoutePoint {
+                    latitude,
+                    longitude,
+                    altitude_meters: Some(100.0),
+                    timestamp: None,
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let result = region
+            .submit_flight_plan(&flight_plan, &airspace)
+            .await
+            .unwrap();
+        ut_debug!("[nl] Result: {:?}", result);
+        assert!(!result.accepted);
+        assert!(result.result.unwrap().contains("ARROW-NL-NOFLY-schiphol"));
 
         ut_info!("[nl] Success.");
     }
@@ -367,29 +692,341 @@ mod tests {
         ut_info!("[nl] Start.");
 
         let region = RegionImpl::default();
-        let result = region.request_flight_release(tonic::Request::new(FlightReleaseRequest {
-            flight_plan_id: "".to_string(),
-            data: "".to_string(),
-        }));
+        let result = region
+            .request_flight_release(&FlightPlan::default(), &loaded_airspace())
+            .await;
 
         assert!(result.is_ok());
-        let result: FlightReleaseResponse = result.unwrap().into_inner();
+        let result: Decision = result.unwrap();
         ut_debug!("[nl] Result: {:?}", result);
-        assert_eq!(result.released, true);
+        assert!(result.accepted);
+
+        // a restriction published after the acceptance denies the release
+        let region = get_region_impl();
+        let mut restrictions = HashMap::new();
+        region.acquire_restrictions(&mut restrictions).await;
+        let airspace = Airspace {
+            restrictions: std::sync::Arc::new(restrictions),
+            loaded: true,
+            ..Default::default()
+        };
+        let flight_plan = FlightPlan {
+            flight_plan_id: "test".to_string(),
+            route: [(52.3874, 4.6462), (52.3114, 4.8725)]
+                .into_iter()
+                .map(|(latitude, longitude)| crate::region::utils::RoutePoint {
+                    latitude,
+                    longitude,
+                    altitude_meters: Some(100.0),
+                    timestamp: None,
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let result = region
+            .request_flight_release(&flight_plan, &airspace)
+            .await
+            .unwrap();
+        assert!(!result.accepted);
+        assert!(result.result.unwrap().contains("ARROW-NL-NOFLY-schiphol"));
 
         ut_info!("[nl] Success.");
     }
 
     #[tokio::test]
-    async fn test_acquire_restrictions() {
+    async fn test_cancel_flight_plan() {
         lib_common::logger::get_log_handle().await;
         ut_info!("[nl] Start.");
 
         let region = RegionImpl::default();
+        let result = region.cancel_flight_plan(&FlightPlan::default()).await;
+
+        assert!(result.is_ok());
+        let result: Decision = result.unwrap();
+        ut_debug!("[nl] Result: {:?}", result);
+        assert!(result.accepted);
+
+        ut_info!("[nl] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_amend_flight_plan() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[nl] Start.");
+
+        let region = RegionImpl::default();
+        let result = region
+            .amend_flight_plan(&FlightPlan::default(), &loaded_airspace())
+            .await;
+
+        assert!(result.is_ok());
+        let result: Decision = result.unwrap();
+        ut_debug!("[nl] Result: {:?}", result);
+        assert!(result.accepted);
+
+        ut_info!("[nl] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_acquire_restrictions() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[nl] Start.");
+
+        let region = get_region_impl();
         let mut cache = HashMap::<String, RestrictionDetails>::new();
+        cache.insert(
+            "ARROW-NL-NOFLY-withdrawn".to_string(),
+            RestrictionDetails {
+                shape: Shape::Polygon(vec![]),
+                timestamp_start: None,
+                timestamp_end: None,
+                schedule: None,
+                zone_type: ZoneType::Restriction,
+                lower: AltitudeLimit::SURFACE,
+                upper: AltitudeLimit::SURFACE,
+                kind: RestrictionKind::NoFly,
+            },
+        );
         region.acquire_restrictions(&mut cache).await;
         ut_debug!("[nl] Cache content: {:?}", cache);
-        assert!(cache.keys().len() > 0);
+        assert_eq!(cache.keys().len(), 14);
+        assert!(cache.contains_key("ARROW-NL-NOFLY-schiphol"));
+        assert!(cache.contains_key("ARROW-NL-OPENAIR-EHP-1-UTRECHT"));
+        assert!(!cache.contains_key("ARROW-NL-NOFLY-withdrawn"));
+
+        // Only the permanent NOTAM is still active
+        assert!(cache.contains_key("ARROW-NL-NOTAM-A0105-24"));
+
+        // Without a readable source the current zones, NOTAMs and airspaces are kept
+        let region = RegionImpl {
+            zones_source: Some(Source::File("/nonexistent".into())),
+            notam_source: Some(Source::File("/nonexistent".into())),
+            openair_source: Some(Source::File("/nonexistent".into())),
+            ..Default::default()
+        };
+        region.acquire_restrictions(&mut cache).await;
+        assert_eq!(cache.keys().len(), 14);
+
+        // Without sources no restrictions remain, as in the US region
+        let region = RegionImpl::default();
+        region.acquire_restrictions(&mut cache).await;
+        assert!(cache.is_empty());
+
+        ut_info!("[nl] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_new() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[nl] Start.");
+
+        let mut config = Config::new();
+        let region = RegionImpl::new(&config).unwrap();
+        assert!(region.zones_source.is_none());
+
+        config.nl_zones_source = Some(ZONES_FIXTURE.to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert_eq!(
+            region.zones_source,
+            Some(Source::File(ZONES_FIXTURE.into()))
+        );
+
+        config.nl_zones_source = Some("ftp://example.com/zones.geojson".to_string());
+        let error = RegionImpl::new(&config).unwrap_err();
+        assert!(matches!(error, RegionError::InvalidConfiguration(_)));
+
+        config.nl_zones_source = None;
+        config.nl_notam_source = Some(NOTAM_FIXTURE.to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert_eq!(
+            region.notam_source,
+            Some(Source::File(NOTAM_FIXTURE.into()))
+        );
+
+        config.nl_notam_source = None;
+        config.nl_openair_source = Some(OPENAIR_FIXTURE.to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert_eq!(
+            region.openair_source,
+            Some(Source::File(OPENAIR_FIXTURE.into()))
+        );
+
+        config.nl_arinc424_source = Some(ARINC424_FIXTURE.to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert!(matches!(region.arinc424_source, Some(Source::File(_))));
+
+        ut_info!("[nl] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_to_restrictions() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[nl] Start.");
+
+        let ring = serde_json::json!([[[4.0, 52.0], [5.0, 52.0], [5.0, 53.0], [4.0, 52.0]]]);
+        let feature = to_feature(serde_json::json!({
+            "type": "Feature",
+            "geometry": { "type": "Polygon", "coordinates": ring },
+            "properties": {
+                "Identifier": "event",
+                "lowerLimit": "GND",
+                "upperLimit": 400,
+                "upperVerticalReference": "AGL",
+                "uomDimensions": "FT",
+                "validFrom": "2024-06-01T10:00:00+02:00",
+                "validTo": "2024-06-01T18:00:00Z"
+            }
+        }));
+
+        let zones = to_restrictions(&feature).unwrap();
+        assert_eq!(zones.len(), 1);
+        let (label, details) = &zones[0];
+        assert_eq!(label, "ARROW-NL-NOFLY-event");
+        assert_eq!(details.zone_type, ZoneType::Restriction);
+        assert_eq!(details.lower, AltitudeLimit::SURFACE);
+        assert_eq!(details.upper.to_string(), "400 ft AGL");
+        assert_eq!(
+            details.timestamp_start,
+            Some("2024-06-01T08:00:00Z".parse::<DateTime<Utc>>().unwrap())
+        );
+        assert_eq!(
+            details.timestamp_end,
+            Some("2024-06-01T18:00:00Z".parse::<DateTime<Utc>>().unwrap())
+        );
+        assert!(matches!(&details.shape, Shape::Polygon(vertices) if vertices.len() == 4));
+
+        // Defaults, feature id and one zone per polygon
+        let feature = to_feature(serde_json::json!({
+            "type": "Feature",
+            "id": 12,
+            "geometry": { "type": "MultiPolygon", "coordinates": [ring, ring] },
+            "properties": { "localType": "Heliport" }
+        }));
+
+        let zones = to_restrictions(&feature).unwrap();
+        let labels = zones
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["ARROW-NL-NOFLY-12-1", "ARROW-NL-NOFLY-12-2"]);
+        for (_, details) in &zones {
+            assert_eq!(details.zone_type, ZoneType::Port);
+            assert_eq!(
+                details.lower,
+                AltitudeLimit::msl(DEFAULT_ALTITUDE_METERS_MIN)
+            );
+            assert_eq!(details.upper, AltitudeLimit::UNLIMITED);
+            assert!(details.timestamp_start.is_none());
+            assert!(details.timestamp_end.is_none());
+        }
+
+        ut_info!("[nl] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_to_restrictions_malformed() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[nl] Start.");
+
+        let ring = serde_json::json!([[[4.0, 52.0], [5.0, 52.0], [5.0, 53.0], [4.0, 52.0]]]);
+        let malformed = [
+            serde_json::json!({}),
+            serde_json::json!({ "name": "" }),
+            serde_json::json!({ "name": "zone", "upperLimit": "high" }),
+            serde_json::json!({ "name": "zone", "upperLimit": 10, "uom": "NM" }),
+            serde_json::json!({ "name": "zone", "lowerLimit": 200, "upperLimit": 100 }),
+            serde_json::json!({ "name": "zone", "validFrom": "yesterday" }),
+            serde_json::json!({
+                "name": "zone",
+                "validFrom": "2024-06-02T00:00:00Z",
+                "validTo": "2024-06-01T00:00:00Z"
+            }),
+        ];
+
+        for properties in malformed {
+            let feature = to_feature(serde_json::json!({
+                "type": "Feature",
+                "geometry": { "type": "Polygon", "coordinates": ring },
+                "properties": properties
+            }));
+            assert!(to_restrictions(&feature).is_err());
+        }
+
+        ut_info!("[nl] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_load_restrictions_skips_malformed() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[nl] Start.");
+
+        let data = serde_json::json!({
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[[4.0, 52.0], [5.0, 52.0], [5.0, 53.0], [4.0, 52.0]]]
+                    },
+                    "properties": { "name": "valid" }
+                },
+                {
+                    "type": "Feature",
+                    "geometry": { "type": "Point", "coordinates": [4.0, 52.0] },
+                    "properties": { "name": "point" }
+                },
+                {
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[[4.0, 52.0], [5.0, 52.0], [4.0, 52.0]]]
+                    },
+                    "properties": { "name": "degenerate" }
+                }
+            ]
+        });
+
+        let dir = std::env::temp_dir().join(format!("svc-compliance-nl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("zones.geojson");
+        std::fs::write(&path, data.to_string()).unwrap();
+
+        let restrictions = load_restrictions(&Source::File(path), Utc::now())
+            .await
+            .unwrap();
+        let labels = restrictions.keys().collect::<Vec<_>>();
+        assert_eq!(labels, vec!["ARROW-NL-NOFLY-valid"]);
+
+        let error = load_restrictions(
+            &Source::File(ZONES_FIXTURE.replace(".geojson", ".missing").into()),
+            Utc::now(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(error, RegionError::DatasetFailure(_)));
+
+        std::fs::remove_dir_all(dir).unwrap();
+
+        ut_info!("[nl] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_load_restrictions_ed269() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[nl] Start.");
+
+        let now = "2024-06-05T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let restrictions = load_restrictions(&Source::File(ED269_FIXTURE.into()), now)
+            .await
+            .unwrap();
+
+        // one prohibited zone, two scheduled volumes and one conditional zone
+        assert_eq!(restrictions.len(), 4);
+        assert!(restrictions.contains_key("ARROW-NL-NOFLY-EHP1"));
+        assert!(restrictions["ARROW-NL-NOFLY-EHR2-2"].schedule.is_some());
+        assert!(restrictions.contains_key("ARROW-NL-NOFLY-EHC3"));
+        assert!(!restrictions.keys().any(|k| k.contains("EHN4")));
 
         ut_info!("[nl] Success.");
     }
@@ -403,6 +1040,26 @@ mod tests {
         let mut cache = HashMap::<String, Coordinates>::new();
         region.acquire_waypoints(&mut cache).await;
         assert!(cache.keys().len() > 0);
+        let hardcoded = cache.keys().len();
+
+        // Waypoints of other regions in the database are left out
+        let region = RegionImpl {
+            arinc424_source: Some(Source::File(ARINC424_FIXTURE.into())),
+            ..Default::default()
+        };
+        region.acquire_waypoints(&mut cache).await;
+        assert_eq!(cache.keys().len(), hardcoded + 3);
+        assert!(cache.contains_key("ARTIP"));
+        assert!(cache.contains_key("EH610"));
+        assert!(cache.contains_key("SPY"));
+
+        // The current waypoints are kept if the database cannot be read
+        let region = RegionImpl {
+            arinc424_source: Some(Source::File("/nonexistent".into())),
+            ..Default::default()
+        };
+        region.acquire_waypoints(&mut cache).await;
+        assert_eq!(cache.keys().len(), hardcoded + 3);
 
         ut_info!("[nl] Success.");
     }
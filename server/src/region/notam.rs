@@ -0,0 +1,883 @@
+//! Reader for NOTAMs in the ICAO format
+//!
+//! A NOTAM is enclosed in parentheses and starts with its series, number
+//!  and year, followed by its type: `NOTAMN` (new), `NOTAMR` (replacing an
+//!  earlier NOTAM) or `NOTAMC` (cancelling an earlier NOTAM). The qualifier
+//!  line `Q)` and the items `A)` to `G)` follow:
+//!
+//! ```text
+//! (A0123/24 NOTAMN
+//! Q) EHAA/QRTCA/IV/BO/W/000/030/5212N00445E005
+//! A) EHAA B) 2406011200 C) 2406021800
+//! E) TEMPORARY RESTRICTED AREA ACTIVATED
+//! F) SFC G) 3000FT AMSL)
+//! ```
+//!
+//! The area of a NOTAM is the circle of the qualifier line, or the boundary
+//!  of the region if the radius of 999 nautical miles covers the whole
+//!  FIR. Items `F)` and
+//!  `G)` give the altitude limits, otherwise the flight levels of the
+//!  qualifier line are used. The NOTAM is active from item `B)` until item
+//!  `C)`, on the schedule of item `D)` if any, in UTC. A NOTAM with a
+//!  schedule that cannot be read is taken as active all the time.
+//!
+//! Only NOTAMs restricting the airspace become restrictions, those with a
+//!  code on airspace restrictions (`QR`) or warnings (`QW`). Other NOTAMs,
+//!  such as checklists or navaid outages, are skipped.
+
+use crate::region::coordinates::{parse_packed, Axis};
+use crate::region::geometry::normalize_ring;
+use crate::region::schedule::Schedule;
+use crate::region::shape::Shape;
+use crate::region::source::Source;
+use crate::region::utils::{AltitudeLimit, AltitudeReference, METERS_PER_NAUTICAL_MILE};
+use crate::region::{RegionError, RestrictionDetails, RestrictionKind};
+use chrono_tz::Tz;
+use lib_common::time::{DateTime, NaiveDateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use svc_gis_client_grpc::prelude::gis::{Coordinates, ZoneType};
+
+/// File extension of the documents in a NOTAM directory
+const NOTAM_EXTENSION: &str = "txt";
+
+/// Items of a NOTAM in the order they are published
+const ITEM_ORDER: &str = "QABCDEFG";
+
+/// Format of the times in items `B)` and `C)`
+const TIME_FORMAT: &str = "%y%m%d%H%M";
+
+/// Radius of the qualifier line covering the whole FIR, not an area
+const FIR_RADIUS_NAUTICAL_MILES: u16 = 999;
+
+/// Flight level of the qualifier line for an unlimited upper limit
+const UNLIMITED_FLIGHT_LEVEL: u16 = 999;
+
+/// Subjects of the NOTAM codes that restrict the airspace: airspace
+///  restrictions (`QR`) and warnings (`QW`)
+const AIRSPACE_CODE_PREFIXES: [&str; 2] = ["QR", "QW"];
+
+/// Type of a NOTAM
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotamKind {
+    /// A new NOTAM (`NOTAMN`)
+    New,
+
+    /// A NOTAM replacing the NOTAM with the given identifier (`NOTAMR`)
+    Replace(String),
+
+    /// A NOTAM cancelling the NOTAM with the given identifier (`NOTAMC`)
+    Cancel(String),
+}
+
+/// The restricted area of a new or replacing NOTAM
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotamArea {
+    /// NOTAM code of the qualifier line, such as `QRTCA`
+    pub code: String,
+
+    /// Locations of item `A)`
+    pub locations: Vec<String>,
+
+    /// Start of activity, item `B)`
+    pub start: DateTime<Utc>,
+
+    /// End of activity, item `C)`, or `None` if permanent
+    pub end: Option<DateTime<Utc>>,
+
+    /// Schedule of activity within the period, item `D)`
+    pub schedule: Option<Schedule>,
+
+    /// Center of the area
+    pub center: Coordinates,
+
+    /// Radius of the area in meters, `None` if the NOTAM covers the whole
+    ///  FIR
+    pub radius_meters: Option<f64>,
+
+    /// Lower altitude limit
+    pub lower: AltitudeLimit,
+
+    /// Upper altitude limit
+    pub upper: AltitudeLimit,
+
+    /// Plain language text, item `E)`
+    pub text: String,
+}
+
+/// A NOTAM
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notam {
+    /// Series, number and year, such as `A0123/24`
+    pub id: String,
+
+    /// Type of the NOTAM
+    pub kind: NotamKind,
+
+    /// The restricted area, `None` for a cancellation
+    pub area: Option<NotamArea>,
+}
+
+/// Parses all NOTAMs in the text
+///
+/// Returns the result of parsing each NOTAM in text order.
+pub fn parse_notams(text: &str) -> Vec<Result<Notam, String>> {
+    let starts = text
+        .match_indices('(')
+        .map(|(index, _)| index)
+        .filter(|index| {
+            text[index + 1..]
+                .split_whitespace()
+                .next()
+                .is_some_and(is_notam_id)
+        })
+        .collect::<Vec<_>>();
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(text.len());
+            let body = text[start + 1..end].trim_end();
+            let body = body.strip_suffix(')').unwrap_or(body);
+            parse_notam(body)
+        })
+        .collect()
+}
+
+/// Returns true if the text is a NOTAM identifier such as `A0123/24`
+fn is_notam_id(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() == 8
+        && bytes[0].is_ascii_uppercase()
+        && bytes[1..5].iter().all(u8::is_ascii_digit)
+        && bytes[5] == b'/'
+        && bytes[6..].iter().all(u8::is_ascii_digit)
+}
+
+/// Parses a single NOTAM without its enclosing parentheses
+fn parse_notam(body: &str) -> Result<Notam, String> {
+    let mut tokens = body.split_whitespace();
+    let id = tokens.next().unwrap_or_default().to_string();
+    let error = |e: String| format!("{}: {}", id, e);
+
+    let reference = |tokens: &mut std::str::SplitWhitespace| match tokens.next() {
+        Some(reference) if is_notam_id(reference) => Ok(reference.to_string()),
+        _ => Err(error("missing reference to the earlier NOTAM".to_string())),
+    };
+
+    let kind = match tokens.next() {
+        Some("NOTAMN") => NotamKind::New,
+        Some("NOTAMR") => NotamKind::Replace(reference(&mut tokens)?),
+        Some("NOTAMC") => NotamKind::Cancel(reference(&mut tokens)?),
+        other => {
+            return Err(error(format!(
+                "unknown type '{}'",
+                other.unwrap_or_default()
+            )))
+        }
+    };
+
+    let area = match kind {
+        NotamKind::Cancel(_) => None,
+        _ => Some(to_area(&split_items(body)).map_err(error)?),
+    };
+
+    Ok(Notam { id, kind, area })
+}
+
+/// Splits the NOTAM into its items, keyed by item letter
+///
+/// An item marker is only recognized after whitespace and after the
+///  markers of the preceding items.
+fn split_items(body: &str) -> HashMap<char, &str> {
+    let chars = body.char_indices().collect::<Vec<_>>();
+    let mut markers: Vec<(char, usize, usize)> = vec![];
+    for (i, window) in chars.windows(2).enumerate() {
+        let [(start, letter), (close, ')')] = window else {
+            continue;
+        };
+
+        let Some(order) = ITEM_ORDER.find(*letter) else {
+            continue;
+        };
+
+        let after_whitespace = i > 0 && chars[i - 1].1.is_whitespace();
+        let in_order = match markers.last() {
+            Some((last, ..)) => ITEM_ORDER.find(*last) < Some(order),
+            None => true,
+        };
+        if after_whitespace && in_order {
+            markers.push((*letter, *start, close + 1));
+        }
+    }
+
+    markers
+        .iter()
+        .enumerate()
+        .map(|(i, (letter, _, content))| {
+            let end = markers
+                .get(i + 1)
+                .map_or(body.len(), |(_, start, _)| *start);
+            (*letter, body[*content..end].trim())
+        })
+        .collect()
+}
+
+/// Converts the items of a new or replacing NOTAM to its area
+fn to_area(items: &HashMap<char, &str>) -> Result<NotamArea, String> {
+    let item = |letter: char| {
+        items
+            .get(&letter)
+            .copied()
+            .filter(|item| !item.is_empty())
+            .ok_or_else(|| format!("missing item {})", letter))
+    };
+
+    let qualifier = item('Q')?.split('/').map(str::trim).collect::<Vec<_>>();
+    let [_fir, code, _traffic, _purpose, _scope, lower, upper, area] = qualifier[..] else {
+        return Err(format!("invalid qualifier line '{}'", item('Q')?));
+    };
+
+    let (center, radius_meters) = to_circle(area)?;
+    let lower = match items.get(&'F') {
+        Some(limit) => to_limit(limit)?,
+        None => to_flight_level_limit(lower)?,
+    };
+    let upper = match items.get(&'G') {
+        Some(limit) => to_limit(limit)?,
+        None => to_flight_level_limit(upper)?,
+    };
+
+    let end = match item('C')?.split_whitespace().next() {
+        Some("PERM") => None,
+        Some(end) => Some(to_time(end)?),
+        None => None,
+    };
+
+    let schedule = items
+        .get(&'D')
+        .filter(|schedule| !schedule.is_empty())
+        .and_then(|schedule| match Schedule::parse(schedule, Tz::UTC) {
+            Ok(schedule) => Some(schedule),
+            Err(e) => {
+                region_warn!(
+                    "Ignoring schedule '{}', active all the time: {}",
+                    schedule,
+                    e
+                );
+                None
+            }
+        });
+
+    Ok(NotamArea {
+        code: code.to_string(),
+        locations: item('A')?.split_whitespace().map(str::to_string).collect(),
+        start: to_time(item('B')?)?,
+        end,
+        schedule,
+        center,
+        radius_meters,
+        lower,
+        upper,
+        text: items.get(&'E').copied().unwrap_or_default().to_string(),
+    })
+}
+
+/// Parses a time of item `B)` or `C)`, ignoring an `EST` suffix
+fn to_time(text: &str) -> Result<DateTime<Utc>, String> {
+    let time = text.trim().trim_end_matches("EST").trim();
+    NaiveDateTime::parse_from_str(time, TIME_FORMAT)
+        .map(|time| time.and_utc())
+        .map_err(|_| format!("invalid time '{}'", text))
+}
+
+/// Parses the area of the qualifier line, such as `5212N00445E005`, as
+///  the center and the radius in meters
+///
+/// The radius is `None` if the area covers the whole FIR.
+fn to_circle(text: &str) -> Result<(Coordinates, Option<f64>), String> {
+    let invalid = || format!("invalid area '{}'", text);
+    if text.len() != 14 || !text.is_ascii() {
+        return Err(invalid());
+    }
+
+    let latitude = parse_packed(&text[0..5], Axis::Latitude);
+    let longitude = parse_packed(&text[5..11], Axis::Longitude);
+    let radius = text[11..14].parse::<u16>().ok();
+    let (Some(latitude), Some(longitude), Some(radius)) = (latitude, longitude, radius) else {
+        return Err(invalid());
+    };
+
+    if radius == 0 {
+        return Err(format!("area '{}' has no usable radius", text));
+    }
+
+    let center = Coordinates {
+        latitude,
+        longitude,
+    };
+
+    let radius_meters = match radius {
+        FIR_RADIUS_NAUTICAL_MILES => None,
+        radius => Some(f64::from(radius) * METERS_PER_NAUTICAL_MILE),
+    };
+
+    Ok((center, radius_meters))
+}
+
+/// Parses a flight level of the qualifier line
+fn to_flight_level_limit(text: &str) -> Result<AltitudeLimit, String> {
+    match text.parse::<u16>() {
+        Ok(0) => Ok(AltitudeLimit::SURFACE),
+        Ok(UNLIMITED_FLIGHT_LEVEL) => Ok(AltitudeLimit::UNLIMITED),
+        Ok(level) => AltitudeLimit::new(f64::from(level), "FL", AltitudeReference::Standard)
+            .ok_or_else(|| format!("invalid flight level '{}'", text)),
+        Err(_) => Err(format!("invalid flight level '{}'", text)),
+    }
+}
+
+/// Parses an altitude limit of item `F)` or `G)`, such as `SFC`, `FL050`,
+///  `3000FT AMSL` or `150M AGL`
+///
+/// Altitudes without a reference are above mean sea level.
+fn to_limit(text: &str) -> Result<AltitudeLimit, String> {
+    let invalid = || format!("invalid altitude limit '{}'", text);
+    let compact = text.split_whitespace().collect::<String>().to_uppercase();
+    match compact.as_str() {
+        "SFC" | "GND" => return Ok(AltitudeLimit::SURFACE),
+        "UNL" => return Ok(AltitudeLimit::UNLIMITED),
+        _ => (),
+    }
+
+    if let Some(level) = compact.strip_prefix("FL") {
+        return match level.parse::<u16>() {
+            Ok(level) => to_flight_level_limit(&level.to_string()),
+            Err(_) => Err(invalid()),
+        };
+    }
+
+    let digits = compact
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(compact.len());
+    let (value, rest) = compact.split_at(digits);
+    let value = value.parse::<f64>().map_err(|_| invalid())?;
+    let (unit, reference) = ["FT", "M"]
+        .iter()
+        .find_map(|unit| rest.strip_prefix(unit).map(|reference| (*unit, reference)))
+        .ok_or_else(invalid)?;
+
+    let reference = match reference {
+        "" | "AMSL" | "MSL" => AltitudeReference::Msl,
+        "AGL" | "SFC" | "GND" => AltitudeReference::Agl,
+        _ => return Err(invalid()),
+    };
+
+    AltitudeLimit::new(value, unit, reference).ok_or_else(invalid)
+}
+
+/// Returns true if the NOTAM code is on airspace restrictions or warnings
+fn is_airspace_code(code: &str) -> bool {
+    let code = code.to_uppercase();
+    AIRSPACE_CODE_PREFIXES
+        .iter()
+        .any(|prefix| code.starts_with(prefix))
+}
+
+/// Converts NOTAMs to restrictions, keyed by the prefix and the NOTAM
+///  identifier
+///
+/// NOTAMs with a code other than an airspace restriction or warning are
+///  skipped.
+/// NOTAMs replaced by a `NOTAMR` or cancelled by a `NOTAMC` are dropped,
+///  regardless of the order in which they are given, as are NOTAMs that
+///  ended before `now`. A NOTAM covering the whole FIR restricts each of
+///  the boundaries of the region, numbered if there are several.
+pub fn to_restrictions(
+    notams: &[Notam],
+    prefix: &str,
+    boundaries: &[Vec<Coordinates>],
+    now: DateTime<Utc>,
+) -> HashMap<String, RestrictionDetails> {
+    let withdrawn = notams
+        .iter()
+        .filter_map(|notam| match &notam.kind {
+            NotamKind::Replace(id) | NotamKind::Cancel(id) => Some(id.as_str()),
+            NotamKind::New => None,
+        })
+        .collect::<HashSet<_>>();
+
+    let boundaries = boundaries
+        .iter()
+        .filter_map(|boundary| normalize_ring(boundary).ok())
+        .collect::<Vec<_>>();
+
+    notams
+        .iter()
+        .filter(|notam| !withdrawn.contains(notam.id.as_str()))
+        .filter_map(|notam| Some((notam, notam.area.as_ref()?)))
+        .filter(|(notam, area)| match is_airspace_code(&area.code) {
+            true => true,
+            false => {
+                region_debug!(
+                    "Skipping NOTAM {} with the code {}, not restricting the airspace.",
+                    notam.id,
+                    area.code
+                );
+                false
+            }
+        })
+        .filter(|(_, area)| match area.end {
+            Some(end) => end > now,
+            None => true,
+        })
+        .flat_map(|(notam, area)| {
+            let label = format!("{}{}", prefix, notam.id.replace('/', "-"));
+            let shapes = match area.radius_meters {
+                Some(radius_meters) => vec![Shape::Circle {
+                    center: area.center,
+                    radius_meters,
+                }],
+                None => boundaries.iter().cloned().map(Shape::Polygon).collect(),
+            };
+            if shapes.is_empty() {
+                region_warn!(
+                    "Skipping NOTAM {} of the whole FIR without a boundary.",
+                    notam.id
+                );
+            }
+
+            let count = shapes.len();
+            shapes.into_iter().enumerate().map(move |(i, shape)| {
+                let label = match count {
+                    1 => label.clone(),
+                    _ => format!("{}-{}", label, i + 1),
+                };
+                let details = RestrictionDetails {
+                    shape,
+                    timestamp_start: Some(area.start),
+                    timestamp_end: area.end,
+                    schedule: area.schedule.clone(),
+                    lower: area.lower,
+                    upper: area.upper,
+                    zone_type: ZoneType::Restriction,
+                    kind: RestrictionKind::NoFly,
+                };
+
+                (label, details)
+            })
+        })
+        .collect()
+}
+
+/// Reads the NOTAMs from the source and converts them to restrictions,
+///  skipping malformed NOTAMs
+///
+/// The source is a single file, a directory of `.txt` files or an http(s) URL.
+///  NOTAMs covering the whole FIR restrict the boundaries of the region.
+pub async fn load_notams(
+    source: &Source,
+    prefix: &str,
+    boundaries: &[Vec<Coordinates>],
+    now: DateTime<Utc>,
+) -> Result<HashMap<String, RestrictionDetails>, RegionError> {
+    let documents = source
+        .read_all(NOTAM_EXTENSION)
+        .await
+        .map_err(|e| RegionError::DatasetFailure(e.to_string()))?;
+
+    let mut notams = vec![];
+    let mut skipped = 0;
+    for (name, data) in documents {
+        for notam in parse_notams(&String::from_utf8_lossy(&data)) {
+            match notam {
+                Ok(notam) => notams.push(notam),
+                Err(e) => {
+                    region_warn!("Skipping malformed NOTAM in {}: {}", name, e);
+                    skipped += 1;
+                }
+            }
+        }
+    }
+
+    let restrictions = to_restrictions(&notams, prefix, boundaries, now);
+    region_info!(
+        "Loaded {} active NOTAMs of {} from {}, skipped {} malformed.",
+        restrictions.len(),
+        notams.len(),
+        source,
+        skipped
+    );
+
+    Ok(restrictions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::utils::{AltitudeUnit, UNLIMITED_ALTITUDE_METERS};
+
+    /// NOTAMs shipped for tests
+    const NOTAM_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/notams.txt");
+
+    fn time(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parse_notam() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let text = "(A0123/24 NOTAMN
+Q) EHAA/QRTCA/IV/BO/W/000/030/5212N00445E005
+A) EHAA B) 2406011200 C) 2406021800 EST
+D) DAILY 1200-1800
+E) TEMPORARY RESTRICTED AREA (TRA) ACTIVATED FOR A) AIR SHOW
+F) SFC G) 3000FT AMSL)";
+
+        let notams = parse_notams(text);
+        assert_eq!(notams.len(), 1);
+        let notam = notams[0].as_ref().unwrap();
+        assert_eq!(notam.id, "A0123/24");
+        assert_eq!(notam.kind, NotamKind::New);
+
+        let area = notam.area.as_ref().unwrap();
+        assert_eq!(area.code, "QRTCA");
+        assert_eq!(area.locations, vec!["EHAA"]);
+        assert_eq!(area.start, time("2024-06-01T12:00:00Z"));
+        assert_eq!(area.end, Some(time("2024-06-02T18:00:00Z")));
+        let schedule = area.schedule.as_ref().unwrap();
+        assert!(!schedule.is_active(
+            &area.center,
+            time("2024-06-02T08:00:00Z"),
+            time("2024-06-02T11:00:00Z")
+        ));
+        assert!(schedule.is_active(
+            &area.center,
+            time("2024-06-02T13:00:00Z"),
+            time("2024-06-02T13:00:00Z")
+        ));
+        assert_eq!(area.center.latitude, 52.2);
+        assert_eq!(area.center.longitude, 4.75);
+        assert_eq!(area.radius_meters, Some(5.0 * METERS_PER_NAUTICAL_MILE));
+        assert_eq!(area.lower, AltitudeLimit::SURFACE);
+        assert_eq!(area.upper.reference, AltitudeReference::Msl);
+        assert!((area.upper.meters - 914.4).abs() < 1e-9);
+        assert_eq!(
+            area.text,
+            "TEMPORARY RESTRICTED AREA (TRA) ACTIVATED FOR A) AIR SHOW"
+        );
+
+        // an unreadable schedule leaves the NOTAM active all the time
+        let notams = parse_notams(&text.replace("DAILY 1200-1800", "AT DUSK"));
+        let area = notams[0].as_ref().unwrap().area.as_ref().unwrap();
+        assert!(area.schedule.is_none());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_unreadable_schedule() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let notam = |schedule: &str| {
+            format!(
+                "(A0123/24 NOTAMN
+Q) EHAA/QRTCA/IV/BO/W/000/030/5212N00445E005
+A) EHAA B) 2406011200 C) 2406021800
+D) {}
+F) SFC G) 3000FT AMSL)",
+                schedule
+            )
+        };
+
+        for schedule in ["AT DUSK", "DAILY 2500-2600", "MON-XYZ 0600-1800"] {
+            assert!(Schedule::parse(schedule, Tz::UTC).is_err());
+
+            // the NOTAM is kept, active over its whole period
+            let notams = parse_notams(&notam(schedule))
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert!(notams[0].area.as_ref().unwrap().schedule.is_none());
+
+            let restrictions = to_restrictions(&notams, "TEST-", &[], time("2024-06-01T00:00:00Z"));
+            let details = restrictions.get("TEST-A0123-24").unwrap();
+            assert!(details.schedule.is_none());
+            assert_eq!(details.timestamp_start, Some(time("2024-06-01T12:00:00Z")));
+            assert_eq!(details.timestamp_end, Some(time("2024-06-02T18:00:00Z")));
+        }
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_whole_fir() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let text = "(A0007/24 NOTAMN
+Q) EHAA/QRTCA/IV/BO/W/000/030/5212N00445E999
+A) EHAA B) 2406011200 C) 2406021800
+F) SFC G) 3000FT AMSL)";
+
+        let notams = parse_notams(text)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(notams[0].area.as_ref().unwrap().radius_meters, None);
+
+        let square = |south: f64, west: f64| {
+            [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]
+                .iter()
+                .map(|(latitude, longitude)| Coordinates {
+                    latitude: south + latitude,
+                    longitude: west + longitude,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // the boundary of the region stands in for the FIR
+        let boundaries = vec![square(51.0, 4.0)];
+        let restrictions =
+            to_restrictions(&notams, "TEST-", &boundaries, time("2024-06-01T00:00:00Z"));
+        let details = restrictions.get("TEST-A0007-24").unwrap();
+        let Shape::Polygon(ring) = &details.shape else {
+            panic!("not a polygon: {:?}", details.shape);
+        };
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring.first(), ring.last());
+
+        // one restriction per boundary
+        let boundaries = vec![square(51.0, 4.0), square(53.0, 5.0)];
+        let restrictions =
+            to_restrictions(&notams, "TEST-", &boundaries, time("2024-06-01T00:00:00Z"));
+        let mut labels = restrictions.keys().cloned().collect::<Vec<_>>();
+        labels.sort();
+        assert_eq!(labels, vec!["TEST-A0007-24-1", "TEST-A0007-24-2"]);
+
+        // without a boundary the NOTAM is skipped
+        let restrictions = to_restrictions(&notams, "TEST-", &[], time("2024-06-01T00:00:00Z"));
+        assert!(restrictions.is_empty());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_non_airspace() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // a checklist of the whole FIR, a navaid outage and a warning of
+        //  UAS activity
+        let text = "(A0008/24 NOTAMN
+Q) EHAA/QKKKK/K/K/K/000/999/5212N00445E999
+A) EHAA B) 2406011200 C) 2406021800
+E) CHECKLIST OF VALID NOTAMS)
+
+(A0009/24 NOTAMN
+Q) EHAA/QNVAS/IV/BO/E/000/999/5218N00446E025
+A) EHAM B) 2406011200 C) 2406021800
+E) VOR SPL UNSERVICEABLE)
+
+(A0010/24 NOTAMN
+Q) EHAA/QWULW/IV/BO/W/000/005/5212N00445E002
+A) EHAA B) 2406011200 C) 2406021800
+E) UAS ACTIVITY)";
+
+        let notams = parse_notams(text)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(notams.len(), 3);
+
+        let boundaries = vec![vec![
+            Coordinates {
+                latitude: 51.0,
+                longitude: 4.0,
+            },
+            Coordinates {
+                latitude: 51.0,
+                longitude: 5.0,
+            },
+            Coordinates {
+                latitude: 52.0,
+                longitude: 5.0,
+            },
+        ]];
+        let restrictions =
+            to_restrictions(&notams, "TEST-", &boundaries, time("2024-06-01T00:00:00Z"));
+        let labels = restrictions.keys().cloned().collect::<Vec<_>>();
+        assert_eq!(labels, vec!["TEST-A0010-24"]);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_replace_and_cancel() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let text = "(B0456/24 NOTAMR B0455/24
+Q) KZAU/QRTCA/IV/BO/AW/000/050/4152N08738W003
+A) KORD B) 2406031400 C) PERM
+E) UAS OPERATIONS)
+
+(B0457/24 NOTAMC B0456/24
+Q) KZAU/QRTXX/IV/BO/AW/000/050/4152N08738W003
+A) KORD B) 2406041400)";
+
+        let notams = parse_notams(text);
+        assert_eq!(notams.len(), 2);
+
+        let notam = notams[0].as_ref().unwrap();
+        assert_eq!(notam.kind, NotamKind::Replace("B0455/24".to_string()));
+        let area = notam.area.as_ref().unwrap();
+        assert!(area.end.is_none());
+        assert_eq!(area.center.latitude, 41.0 + 52.0 / 60.0);
+        assert_eq!(area.center.longitude, -(87.0 + 38.0 / 60.0));
+        assert_eq!(area.upper.reference, AltitudeReference::Standard);
+        assert!((area.upper.meters - 1524.0).abs() < 1e-9);
+
+        let notam = notams[1].as_ref().unwrap();
+        assert_eq!(notam.kind, NotamKind::Cancel("B0456/24".to_string()));
+        assert!(notam.area.is_none());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_malformed() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let text = "(A0001/24 NOTAMX)
+(A0002/24 NOTAMR)
+(A0003/24 NOTAMN A) EHAA B) 2406011200 C) 2406021800)
+(A0004/24 NOTAMN Q) EHAA/QRTCA/IV/BO/W/000/030/5212N00445E000
+A) EHAA B) 2406011200 C) 2406021800)
+(A0005/24 NOTAMN Q) EHAA/QRTCA/IV/BO/W/000/030/5212N00445E005
+A) EHAA B) 2406311200 C) 2406021800)
+(A0006/24 NOTAMN Q) EHAA/QRTCA/IV/BO/W/000/030/5212N00445E005
+A) EHAA B) 2406011200 C) 2406021800 F) 500 G) 3000FT)";
+
+        let notams = parse_notams(text);
+        assert_eq!(notams.len(), 6);
+        assert_eq!(
+            notams[0],
+            Err("A0001/24: unknown type 'NOTAMX'".to_string())
+        );
+        assert!(notams[1]
+            .as_ref()
+            .unwrap_err()
+            .contains("missing reference"));
+        assert!(notams[2].as_ref().unwrap_err().contains("missing item Q)"));
+        assert!(notams[3].as_ref().unwrap_err().contains("no usable radius"));
+        assert!(notams[4].as_ref().unwrap_err().contains("invalid time"));
+        assert!(notams[5]
+            .as_ref()
+            .unwrap_err()
+            .contains("invalid altitude limit '500'"));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_to_limit() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert_eq!(to_limit("GND"), Ok(AltitudeLimit::SURFACE));
+        assert_eq!(to_limit("UNL").unwrap().meters, UNLIMITED_ALTITUDE_METERS);
+
+        let limit = to_limit("FL050").unwrap();
+        assert_eq!(limit.reference, AltitudeReference::Standard);
+        assert_eq!(limit.unit, AltitudeUnit::FlightLevel);
+        assert!((limit.meters - 1524.0).abs() < 1e-9);
+
+        let limit = to_limit("150 m agl").unwrap();
+        assert_eq!(limit.reference, AltitudeReference::Agl);
+        assert_eq!(limit.meters, 150.0);
+
+        let limit = to_limit("2500FT").unwrap();
+        assert_eq!(limit.reference, AltitudeReference::Msl);
+        assert_eq!(limit.unit, AltitudeUnit::Feet);
+        assert!((limit.meters - 762.0).abs() < 1e-9);
+
+        assert!(to_limit("2500NM").is_err());
+        assert!(to_limit("FL").is_err());
+        assert!(to_limit("2500FT QNH").is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_to_restrictions() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let text = std::fs::read_to_string(NOTAM_FIXTURE).unwrap();
+        let notams = parse_notams(&text)
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(notams.len(), 5);
+
+        // A0101/24 is replaced by A0102/24, which is later cancelled
+        let restrictions = to_restrictions(&notams, "TEST-", &[], time("2024-06-01T00:00:00Z"));
+        let mut labels = restrictions.keys().cloned().collect::<Vec<_>>();
+        labels.sort();
+        assert_eq!(labels, vec!["TEST-A0104-24", "TEST-A0105-24"]);
+
+        let details = restrictions.get("TEST-A0104-24").unwrap();
+        assert_eq!(details.timestamp_start, Some(time("2024-06-10T06:00:00Z")));
+        assert_eq!(details.timestamp_end, Some(time("2024-06-10T18:00:00Z")));
+        assert_eq!(details.schedule, Schedule::parse("0600-1800", Tz::UTC).ok());
+        assert!(restrictions
+            .get("TEST-A0105-24")
+            .unwrap()
+            .schedule
+            .is_none());
+        assert_eq!(details.lower, AltitudeLimit::SURFACE);
+        assert_eq!(details.upper.meters, 120.0);
+        assert_eq!(details.upper.reference, AltitudeReference::Msl);
+        let Shape::Circle {
+            center,
+            radius_meters,
+        } = details.shape
+        else {
+            panic!("not a circle: {:?}", details.shape);
+        };
+        assert!((center.latitude - (52.0 + 22.0 / 60.0)).abs() < 1e-9);
+        assert!((center.longitude - (4.0 + 54.0 / 60.0)).abs() < 1e-9);
+        assert!((radius_meters - 2.0 * METERS_PER_NAUTICAL_MILE).abs() < 1e-6);
+
+        // Ended NOTAMs are dropped
+        let restrictions = to_restrictions(&notams, "TEST-", &[], time("2024-06-10T18:00:00Z"));
+        assert_eq!(restrictions.len(), 1);
+        assert!(restrictions.contains_key("TEST-A0105-24"));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_load_notams() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let source = Source::File(NOTAM_FIXTURE.into());
+        let restrictions = load_notams(&source, "TEST-", &[], time("2024-06-01T00:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(restrictions.len(), 2);
+
+        let source = Source::File("/nonexistent/notams.txt".into());
+        let error = load_notams(&source, "TEST-", &[], Utc::now())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, RegionError::DatasetFailure(_)));
+
+        ut_info!("Success.");
+    }
+}
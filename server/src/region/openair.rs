@@ -0,0 +1,589 @@
+//! Reader for airspace files in the OpenAir text format
+//!
+//! An airspace starts with an `AC` record for its class, followed by its
+//!  name (`AN`), lower and upper limits (`AL`, `AH`) and boundary. The
+//!  boundary is built from points (`DP`), arcs by angles (`DA`) or between
+//!  two points (`DB`) and circles (`DC`). Arcs and circles use the center
+//!  and direction set by the preceding `V` records. Comments start with `*`
+//!  and other records, such as label positions, are ignored.
+
+use crate::region::coordinates::{parse_colon, Axis};
+use crate::region::shape::{Arc, Segment, Shape};
+use crate::region::source::Source;
+use crate::region::utils::{AltitudeLimit, AltitudeReference, METERS_PER_NAUTICAL_MILE};
+use crate::region::{RegionError, RestrictionDetails, RestrictionKind};
+use std::collections::HashMap;
+use svc_gis_client_grpc::prelude::gis::{Coordinates, ZoneType};
+
+/// File extension of the documents in an OpenAir directory
+const OPENAIR_EXTENSION: &str = "txt";
+
+/// An airspace of an OpenAir file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Airspace {
+    /// Airspace class or type, such as `R` or `CTR`
+    pub class: String,
+
+    /// Name of the airspace
+    pub name: String,
+
+    /// Lower limit
+    pub lower: AltitudeLimit,
+
+    /// Upper limit
+    pub upper: AltitudeLimit,
+
+    /// Boundary
+    pub shape: Shape,
+}
+
+/// Records of an airspace while it is being read
+struct Builder {
+    /// Airspace class
+    class: String,
+
+    /// Name, if set
+    name: Option<String>,
+
+    /// Lower limit, if set
+    lower: Option<AltitudeLimit>,
+
+    /// Upper limit, if set
+    upper: Option<AltitudeLimit>,
+
+    /// Center of the following arcs and circles
+    center: Option<Coordinates>,
+
+    /// Direction of the following arcs
+    clockwise: bool,
+
+    /// Boundary read so far
+    segments: Vec<Segment>,
+
+    /// First error in the records of the airspace
+    error: Option<String>,
+}
+
+impl Builder {
+    /// Starts an airspace of the given class
+    fn new(class: &str) -> Self {
+        Self {
+            class: class.to_string(),
+            name: None,
+            lower: None,
+            upper: None,
+            center: None,
+            clockwise: true,
+            segments: vec![],
+            error: None,
+        }
+    }
+
+    /// Applies a record to the airspace
+    fn apply(&mut self, record: &str, value: &str) -> Result<(), String> {
+        match record {
+            "AN" => self.name = Some(value.to_string()),
+            "AL" => self.lower = Some(parse_altitude(value)?),
+            "AH" => self.upper = Some(parse_altitude(value)?),
+            "V" => self.apply_variable(value)?,
+            "DP" => self.segments.push(Segment::Line(parse_coordinates(value)?)),
+            "DC" => {
+                let center = self.center.ok_or("circle without center")?;
+                self.segments.push(Segment::Arc(Arc {
+                    center,
+                    radius_meters: parse_radius(value)?,
+                    start_bearing_degrees: 0.0,
+                    end_bearing_degrees: 0.0,
+                    clockwise: true,
+                }));
+            }
+            "DA" => {
+                let center = self.center.ok_or("arc without center")?;
+                let fields = value.split(',').map(str::trim).collect::<Vec<_>>();
+                let [radius, start, end] = fields[..] else {
+                    return Err(format!("invalid arc '{}'", value));
+                };
+
+                let angle = |text: &str| {
+                    text.parse::<f64>()
+                        .map_err(|_| format!("invalid arc angle '{}'", text))
+                };
+                self.segments.push(Segment::Arc(Arc {
+                    center,
+                    radius_meters: parse_radius(radius)?,
+                    start_bearing_degrees: angle(start)?,
+                    end_bearing_degrees: angle(end)?,
+                    clockwise: self.clockwise,
+                }));
+            }
+            "DB" => {
+                let center = self.center.ok_or("arc without center")?;
+                let (start, end) = value
+                    .split_once(',')
+                    .ok_or_else(|| format!("invalid arc '{}'", value))?;
+                let start = parse_coordinates(start)?;
+                let end = parse_coordinates(end)?;
+
+                // the arc runs exactly between the given points
+                self.segments.push(Segment::Arc(Arc::between(
+                    center,
+                    &start,
+                    &end,
+                    self.clockwise,
+                )));
+                self.segments.push(Segment::Line(end));
+            }
+            _ => region_debug!("Ignoring OpenAir record {}.", record),
+        }
+
+        Ok(())
+    }
+
+    /// Applies a `V` record, setting the center or direction of arcs
+    fn apply_variable(&mut self, value: &str) -> Result<(), String> {
+        let (name, value) = value
+            .split_once('=')
+            .ok_or_else(|| format!("invalid variable '{}'", value))?;
+        match name.trim().to_uppercase().as_str() {
+            "X" => self.center = Some(parse_coordinates(value)?),
+            "D" => {
+                self.clockwise = match value.trim() {
+                    "+" => true,
+                    "-" => false,
+                    other => return Err(format!("invalid direction '{}'", other)),
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    /// Completes the airspace
+    fn build(self) -> Result<Airspace, String> {
+        let name = self.name.unwrap_or_default();
+        let error = |e: String| match name.is_empty() {
+            true => format!("{} airspace: {}", self.class, e),
+            false => format!("{}: {}", name, e),
+        };
+
+        if let Some(e) = self.error {
+            return Err(error(e));
+        }
+
+        if name.is_empty() {
+            return Err(error("missing name".to_string()));
+        }
+
+        let lower = self.lower.unwrap_or(AltitudeLimit::SURFACE);
+        let upper = self
+            .upper
+            .ok_or_else(|| error("missing upper limit".to_string()))?;
+        if lower.meters > upper.meters {
+            return Err(error(format!(
+                "lower limit {} m above upper limit {} m",
+                lower.meters, upper.meters
+            )));
+        }
+
+        let shape = match &self.segments[..] {
+            [Segment::Arc(arc)] if arc.start_bearing_degrees == arc.end_bearing_degrees => {
+                Shape::Circle {
+                    center: arc.center,
+                    radius_meters: arc.radius_meters,
+                }
+            }
+            _ => Shape::from_segments(self.segments).map_err(error)?,
+        };
+
+        Ok(Airspace {
+            class: self.class,
+            name,
+            lower,
+            upper,
+            shape,
+        })
+    }
+}
+
+/// Parses the airspaces of an OpenAir file
+///
+/// Returns the result of parsing each airspace in file order.
+pub fn parse_airspaces(text: &str) -> Vec<Result<Airspace, String>> {
+    let mut airspaces = vec![];
+    let mut current: Option<Builder> = None;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('*') {
+            continue;
+        }
+
+        let (record, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let record = record.to_uppercase();
+        let value = value.split('*').next().unwrap_or_default().trim();
+        if record == "AC" {
+            airspaces.extend(current.take().map(Builder::build));
+            current = Some(Builder::new(value));
+            continue;
+        }
+
+        let Some(airspace) = current.as_mut() else {
+            region_debug!(
+                "Ignoring OpenAir line {} outside of an airspace.",
+                index + 1
+            );
+            continue;
+        };
+
+        if airspace.error.is_some() {
+            continue;
+        }
+
+        if let Err(e) = airspace.apply(&record, value) {
+            airspace.error = Some(format!("line {}: {}", index + 1, e));
+        }
+    }
+
+    airspaces.extend(current.map(Builder::build));
+    airspaces
+}
+
+/// Parses an altitude limit, such as `SFC`, `2500ft MSL`, `300m AGL`,
+///  `FL65` or `UNL`
+///
+/// Altitudes without a unit are in feet and altitudes without a reference
+///  are above mean sea level.
+pub fn parse_altitude(text: &str) -> Result<AltitudeLimit, String> {
+    let invalid = || format!("invalid altitude '{}'", text);
+    let value = text.to_uppercase().replace(char::is_whitespace, "");
+    match value.as_str() {
+        "SFC" | "GND" | "0" => return Ok(AltitudeLimit::SURFACE),
+        "UNL" | "UNLIM" | "UNLTD" | "UNLIMITED" => return Ok(AltitudeLimit::UNLIMITED),
+        _ => (),
+    }
+
+    if let Some(level) = value.strip_prefix("FL") {
+        let level = level.parse::<f64>().map_err(|_| invalid())?;
+        return AltitudeLimit::new(level, "FL", AltitudeReference::Standard).ok_or_else(invalid);
+    }
+
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, rest) = value.split_at(split);
+    let number = number.parse::<f64>().map_err(|_| invalid())?;
+
+    let (unit, reference) = match rest {
+        _ if rest.starts_with("FT") => ("FT", &rest[2..]),
+        _ if rest.starts_with("MSL") => ("FT", rest),
+        _ if rest.starts_with('M') => ("M", &rest[1..]),
+        _ if rest.starts_with('F') => ("FT", &rest[1..]),
+        _ => ("FT", rest),
+    };
+
+    let reference = match reference {
+        "" => AltitudeReference::Msl,
+        reference => AltitudeReference::parse(reference).ok_or_else(invalid)?,
+    };
+
+    AltitudeLimit::new(number, unit, reference).ok_or_else(invalid)
+}
+
+/// Parses coordinates such as `52:21:30 N 004:45:10 E`
+///
+/// Minutes may carry decimals if seconds are left out, such as
+///  `52:21.5N 004:45.16E`.
+pub fn parse_coordinates(text: &str) -> Result<Coordinates, String> {
+    let invalid = || format!("invalid coordinates '{}'", text.trim());
+    let value = text.to_uppercase();
+    let split = value.find(['N', 'S']).ok_or_else(invalid)?;
+    let (latitude, longitude) = value.split_at(split + 1);
+    let latitude = parse_colon(latitude, Axis::Latitude).ok_or_else(invalid)?;
+    let longitude = parse_colon(longitude, Axis::Longitude).ok_or_else(invalid)?;
+
+    Ok(Coordinates {
+        latitude,
+        longitude,
+    })
+}
+
+/// Parses a radius in nautical miles
+fn parse_radius(text: &str) -> Result<f64, String> {
+    text.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|radius| *radius > 0.0)
+        .map(|radius| radius * METERS_PER_NAUTICAL_MILE)
+        .ok_or_else(|| format!("invalid radius '{}'", text.trim()))
+}
+
+/// Converts airspaces to restrictions
+///
+/// Identifiers are the prefix and the name in upper case, with anything
+///  other than letters and digits replaced by dashes. Airspaces with the
+///  same name are numbered.
+pub fn to_restrictions(
+    airspaces: &[Airspace],
+    prefix: &str,
+) -> HashMap<String, RestrictionDetails> {
+    let mut restrictions = HashMap::new();
+    for airspace in airspaces {
+        let id = airspace
+            .name
+            .to_uppercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+
+        let mut label = format!("{}{}", prefix, id);
+        let mut count = 1;
+        while restrictions.contains_key(&label) {
+            count += 1;
+            label = format!("{}{}-{}", prefix, id, count);
+        }
+
+        let details = RestrictionDetails {
+            shape: airspace.shape.clone(),
+            timestamp_start: None,
+            timestamp_end: None,
+            schedule: None,
+            lower: airspace.lower,
+            upper: airspace.upper,
+            zone_type: ZoneType::Restriction,
+            kind: RestrictionKind::NoFly,
+        };
+
+        restrictions.insert(label, details);
+    }
+
+    restrictions
+}
+
+/// Reads the airspaces from the source, skipping malformed airspaces
+///
+/// A directory source holds one OpenAir file per `.txt` file.
+pub async fn load_openair(
+    source: &Source,
+    prefix: &str,
+) -> Result<HashMap<String, RestrictionDetails>, RegionError> {
+    let documents = source
+        .read_all(OPENAIR_EXTENSION)
+        .await
+        .map_err(|e| RegionError::DatasetFailure(e.to_string()))?;
+
+    let mut airspaces = vec![];
+    let mut skipped = 0;
+    for (name, data) in documents {
+        for airspace in parse_airspaces(&String::from_utf8_lossy(&data)) {
+            match airspace {
+                Ok(airspace) => airspaces.push(airspace),
+                Err(e) => {
+                    region_warn!("Skipping malformed airspace in {}: {}", name, e);
+                    skipped += 1;
+                }
+            }
+        }
+    }
+
+    let restrictions = to_restrictions(&airspaces, prefix);
+    region_info!(
+        "Loaded {} airspaces from {}, skipped {} malformed.",
+        restrictions.len(),
+        source,
+        skipped
+    );
+
+    Ok(restrictions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::utils::{destination, distance_meters};
+
+    /// The OpenAir file shipped for tests
+    const OPENAIR_FIXTURE: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/openair.txt");
+
+    fn airspaces() -> Vec<Result<Airspace, String>> {
+        let text = std::fs::read_to_string(OPENAIR_FIXTURE).unwrap();
+        parse_airspaces(&text)
+    }
+
+    #[tokio::test]
+    async fn test_parse_airspaces() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let airspaces = airspaces();
+        assert_eq!(airspaces.len(), 6);
+
+        // polygon
+        let airspace = airspaces[0].as_ref().unwrap();
+        assert_eq!(airspace.class, "R");
+        assert_eq!(airspace.name, "EHR 4 Hoogeveen");
+        assert_eq!(airspace.lower, AltitudeLimit::SURFACE);
+        assert_eq!(airspace.upper.reference, AltitudeReference::Standard);
+        assert!((airspace.upper.meters - 1981.2).abs() < 1e-6);
+        let Shape::Polygon(vertices) = &airspace.shape else {
+            panic!("not a polygon: {:?}", airspace.shape);
+        };
+        assert_eq!(vertices.len(), 5);
+        assert_eq!(vertices.first(), vertices.last());
+        assert!((vertices[0].latitude - 52.808333).abs() < 1e-6);
+        assert!((vertices[0].longitude - 6.458333).abs() < 1e-6);
+
+        // circle of 2 NM
+        let airspace = airspaces[1].as_ref().unwrap();
+        assert_eq!(airspace.class, "P");
+        assert_eq!(
+            airspace.shape,
+            Shape::Circle {
+                center: parse_coordinates("52:05:30 N 005:07:30 E").unwrap(),
+                radius_meters: 3704.0,
+            }
+        );
+        assert_eq!(
+            airspace.upper,
+            AltitudeLimit::new(1500.0, "FT", AltitudeReference::Msl).unwrap()
+        );
+
+        // counterclockwise arc between two points
+        let airspace = airspaces[2].as_ref().unwrap();
+        assert_eq!(airspace.lower.reference, AltitudeReference::Agl);
+        assert!((airspace.upper.meters - 300.0).abs() < 1e-6);
+        let start = parse_coordinates("52:20:00 N 004:50:00 E").unwrap();
+        let end = parse_coordinates("52:20:00 N 004:40:00 E").unwrap();
+        let Shape::Boundary(segments) = &airspace.shape else {
+            panic!("not a boundary: {:?}", airspace.shape);
+        };
+        let Segment::Arc(arc) = &segments[0] else {
+            panic!("not an arc: {:?}", segments[0]);
+        };
+        assert!(distance_meters(&arc.start(), &start) < 1e-3);
+        assert_eq!(segments[1], Segment::Line(end));
+        // the arc runs north of the center
+        let center = parse_coordinates("52:20:00 N 004:45:00 E").unwrap();
+        assert!(airspace.shape.contains(&destination(&center, 0.0, 5000.0)));
+        assert!(!airspace
+            .shape
+            .contains(&destination(&center, 180.0, 9500.0)));
+
+        // arc by angles
+        let airspace = airspaces[3].as_ref().unwrap();
+        assert_eq!(airspace.upper, AltitudeLimit::UNLIMITED);
+        assert!(matches!(&airspace.shape, Shape::Boundary(s) if s.len() == 1));
+
+        // invalid coordinates
+        assert_eq!(
+            airspaces[4],
+            Err("EHD 9: line 42: invalid coordinates '52:61:00 N 005:00:00 E'".to_string())
+        );
+
+        // missing upper limit
+        assert_eq!(airspaces[5], Err("EHD 10: missing upper limit".to_string()));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_arc_resolution() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // arcs are only converted to vertices for svc-gis
+        let airspaces = airspaces();
+        let circle = &airspaces[1].as_ref().unwrap().shape;
+        assert_eq!(circle.to_polygon(100.0).len(), 15);
+        assert_eq!(circle.to_polygon(1.0).len(), 137);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_altitude() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let msl = |value: f64, unit: &str| {
+            AltitudeLimit::new(value, unit, AltitudeReference::Msl).unwrap()
+        };
+        let agl = |value: f64, unit: &str| {
+            AltitudeLimit::new(value, unit, AltitudeReference::Agl).unwrap()
+        };
+
+        assert_eq!(parse_altitude("GND").unwrap(), AltitudeLimit::SURFACE);
+        assert_eq!(parse_altitude("1000ft MSL").unwrap(), msl(1000.0, "FT"));
+        assert_eq!(parse_altitude("1000 MSL").unwrap(), msl(1000.0, "FT"));
+        assert_eq!(parse_altitude("1000").unwrap(), msl(1000.0, "FT"));
+        assert_eq!(parse_altitude("1000F AGL").unwrap(), agl(1000.0, "FT"));
+        assert_eq!(parse_altitude("1000 ft GND").unwrap(), agl(1000.0, "FT"));
+        assert_eq!(parse_altitude("150m AGL").unwrap(), agl(150.0, "M"));
+        assert_eq!(parse_altitude("150 M AMSL").unwrap(), msl(150.0, "M"));
+        assert!((parse_altitude("1000").unwrap().meters - 304.8).abs() < 1e-9);
+        assert_eq!(parse_altitude("UNL").unwrap(), AltitudeLimit::UNLIMITED);
+        assert_eq!(
+            parse_altitude("FL 95").unwrap().reference,
+            AltitudeReference::Standard
+        );
+        assert!(parse_altitude("FLX").is_err());
+        assert!(parse_altitude("1000 NM").is_err());
+        assert!(parse_altitude("").is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_coordinates() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let point = parse_coordinates("52:21:30 N 004:45:36 E").unwrap();
+        assert!((point.latitude - 52.358333).abs() < 1e-6);
+        assert!((point.longitude - 4.76).abs() < 1e-6);
+
+        let point = parse_coordinates("33:30.5S 070:45.25W").unwrap();
+        assert!((point.latitude + 33.508333).abs() < 1e-6);
+        assert!((point.longitude + 70.754167).abs() < 1e-6);
+
+        assert!(parse_coordinates("52:21:30 004:45:36 E").is_err());
+        assert!(parse_coordinates("52:21:30 N 004:45:36").is_err());
+        assert!(parse_coordinates("91:00:00 N 004:45:36 E").is_err());
+        assert!(parse_coordinates("52 N 004 E").is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_load_openair() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let restrictions = load_openair(&Source::File(OPENAIR_FIXTURE.into()), "TEST-")
+            .await
+            .unwrap();
+        let mut labels = restrictions.keys().cloned().collect::<Vec<_>>();
+        labels.sort();
+        assert_eq!(
+            labels,
+            vec![
+                "TEST-EHD-7",
+                "TEST-EHD-7-2",
+                "TEST-EHP-1-UTRECHT",
+                "TEST-EHR-4-HOOGEVEEN"
+            ]
+        );
+
+        let details = &restrictions["TEST-EHR-4-HOOGEVEEN"];
+        assert_eq!(details.lower, AltitudeLimit::SURFACE);
+        assert_eq!(details.kind, RestrictionKind::NoFly);
+
+        let error = load_openair(&Source::File("/nonexistent".into()), "TEST-")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, RegionError::DatasetFailure(_)));
+
+        ut_info!("Success.");
+    }
+}
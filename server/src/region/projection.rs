@@ -0,0 +1,464 @@
+//! Conversion of projected coordinates to latitude and longitude
+//!
+//! Coordinate reference systems are read from the well-known text (WKT) of
+//!  a `.prj` file, in the Esri or OGC dialect. Geographic systems and the
+//!  Transverse Mercator (including UTM), Lambert Conformal Conic and Web
+//!  Mercator projections are supported. Datum shifts are not applied, so
+//!  the datum of the layer should be close to WGS84, as NAD83 and ETRS89 are.
+
+use regex::Regex;
+use svc_gis_client_grpc::prelude::gis::Coordinates;
+
+/// The WGS84 ellipsoid
+const WGS84: Ellipsoid = Ellipsoid {
+    semi_major_axis: 6_378_137.0,
+    inverse_flattening: 298.257_223_563,
+};
+
+/// Tolerance of the iterative latitude of the Lambert Conformal Conic projection
+const LATITUDE_TOLERANCE_RADIANS: f64 = 1e-12;
+
+/// Maximum iterations for the latitude of the Lambert Conformal Conic projection
+const MAX_ITERATIONS: usize = 15;
+
+/// A reference ellipsoid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    /// Semi-major axis in meters
+    pub semi_major_axis: f64,
+
+    /// Inverse flattening, zero for a sphere
+    pub inverse_flattening: f64,
+}
+
+impl Ellipsoid {
+    /// Square of the eccentricity
+    fn eccentricity_squared(&self) -> f64 {
+        let f = self.inverse_flattening;
+        if f == 0.0 {
+            0.0
+        } else {
+            (2.0 - 1.0 / f) / f
+        }
+    }
+}
+
+/// A map projection with its parameters, angles in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    /// Longitude and latitude in degrees
+    Geographic,
+
+    /// Spherical Mercator as used by web maps
+    WebMercator,
+
+    /// Transverse Mercator, such as UTM
+    TransverseMercator {
+        /// Longitude of the origin
+        central_meridian: f64,
+
+        /// Latitude of the origin
+        latitude_of_origin: f64,
+
+        /// Scale factor on the central meridian
+        scale_factor: f64,
+    },
+
+    /// Lambert Conformal Conic with one or two standard parallels
+    LambertConformalConic {
+        /// Longitude of the origin
+        central_meridian: f64,
+
+        /// Latitude of the origin
+        latitude_of_origin: f64,
+
+        /// First standard parallel
+        standard_parallel_1: f64,
+
+        /// Second standard parallel, the first one if there is only one
+        standard_parallel_2: f64,
+
+        /// Scale factor at the standard parallels
+        scale_factor: f64,
+    },
+}
+
+/// A coordinate reference system
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Projection {
+    /// Projection method and parameters
+    pub method: Method,
+
+    /// Reference ellipsoid of the datum
+    pub ellipsoid: Ellipsoid,
+
+    /// Easting of the origin, in the linear unit
+    pub false_easting: f64,
+
+    /// Northing of the origin, in the linear unit
+    pub false_northing: f64,
+
+    /// Meters per linear unit
+    pub unit_meters: f64,
+}
+
+impl Default for Projection {
+    /// Longitude and latitude on WGS84
+    fn default() -> Self {
+        Self {
+            method: Method::Geographic,
+            ellipsoid: WGS84,
+            false_easting: 0.0,
+            false_northing: 0.0,
+            unit_meters: 1.0,
+        }
+    }
+}
+
+impl Projection {
+    /// Reads the coordinate reference system from its well-known text
+    pub fn from_wkt(wkt: &str) -> Result<Self, String> {
+        let spheroid =
+            Regex::new(r#"(?i)(?:SPHEROID|ELLIPSOID)\["[^"]*",\s*([-0-9.eE+]+),\s*([-0-9.eE+]+)"#)
+                .map_err(|e| e.to_string())?;
+        let parameter = Regex::new(r#"(?i)PARAMETER\["([^"]+)",\s*([-0-9.eE+]+)\]"#)
+            .map_err(|e| e.to_string())?;
+        let unit =
+            Regex::new(r#"(?i)UNIT\["[^"]*",\s*([-0-9.eE+]+)"#).map_err(|e| e.to_string())?;
+        let projection = Regex::new(r#"(?i)PROJECTION\["([^"]+)""#).map_err(|e| e.to_string())?;
+
+        let number = |text: &str| {
+            text.parse::<f64>()
+                .map_err(|_| format!("invalid number '{}'", text))
+        };
+
+        let ellipsoid = match spheroid.captures(wkt) {
+            Some(captures) => Ellipsoid {
+                semi_major_axis: number(&captures[1])?,
+                inverse_flattening: number(&captures[2])?,
+            },
+            None => WGS84,
+        };
+
+        if wkt.trim_start().to_uppercase().starts_with("GEOGCS") {
+            return Ok(Self {
+                ellipsoid,
+                ..Default::default()
+            });
+        }
+
+        let Some(name) = projection.captures(wkt).map(|c| c[1].to_lowercase()) else {
+            return Err("unknown coordinate reference system".to_string());
+        };
+
+        let mut parameters = std::collections::HashMap::new();
+        for captures in parameter.captures_iter(wkt) {
+            parameters.insert(captures[1].to_lowercase(), number(&captures[2])?);
+        }
+        let get = |names: &[&str], default: Option<f64>| {
+            names
+                .iter()
+                .find_map(|name| parameters.get(*name).copied())
+                .or(default)
+                .ok_or_else(|| format!("missing parameter {}", names[0]))
+        };
+
+        // the linear unit of a projected system follows its parameters
+        let unit_meters = match unit.captures_iter(wkt).last() {
+            Some(captures) => number(&captures[1])?,
+            None => 1.0,
+        };
+
+        let central_meridian = get(&["central_meridian", "longitude_of_center"], Some(0.0))?;
+        let latitude_of_origin = get(&["latitude_of_origin", "latitude_of_center"], Some(0.0))?;
+        let scale_factor = get(&["scale_factor"], Some(1.0))?;
+        let method = match name.as_str() {
+            "transverse_mercator" => Method::TransverseMercator {
+                central_meridian,
+                latitude_of_origin,
+                scale_factor,
+            },
+            "lambert_conformal_conic"
+            | "lambert_conformal_conic_1sp"
+            | "lambert_conformal_conic_2sp" => {
+                let standard_parallel_1 = get(&["standard_parallel_1"], Some(latitude_of_origin))?;
+                Method::LambertConformalConic {
+                    central_meridian,
+                    latitude_of_origin,
+                    standard_parallel_1,
+                    standard_parallel_2: get(&["standard_parallel_2"], Some(standard_parallel_1))?,
+                    scale_factor,
+                }
+            }
+            "mercator_auxiliary_sphere" | "popular_visualisation_pseudo_mercator" => {
+                Method::WebMercator
+            }
+            other => return Err(format!("unsupported projection '{}'", other)),
+        };
+
+        Ok(Self {
+            method,
+            ellipsoid,
+            false_easting: get(&["false_easting"], Some(0.0))?,
+            false_northing: get(&["false_northing"], Some(0.0))?,
+            unit_meters,
+        })
+    }
+
+    /// Converts projected coordinates to latitude and longitude
+    ///
+    /// Returns `None` if the result is out of range.
+    pub fn to_coordinates(&self, x: f64, y: f64) -> Option<Coordinates> {
+        let (latitude, longitude) = match self.method {
+            Method::Geographic => (y, x),
+            _ => {
+                let x = (x - self.false_easting) * self.unit_meters;
+                let y = (y - self.false_northing) * self.unit_meters;
+                self.inverse(x, y)
+            }
+        };
+
+        let longitude = (longitude + 540.0).rem_euclid(360.0) - 180.0;
+        ((-90.0..=90.0).contains(&latitude) && longitude.is_finite()).then_some(Coordinates {
+            latitude,
+            longitude,
+        })
+    }
+
+    /// Latitude and longitude in degrees of projected coordinates in meters
+    ///  relative to the false origin
+    fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let a = self.ellipsoid.semi_major_axis;
+        let e2 = self.ellipsoid.eccentricity_squared();
+        match self.method {
+            Method::Geographic => (y, x),
+            Method::WebMercator => (
+                (2.0 * (y / a).exp().atan() - std::f64::consts::FRAC_PI_2).to_degrees(),
+                (x / a).to_degrees(),
+            ),
+            Method::TransverseMercator {
+                central_meridian,
+                latitude_of_origin,
+                scale_factor,
+            } => {
+                let ep2 = e2 / (1.0 - e2);
+                let m = meridian_arc(a, e2, latitude_of_origin.to_radians()) + y / scale_factor;
+                let mu =
+                    m / (a * (1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0));
+                let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+                let phi1 = mu
+                    + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+                    + (21.0 * e1.powi(2) / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+                    + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+                    + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+                let (sin, cos, tan) = (phi1.sin(), phi1.cos(), phi1.tan());
+                let c1 = ep2 * cos.powi(2);
+                let t1 = tan.powi(2);
+                let n1 = a / (1.0 - e2 * sin.powi(2)).sqrt();
+                let r1 = a * (1.0 - e2) / (1.0 - e2 * sin.powi(2)).powf(1.5);
+                let d = x / (n1 * scale_factor);
+
+                let latitude = phi1
+                    - (n1 * tan / r1)
+                        * (d.powi(2) / 2.0
+                            - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1.powi(2) - 9.0 * ep2)
+                                * d.powi(4)
+                                / 24.0
+                            + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1.powi(2)
+                                - 252.0 * ep2
+                                - 3.0 * c1.powi(2))
+                                * d.powi(6)
+                                / 720.0);
+                let longitude = (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+                    + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1.powi(2)
+                        + 8.0 * ep2
+                        + 24.0 * t1.powi(2))
+                        * d.powi(5)
+                        / 120.0)
+                    / cos;
+
+                (
+                    latitude.to_degrees(),
+                    central_meridian + longitude.to_degrees(),
+                )
+            }
+            Method::LambertConformalConic {
+                central_meridian,
+                latitude_of_origin,
+                standard_parallel_1,
+                standard_parallel_2,
+                scale_factor,
+            } => {
+                let e = e2.sqrt();
+                let m = |phi: f64| phi.cos() / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+                let t = |phi: f64| {
+                    (std::f64::consts::FRAC_PI_4 - phi / 2.0).tan()
+                        / ((1.0 - e * phi.sin()) / (1.0 + e * phi.sin())).powf(e / 2.0)
+                };
+
+                let (phi1, phi2) = (
+                    standard_parallel_1.to_radians(),
+                    standard_parallel_2.to_radians(),
+                );
+                let n = match (phi1 - phi2).abs() < f64::EPSILON {
+                    true => phi1.sin(),
+                    false => (m(phi1).ln() - m(phi2).ln()) / (t(phi1).ln() - t(phi2).ln()),
+                };
+                let f = m(phi1) / (n * t(phi1).powf(n));
+                let af = a * f * scale_factor;
+                let rho0 = af * t(latitude_of_origin.to_radians()).powf(n);
+
+                let sign = n.signum();
+                let rho = sign * (x.powi(2) + (rho0 - y).powi(2)).sqrt();
+                let theta = (sign * x).atan2(sign * (rho0 - y));
+                let t = (rho / af).powf(1.0 / n);
+
+                let mut phi = std::f64::consts::FRAC_PI_2 - 2.0 * t.atan();
+                for _ in 0..MAX_ITERATIONS {
+                    let next = std::f64::consts::FRAC_PI_2
+                        - 2.0
+                            * (t * ((1.0 - e * phi.sin()) / (1.0 + e * phi.sin())).powf(e / 2.0))
+                                .atan();
+                    let done = (next - phi).abs() < LATITUDE_TOLERANCE_RADIANS;
+                    phi = next;
+                    if done {
+                        break;
+                    }
+                }
+
+                (
+                    phi.to_degrees(),
+                    central_meridian + (theta / n).to_degrees(),
+                )
+            }
+        }
+    }
+}
+
+/// Distance in meters along the meridian from the equator to the latitude
+fn meridian_arc(a: f64, e2: f64, phi: f64) -> f64 {
+    a * ((1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0) * phi
+        - (3.0 * e2 / 8.0 + 3.0 * e2.powi(2) / 32.0 + 45.0 * e2.powi(3) / 1024.0)
+            * (2.0 * phi).sin()
+        + (15.0 * e2.powi(2) / 256.0 + 45.0 * e2.powi(3) / 1024.0) * (4.0 * phi).sin()
+        - (35.0 * e2.powi(3) / 3072.0) * (6.0 * phi).sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// NAD83 / UTM zone 14N (EPSG:26914)
+    const UTM_14N: &str = r#"PROJCS["NAD_1983_UTM_Zone_14N",GEOGCS["GCS_North_American_1983",DATUM["D_North_American_1983",SPHEROID["GRS_1980",6378137.0,298.257222101]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Transverse_Mercator"],PARAMETER["False_Easting",500000.0],PARAMETER["False_Northing",0.0],PARAMETER["Central_Meridian",-99.0],PARAMETER["Scale_Factor",0.9996],PARAMETER["Latitude_Of_Origin",0.0],UNIT["Meter",1.0]]"#;
+
+    /// NAD83 / Texas Central in US survey feet (EPSG:2277)
+    const TEXAS_CENTRAL: &str = r#"PROJCS["NAD_1983_StatePlane_Texas_Central_FIPS_4203_Feet",GEOGCS["GCS_North_American_1983",DATUM["D_North_American_1983",SPHEROID["GRS_1980",6378137.0,298.257222101]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Lambert_Conformal_Conic"],PARAMETER["False_Easting",2296583.333333333],PARAMETER["False_Northing",9842500.0],PARAMETER["Central_Meridian",-100.3333333333333],PARAMETER["Standard_Parallel_1",30.11666666666667],PARAMETER["Standard_Parallel_2",31.88333333333333],PARAMETER["Latitude_Of_Origin",29.66666666666667],UNIT["Foot_US",0.3048006096012192]]"#;
+
+    /// WGS84 / Pseudo-Mercator (EPSG:3857)
+    const WEB_MERCATOR: &str = r#"PROJCS["WGS_1984_Web_Mercator_Auxiliary_Sphere",GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Mercator_Auxiliary_Sphere"],PARAMETER["False_Easting",0.0],PARAMETER["False_Northing",0.0],PARAMETER["Central_Meridian",0.0],PARAMETER["Standard_Parallel_1",0.0],PARAMETER["Auxiliary_Sphere_Type",0.0],UNIT["Meter",1.0]]"#;
+
+    fn assert_near(point: Coordinates, latitude: f64, longitude: f64) {
+        assert!(
+            (point.latitude - latitude).abs() < 1e-7 && (point.longitude - longitude).abs() < 1e-7,
+            "{:?} is not near ({}, {})",
+            point,
+            latitude,
+            longitude
+        );
+    }
+
+    #[tokio::test]
+    async fn test_geographic() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let wkt = r#"GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]]"#;
+        let projection = Projection::from_wkt(wkt).unwrap();
+        assert_eq!(projection, Projection::default());
+        assert_near(projection.to_coordinates(4.76, 52.31).unwrap(), 52.31, 4.76);
+        assert!(projection.to_coordinates(4.76, 95.0).is_none());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_transverse_mercator() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let projection = Projection::from_wkt(UTM_14N).unwrap();
+        assert_near(
+            projection.to_coordinates(500_000.0, 0.0).unwrap(),
+            0.0,
+            -99.0,
+        );
+        assert_near(
+            projection
+                .to_coordinates(608_821.826, 3_346_163.025)
+                .unwrap(),
+            30.242186,
+            -97.868981,
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_lambert_conformal_conic() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let projection = Projection::from_wkt(TEXAS_CENTRAL).unwrap();
+        assert!((projection.unit_meters - 0.3048006096).abs() < 1e-9);
+        assert_near(
+            projection
+                .to_coordinates(2_296_583.333, 9_842_500.0)
+                .unwrap(),
+            29.666_666_667,
+            -100.333_333_333,
+        );
+        assert_near(
+            projection
+                .to_coordinates(3_074_698.326, 10_060_438.249)
+                .unwrap(),
+            30.242186,
+            -97.868981,
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_web_mercator() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let projection = Projection::from_wkt(WEB_MERCATOR).unwrap();
+        assert_eq!(projection.method, Method::WebMercator);
+        assert_near(
+            projection
+                .to_coordinates(529_880.776, 6_856_372.691)
+                .unwrap(),
+            52.31,
+            4.76,
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_unsupported() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let wkt = UTM_14N.replace("Transverse_Mercator", "Polyconic");
+        assert_eq!(
+            Projection::from_wkt(&wkt),
+            Err("unsupported projection 'polyconic'".to_string())
+        );
+        assert!(Projection::from_wkt("LOCAL_CS[\"Grid\"]").is_err());
+
+        ut_info!("Success.");
+    }
+}
@@ -0,0 +1,750 @@
+//! Recurring activation schedules of restrictions
+//!
+//! A schedule is a set of daily rules in a timezone. Each rule applies on
+//!  a set of weekdays, from a start to an end time of day. Times are clock
+//!  times in the timezone of the schedule or sun events at the location of
+//!  the restriction, such as sunrise and sunset. A rule ending at or before
+//!  its start runs overnight into the next day.
+//!
+//! Schedules are written as in item `D)` of a NOTAM:
+//!
+//! ```text
+//! MON-FRI 0800-1800, SAT SUN SR-SS
+//! DAILY 0600-1200 1400-SS PLUS30
+//! H24
+//! ```
+//!
+//! Restrictions keep their schedule, and it is expanded to activation
+//!  windows where needed, such as when the zones are pushed to svc-gis.
+
+use chrono_tz::Tz;
+use lib_common::time::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc};
+use svc_gis_client_grpc::prelude::gis::Coordinates;
+
+/// Hours ahead for which schedules are expanded to activation windows
+pub const SCHEDULE_HORIZON_HOURS: u32 = 168;
+
+/// Day names, starting on Monday
+const DAYS: [&str; 7] = ["MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+
+/// Day names matching every day
+const EVERY_DAY: &[&str] = &["DAILY", "ANY"];
+
+/// Time range of a whole day
+const WHOLE_DAY: &str = "H24";
+
+/// Zenith of the sun in degrees at sunrise and sunset, with refraction
+const SUNRISE_ZENITH_DEGREES: f64 = 90.833;
+
+/// Zenith of the sun in degrees at the start and end of civil twilight
+const CIVIL_TWILIGHT_ZENITH_DEGREES: f64 = 96.0;
+
+/// An activation window, from its start until its end
+pub type Window = (DateTime<Utc>, DateTime<Utc>);
+
+/// Errors when reading a schedule
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ScheduleError {
+    /// A part of the schedule is neither a day nor a time range
+    #[error("error: Invalid schedule '{0}'")]
+    InvalidToken(String),
+
+    /// The schedule has days but no time ranges
+    #[error("error: Schedule has no time ranges.")]
+    NoTimes,
+
+    /// The timezone is not in the tz database
+    #[error("error: Unknown timezone '{0}'")]
+    UnknownTimezone(String),
+}
+
+/// A set of days of the week
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weekdays(u8);
+
+impl Weekdays {
+    /// Every day of the week
+    pub const ALL: Weekdays = Weekdays(0b111_1111);
+
+    /// Parses a day name such as `MON`, a range such as `MON-FRI` or
+    ///  `DAILY`, ignoring case
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_uppercase();
+        if EVERY_DAY.contains(&text.as_str()) {
+            return Some(Self::ALL);
+        }
+
+        let day = |name: &str| DAYS.iter().position(|d| *d == name);
+        let (first, last) = match text.split_once('-') {
+            Some((first, last)) => (day(first)?, day(last)?),
+            None => (day(&text)?, day(&text)?),
+        };
+
+        // ranges such as FRI-MON wrap around the end of the week
+        let count = (last + DAYS.len() - first) % DAYS.len() + 1;
+        Some(Weekdays(
+            (first..first + count).fold(0, |mask, i| mask | 1 << (i % DAYS.len())),
+        ))
+    }
+
+    /// Returns true if the set holds the weekday of the date
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.0 & 1 << date.weekday().num_days_from_monday() != 0
+    }
+}
+
+impl std::ops::BitOr for Weekdays {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Weekdays(self.0 | other.0)
+    }
+}
+
+/// When a sun event happens on a day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SunTime {
+    /// The event happens at the time
+    At(DateTime<Utc>),
+
+    /// The sun stays above the zenith of the event all day, as in a polar
+    ///  day
+    AlwaysUp,
+
+    /// The sun stays below the zenith of the event all day, as in a polar
+    ///  night
+    AlwaysDown,
+}
+
+/// A daily event of the sun
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SunEvent {
+    /// Begin of morning civil twilight (`BMCT`)
+    MorningTwilight,
+
+    /// Sunrise (`SR`)
+    Sunrise,
+
+    /// Sunset (`SS`)
+    Sunset,
+
+    /// End of evening civil twilight (`EECT`)
+    EveningTwilight,
+}
+
+impl SunEvent {
+    /// Parses an event code, ignoring case
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_uppercase().as_str() {
+            "BMCT" => Some(Self::MorningTwilight),
+            "SR" => Some(Self::Sunrise),
+            "SS" => Some(Self::Sunset),
+            "EECT" => Some(Self::EveningTwilight),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the sun rises through the zenith of the event
+    pub fn is_rising(&self) -> bool {
+        matches!(self, Self::MorningTwilight | Self::Sunrise)
+    }
+
+    /// Time of the event on the date at the location
+    ///
+    /// Uses the sunrise equation of the Almanac for Computers, accurate to
+    ///  a few minutes. In a polar day or night the sun does not cross the
+    ///  zenith of the event, and stays above or below it all day.
+    pub fn time(&self, date: NaiveDate, location: &Coordinates) -> SunTime {
+        let zenith = match self {
+            Self::MorningTwilight | Self::EveningTwilight => CIVIL_TWILIGHT_ZENITH_DEGREES,
+            Self::Sunrise | Self::Sunset => SUNRISE_ZENITH_DEGREES,
+        };
+        let rising = self.is_rising();
+
+        let (sin, cos) = (
+            |degrees: f64| degrees.to_radians().sin(),
+            |degrees: f64| degrees.to_radians().cos(),
+        );
+        let longitude_hours = location.longitude / 15.0;
+        let approximate = if rising { 6.0 } else { 18.0 };
+        let t = f64::from(date.ordinal()) + (approximate - longitude_hours) / 24.0;
+
+        // mean anomaly and true longitude of the sun
+        let mean_anomaly = 0.9856 * t - 3.289;
+        let sun_longitude =
+            (mean_anomaly + 1.916 * sin(mean_anomaly) + 0.020 * sin(2.0 * mean_anomaly) + 282.634)
+                .rem_euclid(360.0);
+
+        // right ascension in the same quadrant as the longitude, in hours
+        let ascension = (0.91764 * sun_longitude.to_radians().tan())
+            .atan()
+            .to_degrees()
+            .rem_euclid(360.0);
+        let ascension = (ascension + (sun_longitude / 90.0).floor() * 90.0
+            - (ascension / 90.0).floor() * 90.0)
+            / 15.0;
+
+        let sin_declination = 0.39782 * sin(sun_longitude);
+        let cos_declination = sin_declination.asin().cos();
+        let cos_hour_angle = (cos(zenith) - sin_declination * sin(location.latitude))
+            / (cos_declination * cos(location.latitude));
+        if cos_hour_angle < -1.0 {
+            return SunTime::AlwaysUp;
+        }
+        if cos_hour_angle > 1.0 {
+            return SunTime::AlwaysDown;
+        }
+
+        let hour_angle = cos_hour_angle.acos().to_degrees();
+        let hour_angle = match rising {
+            true => 360.0 - hour_angle,
+            false => hour_angle,
+        } / 15.0;
+
+        let local_time = (hour_angle + ascension - 0.06571 * t - 6.622).rem_euclid(24.0);
+        let seconds = ((local_time - longitude_hours) * 3600.0).round() as i64;
+        SunTime::At(date.and_time(NaiveTime::MIN).and_utc() + Duration::seconds(seconds))
+    }
+}
+
+/// A time of day of a daily rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeOfDay {
+    /// A clock time in the timezone of the schedule
+    Clock(NaiveTime),
+
+    /// A sun event, shifted by the given minutes
+    Sun(SunEvent, i64),
+}
+
+impl TimeOfDay {
+    /// Parses a clock time such as `0800`, `08:00` or `08:00:00Z`, or a
+    ///  sun event such as `SR` or `SS MINUS15`, ignoring case
+    ///
+    /// `2400` is midnight at the end of the day.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_uppercase().replace(char::is_whitespace, "");
+        let offset = ["PLUS", "MINUS", "+"]
+            .iter()
+            .find_map(|sign| text.find(sign));
+        if let Some(offset) = offset {
+            let (event, offset) = text.split_at(offset);
+            let minutes = match offset.strip_prefix("PLUS").or(offset.strip_prefix('+')) {
+                Some(minutes) => minutes.parse::<i64>().ok()?,
+                None => -offset.strip_prefix("MINUS")?.parse::<i64>().ok()?,
+            };
+            return Some(Self::Sun(SunEvent::parse(event)?, minutes));
+        }
+
+        if let Some(event) = SunEvent::parse(&text) {
+            return Some(Self::Sun(event, 0));
+        }
+
+        let text = text.trim_end_matches('Z');
+        if text == "2400" || text == "24:00" {
+            return Some(Self::Clock(NaiveTime::MIN));
+        }
+
+        NaiveTime::parse_from_str(text, "%H%M")
+            .or_else(|_| NaiveTime::parse_from_str(text, "%H:%M"))
+            .or_else(|_| NaiveTime::parse_from_str(text, "%H:%M:%S"))
+            .ok()
+            .map(Self::Clock)
+    }
+
+    /// The time on the local date in the timezone at the location
+    ///
+    /// Clock times skipped by a daylight saving change are moved an hour
+    ///  later. A sun event the sun is past all day, such as the sunrise in
+    ///  a polar day or the sunset in a polar night, is at the start of the
+    ///  day. Returns `None` if the sun does not reach the event that day.
+    pub fn on(
+        &self,
+        date: NaiveDate,
+        timezone: Tz,
+        location: &Coordinates,
+    ) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Clock(time) => {
+                let local = date.and_time(*time);
+                local
+                    .and_local_timezone(timezone)
+                    .earliest()
+                    .or_else(|| {
+                        (local + Duration::hours(1))
+                            .and_local_timezone(timezone)
+                            .earliest()
+                    })
+                    .map(|time| time.with_timezone(&Utc))
+            }
+            Self::Sun(event, minutes) => match event.time(date, location) {
+                SunTime::At(time) => Some(time + Duration::minutes(*minutes)),
+                SunTime::AlwaysUp if event.is_rising() => {
+                    Self::Clock(NaiveTime::MIN).on(date, timezone, location)
+                }
+                SunTime::AlwaysDown if !event.is_rising() => {
+                    Self::Clock(NaiveTime::MIN).on(date, timezone, location)
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+/// A daily activation on a set of weekdays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyRule {
+    /// Weekdays on which the rule starts
+    pub days: Weekdays,
+
+    /// Start of the activation
+    pub start: TimeOfDay,
+
+    /// End of the activation, on the next day if not after the start
+    pub end: TimeOfDay,
+}
+
+/// A recurring activation schedule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    /// Timezone of the days and clock times
+    pub timezone: Tz,
+
+    /// Daily rules, active when any of them is
+    pub rules: Vec<DailyRule>,
+}
+
+impl Schedule {
+    /// Parses a schedule in the form of NOTAM item `D)`
+    ///
+    /// Days, such as `MON`, `MON-FRI` or `DAILY`, apply to the time ranges
+    ///  that follow them. Time ranges without days apply every day. Ranges
+    ///  and days may be separated by spaces or commas.
+    pub fn parse(text: &str, timezone: Tz) -> Result<Self, ScheduleError> {
+        let text = text
+            .to_uppercase()
+            .replace([',', ';'], " ")
+            .replace(" PLUS", "PLUS")
+            .replace(" MINUS", "MINUS");
+
+        let mut rules = vec![];
+        let mut days: Option<Weekdays> = None;
+        let mut after_times = false;
+        for token in text.split_whitespace() {
+            if let Some(weekdays) = Weekdays::parse(token) {
+                days = match (days, after_times) {
+                    (Some(days), false) => Some(days | weekdays),
+                    _ => Some(weekdays),
+                };
+                after_times = false;
+                continue;
+            }
+
+            let (start, end) = match token {
+                WHOLE_DAY => (
+                    TimeOfDay::Clock(NaiveTime::MIN),
+                    TimeOfDay::Clock(NaiveTime::MIN),
+                ),
+                _ => token
+                    .split_once('-')
+                    .and_then(|(start, end)| {
+                        Some((TimeOfDay::parse(start)?, TimeOfDay::parse(end)?))
+                    })
+                    .ok_or_else(|| ScheduleError::InvalidToken(token.to_string()))?,
+            };
+
+            rules.push(DailyRule {
+                days: days.unwrap_or(Weekdays::ALL),
+                start,
+                end,
+            });
+            after_times = true;
+        }
+
+        if rules.is_empty() {
+            return Err(ScheduleError::NoTimes);
+        }
+
+        Ok(Schedule { timezone, rules })
+    }
+
+    /// Activation windows at the location overlapping `[from, to)`, merged
+    ///  where they touch
+    ///
+    /// Windows are not clipped, so a window keeps its start time however
+    ///  far it is underway at `from`.
+    pub fn windows(
+        &self,
+        location: &Coordinates,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<Window> {
+        self.all_windows(location, from, to)
+            .into_iter()
+            .filter(|(start, end)| *start < to && *end > from)
+            .collect()
+    }
+
+    /// Returns true if the schedule is active at any time from `from` until
+    ///  `to`, both included
+    pub fn is_active(
+        &self,
+        location: &Coordinates,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> bool {
+        self.all_windows(location, from, to)
+            .iter()
+            .any(|(start, end)| *start <= to && from < *end)
+    }
+
+    /// Merged windows of the rules starting on the local dates from the day
+    ///  before `from` until `to`
+    fn all_windows(
+        &self,
+        location: &Coordinates,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<Window> {
+        let first = from.with_timezone(&self.timezone).date_naive() - Duration::days(1);
+        let last = to.with_timezone(&self.timezone).date_naive();
+
+        let mut windows = vec![];
+        for date in first.iter_days().take_while(|date| *date <= last) {
+            for rule in self.rules.iter().filter(|rule| rule.days.contains(date)) {
+                let Some(start) = rule.start.on(date, self.timezone, location) else {
+                    continue;
+                };
+
+                let next = date.succ_opt();
+                let end_on_next =
+                    || next.and_then(|next| rule.end.on(next, self.timezone, location));
+                let end = match rule.end.on(date, self.timezone, location) {
+                    Some(end) if end > start => Some(end),
+                    Some(_) => end_on_next(),
+                    // the sun does not reach the end event, such as the
+                    //  sunset in a polar day, so the window lasts until the
+                    //  event on the next day or else the end of the day
+                    None => end_on_next().or_else(|| {
+                        next.and_then(|next| {
+                            TimeOfDay::Clock(NaiveTime::MIN).on(next, self.timezone, location)
+                        })
+                    }),
+                };
+
+                if let Some(end) = end.filter(|end| *end > start) {
+                    windows.push((start, end));
+                }
+            }
+        }
+
+        windows.sort();
+        windows
+            .into_iter()
+            .fold(vec![], |mut merged: Vec<Window>, (start, end)| {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                    _ => merged.push((start, end)),
+                }
+                merged
+            })
+    }
+}
+
+/// Parses the name of a timezone in the tz database, such as
+///  `Europe/Amsterdam` or `UTC`
+pub fn parse_timezone(name: &str) -> Result<Tz, ScheduleError> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| ScheduleError::UnknownTimezone(name.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        text.parse().unwrap()
+    }
+
+    /// Dam square in Amsterdam
+    const AMSTERDAM: Coordinates = Coordinates {
+        latitude: 52.373,
+        longitude: 4.893,
+    };
+
+    /// Tromsø, north of the arctic circle
+    const TROMSO: Coordinates = Coordinates {
+        latitude: 69.65,
+        longitude: 18.96,
+    };
+
+    #[tokio::test]
+    async fn test_weekdays() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // Wednesday
+        let wednesday = date("2024-06-05");
+        assert!(Weekdays::parse("wed").unwrap().contains(wednesday));
+        assert!(Weekdays::parse("MON-FRI").unwrap().contains(wednesday));
+        assert!(!Weekdays::parse("THU-TUE").unwrap().contains(wednesday));
+        assert!(Weekdays::parse("FRI-MON")
+            .unwrap()
+            .contains(date("2024-06-09")));
+        assert_eq!(Weekdays::parse("DAILY"), Some(Weekdays::ALL));
+        assert_eq!(
+            Weekdays::parse("SAT").unwrap() | Weekdays::parse("SUN").unwrap(),
+            Weekdays::parse("SAT-SUN").unwrap()
+        );
+        assert!(Weekdays::parse("MONDAY").is_none());
+        assert!(Weekdays::parse("MON-").is_none());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_sun_events() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // midsummer in Amsterdam, sunrise at 05:18 and sunset at 22:06 local time
+        let midsummer = date("2024-06-21");
+        let at = |event: SunEvent, date: NaiveDate, location: &Coordinates| match event
+            .time(date, location)
+        {
+            SunTime::At(time) => time,
+            other => panic!("no {event:?} on {date}: {other:?}"),
+        };
+        let sunrise = at(SunEvent::Sunrise, midsummer, &AMSTERDAM);
+        let sunset = at(SunEvent::Sunset, midsummer, &AMSTERDAM);
+        assert!((sunrise - time("2024-06-21T03:18:00Z")).num_minutes().abs() <= 3);
+        assert!((sunset - time("2024-06-21T20:06:00Z")).num_minutes().abs() <= 3);
+
+        let dawn = at(SunEvent::MorningTwilight, midsummer, &AMSTERDAM);
+        let dusk = at(SunEvent::EveningTwilight, midsummer, &AMSTERDAM);
+        assert!(dawn < sunrise && sunset < dusk);
+
+        // Los Angeles, where sunset is on the next day in UTC
+        let los_angeles = Coordinates {
+            latitude: 34.05,
+            longitude: -118.24,
+        };
+        let sunset = at(SunEvent::Sunset, midsummer, &los_angeles);
+        assert!((sunset - time("2024-06-22T03:08:00Z")).num_minutes().abs() <= 3);
+
+        // no sunrise in the polar day and night of Tromsø
+        let midwinter = date("2024-12-21");
+        assert_eq!(
+            SunEvent::Sunrise.time(midsummer, &TROMSO),
+            SunTime::AlwaysUp
+        );
+        assert_eq!(SunEvent::Sunset.time(midsummer, &TROMSO), SunTime::AlwaysUp);
+        assert_eq!(
+            SunEvent::Sunset.time(midwinter, &TROMSO),
+            SunTime::AlwaysDown
+        );
+        assert_eq!(
+            SunEvent::Sunrise.time(midwinter, &TROMSO),
+            SunTime::AlwaysDown
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let clock =
+            |text: &str| TimeOfDay::Clock(NaiveTime::parse_from_str(text, "%H:%M").unwrap());
+        assert_eq!(TimeOfDay::parse("0800"), Some(clock("08:00")));
+        assert_eq!(TimeOfDay::parse("08:00Z"), Some(clock("08:00")));
+        assert_eq!(TimeOfDay::parse("2400"), Some(clock("00:00")));
+        assert_eq!(
+            TimeOfDay::parse("ss minus15"),
+            Some(TimeOfDay::Sun(SunEvent::Sunset, -15))
+        );
+        assert_eq!(
+            TimeOfDay::parse("SR+30"),
+            Some(TimeOfDay::Sun(SunEvent::Sunrise, 30))
+        );
+        assert_eq!(
+            TimeOfDay::parse("BMCT"),
+            Some(TimeOfDay::Sun(SunEvent::MorningTwilight, 0))
+        );
+        assert_eq!(TimeOfDay::parse("2500"), None);
+        assert_eq!(TimeOfDay::parse("NOON"), None);
+
+        let schedule =
+            Schedule::parse("MON WED-FRI 0800-1200 1300-SS PLUS30, SAT SR-1400", Tz::UTC).unwrap();
+        let weekdays = Weekdays::parse("MON").unwrap() | Weekdays::parse("WED-FRI").unwrap();
+        assert_eq!(
+            schedule.rules,
+            vec![
+                DailyRule {
+                    days: weekdays,
+                    start: clock("08:00"),
+                    end: clock("12:00"),
+                },
+                DailyRule {
+                    days: weekdays,
+                    start: clock("13:00"),
+                    end: TimeOfDay::Sun(SunEvent::Sunset, 30),
+                },
+                DailyRule {
+                    days: Weekdays::parse("SAT").unwrap(),
+                    start: TimeOfDay::Sun(SunEvent::Sunrise, 0),
+                    end: clock("14:00"),
+                },
+            ]
+        );
+
+        let schedule = Schedule::parse("H24", Tz::UTC).unwrap();
+        assert_eq!(schedule.rules[0].days, Weekdays::ALL);
+
+        assert_eq!(
+            Schedule::parse("MON-FRI", Tz::UTC),
+            Err(ScheduleError::NoTimes)
+        );
+        assert_eq!(
+            Schedule::parse("JUN 01 0800-1200", Tz::UTC),
+            Err(ScheduleError::InvalidToken("JUN".to_string()))
+        );
+
+        assert_eq!(
+            parse_timezone("Europe/Amsterdam"),
+            Ok(Tz::Europe__Amsterdam)
+        );
+        assert_eq!(
+            parse_timezone("Mars/Olympus"),
+            Err(ScheduleError::UnknownTimezone("Mars/Olympus".to_string()))
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_windows() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // weekdays in local time, across the end of daylight saving time
+        let schedule = Schedule::parse("MON-FRI 0800-1800", Tz::Europe__Amsterdam).unwrap();
+        let windows = schedule.windows(
+            &AMSTERDAM,
+            time("2024-10-25T12:00:00Z"),
+            time("2024-10-29T00:00:00Z"),
+        );
+        assert_eq!(
+            windows,
+            vec![
+                (time("2024-10-25T06:00:00Z"), time("2024-10-25T16:00:00Z")),
+                (time("2024-10-28T07:00:00Z"), time("2024-10-28T17:00:00Z")),
+            ]
+        );
+
+        // overnight windows start the day before
+        let schedule = Schedule::parse("DAILY 2200-0200", Tz::UTC).unwrap();
+        let windows = schedule.windows(
+            &AMSTERDAM,
+            time("2024-06-05T01:00:00Z"),
+            time("2024-06-06T12:00:00Z"),
+        );
+        assert_eq!(
+            windows,
+            vec![
+                (time("2024-06-04T22:00:00Z"), time("2024-06-05T02:00:00Z")),
+                (time("2024-06-05T22:00:00Z"), time("2024-06-06T02:00:00Z")),
+            ]
+        );
+
+        // whole days are merged
+        let schedule = Schedule::parse("MON-FRI H24", Tz::UTC).unwrap();
+        let windows = schedule.windows(
+            &AMSTERDAM,
+            time("2024-06-01T00:00:00Z"),
+            time("2024-06-15T00:00:00Z"),
+        );
+        assert_eq!(
+            windows,
+            vec![
+                (time("2024-06-03T00:00:00Z"), time("2024-06-08T00:00:00Z")),
+                (time("2024-06-10T00:00:00Z"), time("2024-06-15T00:00:00Z")),
+            ]
+        );
+
+        // sunrise to sunset
+        let schedule = Schedule::parse("SR-SS", Tz::UTC).unwrap();
+        let windows = schedule.windows(
+            &AMSTERDAM,
+            time("2024-06-21T00:00:00Z"),
+            time("2024-06-22T00:00:00Z"),
+        );
+        assert_eq!(windows.len(), 1);
+        assert!(
+            (windows[0].0 - time("2024-06-21T03:18:00Z"))
+                .num_minutes()
+                .abs()
+                <= 3
+        );
+        assert!(schedule.is_active(
+            &AMSTERDAM,
+            time("2024-06-21T12:00:00Z"),
+            time("2024-06-21T12:00:00Z")
+        ));
+        assert!(!schedule.is_active(
+            &AMSTERDAM,
+            time("2024-06-21T22:00:00Z"),
+            time("2024-06-22T01:00:00Z")
+        ));
+        assert!(schedule.is_active(
+            &AMSTERDAM,
+            time("2024-06-21T22:00:00Z"),
+            time("2024-06-22T04:00:00Z")
+        ));
+
+        // the polar day is active all day from sunrise to sunset, and never
+        //  from sunset to sunrise
+        let schedule = Schedule::parse("SR-SS", Tz::Europe__Oslo).unwrap();
+        let windows = schedule.windows(
+            &TROMSO,
+            time("2024-06-20T00:00:00Z"),
+            time("2024-06-23T00:00:00Z"),
+        );
+        assert_eq!(windows.len(), 1);
+        assert!(windows[0].0 <= time("2024-06-20T00:00:00Z"));
+        assert!(windows[0].1 >= time("2024-06-23T00:00:00Z"));
+        let schedule = Schedule::parse("SS-SR", Tz::Europe__Oslo).unwrap();
+        assert!(schedule
+            .windows(
+                &TROMSO,
+                time("2024-06-20T00:00:00Z"),
+                time("2024-06-23T00:00:00Z")
+            )
+            .is_empty());
+
+        // and the polar night the other way around
+        let windows = schedule.windows(
+            &TROMSO,
+            time("2024-12-20T00:00:00Z"),
+            time("2024-12-23T00:00:00Z"),
+        );
+        assert_eq!(windows.len(), 1);
+        assert!(windows[0].0 <= time("2024-12-20T00:00:00Z"));
+        assert!(windows[0].1 >= time("2024-12-23T00:00:00Z"));
+        let schedule = Schedule::parse("SR-SS", Tz::Europe__Oslo).unwrap();
+        assert!(!schedule.is_active(
+            &TROMSO,
+            time("2024-12-20T00:00:00Z"),
+            time("2024-12-23T00:00:00Z")
+        ));
+
+        ut_info!("Success.");
+    }
+}
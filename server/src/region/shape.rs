@@ -0,0 +1,553 @@
+//! Boundaries of restrictions
+//!
+//! A boundary is a polygon, a circle or a closed sequence of straight edges
+//!  and arcs. Circles and arcs are kept as published, so checks against
+//!  them are exact. They are only converted to polygons for services that
+//!  expect vertices, such as svc-gis, within a maximum chord error.
+//!
+//! Distances to circles and arcs are measured in a plane tangent to the
+//!  earth at their center, which is exact to well below a meter for the
+//!  radii of airspace.
+
+use crate::region::utils::{
+    arc_vertices, bearing_degrees, bounds_center, close_ring, destination, distance_meters,
+    path_intersects_polygon, point_in_polygon, segments_intersect, to_closed_ring,
+    EARTH_RADIUS_METERS,
+};
+use svc_gis_client_grpc::prelude::gis::Coordinates;
+
+/// Default maximum distance in meters between an arc and the chords that
+///  replace it in a polygon
+pub const MAX_CHORD_ERROR_METERS: f64 = 10.0;
+
+/// Largest angle in degrees between consecutive vertices of a polygonized
+///  arc, for arcs that are small compared to the chord error
+const MAX_ARC_STEP_DEGREES: f64 = 45.0;
+
+/// Smallest angle in degrees between consecutive vertices of a polygonized
+///  arc, bounding the vertices of large arcs or small chord errors
+const MIN_ARC_STEP_DEGREES: f64 = 0.1;
+
+/// An arc around a center, from the start to the end bearing
+///
+/// An arc with equal start and end bearings is a full circle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arc {
+    /// Center of the arc
+    pub center: Coordinates,
+
+    /// Radius in meters
+    pub radius_meters: f64,
+
+    /// Bearing in degrees from true north of the start, seen from the center
+    pub start_bearing_degrees: f64,
+
+    /// Bearing in degrees from true north of the end, seen from the center
+    pub end_bearing_degrees: f64,
+
+    /// Direction from the start to the end
+    pub clockwise: bool,
+}
+
+impl Arc {
+    /// Arc around the center from one point to another
+    ///
+    /// The radius is the distance to the start point.
+    pub fn between(
+        center: Coordinates,
+        start: &Coordinates,
+        end: &Coordinates,
+        clockwise: bool,
+    ) -> Self {
+        Self {
+            center,
+            radius_meters: distance_meters(&center, start),
+            start_bearing_degrees: bearing_degrees(&center, start),
+            end_bearing_degrees: bearing_degrees(&center, end),
+            clockwise,
+        }
+    }
+
+    /// First point of the arc
+    pub fn start(&self) -> Coordinates {
+        destination(&self.center, self.start_bearing_degrees, self.radius_meters)
+    }
+
+    /// Last point of the arc
+    pub fn end(&self) -> Coordinates {
+        destination(&self.center, self.end_bearing_degrees, self.radius_meters)
+    }
+
+    /// Angle swept from the start to the end, above zero and up to 360 degrees
+    fn sweep_degrees(&self) -> f64 {
+        let sweep = match self.clockwise {
+            true => self.end_bearing_degrees - self.start_bearing_degrees,
+            false => self.start_bearing_degrees - self.end_bearing_degrees,
+        }
+        .rem_euclid(360.0);
+
+        match sweep < f64::EPSILON {
+            true => 360.0,
+            false => sweep,
+        }
+    }
+
+    /// Returns true if the bearing from the center lies on the arc
+    fn covers_bearing(&self, bearing_degrees: f64) -> bool {
+        let swept = match self.clockwise {
+            true => bearing_degrees - self.start_bearing_degrees,
+            false => self.start_bearing_degrees - bearing_degrees,
+        }
+        .rem_euclid(360.0);
+
+        swept <= self.sweep_degrees()
+    }
+
+    /// Vertices from the start to the end, both included, within the chord error
+    pub fn vertices(&self, max_chord_error_meters: f64) -> Vec<Coordinates> {
+        arc_vertices(
+            &self.center,
+            self.radius_meters,
+            self.start_bearing_degrees,
+            self.end_bearing_degrees,
+            self.clockwise,
+            step_degrees(self.radius_meters, max_chord_error_meters),
+        )
+    }
+
+    /// Returns true if the point lies between the arc and its chord
+    fn segment_contains(&self, point: &Coordinates) -> bool {
+        if distance_meters(&self.center, point) > self.radius_meters {
+            return false;
+        }
+
+        if self.sweep_degrees() >= 360.0 {
+            return true;
+        }
+
+        // on the same side of the chord as the middle of the arc
+        let middle = destination(
+            &self.center,
+            match self.clockwise {
+                true => self.start_bearing_degrees + self.sweep_degrees() / 2.0,
+                false => self.start_bearing_degrees - self.sweep_degrees() / 2.0,
+            },
+            self.radius_meters,
+        );
+        let [start, end, middle, point] =
+            [self.start(), self.end(), middle, *point].map(|p| to_local(&self.center, &p));
+        let side = |p: (f64, f64)| {
+            ((end.0 - start.0) * (p.1 - start.1) - (end.1 - start.1) * (p.0 - start.0)).signum()
+        };
+
+        side(point) == side(middle)
+    }
+
+    /// Returns true if the segment between the two points touches or
+    ///  crosses the arc
+    fn crossed_by(&self, a: &Coordinates, b: &Coordinates) -> bool {
+        let (a, b) = (to_local(&self.center, a), to_local(&self.center, b));
+        let d = (b.0 - a.0, b.1 - a.1);
+
+        // points a + t * d at the radius from the center
+        let qa = d.0 * d.0 + d.1 * d.1;
+        let qb = 2.0 * (a.0 * d.0 + a.1 * d.1);
+        let qc = a.0 * a.0 + a.1 * a.1 - self.radius_meters.powi(2);
+        let discriminant = qb * qb - 4.0 * qa * qc;
+        if qa < f64::EPSILON || discriminant < 0.0 {
+            return false;
+        }
+
+        [-1.0, 1.0]
+            .map(|sign| (-qb + sign * discriminant.sqrt()) / (2.0 * qa))
+            .into_iter()
+            .filter(|t| (0.0..=1.0).contains(t))
+            .any(|t| {
+                let (x, y) = (a.0 + t * d.0, a.1 + t * d.1);
+                self.covers_bearing(x.atan2(y).to_degrees().rem_euclid(360.0))
+            })
+    }
+}
+
+/// A part of a boundary
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    /// A straight edge from the point to the start of the next segment
+    Line(Coordinates),
+
+    /// An arc, followed by a straight edge to the start of the next
+    ///  segment if needed
+    Arc(Arc),
+}
+
+/// The boundary of a restriction
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// A polygon, given as its closed ring of vertices
+    Polygon(Vec<Coordinates>),
+
+    /// A circle around a center
+    Circle {
+        /// Center of the circle
+        center: Coordinates,
+
+        /// Radius in meters
+        radius_meters: f64,
+    },
+
+    /// A closed sequence of straight edges and arcs
+    Boundary(Vec<Segment>),
+}
+
+impl Shape {
+    /// Builds the shape of a sequence of segments
+    ///
+    /// Segments without arcs become a polygon. Returns an error if the
+    ///  boundary does not enclose an area.
+    pub fn from_segments(segments: Vec<Segment>) -> Result<Self, String> {
+        if segments.iter().all(|s| matches!(s, Segment::Line(_))) {
+            let ring = segments
+                .into_iter()
+                .filter_map(|s| match s {
+                    Segment::Line(point) => Some(point),
+                    Segment::Arc(_) => None,
+                })
+                .collect();
+
+            return to_closed_ring(ring).map(Shape::Polygon);
+        }
+
+        let shape = Shape::Boundary(segments);
+        to_closed_ring(shape.to_polygon(MAX_CHORD_ERROR_METERS))?;
+        Ok(shape)
+    }
+
+    /// Closed ring of vertices approximating the shape, with arcs at most
+    ///  the chord error away from the polygon
+    pub fn to_polygon(&self, max_chord_error_meters: f64) -> Vec<Coordinates> {
+        match self {
+            Shape::Polygon(vertices) => vertices.clone(),
+            Shape::Circle {
+                center,
+                radius_meters,
+            } => {
+                let circle = Arc {
+                    center: *center,
+                    radius_meters: *radius_meters,
+                    start_bearing_degrees: 0.0,
+                    end_bearing_degrees: 0.0,
+                    clockwise: true,
+                };
+
+                close_ring(circle.vertices(max_chord_error_meters))
+            }
+            Shape::Boundary(segments) => close_ring(
+                segments
+                    .iter()
+                    .flat_map(|s| match s {
+                        Segment::Line(point) => vec![*point],
+                        Segment::Arc(arc) => arc.vertices(max_chord_error_meters),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Center of the shape, or of its bounding box if it is not a circle
+    pub fn center(&self) -> Coordinates {
+        match self {
+            Shape::Circle { center, .. } => *center,
+            _ => bounds_center(&self.to_polygon(MAX_CHORD_ERROR_METERS)),
+        }
+    }
+
+    /// Returns true if the point lies within the shape
+    pub fn contains(&self, point: &Coordinates) -> bool {
+        match self {
+            Shape::Polygon(vertices) => point_in_polygon(point, vertices),
+            Shape::Circle {
+                center,
+                radius_meters,
+            } => distance_meters(center, point) <= *radius_meters,
+            Shape::Boundary(segments) => {
+                // the polygon of the chords, with the area between each arc
+                //  and its chord added where it bulges out or removed where
+                //  it bulges in
+                segments
+                    .iter()
+                    .filter_map(|s| match s {
+                        Segment::Arc(arc) => Some(arc.segment_contains(point)),
+                        Segment::Line(_) => None,
+                    })
+                    .fold(
+                        point_in_polygon(point, &chords(segments)),
+                        |inside, bulge| inside != bulge,
+                    )
+            }
+        }
+    }
+
+    /// Returns true if any part of the path lies within or crosses the shape
+    pub fn intersects_path(&self, path: &[Coordinates]) -> bool {
+        if path.iter().any(|p| self.contains(p)) {
+            return true;
+        }
+
+        match self {
+            Shape::Polygon(vertices) => path_intersects_polygon(path, vertices),
+            Shape::Circle {
+                center,
+                radius_meters,
+            } => path
+                .windows(2)
+                .any(|s| distance_to_segment_meters(center, &s[0], &s[1]) <= *radius_meters),
+            Shape::Boundary(segments) => {
+                let chords = chords(segments);
+                let edges = chords.iter().zip(chords.iter().cycle().skip(1));
+                let arcs = segments
+                    .iter()
+                    .filter_map(|s| match s {
+                        Segment::Arc(arc) => Some(arc),
+                        Segment::Line(_) => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                // arcs replace the edge between their own start and end
+                let edges = edges
+                    .filter(|(a, b)| {
+                        !arcs
+                            .iter()
+                            .any(|arc| arc.start() == **a && arc.end() == **b)
+                    })
+                    .collect::<Vec<_>>();
+
+                path.windows(2).any(|s| {
+                    edges
+                        .iter()
+                        .any(|(b1, b2)| segments_intersect(&s[0], &s[1], b1, b2))
+                        || arcs.iter().any(|arc| arc.crossed_by(&s[0], &s[1]))
+                })
+            }
+        }
+    }
+}
+
+/// Angle between consecutive vertices of an arc so that no chord is
+///  further than the chord error from the arc
+///
+/// The angle is at least [`MIN_ARC_STEP_DEGREES`], so an arc has at most
+///  3600 vertices whatever the chord error.
+fn step_degrees(radius_meters: f64, max_chord_error_meters: f64) -> f64 {
+    let ratio = (max_chord_error_meters / radius_meters).clamp(0.0, 1.0);
+    let step = (2.0 * (1.0 - ratio).acos()).to_degrees();
+    match step.is_nan() {
+        true => MIN_ARC_STEP_DEGREES,
+        false => step.clamp(MIN_ARC_STEP_DEGREES, MAX_ARC_STEP_DEGREES),
+    }
+}
+
+/// Position of the point in meters east and north of the origin, in the
+///  plane tangent to the earth at the origin
+pub fn to_local(origin: &Coordinates, point: &Coordinates) -> (f64, f64) {
+    let x = (point.longitude - origin.longitude).to_radians()
+        * origin.latitude.to_radians().cos()
+        * EARTH_RADIUS_METERS;
+    let y = (point.latitude - origin.latitude).to_radians() * EARTH_RADIUS_METERS;
+    (x, y)
+}
+
+/// Shortest distance in meters from the point to the segment between two
+///  other points
+fn distance_to_segment_meters(point: &Coordinates, a: &Coordinates, b: &Coordinates) -> f64 {
+    let (a, b) = (to_local(point, a), to_local(point, b));
+    let d = (b.0 - a.0, b.1 - a.1);
+    let length = d.0 * d.0 + d.1 * d.1;
+    let t = match length < f64::EPSILON {
+        true => 0.0,
+        false => (-(a.0 * d.0 + a.1 * d.1) / length).clamp(0.0, 1.0),
+    };
+
+    (a.0 + t * d.0).hypot(a.1 + t * d.1)
+}
+
+/// Vertices of the boundary with each arc replaced by its chord
+fn chords(segments: &[Segment]) -> Vec<Coordinates> {
+    segments
+        .iter()
+        .flat_map(|s| match s {
+            Segment::Line(point) => vec![*point],
+            Segment::Arc(arc) => vec![arc.start(), arc.end()],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(latitude: f64, longitude: f64) -> Coordinates {
+        Coordinates {
+            latitude,
+            longitude,
+        }
+    }
+
+    /// A square of about 2 by 2 kilometers with an arc bulging out to the east
+    fn boundary() -> Shape {
+        let center = point(30.0, -97.0);
+        Shape::from_segments(vec![
+            Segment::Line(point(29.99, -97.01)),
+            Segment::Line(point(30.01, -97.01)),
+            Segment::Arc(Arc::between(
+                center,
+                &point(30.01, -97.0),
+                &point(29.99, -97.0),
+                true,
+            )),
+        ])
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_arc_vertices() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let center = point(30.0, -97.0);
+        let arc = Arc {
+            center,
+            radius_meters: 1000.0,
+            start_bearing_degrees: 350.0,
+            end_bearing_degrees: 80.0,
+            clockwise: true,
+        };
+
+        // 10 meters on a 1000 meter radius allows steps of about 16 degrees
+        let vertices = arc.vertices(10.0);
+        assert_eq!(vertices.len(), 7);
+        assert!((bearing_degrees(&center, &vertices[0]) - 350.0).abs() < 0.01);
+        assert!((bearing_degrees(&center, &vertices[6]) - 80.0).abs() < 0.01);
+
+        // the middle of each chord is within the chord error of the arc
+        for pair in vertices.windows(2) {
+            let middle = point(
+                (pair[0].latitude + pair[1].latitude) / 2.0,
+                (pair[0].longitude + pair[1].longitude) / 2.0,
+            );
+            assert!(1000.0 - distance_meters(&center, &middle) <= 10.0);
+        }
+
+        // a finer chord error needs more vertices
+        assert_eq!(arc.vertices(1.0).len(), 19);
+
+        // down to a step of a tenth of a degree, also without a chord error
+        assert_eq!(arc.vertices(1e-9).len(), 901);
+        assert_eq!(arc.vertices(0.0).len(), 901);
+        assert_eq!(arc.vertices(-1.0).len(), 901);
+
+        let circle = Shape::Circle {
+            center,
+            radius_meters: 500.0,
+        };
+        let polygon = circle.to_polygon(MAX_CHORD_ERROR_METERS);
+        assert_eq!(polygon.len(), 17);
+        assert_eq!(polygon.first(), polygon.last());
+        assert!(point_in_polygon(&center, &polygon));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_from_segments() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // straight edges only make a closed polygon
+        let shape = Shape::from_segments(vec![
+            Segment::Line(point(30.0, -97.0)),
+            Segment::Line(point(30.1, -97.0)),
+            Segment::Line(point(30.1, -96.9)),
+        ])
+        .unwrap();
+        assert!(matches!(shape, Shape::Polygon(ref v) if v.len() == 4));
+
+        assert!(matches!(boundary(), Shape::Boundary(_)));
+
+        let error = Shape::from_segments(vec![
+            Segment::Line(point(30.0, -97.0)),
+            Segment::Line(point(30.1, -97.0)),
+        ])
+        .unwrap_err();
+        assert_eq!(error, "boundary has only 3 vertices");
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_circle() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let center = point(52.0, 4.0);
+        let circle = Shape::Circle {
+            center,
+            radius_meters: 1000.0,
+        };
+
+        assert_eq!(circle.center(), center);
+        assert!(circle.contains(&destination(&center, 45.0, 999.0)));
+        assert!(!circle.contains(&destination(&center, 45.0, 1001.0)));
+
+        // a path passing 998 meters from the center crosses the circle, one
+        //  at 1002 meters does not, although the polygon of the circle with
+        //  the default chord error would miss the first
+        let path = |offset: f64| {
+            let abeam = destination(&center, 90.0, offset);
+            [
+                destination(&abeam, 0.0, 5000.0),
+                destination(&abeam, 180.0, 5000.0),
+            ]
+        };
+        assert!(circle.intersects_path(&path(998.0)));
+        assert!(!circle.intersects_path(&path(1002.0)));
+        let polygon = circle.to_polygon(MAX_CHORD_ERROR_METERS);
+        assert!(!path_intersects_polygon(&path(998.0), &polygon));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_boundary() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let shape = boundary();
+
+        // within the square and within the bulge of the arc
+        assert!(shape.contains(&point(30.0, -97.005)));
+        assert!(shape.contains(&point(30.0, -96.995)));
+        assert!(!shape.contains(&point(30.0, -96.985)));
+        assert!(!shape.contains(&point(30.0, -97.015)));
+
+        // a path through the bulge, crossing only the arc
+        let path = [point(30.012, -96.99), point(29.988, -96.99)];
+        assert!(shape.intersects_path(&path));
+
+        // a path passing just outside the arc
+        let path = [point(30.003, -96.9884), point(29.997, -96.9884)];
+        assert!(!shape.intersects_path(&path));
+
+        // a path crossing a straight edge
+        let path = [point(30.02, -97.005), point(30.005, -97.005)];
+        assert!(shape.intersects_path(&path));
+
+        assert!((shape.center().latitude - 30.0).abs() < 1e-4);
+
+        // the polygon keeps the arc
+        let polygon = shape.to_polygon(MAX_CHORD_ERROR_METERS);
+        assert_eq!(polygon.first(), polygon.last());
+        assert!(point_in_polygon(&point(30.0, -96.995), &polygon));
+
+        ut_info!("Success.");
+    }
+}
@@ -0,0 +1,701 @@
+//! Reader for Esri Shapefile layers of zones and waypoints
+//!
+//! A layer is a `.shp` file with the geometry of each record, a `.dbf`
+//!  file with its attributes and an optional `.prj` file with the
+//!  coordinate reference system. Layers without a `.prj` file are taken to
+//!  be in longitude and latitude on WGS84, other layers are reprojected.
+//!
+//! Polygon records become zones and point records waypoints. The columns
+//!  holding the identifier and altitude limits are set by a
+//!  [`FieldMapping`].
+
+use crate::region::projection::Projection;
+use crate::region::shape;
+use crate::region::utils::{to_closed_ring, AltitudeLimit, AltitudeReference};
+use crate::region::{RestrictionDetails, RestrictionKind};
+use std::collections::HashMap;
+use std::path::Path;
+use svc_gis_client_grpc::prelude::gis::{Coordinates, ZoneType};
+
+/// File code at the start of a `.shp` file
+const SHP_FILE_CODE: i32 = 9994;
+
+/// Length of the `.shp` file header
+const SHP_HEADER_LENGTH: usize = 100;
+
+/// Length of a `.shp` record header
+const SHP_RECORD_HEADER_LENGTH: usize = 8;
+
+/// Length of a `.dbf` field descriptor
+const DBF_DESCRIPTOR_LENGTH: usize = 32;
+
+/// End of the `.dbf` field descriptors
+const DBF_HEADER_TERMINATOR: u8 = 0x0D;
+
+/// Flag of a deleted `.dbf` record
+const DBF_DELETED: u8 = b'*';
+
+/// Unit of measurement of altitudes without a unit column or value
+const DEFAULT_ALTITUDE_UNIT: &str = "FT";
+
+/// Vertical reference of altitudes without a reference column or value
+const DEFAULT_ALTITUDE_REFERENCE: AltitudeReference = AltitudeReference::Msl;
+
+/// Altitude values referring to the surface
+const SURFACE_LIMITS: &[&str] = &["SFC", "GND"];
+
+/// Prefix of altitude values without an upper limit, such as `UNL` or `UNLTD`
+const UNLIMITED_PREFIX: &str = "UNL";
+
+/// Errors when reading a shapefile layer
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum ShapefileError {
+    /// A file of the layer cannot be read
+    #[error("error: Could not read {0}: {1}")]
+    Read(String, String),
+
+    /// The `.shp` file is malformed
+    #[error("error: Invalid shapefile: {0}")]
+    Shp(String),
+
+    /// The `.dbf` file is malformed
+    #[error("error: Invalid dBASE file: {0}")]
+    Dbf(String),
+
+    /// The coordinate reference system is not supported
+    #[error("error: Invalid projection: {0}")]
+    Projection(String),
+
+    /// The `.shp` and `.dbf` files hold a different number of records
+    #[error("error: {0} shapes but {1} attribute records.")]
+    RecordCount(usize, usize),
+}
+
+/// Columns of the attributes holding the fields of zones and waypoints
+///
+/// Column names are matched ignoring case.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldMapping {
+    /// Identifier of the zone or waypoint
+    pub id: String,
+
+    /// Lower limit of a zone
+    pub lower: String,
+
+    /// Unit of the lower limit, such as `FT` or `M`
+    pub lower_unit: String,
+
+    /// Vertical reference of the lower limit, such as `MSL` or `AGL`
+    pub lower_reference: String,
+
+    /// Upper limit of a zone
+    pub upper: String,
+
+    /// Unit of the upper limit
+    pub upper_unit: String,
+
+    /// Vertical reference of the upper limit
+    pub upper_reference: String,
+}
+
+impl Default for FieldMapping {
+    /// The columns of the FAA special use airspace layer
+    fn default() -> Self {
+        Self {
+            id: "NAME".to_string(),
+            lower: "LOWER_VAL".to_string(),
+            lower_unit: "LOWER_UOM".to_string(),
+            lower_reference: "LOWER_CODE".to_string(),
+            upper: "UPPER_VAL".to_string(),
+            upper_unit: "UPPER_UOM".to_string(),
+            upper_reference: "UPPER_CODE".to_string(),
+        }
+    }
+}
+
+impl FieldMapping {
+    /// The columns of the FAA designated points layer
+    pub fn waypoints() -> Self {
+        Self {
+            id: "IDENT".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Replaces columns from a comma separated list of `field=COLUMN`
+    ///  pairs, such as `id=NAME,upper=CEILING`
+    pub fn with_overrides(mut self, text: &str) -> Result<Self, String> {
+        for pair in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let Some((field, column)) = pair.split_once('=') else {
+                return Err(format!("invalid field mapping '{}'", pair));
+            };
+
+            let column = column.trim().to_string();
+            match field.trim().to_lowercase().as_str() {
+                "id" => self.id = column,
+                "lower" => self.lower = column,
+                "lower_unit" => self.lower_unit = column,
+                "lower_reference" => self.lower_reference = column,
+                "upper" => self.upper = column,
+                "upper_unit" => self.upper_unit = column,
+                "upper_reference" => self.upper_reference = column,
+                other => return Err(format!("unknown field '{}'", other)),
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+/// Geometry of a record
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// A record without geometry
+    Null,
+
+    /// A single point
+    Point(Coordinates),
+
+    /// One or more points
+    MultiPoint(Vec<Coordinates>),
+
+    /// Lines, given as their parts
+    PolyLine(Vec<Vec<Coordinates>>),
+
+    /// Rings of one or more polygons
+    Polygon(Vec<Vec<Coordinates>>),
+}
+
+/// A record of a layer
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Geometry in latitude and longitude
+    pub shape: Shape,
+
+    /// Attribute values by upper case column name
+    pub attributes: HashMap<String, String>,
+}
+
+impl Record {
+    /// Value of the column, if present and not blank
+    pub fn attribute(&self, column: &str) -> Option<&str> {
+        self.attributes
+            .get(&column.to_uppercase())
+            .map(|value| value.as_str())
+            .filter(|value| !value.is_empty())
+    }
+}
+
+/// Reads a little endian integer
+fn le_i32(data: &[u8], offset: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a big endian integer
+fn be_i32(data: &[u8], offset: usize) -> Option<i32> {
+    Some(i32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a little endian double
+fn le_f64(data: &[u8], offset: usize) -> Option<f64> {
+    Some(f64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Reads the geometry of a `.shp` record
+///
+/// Measures and heights of `M` and `Z` shapes are ignored.
+fn to_shape(content: &[u8], projection: &Projection) -> Result<Shape, String> {
+    let truncated = || "truncated record".to_string();
+    let point = |offset: usize| {
+        let x = le_f64(content, offset).ok_or_else(truncated)?;
+        let y = le_f64(content, offset + 8).ok_or_else(truncated)?;
+        projection
+            .to_coordinates(x, y)
+            .ok_or_else(|| format!("invalid coordinates ({}, {})", x, y))
+    };
+
+    let shape_type = le_i32(content, 0).ok_or_else(truncated)?;
+    match shape_type {
+        0 => Ok(Shape::Null),
+        1 | 11 | 21 => Ok(Shape::Point(point(4)?)),
+        8 | 18 | 28 => {
+            let count = le_i32(content, 36).ok_or_else(truncated)?;
+            let points = (0..usize::try_from(count).map_err(|_| truncated())?)
+                .map(|i| point(40 + i * 16))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Shape::MultiPoint(points))
+        }
+        3 | 13 | 23 | 5 | 15 | 25 => {
+            let parts = le_i32(content, 36).ok_or_else(truncated)?;
+            let count = le_i32(content, 40).ok_or_else(truncated)?;
+            let (parts, count) = match (usize::try_from(parts), usize::try_from(count)) {
+                (Ok(parts), Ok(count)) => (parts, count),
+                _ => return Err(truncated()),
+            };
+
+            let starts = (0..parts)
+                .map(|i| {
+                    le_i32(content, 44 + i * 4)
+                        .and_then(|start| usize::try_from(start).ok())
+                        .filter(|start| *start <= count)
+                        .ok_or_else(truncated)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let points_offset = 44 + parts * 4;
+            let rings = starts
+                .iter()
+                .zip(starts.iter().skip(1).chain([count].iter()))
+                .map(|(start, end)| {
+                    (*start..*end)
+                        .map(|i| point(points_offset + i * 16))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            match shape_type % 10 {
+                3 => Ok(Shape::PolyLine(rings)),
+                _ => Ok(Shape::Polygon(rings)),
+            }
+        }
+        other => Err(format!("unsupported shape type {}", other)),
+    }
+}
+
+/// Reads the records of a `.shp` file
+fn parse_shp(
+    data: &[u8],
+    projection: &Projection,
+) -> Result<Vec<Result<Shape, String>>, ShapefileError> {
+    if be_i32(data, 0) != Some(SHP_FILE_CODE) || data.len() < SHP_HEADER_LENGTH {
+        return Err(ShapefileError::Shp("missing file header".to_string()));
+    }
+
+    let mut shapes = vec![];
+    let mut offset = SHP_HEADER_LENGTH;
+    while offset < data.len() {
+        // the content length is given in 16 bit words
+        let length = be_i32(data, offset + 4)
+            .and_then(|length| usize::try_from(length).ok())
+            .map(|length| length * 2)
+            .ok_or_else(|| ShapefileError::Shp(format!("truncated record at {}", offset)))?;
+
+        let start = offset + SHP_RECORD_HEADER_LENGTH;
+        let content = data
+            .get(start..start + length)
+            .ok_or_else(|| ShapefileError::Shp(format!("truncated record at {}", offset)))?;
+        shapes.push(to_shape(content, projection));
+        offset = start + length;
+    }
+
+    Ok(shapes)
+}
+
+/// Reads the records of a `.dbf` file, `None` for deleted records
+///
+/// Text is read as Latin-1.
+fn parse_dbf(data: &[u8]) -> Result<Vec<Option<HashMap<String, String>>>, ShapefileError> {
+    let invalid = |e: &str| ShapefileError::Dbf(e.to_string());
+    let count = data
+        .get(4..8)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| invalid("missing file header"))?;
+    let header_length = data
+        .get(8..10)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+        .ok_or_else(|| invalid("missing file header"))?;
+    let record_length = data
+        .get(10..12)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+        .ok_or_else(|| invalid("missing file header"))?;
+
+    let latin1 = |bytes: &[u8]| bytes.iter().map(|b| char::from(*b)).collect::<String>();
+
+    // name and length of each field, in record order
+    let mut fields = vec![];
+    let mut offset = DBF_DESCRIPTOR_LENGTH;
+    while data.get(offset) != Some(&DBF_HEADER_TERMINATOR) {
+        let descriptor = data
+            .get(offset..offset + DBF_DESCRIPTOR_LENGTH)
+            .ok_or_else(|| invalid("truncated field descriptors"))?;
+        let name = descriptor[..11]
+            .split(|b| *b == 0)
+            .next()
+            .map(latin1)
+            .unwrap_or_default();
+        fields.push((name.trim().to_uppercase(), descriptor[16] as usize));
+        offset += DBF_DESCRIPTOR_LENGTH;
+    }
+
+    (0..count)
+        .map(|i| {
+            let start = header_length + i * record_length;
+            let record = data
+                .get(start..start + record_length)
+                .ok_or_else(|| invalid("truncated records"))?;
+            if record[0] == DBF_DELETED {
+                return Ok(None);
+            }
+
+            let mut attributes = HashMap::new();
+            let mut position = 1;
+            for (name, length) in &fields {
+                let value = record
+                    .get(position..position + length)
+                    .ok_or_else(|| invalid("record shorter than its fields"))?;
+                attributes.insert(name.clone(), latin1(value).trim().to_string());
+                position += length;
+            }
+
+            Ok(Some(attributes))
+        })
+        .collect()
+}
+
+/// Parses a layer from the content of its files
+///
+/// Returns an error if a file itself cannot be read, otherwise the result
+///  of parsing each record in file order. Deleted records are left out.
+pub fn parse_layer(
+    shp: &[u8],
+    dbf: &[u8],
+    prj: Option<&str>,
+) -> Result<Vec<Result<Record, String>>, ShapefileError> {
+    let projection = match prj {
+        Some(wkt) => Projection::from_wkt(wkt).map_err(ShapefileError::Projection)?,
+        None => Projection::default(),
+    };
+
+    let shapes = parse_shp(shp, &projection)?;
+    let attributes = parse_dbf(dbf)?;
+    if shapes.len() != attributes.len() {
+        return Err(ShapefileError::RecordCount(shapes.len(), attributes.len()));
+    }
+
+    Ok(shapes
+        .into_iter()
+        .zip(attributes)
+        .enumerate()
+        .filter_map(|(index, (shape, attributes))| {
+            let attributes = attributes?;
+            Some(
+                shape
+                    .map(|shape| Record { shape, attributes })
+                    .map_err(|e| format!("record {}: {}", index + 1, e)),
+            )
+        })
+        .collect())
+}
+
+/// Reads a layer from the `.shp` file and the files next to it
+pub async fn load_layer(path: &Path) -> Result<Vec<Result<Record, String>>, ShapefileError> {
+    let read = |extension: &'static str| {
+        let path = path.with_extension(extension);
+        async move {
+            tokio::fs::read(&path)
+                .await
+                .map_err(|e| (path.display().to_string(), e))
+        }
+    };
+
+    let shp = read("shp")
+        .await
+        .map_err(|(path, e)| ShapefileError::Read(path, e.to_string()))?;
+    let dbf = read("dbf")
+        .await
+        .map_err(|(path, e)| ShapefileError::Read(path, e.to_string()))?;
+    let prj = match read("prj").await {
+        Ok(prj) => Some(String::from_utf8_lossy(&prj).into_owned()),
+        Err((_, e)) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err((path, e)) => return Err(ShapefileError::Read(path, e.to_string())),
+    };
+
+    parse_layer(&shp, &dbf, prj.as_deref())
+}
+
+/// Identifier of a record, with whitespace replaced by dashes
+fn to_id(record: &Record, fields: &FieldMapping) -> Result<String, String> {
+    record
+        .attribute(&fields.id)
+        .map(|id| id.split_whitespace().collect::<Vec<_>>().join("-"))
+        .ok_or_else(|| format!("missing identifier in column {}", fields.id))
+}
+
+/// Converts an altitude value with its unit and reference
+///
+/// Values may also be `SFC` or `GND` for the surface and `UNL` or `UNLTD`
+///  for no upper limit.
+fn to_limit(
+    value: Option<&str>,
+    unit: Option<&str>,
+    reference: Option<&str>,
+    default: AltitudeLimit,
+) -> Result<AltitudeLimit, String> {
+    let Some(value) = value else {
+        return Ok(default);
+    };
+
+    let upper = value.to_uppercase();
+    if SURFACE_LIMITS.contains(&upper.as_str()) {
+        return Ok(AltitudeLimit::SURFACE);
+    }
+
+    if upper.starts_with(UNLIMITED_PREFIX) {
+        return Ok(AltitudeLimit::UNLIMITED);
+    }
+
+    let reference = match reference {
+        Some(reference) => AltitudeReference::parse(reference)
+            .ok_or_else(|| format!("unknown vertical reference '{}'", reference))?,
+        None => DEFAULT_ALTITUDE_REFERENCE,
+    };
+
+    let unit = unit.unwrap_or(DEFAULT_ALTITUDE_UNIT);
+    value
+        .parse::<f64>()
+        .ok()
+        .and_then(|value| AltitudeLimit::new(value, unit, reference))
+        .ok_or_else(|| format!("invalid altitude '{}' {}", value, unit))
+}
+
+/// Converts a polygon record to restrictions, one per outer ring
+///
+/// Outer rings run clockwise, holes are ignored. If no ring runs clockwise
+///  every ring is taken as an outer ring. Identifiers are the prefix and the
+///  identifier, followed by a sequence number if there is more than one
+///  outer ring.
+pub fn to_restrictions(
+    record: &Record,
+    fields: &FieldMapping,
+    prefix: &str,
+) -> Result<Vec<(String, RestrictionDetails)>, String> {
+    let id = to_id(record, fields)?;
+    let error = |e: String| format!("{}: {}", id, e);
+
+    let Shape::Polygon(rings) = &record.shape else {
+        return Err(error("not a polygon".to_string()));
+    };
+
+    let lower = to_limit(
+        record.attribute(&fields.lower),
+        record.attribute(&fields.lower_unit),
+        record.attribute(&fields.lower_reference),
+        AltitudeLimit::SURFACE,
+    )
+    .map_err(error)?;
+    let upper = to_limit(
+        record.attribute(&fields.upper),
+        record.attribute(&fields.upper_unit),
+        record.attribute(&fields.upper_reference),
+        AltitudeLimit::UNLIMITED,
+    )
+    .map_err(error)?;
+    if lower.meters > upper.meters {
+        return Err(error(format!(
+            "lower limit {} m above upper limit {} m",
+            lower.meters, upper.meters
+        )));
+    }
+
+    let clockwise = rings
+        .iter()
+        .filter(|ring| signed_area(ring) < 0.0)
+        .collect::<Vec<_>>();
+    let outer = match clockwise.is_empty() {
+        true => rings.iter().collect(),
+        false => clockwise,
+    };
+
+    let count = outer.len();
+    outer
+        .into_iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            let label = match count {
+                1 => format!("{}{}", prefix, id),
+                _ => format!("{}{}-{}", prefix, id, i + 1),
+            };
+
+            let details = RestrictionDetails {
+                shape: shape::Shape::Polygon(to_closed_ring(ring.clone()).map_err(error)?),
+                timestamp_start: None,
+                timestamp_end: None,
+                schedule: None,
+                lower,
+                upper,
+                zone_type: ZoneType::Restriction,
+                kind: RestrictionKind::NoFly,
+            };
+
+            Ok((label, details))
+        })
+        .collect()
+}
+
+/// Converts a point record to a waypoint
+pub fn to_waypoint(
+    record: &Record,
+    fields: &FieldMapping,
+) -> Result<(String, Coordinates), String> {
+    let id = to_id(record, fields)?;
+    match &record.shape {
+        Shape::Point(point) => Ok((id, *point)),
+        Shape::MultiPoint(points) if points.len() == 1 => Ok((id, points[0])),
+        _ => Err(format!("{}: not a point", id)),
+    }
+}
+
+/// Twice the signed area of a ring in degrees, negative if it runs clockwise
+fn signed_area(ring: &[Coordinates]) -> f64 {
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(a, b)| a.longitude * b.latitude - b.longitude * a.latitude)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The shapefile layers shipped for tests
+    const SHAPEFILE_FIXTURES: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/us_shapefile");
+
+    async fn layer(name: &str) -> Vec<Result<Record, String>> {
+        load_layer(&Path::new(SHAPEFILE_FIXTURES).join(name))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_load_zones() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // geographic layer without a .prj file, one deleted record
+        let records = layer("sua.shp").await;
+        assert_eq!(records.len(), 4);
+
+        let fields = FieldMapping::default();
+        let restrictions = to_restrictions(records[0].as_ref().unwrap(), &fields, "TEST-").unwrap();
+        assert_eq!(restrictions.len(), 1);
+        let (label, details) = &restrictions[0];
+        assert_eq!(label, "TEST-R-6302A");
+        let shape::Shape::Polygon(vertices) = &details.shape else {
+            panic!("expected a polygon");
+        };
+        assert_eq!(vertices.len(), 5);
+        assert_eq!(vertices[1].latitude, 30.5);
+        assert_eq!(vertices[1].longitude, -97.5);
+        assert_eq!(details.lower.meters, 0.0);
+        assert_eq!(details.upper.to_string(), "15000 ft AMSL");
+
+        // two outer rings and a hole, limits in meters
+        let restrictions = to_restrictions(records[1].as_ref().unwrap(), &fields, "TEST-").unwrap();
+        let labels = restrictions
+            .iter()
+            .map(|(l, _)| l.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, vec!["TEST-MOA-1", "TEST-MOA-2"]);
+        assert_eq!(restrictions[0].1.lower, AltitudeLimit::msl(150.0));
+        assert_eq!(restrictions[0].1.upper, AltitudeLimit::UNLIMITED);
+
+        // not a polygon
+        let error = to_restrictions(records[2].as_ref().unwrap(), &fields, "TEST-").unwrap_err();
+        assert_eq!(error, "POINT: not a polygon");
+
+        // upper limit in another column
+        let fields = fields.with_overrides("upper=CEILING").unwrap();
+        let restrictions = to_restrictions(records[3].as_ref().unwrap(), &fields, "TEST-").unwrap();
+        assert!((restrictions[0].1.upper.meters - 121.92).abs() < 1e-3);
+
+        // reference in another column, a surface code means above ground
+        let fields = fields.with_overrides("upper_reference=LOWER_VAL").unwrap();
+        let restrictions = to_restrictions(records[3].as_ref().unwrap(), &fields, "TEST-").unwrap();
+        assert_eq!(restrictions[0].1.upper.reference, AltitudeReference::Agl);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_load_waypoints() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // projected layer in UTM zone 14N
+        let records = layer("fixes.shp").await;
+        assert_eq!(records.len(), 3);
+
+        let fields = FieldMapping::waypoints();
+        let (id, point) = to_waypoint(records[0].as_ref().unwrap(), &fields).unwrap();
+        assert_eq!(id, "BLEWE");
+        assert!((point.latitude - 30.242186).abs() < 1e-6);
+        assert!((point.longitude + 97.868981).abs() < 1e-6);
+
+        assert_eq!(
+            to_waypoint(records[2].as_ref().unwrap(), &fields),
+            Err("missing identifier in column IDENT".to_string())
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_field_mapping() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let fields = FieldMapping::default()
+            .with_overrides(" id = IDENT , lower_unit=UOM,upper_unit=UOM")
+            .unwrap();
+        assert_eq!(fields.id, "IDENT");
+        assert_eq!(fields.lower_unit, "UOM");
+        assert_eq!(fields.upper_unit, "UOM");
+        assert_eq!(fields.upper, "UPPER_VAL");
+
+        assert!(FieldMapping::default().with_overrides("id").is_err());
+        assert!(FieldMapping::default().with_overrides("name=NAME").is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_layer() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let dbf = std::fs::read(format!("{}/sua.dbf", SHAPEFILE_FIXTURES)).unwrap();
+        let shp = std::fs::read(format!("{}/sua.shp", SHAPEFILE_FIXTURES)).unwrap();
+
+        assert!(matches!(
+            parse_layer(b"not a shapefile", &dbf, None),
+            Err(ShapefileError::Shp(_))
+        ));
+        assert!(matches!(
+            parse_layer(&shp[..shp.len() - 4], &dbf, None),
+            Err(ShapefileError::Shp(_))
+        ));
+        assert!(matches!(
+            parse_layer(&shp, &dbf[..40], None),
+            Err(ShapefileError::Dbf(_))
+        ));
+        assert!(matches!(
+            parse_layer(
+                &shp,
+                &dbf,
+                Some("PROJCS[\"Grid\",PROJECTION[\"Polyconic\"]]")
+            ),
+            Err(ShapefileError::Projection(_))
+        ));
+
+        let error = load_layer(Path::new("/nonexistent.shp")).await.unwrap_err();
+        assert!(matches!(error, ShapefileError::Read(_, _)));
+
+        ut_info!("Success.");
+    }
+}
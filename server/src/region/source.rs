@@ -0,0 +1,382 @@
+//! Published datasets read from a local file or an HTTP URL
+//!
+//! https servers are verified against the system certificates.
+
+use hyper::client::HttpConnector;
+use hyper::{Client, StatusCode, Uri};
+use hyper_tls::HttpsConnector;
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Time allowed to download a dataset
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Errors when reading a dataset
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum SourceError {
+    /// The source is neither a path nor a URL
+    #[error("error: Invalid dataset source '{0}'.")]
+    Invalid(String),
+
+    /// Only http and https URLs can be downloaded
+    #[error("error: Unsupported URL scheme in '{0}', expected http, https or a file path.")]
+    UnsupportedScheme(String),
+
+    /// The file could not be read or the download failed
+    #[error("error: Could not read '{0}': {1}")]
+    Read(String, String),
+
+    /// The server responded with an error status
+    #[error("error: Request to '{0}' failed with status {1}.")]
+    Status(String, u16),
+}
+
+/// Location of a published dataset
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// A local file
+    File(PathBuf),
+
+    /// A dataset downloaded over http or https
+    Url(Uri),
+}
+
+impl FromStr for Source {
+    type Err = SourceError;
+
+    /// Parses a file path, a `file://` URL or an `http://` or `https://` URL
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let source = source.trim();
+        if source.is_empty() {
+            return Err(SourceError::Invalid(source.to_string()));
+        }
+
+        if let Some(path) = source.strip_prefix("file://") {
+            return Ok(Source::File(PathBuf::from(path)));
+        }
+
+        if !source.contains("://") {
+            return Ok(Source::File(PathBuf::from(source)));
+        }
+
+        let uri = source
+            .parse::<Uri>()
+            .map_err(|_| SourceError::Invalid(source.to_string()))?;
+
+        match uri.scheme_str() {
+            Some("http" | "https") => Ok(Source::Url(uri)),
+            _ => Err(SourceError::UnsupportedScheme(source.to_string())),
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Url(uri) => write!(f, "{}", uri),
+        }
+    }
+}
+
+impl Source {
+    /// Returns true if the source is a local directory
+    pub async fn is_directory(&self) -> bool {
+        match self {
+            Source::File(path) => tokio::fs::metadata(path)
+                .await
+                .is_ok_and(|metadata| metadata.is_dir()),
+            Source::Url(_) => false,
+        }
+    }
+
+    /// Reads the complete dataset
+    pub async fn read(&self) -> Result<Vec<u8>, SourceError> {
+        match self {
+            Source::File(path) => tokio::fs::read(path)
+                .await
+                .map_err(|e| SourceError::Read(self.to_string(), e.to_string())),
+            Source::Url(uri) => {
+                let client = Client::builder().build(HttpsConnector::new());
+                tokio::time::timeout(DOWNLOAD_TIMEOUT, download(&client, uri.clone()))
+                    .await
+                    .map_err(|_| SourceError::Read(self.to_string(), "timed out".to_string()))?
+            }
+        }
+    }
+
+    /// Reads all documents of the dataset, with the name of each document
+    ///
+    /// A directory holds one document per file with the given extension,
+    ///  read in name order. Any other source is a single document.
+    pub async fn read_all(&self, extension: &str) -> Result<Vec<(String, Vec<u8>)>, SourceError> {
+        let directory = match self {
+            Source::File(path) if self.is_directory().await => path,
+            _ => return Ok(vec![(self.to_string(), self.read().await?)]),
+        };
+
+        let read_error = |e: std::io::Error| SourceError::Read(self.to_string(), e.to_string());
+        let mut entries = tokio::fs::read_dir(directory).await.map_err(read_error)?;
+        let mut paths = vec![];
+        while let Some(entry) = entries.next_entry().await.map_err(read_error)? {
+            let path = entry.path();
+            let matches = path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case(extension));
+            if matches && entry.file_type().await.map_err(read_error)?.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut documents = vec![];
+        for path in paths {
+            let source = Source::File(path);
+            documents.push((source.to_string(), source.read().await?));
+        }
+
+        Ok(documents)
+    }
+}
+
+/// Downloads the body of an http or https resource
+async fn download(
+    client: &Client<HttpsConnector<HttpConnector>>,
+    uri: Uri,
+) -> Result<Vec<u8>, SourceError> {
+    let name = uri.to_string();
+    let response = client
+        .get(uri)
+        .await
+        .map_err(|e| SourceError::Read(name.clone(), e.to_string()))?;
+
+    if response.status() != StatusCode::OK {
+        return Err(SourceError::Status(name, response.status().as_u16()));
+    }
+
+    let body = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|e| SourceError::Read(name, e.to_string()))?;
+
+    Ok(body.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves a single http response on a local port, returning its URL
+    async fn serve_once(status: &'static str, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        format!("http://{}/zones.geojson", address)
+    }
+
+    /// Serves a single https response on a local port with a self-signed
+    ///  certificate, returning its URL and the certificate
+    async fn serve_once_tls(body: &'static str) -> (String, native_tls::Certificate) {
+        use openssl::asn1::Asn1Time;
+        use openssl::hash::MessageDigest;
+        use openssl::pkey::PKey;
+        use openssl::rsa::Rsa;
+        use openssl::x509::extension::SubjectAlternativeName;
+        use openssl::x509::{X509NameBuilder, X509};
+
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "localhost").unwrap();
+        let name = name.build();
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("localhost")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        let cert = builder.build().to_pem().unwrap();
+
+        let identity =
+            native_tls::Identity::from_pkcs8(&cert, &key.private_key_to_pem_pkcs8().unwrap())
+                .unwrap();
+        let acceptor =
+            tokio_native_tls::TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = acceptor.accept(stream).await.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            stream.shutdown().await.unwrap();
+        });
+
+        let url = format!("https://localhost:{}/zones.geojson", port);
+        (url, native_tls::Certificate::from_pem(&cert).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_source_from_str() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert_eq!(
+            "/data/zones.geojson".parse::<Source>().unwrap(),
+            Source::File(PathBuf::from("/data/zones.geojson"))
+        );
+        assert_eq!(
+            " file:///data/zones.geojson ".parse::<Source>().unwrap(),
+            Source::File(PathBuf::from("/data/zones.geojson"))
+        );
+        assert_eq!(
+            "http://example.com/zones.geojson"
+                .parse::<Source>()
+                .unwrap(),
+            Source::Url(Uri::from_static("http://example.com/zones.geojson"))
+        );
+        assert_eq!(
+            "https://example.com/zones.geojson"
+                .parse::<Source>()
+                .unwrap(),
+            Source::Url(Uri::from_static("https://example.com/zones.geojson"))
+        );
+        assert_eq!(
+            "ftp://example.com/zones.geojson"
+                .parse::<Source>()
+                .unwrap_err(),
+            SourceError::UnsupportedScheme("ftp://example.com/zones.geojson".to_string())
+        );
+        assert_eq!(
+            " ".parse::<Source>().unwrap_err(),
+            SourceError::Invalid(String::new())
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_read_file() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let source: Source = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")
+            .parse()
+            .unwrap();
+        let data = source.read().await.unwrap();
+        assert!(String::from_utf8(data).unwrap().contains("svc-compliance"));
+
+        let source: Source = "/nonexistent/zones.geojson".parse().unwrap();
+        let error = source.read().await.unwrap_err();
+        assert!(matches!(error, SourceError::Read(..)));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_read_all() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let dir =
+            std::env::temp_dir().join(format!("svc-compliance-source-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested.xml")).unwrap();
+        std::fs::write(dir.join("b.XML"), "b").unwrap();
+        std::fs::write(dir.join("a.xml"), "a").unwrap();
+        std::fs::write(dir.join("readme.txt"), "ignored").unwrap();
+
+        let source = Source::File(dir.clone());
+        let documents = source.read_all("xml").await.unwrap();
+        let contents = documents
+            .iter()
+            .map(|(_, data)| data.as_slice())
+            .collect::<Vec<_>>();
+        assert_eq!(contents, vec![b"a", b"b"]);
+        assert!(documents[0].0.ends_with("a.xml"));
+
+        let source = Source::File(dir.join("readme.txt"));
+        let documents = source.read_all("xml").await.unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].1, b"ignored".to_vec());
+
+        std::fs::remove_dir_all(dir).unwrap();
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_read_url() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let url = serve_once("200 OK", "{}").await;
+        let source: Source = url.parse().unwrap();
+        assert_eq!(source.read().await.unwrap(), b"{}".to_vec());
+
+        let url = serve_once("404 Not Found", "").await;
+        let source: Source = url.parse().unwrap();
+        assert_eq!(
+            source.read().await.unwrap_err(),
+            SourceError::Status(url, 404)
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_read_https_url() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // the system certificates do not trust a self-signed certificate
+        let (url, _) = serve_once_tls("{}").await;
+        let source: Source = url.parse().unwrap();
+        let error = source.read().await.unwrap_err();
+        assert!(matches!(error, SourceError::Read(..)));
+
+        let (url, cert) = serve_once_tls("{}").await;
+        let tls = native_tls::TlsConnector::builder()
+            .add_root_certificate(cert)
+            .build()
+            .unwrap();
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        let client = Client::builder().build(HttpsConnector::from((http, tls.into())));
+        let data = download(&client, url.parse().unwrap()).await.unwrap();
+        assert_eq!(data, b"{}".to_vec());
+
+        ut_info!("Success.");
+    }
+}
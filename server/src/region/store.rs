@@ -0,0 +1,241 @@
+//! Shared in memory store of the airspace data acquired from the regional authorities
+//!
+//! The refresh loops replace the data of a region as a whole, while the
+//!  request handlers read cheap snapshots. A snapshot is never modified after
+//!  it is taken, so a request sees a consistent view of the airspace even
+//!  while a refresh is in progress.
+
+use super::vertical::VerticalModel;
+use super::RestrictionDetails;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use svc_gis_client_grpc::prelude::gis;
+
+/// Consistent view of the airspace data of a single region
+#[derive(Debug, Clone, Default)]
+pub struct Airspace {
+    /// Restrictions by identifier
+    pub restrictions: Arc<HashMap<String, RestrictionDetails>>,
+
+    /// Waypoints by identifier
+    pub waypoints: Arc<HashMap<String, gis::Coordinates>>,
+
+    /// Terrain and geoid models to convert the altitude limits
+    pub vertical: Arc<VerticalModel>,
+
+    /// True once the restrictions of the region were stored, until then
+    ///  flight plans cannot be checked against them
+    pub loaded: bool,
+}
+
+/// Airspace data of all served regions, shared between the refresh loops
+///  and the request handlers
+#[derive(Debug, Default)]
+pub struct AirspaceStore {
+    /// Airspace data by region short code
+    regions: RwLock<HashMap<String, Airspace>>,
+
+    /// Ad-hoc restrictions by identifier, shared by all regions
+    adhoc: RwLock<Arc<HashMap<String, RestrictionDetails>>>,
+
+    /// Terrain and geoid models, shared by all regions
+    vertical: Arc<VerticalModel>,
+}
+
+impl AirspaceStore {
+    /// Creates an empty store using the terrain and geoid models
+    pub fn new(vertical: VerticalModel) -> Self {
+        Self {
+            vertical: Arc::new(vertical),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the terrain and geoid models
+    pub fn vertical(&self) -> Arc<VerticalModel> {
+        self.vertical.clone()
+    }
+
+    /// Returns a read lock, recovering the data if a writer panicked
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, Airspace>> {
+        self.regions.read().unwrap_or_else(|e| {
+            region_warn!("Store lock was poisoned, recovering.");
+            e.into_inner()
+        })
+    }
+
+    /// Returns a write lock, recovering the data if a writer panicked
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, Airspace>> {
+        self.regions.write().unwrap_or_else(|e| {
+            region_warn!("Store lock was poisoned, recovering.");
+            e.into_inner()
+        })
+    }
+
+    /// Returns a snapshot of the airspace data of the region
+    ///
+    /// Regions that have not been refreshed yet have no airspace data.
+    pub fn snapshot(&self, region: &str) -> Airspace {
+        Airspace {
+            vertical: self.vertical.clone(),
+            ..self.read().get(region).cloned().unwrap_or_default()
+        }
+    }
+
+    /// Replaces the restrictions of the region, which are then loaded
+    pub fn set_restrictions(
+        &self,
+        region: &str,
+        restrictions: HashMap<String, RestrictionDetails>,
+    ) {
+        region_debug!("[{}] Storing {} restrictions.", region, restrictions.len());
+
+        let mut regions = self.write();
+        let airspace = regions.entry(region.to_string()).or_default();
+        airspace.restrictions = Arc::new(restrictions);
+        airspace.loaded = true;
+    }
+
+    /// Replaces the ad-hoc restrictions shared by all regions
+    pub fn set_adhoc_restrictions(&self, restrictions: HashMap<String, RestrictionDetails>) {
+        region_debug!("Storing {} ad-hoc restrictions.", restrictions.len());
+
+        *self.adhoc.write().unwrap_or_else(|e| {
+            region_warn!("Store lock was poisoned, recovering.");
+            e.into_inner()
+        }) = Arc::new(restrictions);
+    }
+
+    /// Returns the ad-hoc restrictions shared by all regions
+    pub fn adhoc_restrictions(&self) -> Arc<HashMap<String, RestrictionDetails>> {
+        self.adhoc
+            .read()
+            .unwrap_or_else(|e| {
+                region_warn!("Store lock was poisoned, recovering.");
+                e.into_inner()
+            })
+            .clone()
+    }
+
+    /// Replaces the waypoints of the region
+    pub fn set_waypoints(&self, region: &str, waypoints: HashMap<String, gis::Coordinates>) {
+        region_debug!("[{}] Storing {} waypoints.", region, waypoints.len());
+
+        self.write()
+            .entry(region.to_string())
+            .or_default()
+            .waypoints = Arc::new(waypoints);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::shape::Shape;
+    use crate::region::utils::AltitudeLimit;
+    use crate::region::RestrictionKind;
+
+    fn restriction() -> RestrictionDetails {
+        RestrictionDetails {
+            shape: Shape::Polygon(vec![]),
+            timestamp_start: None,
+            timestamp_end: None,
+            schedule: None,
+            zone_type: gis::ZoneType::Restriction,
+            lower: AltitudeLimit::SURFACE,
+            upper: AltitudeLimit::msl(100.),
+            kind: RestrictionKind::NoFly,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_unknown_region() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let store = AirspaceStore::new(VerticalModel::default());
+        let airspace = store.snapshot("nl");
+        assert!(airspace.restrictions.is_empty());
+        assert!(airspace.waypoints.is_empty());
+        assert!(!airspace.loaded);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_per_region() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let store = AirspaceStore::default();
+        store.set_restrictions("nl", HashMap::from([("zone".to_string(), restriction())]));
+        store.set_waypoints(
+            "us",
+            HashMap::from([(
+                "fix".to_string(),
+                gis::Coordinates {
+                    latitude: 40.0,
+                    longitude: -74.0,
+                },
+            )]),
+        );
+
+        let nl = store.snapshot("nl");
+        assert_eq!(nl.restrictions.len(), 1);
+        assert!(nl.waypoints.is_empty());
+        assert!(nl.loaded);
+
+        // waypoints alone do not load the airspace
+        let us = store.snapshot("us");
+        assert!(us.restrictions.is_empty());
+        assert_eq!(us.waypoints.len(), 1);
+        assert!(!us.loaded);
+
+        // all regions share the terrain and geoid models
+        assert!(Arc::ptr_eq(&nl.vertical, &us.vertical));
+        assert!(Arc::ptr_eq(&nl.vertical, &store.vertical()));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_is_stable() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let store = AirspaceStore::default();
+        store.set_restrictions("nl", HashMap::from([("a".to_string(), restriction())]));
+
+        let before = store.snapshot("nl");
+        store.set_restrictions(
+            "nl",
+            HashMap::from([
+                ("a".to_string(), restriction()),
+                ("b".to_string(), restriction()),
+            ]),
+        );
+
+        // a refresh does not change a snapshot that was already taken
+        assert_eq!(before.restrictions.len(), 1);
+        assert_eq!(store.snapshot("nl").restrictions.len(), 2);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_adhoc_restrictions() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let store = AirspaceStore::default();
+        assert!(store.adhoc_restrictions().is_empty());
+
+        store.set_adhoc_restrictions(HashMap::from([("kml".to_string(), restriction())]));
+        assert_eq!(store.adhoc_restrictions().len(), 1);
+
+        // ad-hoc restrictions are kept apart from those of the regions
+        assert!(store.snapshot("nl").restrictions.is_empty());
+
+        ut_info!("Success.");
+    }
+}
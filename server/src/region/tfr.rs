@@ -0,0 +1,854 @@
+//! Reader for FAA temporary flight restrictions (TFRs)
+//!
+//! Two encodings of the FAA TFR feed are supported:
+//! - XNOTAM (`XNOTAM-Update`), where each `Not` element is a NOTAM with
+//!   `TFRAreaGroup` areas, bounded by `Avx` vertices.
+//! - AIXM 5.1 (`AIXMBasicMessage`), where each `Airspace` feature holds
+//!   `AirspaceVolume` elements bounded by a GML surface.
+//!
+//! Element names are matched without their namespace prefix. Arcs and
+//!  circles are kept as such in the boundary of an area.
+
+use crate::region::coordinates::{parse_degrees, Axis};
+use crate::region::shape::{Arc, Segment, Shape};
+use crate::region::utils::{
+    bearing_degrees, distance_meters, distance_to_meters, AltitudeLimit, AltitudeReference,
+};
+use lib_common::time::{DateTime, NaiveDateTime, Utc};
+use roxmltree::{Document, Node};
+use svc_gis_client_grpc::prelude::gis::Coordinates;
+
+/// GML elements making up the exterior of an AIXM surface
+const GML_SEGMENTS: &[&str] = &[
+    "LinearRing",
+    "GeodesicString",
+    "LineStringSegment",
+    "ArcByCenterPoint",
+    "CircleByCenterPoint",
+];
+
+/// Effective and expiry time of a TFR
+type Period = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// Errors when reading a TFR document
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum TfrError {
+    /// The document is not well-formed XML
+    #[error("error: Invalid XML: {0}")]
+    Xml(String),
+
+    /// The document is neither XNOTAM nor AIXM
+    #[error("error: Unknown TFR document '{0}', expected XNOTAM-Update or AIXMBasicMessage.")]
+    UnknownFormat(String),
+}
+
+/// A single area of a TFR
+#[derive(Debug, Clone, PartialEq)]
+pub struct TfrArea {
+    /// Boundary of the area
+    pub shape: Shape,
+
+    /// Lower altitude limit
+    pub lower: AltitudeLimit,
+
+    /// Upper altitude limit
+    pub upper: AltitudeLimit,
+}
+
+/// A temporary flight restriction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tfr {
+    /// NOTAM number or designator of the TFR (e.g. `4/1234`)
+    pub id: String,
+
+    /// Start of the restriction, immediately if not set
+    pub effective: Option<DateTime<Utc>>,
+
+    /// End of the restriction, until further notice if not set
+    pub expiry: Option<DateTime<Utc>>,
+
+    /// The restricted areas
+    pub areas: Vec<TfrArea>,
+}
+
+/// Parses a TFR document
+///
+/// Returns an error if the document itself cannot be read, otherwise the
+///  result of parsing each TFR in document order.
+pub fn parse_tfrs(data: &[u8]) -> Result<Vec<Result<Tfr, String>>, TfrError> {
+    let text = std::str::from_utf8(data).map_err(|e| TfrError::Xml(e.to_string()))?;
+    let document = Document::parse(text).map_err(|e| TfrError::Xml(e.to_string()))?;
+    let root = document.root_element();
+
+    match root.tag_name().name() {
+        "XNOTAM-Update" => Ok(descendants(root, "Not")
+            .filter(|notam| !notam.ancestors().any(|n| has_name(n, "Cancel")))
+            .map(xnotam_to_tfr)
+            .collect()),
+        "AIXMBasicMessage" => Ok(descendants(root, "Airspace").map(aixm_to_tfr).collect()),
+        name => Err(TfrError::UnknownFormat(name.to_string())),
+    }
+}
+
+/// Returns true if the node is an element with the local name
+fn has_name(node: Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
+
+/// Elements below the node with the local name, in document order
+fn descendants<'a, 'i>(
+    node: Node<'a, 'i>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'i>> {
+    node.descendants().filter(move |n| has_name(*n, name))
+}
+
+/// Trimmed text of the first element below the node with the local name
+fn text<'a>(node: Node<'a, '_>, name: &'static str) -> Option<&'a str> {
+    descendants(node, name)
+        .next()
+        .and_then(|n| n.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+/// Parses a timestamp, in UTC if no offset is given
+fn to_timestamp(timestamp: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(timestamp) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+
+    NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S")
+        .map(|t| t.and_utc())
+        .map_err(|e| format!("invalid timestamp '{}': {}", timestamp, e))
+}
+
+/// Checks the validity period, returning it if the expiry follows the effective time
+fn to_period(effective: Option<&str>, expiry: Option<&str>) -> Result<Period, String> {
+    let effective = effective.map(to_timestamp).transpose()?;
+    let expiry = expiry.map(to_timestamp).transpose()?;
+    if let (Some(effective), Some(expiry)) = (effective, expiry) {
+        if effective >= expiry {
+            return Err(format!("expires at {} before it is effective", expiry));
+        }
+    }
+
+    Ok((effective, expiry))
+}
+
+/// Converts a published altitude limit
+///
+/// `SFC` and `GND` are the surface, `UNL` is unlimited. Flight levels are
+///  always pressure altitudes.
+fn to_limit(value: &str, unit: &str, reference: &str) -> Result<AltitudeLimit, String> {
+    match value.to_uppercase().as_str() {
+        "SFC" | "GND" => return Ok(AltitudeLimit::SURFACE),
+        "UNL" => return Ok(AltitudeLimit::UNLIMITED),
+        _ => (),
+    }
+
+    let value = value
+        .parse::<f64>()
+        .map_err(|_| format!("invalid altitude '{}'", value))?;
+    let reference = match unit.eq_ignore_ascii_case("FL") {
+        true => AltitudeReference::Standard,
+        false => AltitudeReference::parse(reference)
+            .ok_or_else(|| format!("unknown altitude reference '{}'", reference))?,
+    };
+
+    AltitudeLimit::new(value, unit, reference)
+        .ok_or_else(|| format!("unknown altitude unit '{}'", unit))
+}
+
+/// Converts an XNOTAM `Not` element
+fn xnotam_to_tfr(notam: Node) -> Result<Tfr, String> {
+    let id = text(notam, "txtLocalName").ok_or("missing NOTAM number")?;
+    let (effective, expiry) = to_period(text(notam, "dateEffective"), text(notam, "dateExpire"))
+        .map_err(|e| format!("NOTAM {}: {}", id, e))?;
+
+    let areas = descendants(notam, "TFRAreaGroup")
+        .map(xnotam_to_area)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("NOTAM {}: {}", id, e))?;
+
+    if areas.is_empty() {
+        return Err(format!("NOTAM {}: no TFR areas", id));
+    }
+
+    Ok(Tfr {
+        id: id.to_string(),
+        effective,
+        expiry,
+        areas,
+    })
+}
+
+/// Converts an XNOTAM `TFRAreaGroup` element
+fn xnotam_to_area(group: Node) -> Result<TfrArea, String> {
+    let limit = |value: &'static str, unit: &'static str, reference: &'static str| {
+        text(group, value)
+            .map(|value| {
+                to_limit(
+                    value,
+                    text(group, unit).unwrap_or("FT"),
+                    text(group, reference).unwrap_or("ALT"),
+                )
+            })
+            .transpose()
+    };
+
+    let upper = limit("valDistVerUpper", "uomDistVerUpper", "codeDistVerUpper")?
+        .ok_or("missing upper limit")?;
+    let lower = limit("valDistVerLower", "uomDistVerLower", "codeDistVerLower")?
+        .unwrap_or(AltitudeLimit::SURFACE);
+
+    Ok(TfrArea {
+        shape: xnotam_to_shape(group)?,
+        lower,
+        upper,
+    })
+}
+
+/// Position of an `Avx` vertex, or of its arc center
+fn xnotam_position(vertex: Node, center: bool) -> Result<Option<Coordinates>, String> {
+    let (latitude, longitude) = match center {
+        true => (text(vertex, "geoLatArc"), text(vertex, "geoLongArc")),
+        false => (text(vertex, "geoLat"), text(vertex, "geoLong")),
+    };
+
+    match (latitude, longitude) {
+        (Some(latitude), Some(longitude)) => Ok(Some(Coordinates {
+            latitude: parse_degrees(latitude, Axis::Latitude)
+                .ok_or_else(|| format!("invalid coordinate '{}'", latitude))?,
+            longitude: parse_degrees(longitude, Axis::Longitude)
+                .ok_or_else(|| format!("invalid coordinate '{}'", longitude))?,
+        })),
+        (None, None) => Ok(None),
+        _ => Err("vertex with a partial position".to_string()),
+    }
+}
+
+/// Radius of an `Avx` arc or circle in meters
+fn xnotam_radius(vertex: Node) -> Result<Option<f64>, String> {
+    let Some(radius) = text(vertex, "valRadiusArc") else {
+        return Ok(None);
+    };
+
+    let unit = text(vertex, "uomRadiusArc").unwrap_or("NM");
+    let radius = radius
+        .parse::<f64>()
+        .map_err(|_| format!("invalid radius '{}'", radius))?;
+
+    distance_to_meters(radius, unit)
+        .filter(|radius| *radius > 0.0)
+        .map(Some)
+        .ok_or_else(|| format!("invalid radius {} {}", radius, unit))
+}
+
+/// Converts the `Avx` vertices of an area to its boundary
+///
+/// `CWA` and `CCA` vertices start a clockwise or counter-clockwise arc to
+///  the next vertex, `CIR` is a circle. Other vertices are connected by
+///  straight lines.
+fn xnotam_to_shape(group: Node) -> Result<Shape, String> {
+    let vertices = descendants(group, "Avx").collect::<Vec<_>>();
+    let mut segments = vec![];
+    for (i, vertex) in vertices.iter().enumerate() {
+        let code = text(*vertex, "codeType").unwrap_or("GRC").to_uppercase();
+        match code.as_str() {
+            "CIR" => {
+                if vertices.len() != 1 {
+                    return Err("circle combined with other vertices".to_string());
+                }
+
+                let center = match xnotam_position(*vertex, true)? {
+                    Some(center) => center,
+                    None => xnotam_position(*vertex, false)?.ok_or("circle without center")?,
+                };
+                let radius = xnotam_radius(*vertex)?.ok_or("circle without radius")?;
+                return Ok(Shape::Circle {
+                    center,
+                    radius_meters: radius,
+                });
+            }
+            "CWA" | "CCA" => {
+                let start = xnotam_position(*vertex, false)?.ok_or("arc without start")?;
+                let end = xnotam_position(vertices[(i + 1) % vertices.len()], false)?
+                    .ok_or("arc without end")?;
+                let center = xnotam_position(*vertex, true)?.ok_or("arc without center")?;
+                let radius = xnotam_radius(*vertex)?.unwrap_or(distance_meters(&center, &start));
+
+                segments.push(Segment::Arc(Arc {
+                    center,
+                    radius_meters: radius,
+                    start_bearing_degrees: bearing_degrees(&center, &start),
+                    end_bearing_degrees: bearing_degrees(&center, &end),
+                    clockwise: code == "CWA",
+                }));
+            }
+            _ => segments.push(Segment::Line(
+                xnotam_position(*vertex, false)?.ok_or("vertex without position")?,
+            )),
+        }
+    }
+
+    Shape::from_segments(segments)
+}
+
+/// Converts an AIXM `Airspace` feature
+fn aixm_to_tfr(airspace: Node) -> Result<Tfr, String> {
+    let id = text(airspace, "designator")
+        .or_else(|| {
+            airspace
+                .attributes()
+                .find(|a| a.name() == "id")
+                .map(|a| a.value())
+        })
+        .ok_or("missing designator")?;
+
+    let period = descendants(airspace, "validTime")
+        .next()
+        .or_else(|| descendants(airspace, "featureLifetime").next());
+    let (effective, expiry) = match period {
+        Some(period) => to_period(text(period, "beginPosition"), text(period, "endPosition")),
+        None => Ok((None, None)),
+    }
+    .map_err(|e| format!("airspace {}: {}", id, e))?;
+
+    let areas = descendants(airspace, "AirspaceVolume")
+        .map(aixm_to_area)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("airspace {}: {}", id, e))?;
+
+    if areas.is_empty() {
+        return Err(format!("airspace {}: no airspace volumes", id));
+    }
+
+    Ok(Tfr {
+        id: id.to_string(),
+        effective,
+        expiry,
+        areas,
+    })
+}
+
+/// Converts an AIXM `AirspaceVolume` element
+fn aixm_to_area(volume: Node) -> Result<TfrArea, String> {
+    let limit = |value: &'static str, reference: &'static str| {
+        descendants(volume, value)
+            .next()
+            .and_then(|node| Some((node.text()?.trim(), node.attribute("uom").unwrap_or("FT"))))
+            .filter(|(value, _)| !value.is_empty())
+            .map(|(value, unit)| to_limit(value, unit, text(volume, reference).unwrap_or("MSL")))
+            .transpose()
+    };
+
+    let upper = limit("upperLimit", "upperLimitReference")?.ok_or("missing upper limit")?;
+    let lower = limit("lowerLimit", "lowerLimitReference")?.unwrap_or(AltitudeLimit::SURFACE);
+
+    let exterior = descendants(volume, "horizontalProjection")
+        .flat_map(|projection| descendants(projection, "exterior"))
+        .next()
+        .ok_or("missing horizontal projection")?;
+
+    Ok(TfrArea {
+        shape: aixm_to_shape(exterior)?,
+        lower,
+        upper,
+    })
+}
+
+/// Positions of the `pos` and `posList` elements below the node
+///
+/// Positions are latitude first, unless the coordinate reference system
+///  is `CRS84`.
+fn aixm_positions(node: Node) -> Result<Vec<Coordinates>, String> {
+    let longitude_first = node
+        .ancestors()
+        .find_map(|n| n.attribute("srsName"))
+        .is_some_and(|srs| srs.contains("CRS84"));
+
+    let mut positions = vec![];
+    for list in node
+        .descendants()
+        .filter(|n| has_name(*n, "pos") || has_name(*n, "posList"))
+    {
+        let dimension = list
+            .attribute("srsDimension")
+            .and_then(|d| d.parse::<usize>().ok())
+            .unwrap_or(2);
+        let values = list
+            .text()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|v| v.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid position: {}", e))?;
+
+        if dimension < 2 || values.len() % dimension != 0 {
+            return Err(format!("invalid position list of {} values", values.len()));
+        }
+
+        for position in values.chunks(dimension) {
+            let (latitude, longitude) = match longitude_first {
+                true => (position[1], position[0]),
+                false => (position[0], position[1]),
+            };
+
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                return Err(format!("invalid position {:?}", position));
+            }
+
+            positions.push(Coordinates {
+                latitude,
+                longitude,
+            });
+        }
+    }
+
+    Ok(positions)
+}
+
+/// Converts a GML length or angle element, with its unit of measurement
+fn aixm_measure<'a>(node: Node<'a, '_>, name: &'static str) -> Result<(f64, &'a str), String> {
+    let element = descendants(node, name)
+        .next()
+        .ok_or_else(|| format!("missing {}", name))?;
+    let value = element.text().unwrap_or_default().trim();
+    let value = value
+        .parse::<f64>()
+        .map_err(|_| format!("invalid {} '{}'", name, value))?;
+
+    Ok((value, element.attribute("uom").unwrap_or_default()))
+}
+
+/// Radius of a GML arc or circle in meters
+fn aixm_radius(segment: Node) -> Result<f64, String> {
+    let (radius, unit) = aixm_measure(segment, "radius")?;
+    distance_to_meters(radius, unit)
+        .filter(|radius| *radius > 0.0)
+        .ok_or_else(|| format!("invalid radius {} {}", radius, unit))
+}
+
+/// Converts the exterior of a GML surface to its boundary
+///
+/// The angles of an `ArcByCenterPoint` are bearings from true north, see
+///  [`aixm_clockwise`] for the direction of an arc.
+fn aixm_to_shape(exterior: Node) -> Result<Shape, String> {
+    let elements = exterior
+        .descendants()
+        .filter(|n| GML_SEGMENTS.iter().any(|name| has_name(*n, name)))
+        .collect::<Vec<_>>();
+
+    let mut segments = vec![];
+    for segment in elements.iter().copied() {
+        match segment.tag_name().name() {
+            "ArcByCenterPoint" | "CircleByCenterPoint" => {
+                let center = *aixm_positions(segment)?
+                    .first()
+                    .ok_or("arc without center")?;
+                let radius = aixm_radius(segment)?;
+                if has_name(segment, "CircleByCenterPoint") {
+                    if elements.len() != 1 {
+                        return Err("circle combined with other segments".to_string());
+                    }
+
+                    return Ok(Shape::Circle {
+                        center,
+                        radius_meters: radius,
+                    });
+                }
+
+                let (start, _) = aixm_measure(segment, "startAngle")?;
+                let (end, _) = aixm_measure(segment, "endAngle")?;
+                segments.push(Segment::Arc(Arc {
+                    center,
+                    radius_meters: radius,
+                    start_bearing_degrees: start,
+                    end_bearing_degrees: end,
+                    clockwise: aixm_clockwise(start, end),
+                }));
+            }
+            _ => segments.extend(aixm_positions(segment)?.into_iter().map(Segment::Line)),
+        }
+    }
+
+    Shape::from_segments(segments)
+}
+
+/// Direction of an `ArcByCenterPoint` from its start and end bearings
+///
+/// The arc sweeps from the start to the end angle, clockwise if the sweep
+///  is positive. Bearings within [0, 360) cannot sweep across north, so a
+///  sweep of more than half a turn between them runs the other way around,
+///  across north.
+fn aixm_clockwise(start: f64, end: f64) -> bool {
+    let sweep = end - start;
+    let normalized = [start, end].iter().all(|a| (0.0..360.0).contains(a));
+    match normalized && sweep.abs() > 180.0 {
+        true => sweep < 0.0,
+        false => sweep > 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::region::utils::destination;
+
+    /// Wraps NOTAMs in an XNOTAM document
+    fn xnotam(notams: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <XNOTAM-Update version="1.0"><Group><Add>{}</Add></Group></XNOTAM-Update>"#,
+            notams
+        )
+    }
+
+    /// A NOTAM with a single area
+    fn notam(id: &str, limits: &str, vertices: &str) -> String {
+        format!(
+            r#"<Not>
+                <NotUid><txtLocalName>{}</txtLocalName></NotUid>
+                <dateEffective>2024-06-01T12:00:00</dateEffective>
+                <dateExpire>2024-06-01T18:00:00</dateExpire>
+                <TfrNot><TFRAreaGroup>
+                    <aseTFRArea>{}</aseTFRArea>
+                    <abdMergedArea>{}</abdMergedArea>
+                </TFRAreaGroup></TfrNot>
+            </Not>"#,
+            id, limits, vertices
+        )
+    }
+
+    const LIMITS: &str = r#"
+        <codeDistVerUpper>HEI</codeDistVerUpper>
+        <valDistVerUpper>3000</valDistVerUpper>
+        <uomDistVerUpper>FT</uomDistVerUpper>"#;
+
+    const SQUARE: &str = r#"
+        <Avx><codeType>GRC</codeType><geoLat>30.0N</geoLat><geoLong>097.0W</geoLong></Avx>
+        <Avx><codeType>GRC</codeType><geoLat>30.1N</geoLat><geoLong>097.0W</geoLong></Avx>
+        <Avx><codeType>GRC</codeType><geoLat>30.1N</geoLat><geoLong>096.9W</geoLong></Avx>"#;
+
+    #[tokio::test]
+    async fn test_to_limit() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert_eq!(to_limit("SFC", "FT", "HEI"), Ok(AltitudeLimit::SURFACE));
+        assert_eq!(to_limit("UNL", "", ""), Ok(AltitudeLimit::UNLIMITED));
+
+        let limit = to_limit("180", "FL", "ALT").unwrap();
+        assert_eq!(limit.reference, AltitudeReference::Standard);
+        assert!((limit.meters - 5486.4).abs() < 1e-6);
+
+        let limit = to_limit("1000", "FT", "ALT").unwrap();
+        assert_eq!(limit.reference, AltitudeReference::Msl);
+        assert!((limit.meters - 304.8).abs() < 1e-6);
+
+        let limit = to_limit("120", "M", "SFC").unwrap();
+        assert_eq!(limit.reference, AltitudeReference::Agl);
+        assert_eq!(limit.meters, 120.0);
+
+        let limit = to_limit("500", "FT", "W84").unwrap();
+        assert_eq!(limit.reference, AltitudeReference::Ellipsoid);
+
+        assert!(to_limit("high", "FT", "ALT").is_err());
+        assert!(to_limit("100", "NM", "ALT").is_err());
+        assert!(to_limit("100", "FT", "QFE").is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_xnotam() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let arc = r#"
+            <Avx><codeType>GRC</codeType><geoLat>30.0N</geoLat><geoLong>097.0W</geoLong></Avx>
+            <Avx>
+                <codeType>CWA</codeType><geoLat>30.0N</geoLat><geoLong>096.9W</geoLong>
+                <geoLatArc>30.0N</geoLatArc><geoLongArc>096.95W</geoLongArc>
+            </Avx>"#;
+        let circle = r#"
+            <Avx>
+                <codeType>CIR</codeType><geoLat>301000N</geoLat><geoLong>0970000W</geoLong>
+                <valRadiusArc>3</valRadiusArc><uomRadiusArc>NM</uomRadiusArc>
+            </Avx>"#;
+
+        let document = xnotam(
+            &[
+                notam("4/0001", LIMITS, SQUARE),
+                notam("4/0002", LIMITS, arc),
+                notam("4/0003", LIMITS, circle),
+            ]
+            .concat(),
+        );
+        let tfrs = parse_tfrs(document.as_bytes()).unwrap();
+        let tfrs = tfrs.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(tfrs.len(), 3);
+
+        let tfr = &tfrs[0];
+        assert_eq!(tfr.id, "4/0001");
+        assert_eq!(
+            tfr.effective,
+            Some("2024-06-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap())
+        );
+        assert_eq!(
+            tfr.expiry,
+            Some("2024-06-01T18:00:00Z".parse::<DateTime<Utc>>().unwrap())
+        );
+        assert_eq!(tfr.areas.len(), 1);
+        let area = &tfr.areas[0];
+        assert_eq!(area.lower, AltitudeLimit::SURFACE);
+        assert_eq!(area.upper.reference, AltitudeReference::Agl);
+        assert!((area.upper.meters - 914.4).abs() < 1e-6);
+        assert!(matches!(&area.shape, Shape::Polygon(v) if v.len() == 4 && v[0] == v[3]));
+
+        // straight line east, then clockwise back along the southern half circle
+        let Shape::Boundary(segments) = &tfrs[1].areas[0].shape else {
+            panic!("not a boundary: {:?}", tfrs[1].areas[0].shape);
+        };
+        assert_eq!(segments.len(), 2);
+        let Segment::Arc(arc) = &segments[1] else {
+            panic!("not an arc: {:?}", segments[1]);
+        };
+        assert_eq!(
+            arc.center,
+            Coordinates {
+                latitude: 30.0,
+                longitude: -96.95,
+            }
+        );
+        assert!(arc.clockwise);
+        assert!((arc.start_bearing_degrees - 90.0).abs() < 0.1);
+        assert!((arc.end_bearing_degrees - 270.0).abs() < 0.1);
+        assert!(tfrs[1].areas[0].shape.contains(&destination(
+            &arc.center,
+            180.0,
+            arc.radius_meters - 10.0
+        )));
+
+        assert_eq!(
+            tfrs[2].areas[0].shape,
+            Shape::Circle {
+                center: Coordinates {
+                    latitude: 30.0 + 10.0 / 60.0,
+                    longitude: -97.0,
+                },
+                radius_meters: 5556.0,
+            }
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_xnotam_malformed() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let upper_fl = r#"
+            <codeDistVerUpper>STD</codeDistVerUpper>
+            <valDistVerUpper>180</valDistVerUpper>
+            <uomDistVerUpper>FL</uomDistVerUpper>"#;
+        let no_radius =
+            r#"<Avx><codeType>CIR</codeType><geoLat>30.0N</geoLat><geoLong>097.0W</geoLong></Avx>"#;
+        let bad_position = r#"<Avx><geoLat>30.0N</geoLat><geoLong>197.0W</geoLong></Avx>"#;
+
+        let document = xnotam(
+            &[
+                notam("4/0001", upper_fl, SQUARE),
+                notam("4/0002", "", SQUARE),
+                notam("4/0003", LIMITS, no_radius),
+                notam("4/0004", LIMITS, bad_position),
+                notam("4/0005", LIMITS, &SQUARE[..SQUARE.rfind("<Avx>").unwrap()]),
+                notam("4/0006", LIMITS, SQUARE).replace("2024-06-01T18", "2024-06-01T06"),
+                notam("", LIMITS, SQUARE),
+            ]
+            .concat(),
+        );
+
+        let tfrs = parse_tfrs(document.as_bytes()).unwrap();
+        assert_eq!(tfrs.len(), 7);
+        assert_eq!(
+            tfrs[0].as_ref().unwrap().areas[0].upper.reference,
+            AltitudeReference::Standard
+        );
+        for (i, tfr) in tfrs.iter().enumerate().skip(1) {
+            ut_debug!("TFR {}: {:?}", i, tfr);
+            assert!(tfr.is_err());
+        }
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_aixm() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let document = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <message:AIXMBasicMessage
+                xmlns:message="http://www.aixm.aero/schema/5.1/message"
+                xmlns:aixm="http://www.aixm.aero/schema/5.1"
+                xmlns:gml="http://www.opengis.net/gml/3.2">
+              <message:hasMember>
+                <aixm:Airspace gml:id="TFR_4_0010">
+                  <aixm:timeSlice><aixm:AirspaceTimeSlice gml:id="TS1">
+                    <gml:validTime><gml:TimePeriod gml:id="TP1">
+                      <gml:beginPosition>2024-06-01T12:00:00Z</gml:beginPosition>
+                      <gml:endPosition indeterminatePosition="unknown"/>
+                    </gml:TimePeriod></gml:validTime>
+                    <aixm:designator>4/0010</aixm:designator>
+                    <aixm:geometryComponent><aixm:AirspaceGeometryComponent>
+                      <aixm:theAirspaceVolume><aixm:AirspaceVolume gml:id="V1">
+                        <aixm:upperLimit uom="FL">180</aixm:upperLimit>
+                        <aixm:upperLimitReference>STD</aixm:upperLimitReference>
+                        <aixm:lowerLimit uom="FT">1000</aixm:lowerLimit>
+                        <aixm:lowerLimitReference>MSL</aixm:lowerLimitReference>
+                        <aixm:horizontalProjection><aixm:Surface gml:id="S1" srsName="urn:ogc:def:crs:EPSG::4326">
+                          <gml:patches><gml:PolygonPatch><gml:exterior><gml:Ring><gml:curveMember><gml:Curve gml:id="C1">
+                            <gml:segments>
+                              <gml:GeodesicString><gml:posList>30.0 -97.0 30.0 -96.9</gml:posList></gml:GeodesicString>
+                              <gml:ArcByCenterPoint numArc="1">
+                                <gml:pointProperty><aixm:Point gml:id="P1"><gml:pos>30.0 -96.95</gml:pos></aixm:Point></gml:pointProperty>
+                                <gml:radius uom="NM">2.6</gml:radius>
+                                <gml:startAngle uom="deg">90</gml:startAngle>
+                                <gml:endAngle uom="deg">270</gml:endAngle>
+                              </gml:ArcByCenterPoint>
+                            </gml:segments>
+                          </gml:Curve></gml:curveMember></gml:Ring></gml:exterior></gml:PolygonPatch></gml:patches>
+                        </aixm:Surface></aixm:horizontalProjection>
+                      </aixm:AirspaceVolume></aixm:theAirspaceVolume>
+                    </aixm:AirspaceGeometryComponent></aixm:geometryComponent>
+                  </aixm:AirspaceTimeSlice></aixm:timeSlice>
+                </aixm:Airspace>
+              </message:hasMember>
+              <message:hasMember>
+                <aixm:Airspace gml:id="TFR_4_0011">
+                  <aixm:timeSlice><aixm:AirspaceTimeSlice gml:id="TS2">
+                    <aixm:geometryComponent><aixm:AirspaceGeometryComponent>
+                      <aixm:theAirspaceVolume><aixm:AirspaceVolume gml:id="V2">
+                        <aixm:upperLimit uom="FT">400</aixm:upperLimit>
+                        <aixm:upperLimitReference>SFC</aixm:upperLimitReference>
+                        <aixm:horizontalProjection><aixm:Surface gml:id="S2" srsName="urn:ogc:def:crs:OGC:1.3:CRS84">
+                          <gml:patches><gml:PolygonPatch><gml:exterior><gml:Ring><gml:curveMember><gml:Curve gml:id="C2">
+                            <gml:segments>
+                              <gml:CircleByCenterPoint numArc="1">
+                                <gml:pos>-97.0 30.0</gml:pos>
+                                <gml:radius uom="M">500</gml:radius>
+                              </gml:CircleByCenterPoint>
+                            </gml:segments>
+                          </gml:Curve></gml:curveMember></gml:Ring></gml:exterior></gml:PolygonPatch></gml:patches>
+                        </aixm:Surface></aixm:horizontalProjection>
+                      </aixm:AirspaceVolume></aixm:theAirspaceVolume>
+                    </aixm:AirspaceGeometryComponent></aixm:geometryComponent>
+                  </aixm:AirspaceTimeSlice></aixm:timeSlice>
+                </aixm:Airspace>
+              </message:hasMember>
+            </message:AIXMBasicMessage>"#;
+
+        let tfrs = parse_tfrs(document.as_bytes()).unwrap();
+        let tfrs = tfrs.into_iter().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(tfrs.len(), 2);
+
+        let tfr = &tfrs[0];
+        assert_eq!(tfr.id, "4/0010");
+        assert_eq!(
+            tfr.effective,
+            Some("2024-06-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap())
+        );
+        assert!(tfr.expiry.is_none());
+        let area = &tfr.areas[0];
+        assert_eq!(area.upper.reference, AltitudeReference::Standard);
+        assert_eq!(area.lower.reference, AltitudeReference::Msl);
+        assert!((area.lower.meters - 304.8).abs() < 1e-6);
+
+        // straight line east, then back along the southern half circle
+        let Shape::Boundary(segments) = &area.shape else {
+            panic!("not a boundary: {:?}", area.shape);
+        };
+        assert!(matches!(segments.last(), Some(Segment::Arc(arc)) if arc.clockwise));
+        let center = Coordinates {
+            latitude: 30.0,
+            longitude: -96.95,
+        };
+        assert!(area.shape.contains(&destination(&center, 180.0, 1000.0)));
+        assert!(!area.shape.contains(&destination(&center, 0.0, 1000.0)));
+
+        let tfr = &tfrs[1];
+        assert_eq!(tfr.id, "TFR_4_0011");
+        let area = &tfr.areas[0];
+        assert_eq!(area.lower, AltitudeLimit::SURFACE);
+        assert_eq!(area.upper.reference, AltitudeReference::Agl);
+        assert_eq!(
+            area.shape,
+            Shape::Circle {
+                center: Coordinates {
+                    latitude: 30.0,
+                    longitude: -97.0,
+                },
+                radius_meters: 500.0,
+            }
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_aixm_arc_across_north() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // normalized bearings, the shorter way across north
+        assert!(aixm_clockwise(300.0, 60.0));
+        assert!(!aixm_clockwise(60.0, 300.0));
+        assert!(!aixm_clockwise(10.0, 350.0));
+
+        // bearings beyond a turn carry the direction themselves
+        assert!(aixm_clockwise(300.0, 420.0));
+        assert!(!aixm_clockwise(60.0, -60.0));
+
+        // not across north
+        assert!(aixm_clockwise(90.0, 270.0));
+        assert!(!aixm_clockwise(270.0, 90.0));
+
+        let center = Coordinates {
+            latitude: 30.0,
+            longitude: -97.0,
+        };
+        let arc = Arc {
+            center,
+            radius_meters: 1000.0,
+            start_bearing_degrees: 300.0,
+            end_bearing_degrees: 60.0,
+            clockwise: aixm_clockwise(300.0, 60.0),
+        };
+        let shape = Shape::from_segments(vec![Segment::Line(center), Segment::Arc(arc)]).unwrap();
+        assert!(shape.contains(&destination(&center, 0.0, 900.0)));
+        assert!(!shape.contains(&destination(&center, 180.0, 500.0)));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_invalid_document() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert!(matches!(
+            parse_tfrs(b"<XNOTAM-Update>"),
+            Err(TfrError::Xml(_))
+        ));
+        assert_eq!(
+            parse_tfrs(b"<kml/>").unwrap_err(),
+            TfrError::UnknownFormat("kml".to_string())
+        );
+        assert!(parse_tfrs(b"<XNOTAM-Update/>").unwrap().is_empty());
+
+        ut_info!("Success.");
+    }
+}
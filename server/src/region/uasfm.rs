@@ -0,0 +1,335 @@
+//! Reader for FAA UAS Facility Map (UASFM) grids
+//!
+//! Facility maps divide the airspace around controlled airports into cells
+//!  of 30 by 30 arc seconds, each with the maximum altitude that can be
+//!  authorized through LAANC. The grid is published as CSV, with the center
+//!  of each cell, or as GeoJSON, with the polygon of each cell. Column and
+//!  property names are matched ignoring case.
+
+use crate::region::geojson::{parse_features, Feature, GeoJsonError};
+use crate::region::utils::{bounds_center, AltitudeLimit, AltitudeReference};
+use svc_gis_client_grpc::prelude::gis::Coordinates;
+
+/// Width and height of a grid cell in degrees
+pub const CELL_SIZE_DEGREES: f64 = 30.0 / 3600.0;
+
+/// Unit of the ceiling if the grid does not name one
+const DEFAULT_UNIT: &str = "FT";
+
+/// Byte order mark written by some CSV exports
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Names of the cell identifier
+const PROPERTY_ID: &[&str] = &["OBJECTID", "GLOBALID", "ID"];
+
+/// Names of the ceiling
+const PROPERTY_CEILING: &[&str] = &["CEILING"];
+
+/// Names of the unit of the ceiling
+const PROPERTY_UNIT: &[&str] = &["UNIT", "UOM"];
+
+/// Names of the latitude of the cell center
+const PROPERTY_LATITUDE: &[&str] = &["LATITUDE", "Y"];
+
+/// Names of the longitude of the cell center
+const PROPERTY_LONGITUDE: &[&str] = &["LONGITUDE", "X"];
+
+/// Errors when reading a facility map grid
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum FacilityMapError {
+    /// The CSV header cannot be read
+    #[error("error: Invalid CSV document: {0}")]
+    Csv(String),
+
+    /// A required CSV column is missing
+    #[error("error: Missing column '{0}'.")]
+    MissingColumn(String),
+
+    /// The GeoJSON document cannot be read
+    #[error("{0}")]
+    GeoJson(#[from] GeoJsonError),
+}
+
+/// A single cell of a facility map
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridCell {
+    /// Identifier of the cell
+    pub id: String,
+
+    /// Closed boundary of the cell
+    pub vertices: Vec<Coordinates>,
+
+    /// Maximum height above ground that can be authorized
+    pub ceiling: AltitudeLimit,
+}
+
+/// Parses a facility map grid in CSV or GeoJSON
+///
+/// The format is detected from the content. Returns an error if the
+///  document itself cannot be read, otherwise the result of parsing each
+///  cell in document order.
+pub fn parse_facility_map(data: &[u8]) -> Result<Vec<Result<GridCell, String>>, FacilityMapError> {
+    let data = data.strip_prefix(UTF8_BOM).unwrap_or(data);
+    match data.iter().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'{') => Ok(parse_features(data)?
+            .into_iter()
+            .map(|feature| to_grid_cell(&feature?))
+            .collect()),
+        _ => parse_csv(data),
+    }
+}
+
+/// Parses a CSV grid with one row per cell center
+fn parse_csv(data: &[u8]) -> Result<Vec<Result<GridCell, String>>, FacilityMapError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(data);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| FacilityMapError::Csv(e.to_string()))?
+        .clone();
+
+    let column = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| headers.iter().position(|h| h.eq_ignore_ascii_case(name)))
+    };
+    let required = |names: &[&str]| {
+        column(names).ok_or_else(|| FacilityMapError::MissingColumn(names[0].to_string()))
+    };
+
+    let ceiling = required(PROPERTY_CEILING)?;
+    let latitude = required(PROPERTY_LATITUDE)?;
+    let longitude = required(PROPERTY_LONGITUDE)?;
+    let id = column(PROPERTY_ID);
+    let unit = column(PROPERTY_UNIT);
+
+    Ok(reader
+        .records()
+        .enumerate()
+        .map(|(row, record)| {
+            let record = record.map_err(|e| e.to_string())?;
+            let field = |index: usize| record.get(index).filter(|value| !value.is_empty());
+            let number = |index: usize, name: &str| {
+                let value = field(index).ok_or_else(|| format!("row {}: missing {}", row, name))?;
+                value
+                    .parse::<f64>()
+                    .map_err(|_| format!("row {}: invalid {} '{}'", row, name, value))
+            };
+
+            let center = Coordinates {
+                latitude: number(latitude, "latitude")?,
+                longitude: number(longitude, "longitude")?,
+            };
+
+            let ceiling = field(ceiling).ok_or_else(|| format!("row {}: missing ceiling", row))?;
+            let ceiling = to_ceiling(ceiling, unit.and_then(field))
+                .map_err(|e| format!("row {}: {}", row, e))?;
+
+            let id = id
+                .and_then(field)
+                .map(|id| id.trim_matches(['{', '}']).to_string())
+                .unwrap_or_else(|| center_id(&center));
+
+            Ok(GridCell {
+                id,
+                vertices: cell_vertices(&center).map_err(|e| format!("row {}: {}", row, e))?,
+                ceiling,
+            })
+        })
+        .collect())
+}
+
+/// Converts a GeoJSON feature with the polygon of a cell
+fn to_grid_cell(feature: &Feature) -> Result<GridCell, String> {
+    let ceiling = feature
+        .property_str(PROPERTY_CEILING)
+        .ok_or("missing ceiling")?;
+    let ceiling = to_ceiling(&ceiling, feature.property_str(PROPERTY_UNIT).as_deref())?;
+
+    let vertices = match feature.polygons()?.as_slice() {
+        [vertices] => vertices.clone(),
+        polygons => return Err(format!("expected one polygon, found {}", polygons.len())),
+    };
+
+    let id = match feature.property_str(PROPERTY_ID).or_else(|| feature.id()) {
+        Some(id) => id.trim_matches(['{', '}']).to_string(),
+        None => center_id(&bounds_center(&vertices)),
+    };
+
+    Ok(GridCell {
+        id,
+        vertices,
+        ceiling,
+    })
+}
+
+/// Converts a ceiling in the given unit to a height above ground
+///
+/// Units are `FT`, `M` or their spelled out names, feet if not given.
+fn to_ceiling(value: &str, unit: Option<&str>) -> Result<AltitudeLimit, String> {
+    let ceiling = value
+        .parse::<f64>()
+        .map_err(|_| format!("invalid ceiling '{}'", value))?;
+
+    if !ceiling.is_finite() || ceiling < 0.0 {
+        return Err(format!("invalid ceiling '{}'", value));
+    }
+
+    let unit = unit.unwrap_or(DEFAULT_UNIT).to_uppercase();
+    let unit = match unit.as_str() {
+        "FEET" | "FOOT" => "FT",
+        "METERS" | "METER" | "METRES" | "METRE" => "M",
+        unit => unit,
+    };
+
+    AltitudeLimit::new(ceiling, unit, AltitudeReference::Agl)
+        .ok_or_else(|| format!("unknown unit '{}'", unit))
+}
+
+/// Returns the closed boundary of the cell around the given center
+fn cell_vertices(center: &Coordinates) -> Result<Vec<Coordinates>, String> {
+    if !(-90.0..=90.0).contains(&center.latitude) || !(-180.0..=180.0).contains(&center.longitude) {
+        return Err(format!(
+            "invalid center ({}, {})",
+            center.latitude, center.longitude
+        ));
+    }
+
+    let half = CELL_SIZE_DEGREES / 2.0;
+    let corners = [
+        (-half, -half),
+        (half, -half),
+        (half, half),
+        (-half, half),
+        (-half, -half),
+    ];
+    Ok(corners
+        .iter()
+        .map(|(d_lat, d_lon)| Coordinates {
+            latitude: center.latitude + d_lat,
+            longitude: center.longitude + d_lon,
+        })
+        .collect())
+}
+
+/// Identifies a cell without an identifier by its center
+fn center_id(center: &Coordinates) -> String {
+    format!("{:.5}_{:.5}", center.latitude, center.longitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The facility map grid shipped for tests
+    const CSV_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/us_uasfm.csv");
+
+    #[tokio::test]
+    async fn test_parse_csv() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let data = std::fs::read(CSV_FIXTURE).unwrap();
+        let cells = parse_facility_map(&data).unwrap();
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells.iter().filter(|c| c.is_err()).count(), 2);
+
+        let cell = cells[0].as_ref().unwrap();
+        assert_eq!(cell.id, "1042");
+        assert!((cell.ceiling.meters - 0.0).abs() < 1e-9);
+
+        let cell = cells[1].as_ref().unwrap();
+        assert!((cell.ceiling.meters - 60.96).abs() < 1e-9);
+        assert_eq!(cell.ceiling.to_string(), "200 ft AGL");
+        assert_eq!(cell.vertices.len(), 5);
+        assert_eq!(cell.vertices.first(), cell.vertices.last());
+        assert!(
+            (cell.vertices[2].latitude - cell.vertices[0].latitude - CELL_SIZE_DEGREES).abs()
+                < 1e-9
+        );
+        assert!(
+            (cell.vertices[2].longitude - cell.vertices[0].longitude - CELL_SIZE_DEGREES).abs()
+                < 1e-9
+        );
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_csv_columns() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        // lower case names, ceiling in meters, no identifier
+        let data = b"\xEF\xBB\xBFceiling,uom,y,x\n120,Meters,30.2,-97.7\n";
+        let cells = parse_facility_map(data).unwrap();
+        let cell = cells[0].as_ref().unwrap();
+        assert_eq!(cell.id, "30.20000_-97.70000");
+        assert_eq!(cell.ceiling, AltitudeLimit::agl(120.0));
+
+        assert_eq!(
+            parse_facility_map(b"CEILING,LONGITUDE\n100,-97.7\n").unwrap_err(),
+            FacilityMapError::MissingColumn("LATITUDE".to_string())
+        );
+
+        let cells = parse_facility_map(
+            b"CEILING,UNIT,LATITUDE,LONGITUDE\n100,FL,95,-97.7\n-5,FT,30,-97\n100,NM,30,-97\n",
+        )
+        .unwrap();
+        assert!(cells.iter().all(|cell| cell.is_err()));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_parse_geojson() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let data = br#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[
+                            [-97.675, 30.2], [-97.6667, 30.2], [-97.6667, 30.2083],
+                            [-97.675, 30.2083], [-97.675, 30.2]
+                        ]]
+                    },
+                    "properties": { "OBJECTID": 7, "CEILING": 100, "UNIT": "Feet" }
+                },
+                {
+                    "type": "Feature",
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [[
+                            [-97.675, 30.2], [-97.6667, 30.2], [-97.6667, 30.2083],
+                            [-97.675, 30.2]
+                        ]]
+                    },
+                    "properties": { "UNIT": "Feet" }
+                }
+            ]
+        }"#;
+
+        let cells = parse_facility_map(data).unwrap();
+        assert_eq!(cells.len(), 2);
+        assert!(cells[1].is_err());
+
+        let cell = cells[0].as_ref().unwrap();
+        assert_eq!(cell.id, "7");
+        assert!((cell.ceiling.meters - 30.48).abs() < 1e-9);
+        assert_eq!(cell.vertices.len(), 5);
+
+        assert!(matches!(
+            parse_facility_map(br#"{ "type": "Feature" }"#),
+            Err(FacilityMapError::GeoJson(_))
+        ));
+
+        ut_info!("Success.");
+    }
+}
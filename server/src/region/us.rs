@@ -1,132 +1,512 @@
 //! Region implementation for the United States (US)
 
-use crate::grpc::server::{
-    FlightPlanRequest, FlightPlanResponse, FlightReleaseRequest, FlightReleaseResponse,
-};
-
-use crate::region::RegionInterface;
-use crate::region::RestrictionDetails;
-use lib_common::time::{Duration, Utc};
-use std::collections::HashMap;
+use crate::config::Config;
+use crate::region::arinc424;
+use crate::region::compliance::check_flight_plan;
+use crate::region::nasr::{parse_points, NasrFile};
+use crate::region::notam::load_notams;
+use crate::region::openair::load_openair;
+use crate::region::shape::Shape;
+use crate::region::shapefile::{self, load_layer, FieldMapping};
+use crate::region::source::Source;
+use crate::region::store::Airspace;
+use crate::region::tfr::{parse_tfrs, Tfr};
+use crate::region::uasfm::{parse_facility_map, GridCell};
+use crate::region::utils::AltitudeLimit;
+use crate::region::{load_or_keep, RegionInterface};
+use crate::region::{Decision, FlightPlan, RegionError};
+use crate::region::{RestrictionDetails, RestrictionKind};
+use lib_common::time::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
 use svc_gis_client_grpc::prelude::gis::{Coordinates, ZoneType};
-use tonic::{Request, Response, Status};
 
-impl Default for super::RegionImpl {
+/// Prefix of the identifiers of temporary flight restrictions
+const TFR_ID_PREFIX: &str = "ARROW-USA-TFR-";
+
+/// File extension of the documents in a TFR directory
+const TFR_EXTENSION: &str = "xml";
+
+/// Prefix of the identifiers of UAS Facility Map grid cells
+const UASFM_ID_PREFIX: &str = "ARROW-USA-UASFM-";
+
+/// Prefix of the identifiers of US NOTAMs
+const NOTAM_ID_PREFIX: &str = "ARROW-USA-NOTAM-";
+
+/// Prefix of the identifiers of US OpenAir airspaces
+const OPENAIR_ID_PREFIX: &str = "ARROW-USA-OPENAIR-";
+
+/// ICAO region codes of the US in navigation databases, matched by their start
+const ARINC424_ICAO_CODES: &[&str] = &["K", "PA", "PH"];
+
+/// Prefix of the identifiers of US shapefile zones
+const SHAPEFILE_ID_PREFIX: &str = "ARROW-USA-SHP-";
+
+/// Region implementation for the United States
+#[derive(Debug, Clone)]
+pub struct RegionImpl {
+    /// The implemented region short code
+    pub region: String,
+
+    /// The FAA TFR feed, in XNOTAM or AIXM 5.1
+    pub tfr_source: Option<Source>,
+
+    /// The FAA UAS Facility Map grid, in CSV or GeoJSON
+    pub uasfm_source: Option<Source>,
+
+    /// Directory of the FAA NASR subscription
+    pub nasr_path: Option<PathBuf>,
+
+    /// The US NOTAMs, in the ICAO format
+    pub notam_source: Option<Source>,
+
+    /// US airspace, in the OpenAir format
+    pub openair_source: Option<Source>,
+
+    /// Navigation database, in ARINC 424
+    pub arinc424_source: Option<Source>,
+
+    /// Zone layer, as an Esri Shapefile
+    pub zones_shapefile: Option<PathBuf>,
+
+    /// Columns of the zone layer
+    pub zones_fields: FieldMapping,
+
+    /// Waypoint layer, as an Esri Shapefile
+    pub waypoints_shapefile: Option<PathBuf>,
+
+    /// Columns of the waypoint layer
+    pub waypoints_fields: FieldMapping,
+}
+
+impl Default for RegionImpl {
     fn default() -> Self {
         Self {
             region: String::from("us"),
+            tfr_source: None,
+            uasfm_source: None,
+            nasr_path: None,
+            notam_source: None,
+            openair_source: None,
+            arinc424_source: None,
+            zones_shapefile: None,
+            zones_fields: FieldMapping::default(),
+            waypoints_shapefile: None,
+            waypoints_fields: FieldMapping::waypoints(),
         }
     }
 }
 
-/// Processes for submission to the US authorities
-#[tonic::async_trait]
-impl RegionInterface for super::RegionImpl {
-    fn get_region(&self) -> &str {
-        &self.region
+impl RegionImpl {
+    /// Creates the region implementation from the service configuration
+    pub fn new(config: &Config) -> Result<Self, RegionError> {
+        let parse = |source: &Option<String>| match source {
+            Some(source) => source
+                .parse::<Source>()
+                .map(Some)
+                .map_err(|e| RegionError::InvalidConfiguration(e.to_string())),
+            None => Ok(None),
+        };
+
+        let fields = |defaults: FieldMapping, overrides: &Option<String>| match overrides {
+            Some(overrides) => defaults
+                .with_overrides(overrides)
+                .map_err(RegionError::InvalidConfiguration),
+            None => Ok(defaults),
+        };
+
+        Ok(Self {
+            tfr_source: parse(&config.us_tfr_source)?,
+            uasfm_source: parse(&config.us_uasfm_source)?,
+            nasr_path: config.us_nasr_path.as_ref().map(PathBuf::from),
+            notam_source: parse(&config.us_notam_source)?,
+            openair_source: parse(&config.us_openair_source)?,
+            arinc424_source: parse(&config.us_arinc424_source)?,
+            zones_shapefile: config.us_zones_shapefile.as_ref().map(PathBuf::from),
+            zones_fields: fields(FieldMapping::default(), &config.us_zones_shapefile_fields)?,
+            waypoints_shapefile: config.us_waypoints_shapefile.as_ref().map(PathBuf::from),
+            waypoints_fields: fields(
+                FieldMapping::waypoints(),
+                &config.us_waypoints_shapefile_fields,
+            )?,
+            ..Default::default()
+        })
     }
+}
 
-    fn submit_flight_plan(
-        &self,
-        request: FlightPlanRequest,
-    ) -> Result<Response<FlightPlanResponse>, Status> {
-        region_info!("[us] entry.");
-        // TODO(R5) implement
-        let flight_plan_id = request.flight_plan_id;
-        Ok(Response::new(FlightPlanResponse {
-            flight_plan_id,
-            submitted: true,
-            result: None,
-        }))
+/// Reads the TFRs from the source, skipping malformed and expired TFRs
+async fn load_tfrs(
+    source: &Source,
+    now: DateTime<Utc>,
+) -> Result<HashMap<String, RestrictionDetails>, RegionError> {
+    let documents = source
+        .read_all(TFR_EXTENSION)
+        .await
+        .map_err(|e| RegionError::DatasetFailure(e.to_string()))?;
+
+    let entries = documents
+        .into_iter()
+        .flat_map(|(name, data)| match parse_tfrs(&data) {
+            Ok(tfrs) => tfrs
+                .into_iter()
+                .map(|tfr| match tfr {
+                    Ok(tfr) if tfr.expiry.is_some_and(|expiry| expiry <= now) => {
+                        region_debug!("[us] Skipping expired TFR {} in {}.", tfr.id, name);
+                        Ok(vec![])
+                    }
+                    Ok(tfr) => Ok(to_restrictions(&tfr)),
+                    Err(e) => Err(format!("{} in {}", e, name)),
+                })
+                .collect::<Vec<_>>(),
+            Err(e) => vec![Err(format!("document {}: {}", name, e))],
+        });
+
+    Ok(collect_restrictions(entries, "TFR", source))
+}
+
+/// Converts a TFR to restrictions, one per area
+fn to_restrictions(tfr: &Tfr) -> Vec<(String, RestrictionDetails)> {
+    let id = tfr.id.replace(['/', ' '], "-");
+    tfr.areas
+        .iter()
+        .enumerate()
+        .map(|(i, area)| {
+            let label = match tfr.areas.len() {
+                1 => format!("{}{}", TFR_ID_PREFIX, id),
+                _ => format!("{}{}-{}", TFR_ID_PREFIX, id, i + 1),
+            };
+
+            let details = RestrictionDetails {
+                shape: area.shape.clone(),
+                timestamp_start: tfr.effective,
+                timestamp_end: tfr.expiry,
+                schedule: None,
+                lower: area.lower,
+                upper: area.upper,
+                zone_type: ZoneType::Restriction,
+                kind: RestrictionKind::NoFly,
+            };
+
+            (label, details)
+        })
+        .collect()
+}
+
+/// Reads the UAS Facility Map grid from the source, skipping malformed cells
+async fn load_facility_map(
+    source: &Source,
+) -> Result<HashMap<String, RestrictionDetails>, RegionError> {
+    let data = source
+        .read()
+        .await
+        .map_err(|e| RegionError::DatasetFailure(e.to_string()))?;
+
+    let cells =
+        parse_facility_map(&data).map_err(|e| RegionError::DatasetFailure(e.to_string()))?;
+    let entries = cells
+        .into_iter()
+        .map(|cell| cell.map(|cell| vec![to_ceiling_restriction(cell)]));
+
+    Ok(collect_restrictions(entries, "facility map", source))
+}
+
+/// Converts a facility map cell to a restriction limited to its ceiling
+fn to_ceiling_restriction(cell: GridCell) -> (String, RestrictionDetails) {
+    let details = RestrictionDetails {
+        shape: Shape::Polygon(cell.vertices),
+        timestamp_start: None,
+        timestamp_end: None,
+        schedule: None,
+        lower: AltitudeLimit::SURFACE,
+        upper: cell.ceiling,
+        zone_type: ZoneType::Restriction,
+        kind: RestrictionKind::Ceiling,
+    };
+
+    (format!("{}{}", UASFM_ID_PREFIX, cell.id), details)
+}
+
+/// Reads the fixes and navaids of the NASR subscription, skipping
+///  malformed records
+///
+/// The first fix or navaid with an identifier is kept, fixes are read
+///  before navaids.
+async fn load_waypoints(path: &Path) -> Result<HashMap<String, Coordinates>, RegionError> {
+    let mut waypoints = HashMap::new();
+    let mut skipped = 0;
+    for file in [NasrFile::Fix, NasrFile::Nav] {
+        let source = Source::File(path.join(file.file_name()));
+        let data = source
+            .read()
+            .await
+            .map_err(|e| RegionError::DatasetFailure(e.to_string()))?;
+
+        for point in parse_points(&data, file) {
+            match point {
+                Ok(point) if waypoints.contains_key(&point.id) => {
+                    region_debug!(
+                        "[us] Skipping duplicate waypoint {} in {}.",
+                        point.id,
+                        source
+                    );
+                }
+                Ok(point) => {
+                    waypoints.insert(point.id, point.coordinates);
+                }
+                Err(e) => {
+                    region_warn!("[us] Skipping malformed record in {}: {}", source, e);
+                    skipped += 1;
+                }
+            }
+        }
     }
 
-    fn request_flight_release(
-        &self,
-        request: Request<FlightReleaseRequest>,
-    ) -> Result<Response<FlightReleaseResponse>, Status> {
-        region_info!("[us] entry.");
-        // TODO(R5) implement
-        let flight_plan_id = request.into_inner().flight_plan_id;
-        Ok(Response::new(FlightReleaseResponse {
-            flight_plan_id,
-            released: true,
-            result: None,
-        }))
+    region_info!(
+        "[us] Loaded {} waypoints from {}, skipped {} malformed.",
+        waypoints.len(),
+        path.display(),
+        skipped
+    );
+
+    Ok(waypoints)
+}
+
+/// Reads the zones of a shapefile layer, skipping malformed records
+///
+/// Zones sharing an identifier are numbered from the second one on.
+async fn load_shapefile_zones(
+    path: &Path,
+    fields: &FieldMapping,
+) -> Result<HashMap<String, RestrictionDetails>, RegionError> {
+    let records = load_layer(path)
+        .await
+        .map_err(|e| RegionError::DatasetFailure(e.to_string()))?;
+
+    let mut labels = HashSet::new();
+    let entries = records.into_iter().map(|record| {
+        let zones = record
+            .and_then(|record| shapefile::to_restrictions(&record, fields, SHAPEFILE_ID_PREFIX))?;
+        let zones = zones
+            .into_iter()
+            .map(|(id, details)| {
+                let mut label = id.clone();
+                let mut count = 1;
+                while !labels.insert(label.clone()) {
+                    count += 1;
+                    label = format!("{}-{}", id, count);
+                }
+                (label, details)
+            })
+            .collect();
+
+        Ok(zones)
+    });
+
+    Ok(collect_restrictions(
+        entries,
+        "shapefile zone",
+        &path.display(),
+    ))
+}
+
+/// Collects the restrictions of a dataset, skipping malformed entries
+///
+/// Each entry holds the restrictions read from one record of the dataset.
+fn collect_restrictions(
+    entries: impl IntoIterator<Item = Result<Vec<(String, RestrictionDetails)>, String>>,
+    dataset: &str,
+    source: &dyn Display,
+) -> HashMap<String, RestrictionDetails> {
+    let mut restrictions = HashMap::new();
+    let mut skipped = 0;
+    for entry in entries {
+        match entry {
+            Ok(entry) => restrictions.extend(entry),
+            Err(e) => {
+                region_warn!("[us] Skipping malformed {} in {}: {}", dataset, source, e);
+                skipped += 1;
+            }
+        }
     }
 
-    async fn acquire_restrictions(&self, restrictions: &mut HashMap<String, RestrictionDetails>) {
-        //
-        // TODO(R5): This is currently hardcoded. This should be replaced with a call to
-        //  an API.
-        //
-        let mut from_remote: HashMap<String, RestrictionDetails> = HashMap::new();
+    region_info!(
+        "[us] Loaded {} {} restrictions from {}, skipped {} malformed.",
+        restrictions.len(),
+        dataset,
+        source,
+        skipped
+    );
 
-        let vertices = vec![
-            (30.9310, -104.0424),
-            (30.9316, -104.0399),
-            (30.9301, -104.039),
-            (30.9299, -104.0405),
-            (30.9310, -104.0424),
-        ];
+    restrictions
+}
 
-        let Some(delta) = Duration::try_hours(1) else {
-            region_error!("Failed to create duration.");
-            return;
-        };
+/// Reads the waypoints of a shapefile layer, skipping malformed records
+async fn load_shapefile_waypoints(
+    path: &Path,
+    fields: &FieldMapping,
+) -> Result<HashMap<String, Coordinates>, RegionError> {
+    let records = load_layer(path)
+        .await
+        .map_err(|e| RegionError::DatasetFailure(e.to_string()))?;
+
+    let mut waypoints = HashMap::new();
+    let mut skipped = 0;
+    for record in records {
+        match record.and_then(|record| shapefile::to_waypoint(&record, fields)) {
+            Ok((id, coordinates)) => {
+                waypoints.entry(id).or_insert(coordinates);
+            }
+            Err(e) => {
+                region_warn!(
+                    "[us] Skipping malformed waypoint in {}: {}",
+                    path.display(),
+                    e
+                );
+                skipped += 1;
+            }
+        }
+    }
 
-        from_remote.insert(
-            "ARROW-USA-TFR-ZONE".to_string(),
-            RestrictionDetails {
-                vertices: vertices
-                    .into_iter()
-                    .map(|(latitude, longitude)| Coordinates {
-                        latitude,
-                        longitude,
-                    })
-                    .collect(),
-                timestamp_end: Some(Utc::now() + delta),
-                timestamp_start: Some(Utc::now()),
-                altitude_meters_min: 0.0,
-                altitude_meters_max: 2000.0,
-                zone_type: ZoneType::Restriction,
-            },
-        );
+    region_info!(
+        "[us] Loaded {} shapefile waypoints from {}, skipped {} malformed.",
+        waypoints.len(),
+        path.display(),
+        skipped
+    );
+
+    Ok(waypoints)
+}
+
+/// Processes for submission to the US authorities
+#[tonic::async_trait]
+impl RegionInterface for RegionImpl {
+    fn get_region(&self) -> &str {
+        &self.region
+    }
 
-        let vertices = vec![
-            (30.9321, -104.0471),
-            (30.9313, -104.0428),
-            (30.9316, -104.042),
-            (30.9332, -104.0399),
-            (30.9319, -104.0398),
-            (30.9326, -104.0374),
-            (30.9352, -104.0394),
-            (30.9341, -104.0465),
-            (30.9321, -104.0471),
+    fn get_boundaries(&self) -> Vec<Vec<Coordinates>> {
+        // Coarse bounding boxes of the contiguous US, Alaska and Hawaii
+        //  as (south, west, north, east)
+        let boxes: Vec<(f64, f64, f64, f64)> = vec![
+            (24.3, -125.0, 49.5, -66.8),
+            (51.0, -180.0, 71.5, -129.9),
+            (18.8, -160.5, 22.4, -154.7),
         ];
 
-        from_remote.insert(
-            "ARROW-USA-NOFLY-ZONE".to_string(),
-            RestrictionDetails {
-                vertices: vertices
+        boxes
+            .into_iter()
+            .map(|(south, west, north, east)| {
+                vec![(south, west), (north, west), (north, east), (south, east)]
                     .into_iter()
                     .map(|(latitude, longitude)| Coordinates {
                         latitude,
                         longitude,
                     })
-                    .collect(),
-                // altitude_meters_min: 0,
-                // altitude_meters_max: 6000,
-                timestamp_end: None,
-                timestamp_start: None,
-                altitude_meters_min: 0.0,
-                altitude_meters_max: 200.0,
-                zone_type: ZoneType::Restriction,
-            },
-        );
+                    .collect()
+            })
+            .collect()
+    }
+
+    async fn submit_flight_plan(
+        &self,
+        flight_plan: &FlightPlan,
+        airspace: &Airspace,
+    ) -> Result<Decision, RegionError> {
+        region_info!("[us] entry.");
+        region_debug!("[us] flight plan: {}", flight_plan.flight_plan_id);
+        // TODO(R5) submit accepted flight plans to the authority
+        Ok(check_flight_plan(flight_plan, airspace))
+    }
+
+    async fn request_flight_release(
+        &self,
+        flight_plan: &FlightPlan,
+        airspace: &Airspace,
+    ) -> Result<Decision, RegionError> {
+        region_info!("[us] entry.");
+        region_debug!("[us] flight plan: {}", flight_plan.flight_plan_id);
+        // TODO(R5) request the release from the authority
+        // Restrictions published since the acceptance, such as a new TFR, are caught here
+        Ok(check_flight_plan(flight_plan, airspace))
+    }
+
+    async fn cancel_flight_plan(&self, flight_plan: &FlightPlan) -> Result<Decision, RegionError> {
+        region_info!("[us] entry.");
+        region_debug!("[us] flight plan: {}", flight_plan.flight_plan_id);
+        // TODO(R5) forward the cancellation to the authority
+        Ok(Decision {
+            accepted: true,
+            result: None,
+            violations: vec![],
+        })
+    }
 
-        //
-        // END HARDCODE
-        //
+    async fn amend_flight_plan(
+        &self,
+        flight_plan: &FlightPlan,
+        airspace: &Airspace,
+    ) -> Result<Decision, RegionError> {
+        region_info!("[us] entry.");
+        region_debug!("[us] flight plan: {}", flight_plan.flight_plan_id);
+        // TODO(R5) submit accepted amendments to the authority
+        Ok(check_flight_plan(flight_plan, airspace))
+    }
+
+    async fn acquire_restrictions(&self, restrictions: &mut HashMap<String, RestrictionDetails>) {
+        let mut from_remote: HashMap<String, RestrictionDetails> = HashMap::new();
+        let now = Utc::now();
+        let boundaries = self.get_boundaries();
+
+        load_or_keep(
+            "TFR",
+            self.tfr_source.as_ref(),
+            |source| load_tfrs(source, now),
+            TFR_ID_PREFIX,
+            restrictions,
+            &mut from_remote,
+        )
+        .await;
+
+        load_or_keep(
+            "facility map",
+            self.uasfm_source.as_ref(),
+            load_facility_map,
+            UASFM_ID_PREFIX,
+            restrictions,
+            &mut from_remote,
+        )
+        .await;
+
+        load_or_keep(
+            "US NOTAM",
+            self.notam_source.as_ref(),
+            |source| load_notams(source, NOTAM_ID_PREFIX, &boundaries, now),
+            NOTAM_ID_PREFIX,
+            restrictions,
+            &mut from_remote,
+        )
+        .await;
+
+        load_or_keep(
+            "US OpenAir",
+            self.openair_source.as_ref(),
+            |source| load_openair(source, OPENAIR_ID_PREFIX),
+            OPENAIR_ID_PREFIX,
+            restrictions,
+            &mut from_remote,
+        )
+        .await;
+
+        load_or_keep(
+            "shapefile zone",
+            self.zones_shapefile.as_deref(),
+            |path| load_shapefile_zones(path, &self.zones_fields),
+            SHAPEFILE_ID_PREFIX,
+            restrictions,
+            &mut from_remote,
+        )
+        .await;
 
         restrictions.retain(|k, _| from_remote.contains_key(k));
         for (label, details) in from_remote.into_iter() {
@@ -135,35 +515,49 @@ impl RegionInterface for super::RegionImpl {
     }
 
     async fn acquire_waypoints(&self, waypoints: &mut HashMap<String, Coordinates>) {
-        //
-        // TODO(R5): This is currently hardcoded. This should be replaced with a call to an API
-        //
-
-        // West TX
-        let from_remote: Vec<(f64, f64)> = vec![
-            // Ideal waypoint around hardcoded flight restriction
-            (30.9311, -104.0428),
-            // waypoint within the hardcoded flight restriction
-            (30.9308, -104.0412),
-        ];
+        let mut from_remote = match &self.nasr_path {
+            Some(path) => match load_waypoints(path).await {
+                Ok(from_remote) => from_remote,
+                Err(e) => {
+                    region_error!("[us] Keeping current waypoints: {}", e);
+                    return;
+                }
+            },
+            None => {
+                region_warn!("[us] No NASR subscription configured.");
+                HashMap::new()
+            }
+        };
 
-        let from_remote: HashMap<String, Coordinates> = from_remote
-            .into_iter()
-            .enumerate()
-            .map(|(i, (longitude, latitude))| {
-                (
-                    format!("ARROW-WEG-{}", i),
-                    Coordinates {
-                        latitude,
-                        longitude,
-                    },
-                )
-            })
-            .collect();
+        // the NASR subscription takes precedence over the navigation database
+        if let Some(source) = &self.arinc424_source {
+            match arinc424::load_waypoints(source, ARINC424_ICAO_CODES).await {
+                Ok(points) => {
+                    for (id, coordinates) in points {
+                        from_remote.entry(id).or_insert(coordinates);
+                    }
+                }
+                Err(e) => {
+                    region_error!("[us] Keeping current waypoints: {}", e);
+                    return;
+                }
+            }
+        }
 
-        //
-        // END HARDCODE
-        //
+        // and both over the shapefile layer
+        if let Some(path) = &self.waypoints_shapefile {
+            match load_shapefile_waypoints(path, &self.waypoints_fields).await {
+                Ok(points) => {
+                    for (id, coordinates) in points {
+                        from_remote.entry(id).or_insert(coordinates);
+                    }
+                }
+                Err(e) => {
+                    region_error!("[us] Keeping current waypoints: {}", e);
+                    return;
+                }
+            }
+        }
 
         waypoints.retain(|k, _| from_remote.contains_key(k));
         for (label, details) in from_remote.into_iter() {
@@ -175,7 +569,44 @@ impl RegionInterface for super::RegionImpl {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::region::RegionImpl;
+    use crate::region::utils::AltitudeReference;
+
+    /// The FAA TFRs shipped for tests
+    const TFR_FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/us_tfr");
+
+    /// The FAA UAS Facility Map grid shipped for tests
+    const UASFM_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/us_uasfm.csv");
+
+    /// The FAA NASR subscription shipped for tests
+    const NASR_FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/us_nasr");
+
+    /// The navigation database shipped for tests
+    const ARINC424_FIXTURE: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/arinc424.dat");
+
+    /// The shapefile layers shipped for tests
+    const SHAPEFILE_FIXTURES: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/us_shapefile");
+
+    fn get_region_impl() -> RegionImpl {
+        RegionImpl {
+            tfr_source: Some(Source::File(TFR_FIXTURES.into())),
+            uasfm_source: Some(Source::File(UASFM_FIXTURE.into())),
+            nasr_path: Some(NASR_FIXTURES.into()),
+            arinc424_source: Some(Source::File(ARINC424_FIXTURE.into())),
+            zones_shapefile: Some(format!("{}/sua.shp", SHAPEFILE_FIXTURES).into()),
+            waypoints_shapefile: Some(format!("{}/fixes.shp", SHAPEFILE_FIXTURES).into()),
+            ..Default::default()
+        }
+    }
+
+    /// Airspace without restrictions, as loaded from empty sources
+    fn loaded_airspace() -> Airspace {
+        Airspace {
+            loaded: true,
+            ..Default::default()
+        }
+    }
 
     #[tokio::test]
     async fn test_region_code() {
@@ -194,15 +625,14 @@ mod tests {
         ut_info!("[us] Start.");
 
         let region = RegionImpl::default();
-        let result = region.submit_flight_plan(FlightPlanRequest {
-            flight_plan_id: "".to_string(),
-            data: "".to_string(),
-        });
+        let result = region
+            .submit_flight_plan(&FlightPlan::default(), &loaded_airspace())
+            .await;
 
         assert!(result.is_ok());
-        let result: FlightPlanResponse = result.unwrap().into_inner();
+        let result: Decision = result.unwrap();
         ut_debug!("[us] Result: {:?}", result);
-        assert_eq!(result.submitted, true);
+        assert!(result.accepted);
 
         ut_info!("[us] Success.");
     }
@@ -213,29 +643,301 @@ mod tests {
         ut_info!("[us] Start.");
 
         let region = RegionImpl::default();
-        let result = region.request_flight_release(tonic::Request::new(FlightReleaseRequest {
-            flight_plan_id: "".to_string(),
-            data: "".to_string(),
-        }));
+        let result = region
+            .request_flight_release(&FlightPlan::default(), &loaded_airspace())
+            .await;
 
         assert!(result.is_ok());
-        let result: FlightReleaseResponse = result.unwrap().into_inner();
+        let result: Decision = result.unwrap();
         ut_debug!("[us] Result: {:?}", result);
-        assert_eq!(result.released, true);
+        assert!(result.accepted);
+
+        // a restriction published after the acceptance denies the release
+        let region = get_region_impl();
+        let mut restrictions = HashMap::new();
+        region.acquire_restrictions(&mut restrictions).await;
+        let airspace = Airspace {
+            restrictions: std::sync::Arc::new(restrictions),
+            loaded: true,
+            ..Default::default()
+        };
+        let flight_plan = FlightPlan {
+            route: vec![crate::region::utils::RoutePoint {
+                latitude: 30.2041667,
+                longitude: -97.6708333,
+                altitude_meters: Some(90.0),
+                timestamp: None,
+            }],
+            ..Default::default()
+        };
+        let result = region
+            .request_flight_release(&flight_plan, &airspace)
+            .await
+            .unwrap();
+        assert!(!result.accepted);
 
         ut_info!("[us] Success.");
     }
 
     #[tokio::test]
-    async fn test_acquire_restrictions() {
+    async fn test_cancel_flight_plan() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[us] Start.");
+
+        let region = RegionImpl::default();
+        let result = region.cancel_flight_plan(&FlightPlan::default()).await;
+
+        assert!(result.is_ok());
+        let result: Decision = result.unwrap();
+        ut_debug!("[us] Result: {:?}", result);
+        assert!(result.accepted);
+
+        ut_info!("[us] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_amend_flight_plan() {
         lib_common::logger::get_log_handle().await;
         ut_info!("[us] Start.");
 
         let region = RegionImpl::default();
+        let result = region
+            .amend_flight_plan(&FlightPlan::default(), &loaded_airspace())
+            .await;
+
+        assert!(result.is_ok());
+        let result: Decision = result.unwrap();
+        ut_debug!("[us] Result: {:?}", result);
+        assert!(result.accepted);
+
+        ut_info!("[us] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_acquire_restrictions() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[us] Start.");
+
+        let region = get_region_impl();
         let mut cache = HashMap::<String, RestrictionDetails>::new();
         region.acquire_restrictions(&mut cache).await;
         ut_debug!("[us] Cache content: {:?}", cache);
-        assert!(cache.keys().len() > 0);
+
+        let mut labels = cache
+            .keys()
+            .filter(|k| !k.starts_with(UASFM_ID_PREFIX))
+            .cloned()
+            .collect::<Vec<_>>();
+        labels.sort();
+        assert_eq!(
+            labels,
+            vec![
+                "ARROW-USA-SHP-MOA-1",
+                "ARROW-USA-SHP-MOA-2",
+                "ARROW-USA-SHP-R-6302A",
+                "ARROW-USA-SHP-Range-3",
+                "ARROW-USA-TFR-4-0811",
+                "ARROW-USA-TFR-4-2155",
+                "ARROW-USA-TFR-4-7301-1",
+                "ARROW-USA-TFR-4-7301-2",
+            ]
+        );
+        assert_eq!(cache.keys().len(), 12);
+
+        // The current TFRs, cells and zones are kept if the sources cannot be read
+        let region = RegionImpl {
+            tfr_source: Some(Source::File("/nonexistent".into())),
+            uasfm_source: Some(Source::File("/nonexistent".into())),
+            zones_shapefile: Some("/nonexistent.shp".into()),
+            ..Default::default()
+        };
+        region.acquire_restrictions(&mut cache).await;
+        assert_eq!(cache.keys().len(), 12);
+
+        // Without a feed no restrictions remain
+        let region = RegionImpl::default();
+        region.acquire_restrictions(&mut cache).await;
+        assert!(cache.is_empty());
+
+        ut_info!("[us] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_new() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[us] Start.");
+
+        let mut config = Config::new();
+        let region = RegionImpl::new(&config).unwrap();
+        assert!(region.tfr_source.is_none());
+
+        config.us_tfr_source = Some("http://tfr.example.com/tfrs.xml".to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert!(matches!(region.tfr_source, Some(Source::Url(_))));
+
+        config.us_tfr_source = Some(" ".to_string());
+        let error = RegionImpl::new(&config).unwrap_err();
+        assert!(matches!(error, RegionError::InvalidConfiguration(_)));
+
+        config.us_tfr_source = None;
+        config.us_uasfm_source = Some("/data/uasfm.csv".to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert!(matches!(region.uasfm_source, Some(Source::File(_))));
+
+        config.us_uasfm_source = Some("https://uasfm.example.com/uasfm.csv".to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert!(matches!(region.uasfm_source, Some(Source::Url(_))));
+
+        config.us_uasfm_source = Some("ftp://uasfm.example.com/uasfm.csv".to_string());
+        let error = RegionImpl::new(&config).unwrap_err();
+        assert!(matches!(error, RegionError::InvalidConfiguration(_)));
+
+        config.us_uasfm_source = None;
+        config.us_nasr_path = Some("/data/nasr".to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert_eq!(region.nasr_path, Some(PathBuf::from("/data/nasr")));
+
+        config.us_notam_source = Some("http://notam.example.com/us.txt".to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert!(matches!(region.notam_source, Some(Source::Url(_))));
+
+        config.us_openair_source = Some("http://openair.example.com/us.txt".to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert!(matches!(region.openair_source, Some(Source::Url(_))));
+
+        config.us_arinc424_source = Some("http://navdata.example.com/usa.pc".to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert!(matches!(region.arinc424_source, Some(Source::Url(_))));
+
+        config.us_zones_shapefile = Some("/data/sua.shp".to_string());
+        config.us_zones_shapefile_fields = Some("id=DESIGNATOR".to_string());
+        let region = RegionImpl::new(&config).unwrap();
+        assert_eq!(region.zones_shapefile, Some(PathBuf::from("/data/sua.shp")));
+        assert_eq!(region.zones_fields.id, "DESIGNATOR");
+        assert_eq!(region.waypoints_fields.id, "IDENT");
+
+        config.us_waypoints_shapefile_fields = Some("bogus".to_string());
+        let error = RegionImpl::new(&config).unwrap_err();
+        assert!(matches!(error, RegionError::InvalidConfiguration(_)));
+
+        ut_info!("[us] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_load_tfrs() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[us] Start.");
+
+        let source = Source::File(TFR_FIXTURES.into());
+
+        // Before the VIP movement TFR expires
+        let now = "2024-06-01T15:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let tfrs = load_tfrs(&source, now).await.unwrap();
+        assert_eq!(tfrs.len(), 5);
+
+        let details = tfrs.get("ARROW-USA-TFR-4-6702").unwrap();
+        assert_eq!(
+            details.timestamp_start,
+            Some("2024-06-01T12:00:00Z".parse().unwrap())
+        );
+        assert_eq!(
+            details.timestamp_end,
+            Some("2024-06-02T00:00:00Z".parse().unwrap())
+        );
+        assert_eq!(details.lower.to_string(), "SFC");
+        assert!((details.upper.meters - 5486.4).abs() < 1e-3);
+
+        let details = tfrs.get("ARROW-USA-TFR-4-2155").unwrap();
+        assert!(details.timestamp_end.is_none());
+        assert!((details.upper.meters - 914.4).abs() < 1e-3);
+        assert!(matches!(details.shape, Shape::Circle { .. }));
+
+        // After it expired
+        let now = "2024-06-02T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let tfrs = load_tfrs(&source, now).await.unwrap();
+        assert_eq!(tfrs.len(), 4);
+        assert!(!tfrs.contains_key("ARROW-USA-TFR-4-6702"));
+
+        ut_info!("[us] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_load_facility_map() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[us] Start.");
+
+        let source = Source::File(UASFM_FIXTURE.into());
+        let cells = load_facility_map(&source).await.unwrap();
+        assert_eq!(cells.len(), 4);
+
+        let details = cells.get("ARROW-USA-UASFM-1043").unwrap();
+        assert_eq!(details.kind, RestrictionKind::Ceiling);
+        assert_eq!(details.zone_type, ZoneType::Restriction);
+        assert_eq!(details.lower, AltitudeLimit::SURFACE);
+        assert!((details.upper.meters - 60.96).abs() < 1e-3);
+        assert_eq!(details.upper.reference, AltitudeReference::Agl);
+        assert!(details.timestamp_start.is_none());
+        assert!(details.timestamp_end.is_none());
+
+        // A flight below the ceiling is accepted, above it rejected
+        let airspace = Airspace {
+            restrictions: std::sync::Arc::new(cells),
+            loaded: true,
+            ..Default::default()
+        };
+        let route_point = |altitude_meters| crate::region::utils::RoutePoint {
+            latitude: 30.2041667,
+            longitude: -97.6708333,
+            altitude_meters: Some(altitude_meters),
+            timestamp: None,
+        };
+        let mut flight_plan = FlightPlan {
+            route: vec![route_point(50.0)],
+            ..Default::default()
+        };
+        assert!(check_flight_plan(&flight_plan, &airspace).accepted);
+
+        flight_plan.route = vec![route_point(90.0)];
+        let decision = check_flight_plan(&flight_plan, &airspace);
+        assert!(!decision.accepted);
+        assert_eq!(
+            decision.violations[0].feature_id,
+            Some("ARROW-USA-UASFM-1043".to_string())
+        );
+
+        let source = Source::File("/nonexistent".into());
+        let error = load_facility_map(&source).await.unwrap_err();
+        assert!(matches!(error, RegionError::DatasetFailure(_)));
+
+        ut_info!("[us] Success.");
+    }
+
+    #[tokio::test]
+    async fn test_load_tfrs_skips_malformed() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("[us] Start.");
+
+        let dir = std::env::temp_dir().join(format!("svc-compliance-us-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(
+            format!("{}/detail_4_2155.xml", TFR_FIXTURES),
+            dir.join("detail_4_2155.xml"),
+        )
+        .unwrap();
+        std::fs::write(dir.join("truncated.xml"), "<XNOTAM-Update><Group>").unwrap();
+        std::fs::write(
+            dir.join("no_area.xml"),
+            "<XNOTAM-Update><Group><Add><Not><NotUid><txtLocalName>4/1</txtLocalName></NotUid></Not></Add></Group></XNOTAM-Update>",
+        )
+        .unwrap();
+
+        let tfrs = load_tfrs(&Source::File(dir.clone()), Utc::now())
+            .await
+            .unwrap();
+        let labels = tfrs.keys().collect::<Vec<_>>();
+        assert_eq!(labels, vec!["ARROW-USA-TFR-4-2155"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
 
         ut_info!("[us] Success.");
     }
@@ -245,10 +947,58 @@ mod tests {
         lib_common::logger::get_log_handle().await;
         ut_info!("[us] Start.");
 
-        let region = RegionImpl::default();
+        let region = get_region_impl();
         let mut cache = HashMap::<String, Coordinates>::new();
         region.acquire_waypoints(&mut cache).await;
-        assert!(cache.keys().len() > 0);
+
+        let mut labels = cache.keys().cloned().collect::<Vec<_>>();
+        labels.sort();
+        assert_eq!(
+            labels,
+            vec![
+                "AU", "BLEWE", "BSM", "CF17R", "CREPO", "CWK", "DUBLN", "GRK", "HOOKK", "KAVVA",
+                "ZOOMM"
+            ]
+        );
+
+        // the VOR/DME takes precedence over the later NDB of the same name
+        let bsm = cache.get("BSM").unwrap();
+        assert!((bsm.latitude - 30.211633).abs() < 1e-6);
+        assert!((bsm.longitude + 97.669800).abs() < 1e-6);
+
+        // The current waypoints are kept if the subscription cannot be read
+        let region = RegionImpl {
+            nasr_path: Some("/nonexistent".into()),
+            ..Default::default()
+        };
+        region.acquire_waypoints(&mut cache).await;
+        assert_eq!(cache.keys().len(), 11);
+
+        // The same for the navigation database
+        let region = RegionImpl {
+            arinc424_source: Some(Source::File("/nonexistent".into())),
+            ..Default::default()
+        };
+        region.acquire_waypoints(&mut cache).await;
+        assert_eq!(cache.keys().len(), 11);
+
+        // The shapefile layer alone is enough, reprojected from UTM
+        let region = RegionImpl {
+            waypoints_shapefile: Some(format!("{}/fixes.shp", SHAPEFILE_FIXTURES).into()),
+            ..Default::default()
+        };
+        region.acquire_waypoints(&mut cache).await;
+        let mut labels = cache.keys().cloned().collect::<Vec<_>>();
+        labels.sort();
+        assert_eq!(labels, vec!["BLEWE", "ZOOMM"]);
+        let zoomm = cache.get("ZOOMM").unwrap();
+        assert!((zoomm.latitude - 30.378775).abs() < 1e-6);
+        assert!((zoomm.longitude + 97.530064).abs() < 1e-6);
+
+        // Without a subscription there are no waypoints
+        let region = RegionImpl::default();
+        region.acquire_waypoints(&mut cache).await;
+        assert!(cache.is_empty());
 
         ut_info!("[us] Success.");
     }
@@ -1,6 +1,664 @@
 //! Region utility functions
 
+use lib_common::time::{DateTime, Utc};
+use serde::{Serialize, Serializer};
+use svc_gis_client_grpc::prelude::gis;
+
+/// Meters in one foot
+pub const METERS_PER_FOOT: f64 = 0.3048;
+
+/// Feet in one flight level
+pub const FEET_PER_FLIGHT_LEVEL: f64 = 100.0;
+
+/// Meters in one nautical mile
+pub const METERS_PER_NAUTICAL_MILE: f64 = 1852.0;
+
+/// Meters in one statute mile
+pub const METERS_PER_STATUTE_MILE: f64 = 1609.344;
+
+/// Altitude in meters used for unlimited upper limits (FL600)
+pub const UNLIMITED_ALTITUDE_METERS: f64 = 18_288.0;
+
+/// Mean radius of the earth in meters
+pub const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// Minimum number of vertices of a closed boundary
+const MIN_RING_VERTICES: usize = 4;
+
+/// Distance below which consecutive vertices are the same
+const VERTEX_TOLERANCE_METERS: f64 = 1.0;
+
+/// Vertical reference of an altitude
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AltitudeReference {
+    /// Above mean sea level
+    Msl,
+
+    /// Above ground level
+    Agl,
+
+    /// Pressure altitude relative to the standard atmosphere (flight levels)
+    Standard,
+
+    /// Height above the WGS84 ellipsoid
+    Ellipsoid,
+}
+
+impl AltitudeReference {
+    /// Parses a published vertical reference, ignoring case
+    ///
+    /// Accepts the codes of ICAO NOTAMs, AIXM, ED-269 and the FAA
+    ///  airspace datasets. Returns `None` for other references.
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_uppercase().as_str() {
+            "MSL" | "AMSL" | "ALT" => Some(Self::Msl),
+            "AGL" | "SFC" | "GND" | "HEI" | "ASFC" => Some(Self::Agl),
+            "STD" => Some(Self::Standard),
+            "WGS84" | "W84" => Some(Self::Ellipsoid),
+            _ => None,
+        }
+    }
+}
+
+/// Unit in which an altitude was published
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AltitudeUnit {
+    /// Meters
+    Meters,
+
+    /// Feet
+    Feet,
+
+    /// Flight levels, hundreds of feet
+    FlightLevel,
+}
+
+impl AltitudeUnit {
+    /// Parses a unit of `M`, `FT` or `FL`, ignoring case
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_uppercase().as_str() {
+            "M" => Some(Self::Meters),
+            "FT" => Some(Self::Feet),
+            "FL" => Some(Self::FlightLevel),
+            _ => None,
+        }
+    }
+
+    /// Converts a value in this unit to meters
+    pub fn to_meters(&self, value: f64) -> f64 {
+        match self {
+            Self::Meters => value,
+            Self::Feet => value * METERS_PER_FOOT,
+            Self::FlightLevel => value * FEET_PER_FLIGHT_LEVEL * METERS_PER_FOOT,
+        }
+    }
+
+    /// Converts meters to a value in this unit
+    pub fn from_meters(&self, meters: f64) -> f64 {
+        match self {
+            Self::Meters => meters,
+            Self::Feet => meters / METERS_PER_FOOT,
+            Self::FlightLevel => meters / METERS_PER_FOOT / FEET_PER_FLIGHT_LEVEL,
+        }
+    }
+}
+
+/// An altitude limit of a restriction, with the unit and reference it was
+///  published in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AltitudeLimit {
+    /// Altitude in meters
+    pub meters: f64,
+
+    /// Unit the altitude was published in
+    pub unit: AltitudeUnit,
+
+    /// Vertical reference of the altitude
+    pub reference: AltitudeReference,
+}
+
+impl AltitudeLimit {
+    /// The surface limit
+    pub const SURFACE: AltitudeLimit = AltitudeLimit {
+        meters: 0.0,
+        unit: AltitudeUnit::Meters,
+        reference: AltitudeReference::Agl,
+    };
+
+    /// The limit of restrictions without an upper limit
+    pub const UNLIMITED: AltitudeLimit = AltitudeLimit {
+        meters: UNLIMITED_ALTITUDE_METERS,
+        unit: AltitudeUnit::Meters,
+        reference: AltitudeReference::Msl,
+    };
+
+    /// An altitude in meters above mean sea level
+    pub fn msl(meters: f64) -> Self {
+        AltitudeLimit {
+            meters,
+            unit: AltitudeUnit::Meters,
+            reference: AltitudeReference::Msl,
+        }
+    }
+
+    /// A height in meters above ground level
+    pub fn agl(meters: f64) -> Self {
+        AltitudeLimit {
+            meters,
+            unit: AltitudeUnit::Meters,
+            reference: AltitudeReference::Agl,
+        }
+    }
+
+    /// Creates a limit from a value in the given unit
+    ///
+    /// Flight levels are always pressure altitudes, whatever the reference.
+    ///  Returns `None` for units other than `M`, `FT` or `FL`.
+    pub fn new(value: f64, unit: &str, reference: AltitudeReference) -> Option<Self> {
+        let unit = AltitudeUnit::parse(unit)?;
+        let reference = match unit {
+            AltitudeUnit::FlightLevel => AltitudeReference::Standard,
+            _ => reference,
+        };
+
+        Some(AltitudeLimit {
+            meters: unit.to_meters(value),
+            unit,
+            reference,
+        })
+    }
+
+    /// Returns true if the limit is at or above [`UNLIMITED_ALTITUDE_METERS`]
+    pub fn is_unlimited(&self) -> bool {
+        self.reference != AltitudeReference::Agl && self.meters >= UNLIMITED_ALTITUDE_METERS
+    }
+}
+
+impl std::fmt::Display for AltitudeLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.reference == AltitudeReference::Agl && self.meters == 0.0 {
+            return write!(f, "SFC");
+        }
+
+        if self.is_unlimited() {
+            return write!(f, "UNL");
+        }
+
+        let value = self.unit.from_meters(self.meters);
+        let reference = match self.reference {
+            AltitudeReference::Msl => "AMSL",
+            AltitudeReference::Agl => "AGL",
+            AltitudeReference::Standard => "STD",
+            AltitudeReference::Ellipsoid => "WGS84",
+        };
+
+        match self.unit {
+            AltitudeUnit::FlightLevel => write!(f, "FL{:03.0}", value),
+            AltitudeUnit::Feet => write!(f, "{:.0} ft {}", value, reference),
+            AltitudeUnit::Meters => write!(f, "{} m {}", (value * 10.0).round() / 10.0, reference),
+        }
+    }
+}
+
+/// A single point of a flight plan route
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct RoutePoint {
+    /// Latitude in degrees
+    pub latitude: f64,
+
+    /// Longitude in degrees
+    pub longitude: f64,
+
+    /// Altitude in meters, if known
+    pub altitude_meters: Option<f32>,
+
+    /// Time at which the aircraft passes this point, if known
+    #[serde(serialize_with = "serialize_timestamp")]
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Serializes an optional timestamp as RFC 3339
+fn serialize_timestamp<S>(
+    timestamp: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match timestamp {
+        Some(timestamp) => serializer.serialize_some(&timestamp.to_rfc3339()),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Returns true if the point lies within the polygon
+///
+/// Uses ray casting with longitude as x and latitude as y. The polygon
+///  ring may be open or closed.
+pub fn point_in_polygon(point: &gis::Coordinates, polygon: &[gis::Coordinates]) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+    let mut j = polygon.len() - 1;
+    for i in 0..polygon.len() {
+        let (xi, yi) = (polygon[i].longitude, polygon[i].latitude);
+        let (xj, yj) = (polygon[j].longitude, polygon[j].latitude);
+
+        if (yi > point.latitude) != (yj > point.latitude)
+            && point.longitude < (xj - xi) * (point.latitude - yi) / (yj - yi) + xi
+        {
+            inside = !inside;
+        }
+
+        j = i;
+    }
+
+    inside
+}
+
+/// Orientation of the ordered triplet (a, b, c)
+///  1: counter-clockwise, -1: clockwise, 0: collinear
+fn orientation(a: &gis::Coordinates, b: &gis::Coordinates, c: &gis::Coordinates) -> i8 {
+    let value = (b.longitude - a.longitude) * (c.latitude - a.latitude)
+        - (b.latitude - a.latitude) * (c.longitude - a.longitude);
+
+    if value.abs() < f64::EPSILON {
+        0
+    } else if value > 0.0 {
+        1
+    } else {
+        -1
+    }
+}
+
+/// Returns true if point c lies on the segment (a, b), given that all three are collinear
+fn on_segment(a: &gis::Coordinates, b: &gis::Coordinates, c: &gis::Coordinates) -> bool {
+    c.longitude >= a.longitude.min(b.longitude)
+        && c.longitude <= a.longitude.max(b.longitude)
+        && c.latitude >= a.latitude.min(b.latitude)
+        && c.latitude <= a.latitude.max(b.latitude)
+}
+
+/// Returns true if the segments (a1, a2) and (b1, b2) touch or cross
+pub fn segments_intersect(
+    a1: &gis::Coordinates,
+    a2: &gis::Coordinates,
+    b1: &gis::Coordinates,
+    b2: &gis::Coordinates,
+) -> bool {
+    let o1 = orientation(a1, a2, b1);
+    let o2 = orientation(a1, a2, b2);
+    let o3 = orientation(b1, b2, a1);
+    let o4 = orientation(b1, b2, a2);
+
+    if o1 != o2 && o3 != o4 {
+        return true;
+    }
+
+    (o1 == 0 && on_segment(a1, a2, b1))
+        || (o2 == 0 && on_segment(a1, a2, b2))
+        || (o3 == 0 && on_segment(b1, b2, a1))
+        || (o4 == 0 && on_segment(b1, b2, a2))
+}
+
+/// Returns true if any part of the path lies within or crosses the polygon
+pub fn path_intersects_polygon(path: &[gis::Coordinates], polygon: &[gis::Coordinates]) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+
+    if path.iter().any(|p| point_in_polygon(p, polygon)) {
+        return true;
+    }
+
+    let edges = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .collect::<Vec<_>>();
+
+    path.windows(2).any(|segment| {
+        edges
+            .iter()
+            .any(|(b1, b2)| segments_intersect(&segment[0], &segment[1], b1, b2))
+    })
+}
+
+/// Converts an altitude to meters
+///
+/// The unit of measurement is one of `M`, `FT` or `FL` (flight level),
+///  ignoring case. Returns `None` for other units.
+pub fn altitude_to_meters(value: f64, unit: &str) -> Option<f64> {
+    AltitudeUnit::parse(unit).map(|unit| unit.to_meters(value))
+}
+
+/// Converts a distance to meters
+///
+/// The unit of measurement is one of `M`, `KM`, `FT`, `NM` or `MI`
+///  (statute miles), ignoring case. Returns `None` for other units.
+pub fn distance_to_meters(value: f64, unit: &str) -> Option<f64> {
+    match unit.trim().to_uppercase().as_str() {
+        "M" => Some(value),
+        "KM" => Some(value * 1000.0),
+        "FT" => Some(value * METERS_PER_FOOT),
+        "NM" => Some(value * METERS_PER_NAUTICAL_MILE),
+        "MI" => Some(value * METERS_PER_STATUTE_MILE),
+        _ => None,
+    }
+}
+
+/// Great circle distance in meters between two points
+pub fn distance_meters(from: &gis::Coordinates, to: &gis::Coordinates) -> f64 {
+    let (lat_a, lat_b) = (from.latitude.to_radians(), to.latitude.to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lon = (to.longitude - from.longitude).to_radians();
+
+    let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().min(1.0).asin()
+}
+
+/// Initial bearing in degrees from true north, clockwise, of the great
+///  circle from one point to another
+pub fn bearing_degrees(from: &gis::Coordinates, to: &gis::Coordinates) -> f64 {
+    let (lat_a, lat_b) = (from.latitude.to_radians(), to.latitude.to_radians());
+    let d_lon = (to.longitude - from.longitude).to_radians();
+
+    let y = d_lon.sin() * lat_b.cos();
+    let x = lat_a.cos() * lat_b.sin() - lat_a.sin() * lat_b.cos() * d_lon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Point reached from the origin after travelling the distance along the
+///  great circle with the given initial bearing
+pub fn destination(
+    origin: &gis::Coordinates,
+    bearing_degrees: f64,
+    distance_meters: f64,
+) -> gis::Coordinates {
+    let lat = origin.latitude.to_radians();
+    let lon = origin.longitude.to_radians();
+    let bearing = bearing_degrees.to_radians();
+    let angle = distance_meters / EARTH_RADIUS_METERS;
+
+    let lat_dest = (lat.sin() * angle.cos() + lat.cos() * angle.sin() * bearing.cos()).asin();
+    let lon_dest = lon
+        + (bearing.sin() * angle.sin() * lat.cos()).atan2(angle.cos() - lat.sin() * lat_dest.sin());
+
+    gis::Coordinates {
+        latitude: lat_dest.to_degrees(),
+        longitude: (lon_dest.to_degrees() + 540.0).rem_euclid(360.0) - 180.0,
+    }
+}
+
+/// Vertices of an arc around the center, from the start to the end bearing,
+///  with at most `step_degrees` between consecutive vertices
+///
+/// Both end points are included. An arc with equal start and end bearings
+///  is a full circle.
+pub fn arc_vertices(
+    center: &gis::Coordinates,
+    radius_meters: f64,
+    start_bearing_degrees: f64,
+    end_bearing_degrees: f64,
+    clockwise: bool,
+    step_degrees: f64,
+) -> Vec<gis::Coordinates> {
+    let mut sweep = match clockwise {
+        true => end_bearing_degrees - start_bearing_degrees,
+        false => start_bearing_degrees - end_bearing_degrees,
+    }
+    .rem_euclid(360.0);
+    if sweep < f64::EPSILON {
+        sweep = 360.0;
+    }
+
+    let direction = if clockwise { 1.0 } else { -1.0 };
+    let steps = (sweep / step_degrees).ceil().max(1.0) as usize;
+    (0..=steps)
+        .map(|i| {
+            let bearing = start_bearing_degrees + direction * sweep * i as f64 / steps as f64;
+            destination(center, bearing, radius_meters)
+        })
+        .collect()
+}
+
+/// Returns the center of the bounding box of the vertices
+pub fn bounds_center(vertices: &[gis::Coordinates]) -> gis::Coordinates {
+    let (mut south, mut west, mut north, mut east) = (90.0, 180.0, -90.0, -180.0);
+    for v in vertices {
+        south = v.latitude.min(south);
+        north = v.latitude.max(north);
+        west = v.longitude.min(west);
+        east = v.longitude.max(east);
+    }
+
+    gis::Coordinates {
+        latitude: (south + north) / 2.0,
+        longitude: (west + east) / 2.0,
+    }
+}
+
+/// Merges consecutive vertices closer than [`VERTEX_TOLERANCE_METERS`] and
+///  closes the ring if needed
+pub fn close_ring(mut ring: Vec<gis::Coordinates>) -> Vec<gis::Coordinates> {
+    ring.dedup_by(|a, b| distance_meters(a, b) < VERTEX_TOLERANCE_METERS);
+    if let (Some(first), Some(last)) = (ring.first().copied(), ring.last_mut()) {
+        if distance_meters(&first, last) < VERTEX_TOLERANCE_METERS {
+            *last = first;
+        } else {
+            ring.push(first);
+        }
+    }
+
+    ring
+}
+
+/// Closes the boundary if needed and checks it has enough vertices
+///
+/// Consecutive vertices closer than [`VERTEX_TOLERANCE_METERS`], such as
+///  the end of a line and the start of the following arc, are merged.
+pub fn to_closed_ring(ring: Vec<gis::Coordinates>) -> Result<Vec<gis::Coordinates>, String> {
+    let ring = close_ring(ring);
+    match ring.len() < MIN_RING_VERTICES {
+        true => Err(format!("boundary has only {} vertices", ring.len())),
+        false => Ok(ring),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    // use super::*;
+    use super::*;
+
+    fn coords(points: &[(f64, f64)]) -> Vec<gis::Coordinates> {
+        points
+            .iter()
+            .map(|(latitude, longitude)| gis::Coordinates {
+                latitude: *latitude,
+                longitude: *longitude,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_serialize_route_point() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let point = RoutePoint {
+            latitude: 52.0,
+            longitude: 4.0,
+            altitude_meters: Some(120.0),
+            timestamp: Some("2024-01-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap()),
+        };
+
+        let json = serde_json::to_value(point).unwrap();
+        assert_eq!(json["altitude_meters"], 120.0);
+        assert_eq!(json["timestamp"], "2024-01-01T12:00:00+00:00");
+
+        let point = RoutePoint {
+            timestamp: None,
+            ..point
+        };
+        let json = serde_json::to_value(point).unwrap();
+        assert!(json["timestamp"].is_null());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_point_in_polygon() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let square = coords(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)]);
+        assert!(point_in_polygon(&coords(&[(0.5, 0.5)])[0], &square));
+        assert!(!point_in_polygon(&coords(&[(1.5, 0.5)])[0], &square));
+        assert!(!point_in_polygon(&coords(&[(0.5, 0.5)])[0], &square[..2]));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_path_intersects_polygon() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let square = coords(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)]);
+
+        // endpoint inside
+        let path = coords(&[(0.5, 0.5), (2.0, 2.0)]);
+        assert!(path_intersects_polygon(&path, &square));
+
+        // passes through without a point inside
+        let path = coords(&[(0.5, -1.0), (0.5, 2.0)]);
+        assert!(path_intersects_polygon(&path, &square));
+
+        // misses entirely
+        let path = coords(&[(2.0, -1.0), (2.0, 2.0)]);
+        assert!(!path_intersects_polygon(&path, &square));
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_altitude_to_meters() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert_eq!(altitude_to_meters(120.0, "M"), Some(120.0));
+        assert_eq!(altitude_to_meters(1000.0, "ft"), Some(304.8));
+        let meters = altitude_to_meters(45.0, " FL ").unwrap();
+        assert!((meters - 1371.6).abs() < 1e-9);
+        assert_eq!(altitude_to_meters(1.0, "NM"), None);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_altitude_limit() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let limit = AltitudeLimit::new(400.0, "ft", AltitudeReference::Agl).unwrap();
+        assert!((limit.meters - 121.92).abs() < 1e-9);
+        assert_eq!(limit.unit, AltitudeUnit::Feet);
+        assert_eq!(limit.to_string(), "400 ft AGL");
+
+        // flight levels are pressure altitudes whatever the reference
+        let limit = AltitudeLimit::new(65.0, "FL", AltitudeReference::Msl).unwrap();
+        assert_eq!(limit.reference, AltitudeReference::Standard);
+        assert_eq!(limit.to_string(), "FL065");
+
+        let limit = AltitudeLimit::new(120.0, "M", AltitudeReference::Ellipsoid).unwrap();
+        assert_eq!(limit.to_string(), "120 m WGS84");
+        assert!(AltitudeLimit::new(1.0, "NM", AltitudeReference::Msl).is_none());
+
+        assert_eq!(AltitudeLimit::SURFACE.to_string(), "SFC");
+        assert_eq!(AltitudeLimit::UNLIMITED.to_string(), "UNL");
+        assert!(AltitudeLimit::UNLIMITED.is_unlimited());
+
+        assert_eq!(
+            AltitudeReference::parse(" amsl"),
+            Some(AltitudeReference::Msl)
+        );
+        assert_eq!(
+            AltitudeReference::parse("HEI"),
+            Some(AltitudeReference::Agl)
+        );
+        assert_eq!(
+            AltitudeReference::parse("W84"),
+            Some(AltitudeReference::Ellipsoid)
+        );
+        assert_eq!(AltitudeReference::parse("QNH"), None);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_distance_to_meters() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        assert_eq!(distance_to_meters(3.0, "NM"), Some(5556.0));
+        assert_eq!(distance_to_meters(2.0, "km"), Some(2000.0));
+        assert_eq!(distance_to_meters(1.0, "MI"), Some(1609.344));
+        assert_eq!(distance_to_meters(1000.0, "FT"), Some(304.8));
+        assert_eq!(distance_to_meters(1.0, "FL"), None);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_great_circle() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let origin = coords(&[(52.0, 4.0)])[0];
+
+        // one degree of latitude
+        let north = destination(&origin, 0.0, 111_195.0);
+        assert!((north.latitude - 53.0).abs() < 1e-4);
+        assert!((north.longitude - 4.0).abs() < 1e-9);
+
+        let east = destination(&origin, 90.0, 5000.0);
+        assert!((distance_meters(&origin, &east) - 5000.0).abs() < 1e-6);
+        assert!((bearing_degrees(&origin, &east) - 90.0).abs() < 0.1);
+        assert!((bearing_degrees(&east, &origin) - 270.0).abs() < 0.1);
+
+        // wraps around the antimeridian
+        let west = destination(&coords(&[(0.0, -179.9)])[0], 270.0, 22_239.0);
+        assert!((west.longitude - 179.9).abs() < 1e-3);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_arc_vertices() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let center = coords(&[(30.0, -97.0)])[0];
+
+        let arc = arc_vertices(&center, 1000.0, 350.0, 80.0, true, 10.0);
+        assert_eq!(arc.len(), 10);
+        assert!((bearing_degrees(&center, &arc[0]) - 350.0).abs() < 0.01);
+        assert!((bearing_degrees(&center, &arc[9]) - 80.0).abs() < 0.01);
+        assert!(arc
+            .iter()
+            .all(|p| (distance_meters(&center, p) - 1000.0).abs() < 1e-3));
+
+        // the same end points counter-clockwise take the long way round
+        let arc = arc_vertices(&center, 1000.0, 350.0, 80.0, false, 10.0);
+        assert_eq!(arc.len(), 28);
+        assert!((bearing_degrees(&center, &arc[1]) - 340.0).abs() < 0.01);
+
+        let circle = arc_vertices(&center, 500.0, 0.0, 0.0, true, 10.0);
+        assert_eq!(circle.len(), 37);
+        assert!((circle[0].latitude - circle[36].latitude).abs() < 1e-9);
+        assert!((circle[0].longitude - circle[36].longitude).abs() < 1e-9);
+        assert!(point_in_polygon(&center, &circle));
+
+        ut_info!("Success.");
+    }
 }
@@ -0,0 +1,444 @@
+//! Vertical references of altitude limits
+//!
+//! Restrictions keep their limits as published: above ground level, above
+//!  mean sea level, as flight levels or above the WGS84 ellipsoid. Flight
+//!  plans and svc-gis use heights above the WGS84 ellipsoid, so limits are
+//!  converted with a terrain model for heights above ground and a geoid
+//!  model for altitudes above mean sea level.
+//!
+//! Both models are grids in the ESRI ASCII format, such as exports of the
+//!  SRTM or AHN elevation models and of the EGM2008 geoid. Without a
+//!  terrain model the ground is at mean sea level, without a geoid model
+//!  mean sea level is on the ellipsoid. Flight levels are converted in the
+//!  standard atmosphere, as the pressure at the time of the flight is not
+//!  known in advance.
+
+use crate::region::source::Source;
+use crate::region::utils::{point_in_polygon, AltitudeLimit, AltitudeReference};
+use svc_gis_client_grpc::prelude::gis::Coordinates;
+
+/// Errors when loading a terrain or geoid model
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum VerticalError {
+    /// The grid cannot be read from its source
+    #[error("error: Could not read {0}: {1}")]
+    Read(String, String),
+
+    /// The grid is malformed
+    #[error("error: Invalid grid: {0}")]
+    Grid(String),
+}
+
+/// A regular grid of values in the ESRI ASCII format
+///
+/// Each value applies to the center of its cell. Rows run from north to
+///  south.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    /// Number of columns
+    columns: usize,
+
+    /// Number of rows
+    rows: usize,
+
+    /// Longitude of the western edge in degrees
+    west: f64,
+
+    /// Latitude of the southern edge in degrees
+    south: f64,
+
+    /// Width and height of a cell in degrees
+    cell_size: f64,
+
+    /// Values by row and column, `None` where the grid has no data
+    values: Vec<Option<f64>>,
+}
+
+impl Grid {
+    /// Parses a grid in the ESRI ASCII format
+    pub fn parse(text: &str) -> Result<Self, VerticalError> {
+        let invalid = |e: &str| VerticalError::Grid(e.to_string());
+        let mut tokens = text.split_whitespace().peekable();
+
+        let mut header = std::collections::HashMap::new();
+        while let Some(key) = tokens.next_if(|t| t.starts_with(char::is_alphabetic)) {
+            let value = tokens
+                .next()
+                .and_then(|v| v.parse::<f64>().ok())
+                .ok_or_else(|| invalid(&format!("invalid value of {}", key)))?;
+            header.insert(key.to_lowercase(), value);
+        }
+
+        let get = |key: &str| header.get(key).copied();
+        let (Some(columns), Some(rows), Some(cell_size)) =
+            (get("ncols"), get("nrows"), get("cellsize"))
+        else {
+            return Err(invalid("missing ncols, nrows or cellsize"));
+        };
+
+        if columns < 1.0 || rows < 1.0 || cell_size <= 0.0 {
+            return Err(invalid("empty grid"));
+        }
+
+        let (columns, rows) = (columns as usize, rows as usize);
+        let corner = |corner: &str, center: &str| match (get(corner), get(center)) {
+            (Some(edge), _) => Ok(edge),
+            (None, Some(center)) => Ok(center - cell_size / 2.0),
+            (None, None) => Err(invalid(&format!("missing {} or {}", corner, center))),
+        };
+        let west = corner("xllcorner", "xllcenter")?;
+        let south = corner("yllcorner", "yllcenter")?;
+        let no_data = get("nodata_value");
+
+        let values = tokens
+            .map(|t| match t.parse::<f64>() {
+                Ok(value) if Some(value) == no_data => Ok(None),
+                Ok(value) => Ok(Some(value)),
+                Err(_) => Err(invalid(&format!("invalid value '{}'", t))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if values.len() != columns * rows {
+            return Err(invalid(&format!(
+                "expected {} values, found {}",
+                columns * rows,
+                values.len()
+            )));
+        }
+
+        Ok(Grid {
+            columns,
+            rows,
+            west,
+            south,
+            cell_size,
+            values,
+        })
+    }
+
+    /// Value of the cell, counting rows from the south
+    fn value(&self, column: usize, row_from_south: usize) -> Option<f64> {
+        self.values[(self.rows - 1 - row_from_south) * self.columns + column]
+    }
+
+    /// Center of the cell, counting rows from the south
+    fn center(&self, column: usize, row_from_south: usize) -> Coordinates {
+        Coordinates {
+            latitude: self.south + (row_from_south as f64 + 0.5) * self.cell_size,
+            longitude: self.west + (column as f64 + 0.5) * self.cell_size,
+        }
+    }
+
+    /// Value at the point, interpolated between the four nearest cells
+    ///
+    /// Cells without data are left out of the interpolation. Returns `None`
+    ///  outside the grid or at the center of a cell without data.
+    pub fn value_at(&self, point: &Coordinates) -> Option<f64> {
+        let x = (point.longitude - self.west) / self.cell_size;
+        let y = (point.latitude - self.south) / self.cell_size;
+        if x < 0.0 || y < 0.0 || x > self.columns as f64 || y > self.rows as f64 {
+            return None;
+        }
+
+        let x = (x - 0.5).clamp(0.0, (self.columns - 1) as f64);
+        let y = (y - 0.5).clamp(0.0, (self.rows - 1) as f64);
+        let (column, row) = (x.floor() as usize, y.floor() as usize);
+        let (fx, fy) = (x - column as f64, y - row as f64);
+        let next_column = (column + 1).min(self.columns - 1);
+        let next_row = (row + 1).min(self.rows - 1);
+
+        let (weighted, weights) = [
+            (column, row, (1.0 - fx) * (1.0 - fy)),
+            (next_column, row, fx * (1.0 - fy)),
+            (column, next_row, (1.0 - fx) * fy),
+            (next_column, next_row, fx * fy),
+        ]
+        .into_iter()
+        .filter_map(|(c, r, weight)| self.value(c, r).map(|value| (value, weight)))
+        .fold((0.0, 0.0), |(sum, weights), (value, weight)| {
+            (sum + value * weight, weights + weight)
+        });
+
+        match weights > 0.0 {
+            true => Some(weighted / weights),
+            false => None,
+        }
+    }
+
+    /// Values of the cells whose center lies within the polygon
+    pub fn values_within(&self, polygon: &[Coordinates]) -> Vec<f64> {
+        let index = |value: f64, origin: f64, count: usize| {
+            (((value - origin) / self.cell_size - 0.5).max(0.0) as usize).min(count - 1)
+        };
+
+        let (Some(min_lat), Some(max_lat), Some(min_lon), Some(max_lon)) = (
+            polygon.iter().map(|p| p.latitude).reduce(f64::min),
+            polygon.iter().map(|p| p.latitude).reduce(f64::max),
+            polygon.iter().map(|p| p.longitude).reduce(f64::min),
+            polygon.iter().map(|p| p.longitude).reduce(f64::max),
+        ) else {
+            return vec![];
+        };
+
+        let rows = index(min_lat, self.south, self.rows)..=index(max_lat, self.south, self.rows);
+        let columns =
+            index(min_lon, self.west, self.columns)..=index(max_lon, self.west, self.columns);
+
+        rows.flat_map(|row| columns.clone().map(move |column| (column, row)))
+            .filter(|(column, row)| point_in_polygon(&self.center(*column, *row), polygon))
+            .filter_map(|(column, row)| self.value(column, row))
+            .collect()
+    }
+}
+
+/// Terrain and geoid models used to convert altitude limits to heights
+///  above the WGS84 ellipsoid
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VerticalModel {
+    /// Elevation of the terrain in meters above mean sea level
+    terrain: Option<Grid>,
+
+    /// Height of the geoid in meters above the WGS84 ellipsoid
+    geoid: Option<Grid>,
+}
+
+impl VerticalModel {
+    /// Creates a model from a terrain and a geoid grid
+    pub fn new(terrain: Option<Grid>, geoid: Option<Grid>) -> Self {
+        Self { terrain, geoid }
+    }
+
+    /// Reads the terrain and geoid grids from their sources
+    ///
+    /// A missing grid is logged, as the limits relying on it are then
+    ///  taken closer to the ellipsoid than they are.
+    pub async fn load(
+        terrain: Option<&Source>,
+        geoid: Option<&Source>,
+    ) -> Result<Self, VerticalError> {
+        if terrain.is_none() {
+            region_warn!("No terrain model, heights above ground are taken above mean sea level.");
+        }
+        if geoid.is_none() {
+            region_warn!("No geoid model, altitudes above mean sea level are taken above the WGS84 ellipsoid.");
+        }
+
+        Ok(Self::new(
+            load_grid(terrain).await?,
+            load_grid(geoid).await?,
+        ))
+    }
+
+    /// Elevation of the terrain at the point in meters above mean sea level
+    pub fn terrain_meters(&self, point: &Coordinates) -> f64 {
+        self.terrain
+            .as_ref()
+            .and_then(|grid| grid.value_at(point))
+            .unwrap_or(0.0)
+    }
+
+    /// Height of the geoid at the point in meters above the ellipsoid
+    pub fn geoid_meters(&self, point: &Coordinates) -> f64 {
+        self.geoid
+            .as_ref()
+            .and_then(|grid| grid.value_at(point))
+            .unwrap_or(0.0)
+    }
+
+    /// Height of the limit at the point in meters above the ellipsoid
+    ///
+    /// Unlimited limits are kept as they are.
+    pub fn height_meters(&self, limit: &AltitudeLimit, point: &Coordinates) -> f64 {
+        if limit.is_unlimited() {
+            return limit.meters;
+        }
+
+        match limit.reference {
+            AltitudeReference::Ellipsoid => limit.meters,
+            AltitudeReference::Msl | AltitudeReference::Standard => {
+                limit.meters + self.geoid_meters(point)
+            }
+            AltitudeReference::Agl => {
+                limit.meters + self.terrain_meters(point) + self.geoid_meters(point)
+            }
+        }
+    }
+
+    /// Lowest and highest height of the limit over the polygon in meters
+    ///  above the ellipsoid
+    ///
+    /// Heights above ground are sampled at the vertices and at every
+    ///  terrain cell within the polygon.
+    pub fn height_range_meters(
+        &self,
+        limit: &AltitudeLimit,
+        polygon: &[Coordinates],
+    ) -> (f64, f64) {
+        let mut heights = polygon
+            .iter()
+            .map(|point| self.height_meters(limit, point))
+            .collect::<Vec<_>>();
+
+        if let (AltitudeReference::Agl, Some(terrain), Some(point)) =
+            (limit.reference, &self.terrain, polygon.first())
+        {
+            let geoid = self.geoid_meters(point);
+            heights.extend(
+                terrain
+                    .values_within(polygon)
+                    .into_iter()
+                    .map(|elevation| limit.meters + elevation + geoid),
+            );
+        }
+
+        heights
+            .into_iter()
+            .fold(None, |range: Option<(f64, f64)>, height| match range {
+                Some((min, max)) => Some((min.min(height), max.max(height))),
+                None => Some((height, height)),
+            })
+            .unwrap_or((limit.meters, limit.meters))
+    }
+}
+
+/// Reads a grid from its source, if any
+async fn load_grid(source: Option<&Source>) -> Result<Option<Grid>, VerticalError> {
+    let Some(source) = source else {
+        return Ok(None);
+    };
+
+    let data = source
+        .read()
+        .await
+        .map_err(|e| VerticalError::Read(source.to_string(), e.to_string()))?;
+    Grid::parse(&String::from_utf8_lossy(&data)).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Terrain rising from 0 to 300 meters eastwards, one cell without data
+    const TERRAIN: &str = "ncols 4
+nrows 2
+xllcorner 4.0
+yllcorner 52.0
+cellsize 0.5
+NODATA_value -9999
+0 100 200 300
+0 100 -9999 300
+";
+
+    fn point(latitude: f64, longitude: f64) -> Coordinates {
+        Coordinates {
+            latitude,
+            longitude,
+        }
+    }
+
+    fn model() -> VerticalModel {
+        let terrain = Grid::parse(TERRAIN).unwrap();
+        let geoid =
+            Grid::parse("ncols 1 nrows 1 xllcenter 5.0 yllcenter 52.5 cellsize 10 45.0").unwrap();
+        VerticalModel::new(Some(terrain), Some(geoid))
+    }
+
+    #[tokio::test]
+    async fn test_grid() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let grid = Grid::parse(TERRAIN).unwrap();
+        assert_eq!(grid.value_at(&point(52.75, 4.25)), Some(0.0));
+        assert_eq!(grid.value_at(&point(52.75, 4.5)), Some(50.0));
+        assert_eq!(grid.value_at(&point(52.0, 5.99)), Some(300.0));
+        assert_eq!(grid.value_at(&point(52.25, 5.0)), Some(100.0));
+        assert_eq!(grid.value_at(&point(52.25, 5.25)), None);
+        assert_eq!(grid.value_at(&point(52.5, 3.9)), None);
+        assert_eq!(grid.value_at(&point(53.1, 4.5)), None);
+
+        let square = [
+            point(52.0, 4.0),
+            point(52.0, 5.0),
+            point(53.0, 5.0),
+            point(53.0, 4.0),
+            point(52.0, 4.0),
+        ];
+        let mut values = grid.values_within(&square);
+        values.sort_by(f64::total_cmp);
+        assert_eq!(values, vec![0.0, 0.0, 100.0, 100.0]);
+
+        assert!(Grid::parse("ncols 2 nrows 1 xllcorner 0 yllcorner 0 cellsize 1 5").is_err());
+        assert!(Grid::parse("ncols 1 nrows 1 cellsize 1 5").is_err());
+        assert!(Grid::parse("ncols 1 nrows 1 xllcorner 0 yllcorner 0 cellsize 1 x").is_err());
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_height_meters() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let model = model();
+        let point = point(52.75, 5.75);
+
+        let agl = AltitudeLimit::new(400.0, "FT", AltitudeReference::Agl).unwrap();
+        assert!((model.height_meters(&agl, &point) - (121.92 + 300.0 + 45.0)).abs() < 1e-9);
+
+        let flight_level = AltitudeLimit::new(65.0, "FL", AltitudeReference::Standard).unwrap();
+        assert!((model.height_meters(&flight_level, &point) - (1981.2 + 45.0)).abs() < 1e-9);
+
+        let ellipsoid = AltitudeLimit::new(150.0, "M", AltitudeReference::Ellipsoid).unwrap();
+        assert_eq!(model.height_meters(&ellipsoid, &point), 150.0);
+        assert_eq!(
+            model.height_meters(&AltitudeLimit::UNLIMITED, &point),
+            AltitudeLimit::UNLIMITED.meters
+        );
+
+        // without models the limits are used as they are
+        let flat = VerticalModel::default();
+        assert!((flat.height_meters(&agl, &point) - 121.92).abs() < 1e-9);
+
+        // the terrain within the polygon is sampled, not only the vertices
+        let polygon = [
+            Coordinates {
+                latitude: 52.1,
+                longitude: 4.1,
+            },
+            Coordinates {
+                latitude: 52.1,
+                longitude: 5.9,
+            },
+            Coordinates {
+                latitude: 52.4,
+                longitude: 5.9,
+            },
+            Coordinates {
+                latitude: 52.1,
+                longitude: 4.1,
+            },
+        ];
+        let (min, max) = model.height_range_meters(&AltitudeLimit::SURFACE, &polygon);
+        assert_eq!(min, 45.0);
+        assert_eq!(max, 345.0);
+
+        ut_info!("Success.");
+    }
+
+    #[tokio::test]
+    async fn test_load() {
+        lib_common::logger::get_log_handle().await;
+        ut_info!("Start.");
+
+        let model = VerticalModel::load(None, None).await.unwrap();
+        assert_eq!(model, VerticalModel::default());
+
+        let error = VerticalModel::load(Some(&Source::File("/nonexistent".into())), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, VerticalError::Read(_, _)));
+
+        ut_info!("Success.");
+    }
+}
@@ -1,12 +1,6 @@
 /// Integration Tests
 fn get_log_string(function: &str, name: &str) -> String {
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "us")] {
-            let lang = "us";
-        } else {
-            let lang = "nl";
-        }
-    }
+    let lang = svc_compliance::region::DEFAULT_REGION_CODE;
 
     #[cfg(feature = "stub_server")]
     return format!("({}) (MOCK)[{}] {} server.", function, lang, name);
@@ -29,7 +23,13 @@ async fn test_server_requests_and_logs() {
     {
         let imp = ServerImpl {
             mq_channel: None,
-            region: Box::<svc_compliance::region::RegionImpl>::default(),
+            regions: svc_compliance::region::from_region_codes(
+                svc_compliance::region::DEFAULT_REGION_CODE,
+                &svc_compliance::config::Config::new(),
+            )
+            .unwrap(),
+            store: std::sync::Arc::default(),
+            flight_plans: Default::default(),
         };
 
         let result = imp.is_ready(tonic::Request::new(ReadyRequest {})).await;